@@ -30,6 +30,7 @@ use snarkvm_dpc::{
     AccountPrivateKey,
     AccountScheme,
     Block,
+    BlockHeader,
     DPCComponents,
     DPCScheme,
     LedgerScheme,
@@ -44,6 +45,19 @@ use rand::Rng;
 
 use std::sync::Arc;
 
+/// The outcome of receiving a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockOutcome {
+    /// The block extended the canon chain, possibly by way of a fork to the side chain it belongs to.
+    AcceptedToTip,
+    /// The block was stored without being canonized, i.e. on a side chain that isn't the longest one or as an orphan.
+    AcceptedAsSideChain,
+    /// The block was already known.
+    Duplicate,
+    /// The block failed to verify, for the given reason.
+    Invalid(String),
+}
+
 pub struct Consensus<S: Storage> {
     pub parameters: ConsensusParameters,
     pub public_parameters: PublicParameters<Components>,
@@ -71,6 +85,15 @@ impl<S: Storage> Consensus<S> {
 
     /// Check if the transactions are valid.
     pub fn verify_transactions(&self, transactions: &[Tx]) -> Result<bool, ConsensusError> {
+        self.verify_transactions_against(transactions, &*self.ledger)
+    }
+
+    /// Check if the transactions are valid according to the given ledger state.
+    fn verify_transactions_against<L>(&self, transactions: &[Tx], ledger: &L) -> Result<bool, ConsensusError>
+    where
+        L: LedgerScheme,
+        InstantiatedDPC: DPCScheme<L, NetworkParameters = PublicParameters<Components>, Transaction = Tx>,
+    {
         for tx in transactions {
             if !self
                 .parameters
@@ -84,22 +107,49 @@ impl<S: Storage> Consensus<S> {
         Ok(InstantiatedDPC::verify_transactions(
             &self.public_parameters,
             transactions,
-            &*self.ledger,
+            ledger,
         )?)
     }
 
     /// Check if the block is valid.
     /// Verify transactions and transaction fees.
     pub fn verify_block(&self, block: &Block<Tx>) -> Result<bool, ConsensusError> {
+        self.verify_block_with(block, true)
+    }
+
+    /// Check if the block is valid, optionally skipping the verification of the transaction proofs,
+    /// e.g. if they were already verified concurrently with the ones of other blocks.
+    fn verify_block_with(&self, block: &Block<Tx>, verify_transactions: bool) -> Result<bool, ConsensusError> {
+        let parent_header = if crate::is_genesis(&block.header) {
+            None
+        } else {
+            Some(self.ledger.get_latest_block()?.header)
+        };
+
+        self.verify_block_against(block, parent_header.as_ref(), &*self.ledger, verify_transactions)
+    }
+
+    /// Check if the block is valid as the successor of the given parent block (`None` for the genesis block)
+    /// according to the given ledger state, optionally skipping the verification of the transaction proofs.
+    fn verify_block_against<L>(
+        &self,
+        block: &Block<Tx>,
+        parent_header: Option<&BlockHeader>,
+        ledger: &L,
+        verify_transactions: bool,
+    ) -> Result<bool, ConsensusError>
+    where
+        L: LedgerScheme,
+        InstantiatedDPC: DPCScheme<L, NetworkParameters = PublicParameters<Components>, Transaction = Tx>,
+    {
         let transaction_ids: Vec<_> = block.transactions.to_transaction_ids()?;
         let (merkle_root, pedersen_merkle_root, _) = txids_to_roots(&transaction_ids);
 
         // Verify the block header
-        if !crate::is_genesis(&block.header) {
-            let parent_block = self.ledger.get_latest_block()?;
+        if let Some(parent_header) = parent_header {
             if let Err(err) =
                 self.parameters
-                    .verify_header(&block.header, &parent_block.header, &merkle_root, &pedersen_merkle_root)
+                    .verify_header(&block.header, parent_header, &merkle_root, &pedersen_merkle_root)
             {
                 error!("block header failed to verify: {:?}", err);
                 return Ok(false);
@@ -126,8 +176,15 @@ impl<S: Storage> Consensus<S> {
             return Ok(false);
         }
 
+        // Check that the coinbase transaction doesn't create more value than the block reward plus the fees
+        let block_height = ledger.len() as u32;
+        if !crate::has_valid_coinbase_value(&block.transactions, block_height) {
+            error!("the coinbase value exceeds the block reward plus the transaction fees");
+            return Ok(false);
+        }
+
         // Check that the block value balances are correct
-        let expected_block_reward = crate::get_block_reward(self.ledger.len() as u32).0;
+        let expected_block_reward = crate::get_block_reward(block_height).0;
         if total_value_balance.0 + expected_block_reward != 0 {
             trace!("total_value_balance: {:?}", total_value_balance);
             trace!("expected_block_reward: {:?}", expected_block_reward);
@@ -136,11 +193,96 @@ impl<S: Storage> Consensus<S> {
         }
 
         // Check that all the transaction proofs verify
-        self.verify_transactions(&block.transactions.0)
+        if verify_transactions {
+            self.verify_transactions_against(&block.transactions.0, ledger)
+        } else {
+            Ok(true)
+        }
     }
 
     /// Receive a block from an external source and process it based on ledger state.
-    pub fn receive_block(&self, block: &Block<Tx>) -> Result<(), ConsensusError> {
+    pub fn receive_block(&self, block: &Block<Tx>) -> Result<BlockOutcome, ConsensusError> {
+        self.receive_block_with(block, false)
+    }
+
+    /// Receive a block whose transaction proofs were already verified with `verify_transactions` against
+    /// the current canon tip, which the block extends, and process it based on ledger state.
+    pub fn receive_verified_block(&self, block: &Block<Tx>) -> Result<BlockOutcome, ConsensusError> {
+        self.receive_block_with(block, true)
+    }
+
+    /// Receives a batch of consecutive blocks extending the canon chain and commits either all or none of them.
+    /// Each block is verified against the ledger extended by its predecessors in the batch, and the blocks are
+    /// then written to the storage at once. The outcomes of the blocks are returned in order; if one of them is
+    /// invalid, none of them are committed, and its outcome is the last one returned.
+    pub fn receive_block_batch(&self, blocks: &[Block<Tx>]) -> Result<Vec<BlockOutcome>, ConsensusError> {
+        let mut staged_ledger = self.ledger.stage()?;
+        let mut staged_blocks = Vec::with_capacity(blocks.len());
+        let mut outcomes = Vec::with_capacity(blocks.len());
+
+        for block in blocks {
+            let block_hash = block.header.get_hash();
+
+            let outcome = if self.ledger.is_canon(&block_hash) {
+                BlockOutcome::Duplicate
+            } else if block.header.previous_block_hash != staged_ledger.latest_block_header().get_hash() {
+                BlockOutcome::Invalid(format!(
+                    "block {} doesn't extend the canon chain",
+                    hex::encode(block_hash.0)
+                ))
+            } else if !self.verify_block_against(
+                block,
+                Some(staged_ledger.latest_block_header()),
+                &staged_ledger,
+                true,
+            )? {
+                BlockOutcome::Invalid(format!("block {} failed to verify", hex::encode(block_hash.0)))
+            } else {
+                staged_ledger.push(block)?;
+                staged_blocks.push(block);
+                BlockOutcome::AcceptedToTip
+            };
+
+            let is_invalid = matches!(outcome, BlockOutcome::Invalid(_));
+            outcomes.push(outcome);
+            if is_invalid {
+                return Ok(outcomes);
+            }
+        }
+
+        let latest_block_hash = staged_ledger.latest_block_header().get_hash();
+        staged_ledger.commit()?;
+        self.update_memory_pool(&staged_blocks)?;
+
+        // Attempt to fast forward the block state if the node already stores
+        // the children of the new canon tip.
+        for child_block_hash in self.ledger.longest_child_path(latest_block_hash)? {
+            let child_block = self.ledger.get_block(&child_block_hash)?;
+            if let Err(error) = self.process_block(&child_block) {
+                warn!("Couldn't fast forward to a stored child block: {}", error);
+                break;
+            }
+        }
+
+        Ok(outcomes)
+    }
+
+    fn receive_block_with(
+        &self,
+        block: &Block<Tx>,
+        transactions_verified: bool,
+    ) -> Result<BlockOutcome, ConsensusError> {
+        match self.place_block(block, transactions_verified) {
+            Err(ConsensusError::InvalidBlock(block_hash)) => Ok(BlockOutcome::Invalid(format!(
+                "block {} failed to verify",
+                hex::encode(block_hash)
+            ))),
+            outcome => outcome,
+        }
+    }
+
+    /// Processes a block based on its position in relation to the canon chain.
+    fn place_block(&self, block: &Block<Tx>, transactions_verified: bool) -> Result<BlockOutcome, ConsensusError> {
         // Block is an unknown orphan
         if !self.ledger.previous_block_hash_exists(block) && !self.ledger.is_previous_block_canon(&block.header) {
             debug!("Processing a block that is an unknown orphan");
@@ -149,21 +291,23 @@ impl<S: Storage> Consensus<S> {
             // 1) The block is a genesis block, or
             // 2) The block is unknown and does not correspond with the canon chain.
             if crate::is_genesis(&block.header) && self.ledger.is_empty() {
-                self.process_block(&block)?;
+                self.process_block_with(block, transactions_verified)?;
+                Ok(BlockOutcome::AcceptedToTip)
             } else {
                 self.ledger.insert_only(block)?;
+                Ok(BlockOutcome::AcceptedAsSideChain)
             }
         } else {
             // If the block is not an unknown orphan, find the origin of the block
             match self.ledger.get_block_path(&block.header)? {
                 BlockPath::ExistingBlock => {
                     debug!("Received a pre-existing block");
-                    return Err(ConsensusError::PreExistingBlock);
+                    Ok(BlockOutcome::Duplicate)
                 }
                 BlockPath::CanonChain(block_height) => {
                     debug!("Processing a block that is on canon chain. Height {}", block_height);
 
-                    self.process_block(block)?;
+                    self.process_block_with(block, transactions_verified)?;
 
                     // Attempt to fast forward the block state if the node already stores
                     // the children of the new canon block.
@@ -172,6 +316,8 @@ impl<S: Storage> Consensus<S> {
                         let new_block = self.ledger.get_block(&child_block_hash)?;
                         self.process_block(&new_block)?;
                     }
+
+                    Ok(BlockOutcome::AcceptedToTip)
                 }
                 BlockPath::SideChain(side_chain_path) => {
                     debug!(
@@ -191,25 +337,29 @@ impl<S: Storage> Consensus<S> {
                         // Fork to superior side chain
                         self.ledger.revert_for_fork(&side_chain_path)?;
 
+                        // The block's transactions may have been verified against the abandoned chain,
+                        // so they're verified again.
                         if !side_chain_path.path.is_empty() {
                             for block_hash in side_chain_path.path {
                                 if block_hash == block.header.get_hash() {
-                                    self.process_block(&block)?
+                                    self.process_block(block)?
                                 } else {
                                     let new_block = self.ledger.get_block(&block_hash)?;
                                     self.process_block(&new_block)?;
                                 }
                             }
                         }
+
+                        Ok(BlockOutcome::AcceptedToTip)
                     } else {
                         // If the sidechain is not longer than the main canon chain, simply store the block
                         self.ledger.insert_only(block)?;
+
+                        Ok(BlockOutcome::AcceptedAsSideChain)
                     }
                 }
-            };
+            }
         }
-
-        Ok(())
     }
 
     /// Return whether or not the given block is valid and insert it.
@@ -217,24 +367,54 @@ impl<S: Storage> Consensus<S> {
     /// 2. Verify that the transactions are valid.
     /// 3. Insert/canonize block.
     pub fn process_block(&self, block: &Block<Tx>) -> Result<(), ConsensusError> {
+        self.process_block_with(block, false)
+    }
+
+    fn process_block_with(&self, block: &Block<Tx>, transactions_verified: bool) -> Result<(), ConsensusError> {
         if self.ledger.is_canon(&block.header.get_hash()) {
             return Ok(());
         }
 
         // 1. Verify that the block valid
-        if !self.verify_block(block)? {
+        if !self.verify_block_with(block, !transactions_verified)? {
             return Err(ConsensusError::InvalidBlock(block.header.get_hash().0.to_vec()));
         }
 
         // 2. Insert/canonize block
         self.ledger.insert_and_commit(block)?;
 
-        // 3. Remove transactions from the mempool
+        // 3. Update the mempool
+        self.update_memory_pool(&[block])
+    }
+
+    /// Updates the mempool after the given blocks were committed to the canon chain.
+    fn update_memory_pool(&self, blocks: &[&Block<Tx>]) -> Result<(), ConsensusError> {
+        // 1. Remove the blocks' transactions from the mempool
         let mut memory_pool = self.memory_pool.lock();
-        for transaction_id in block.transactions.to_transaction_ids()? {
-            memory_pool.remove_by_hash(&transaction_id)?;
+        for block in blocks {
+            if memory_pool.auto_cleanse {
+                memory_pool.cleanse_with_block(&block.transactions)?;
+            } else {
+                for transaction_id in block.transactions.to_transaction_ids()? {
+                    memory_pool.remove_by_hash(&transaction_id)?;
+                }
+            }
+        }
+
+        // 2. Move the orphan transactions whose ledger digest is now known into the mempool
+        let resolved_orphans =
+            memory_pool.take_orphans_if(|entry| self.ledger.validate_digest(&entry.transaction.ledger_digest));
+        for entry in resolved_orphans {
+            if let Ok(true) = self.verify_transaction(&entry.transaction) {
+                if let Err(error) = memory_pool.insert(&self.ledger, entry) {
+                    debug!("Dropping an orphan transaction: {}", error);
+                }
+            }
         }
 
+        // 3. Move the transactions scheduled for the new block height into the mempool
+        memory_pool.promote_scheduled(&self.ledger);
+
         Ok(())
     }
 
@@ -26,6 +26,9 @@ pub enum ConsensusError {
     #[error("UTXO has already been spent {:?} index: {:?}", _0, _1)]
     AlreadySpent(Vec<u8>, u32),
 
+    #[error("the transaction {} is blacklisted", _0)]
+    BlacklistedTransaction(String),
+
     #[error("{}", _0)]
     BlockError(BlockError),
 
@@ -53,12 +56,21 @@ pub enum ConsensusError {
     #[error("timestamp more than 2 hours into the future {:?} actual {:?}", _0, _1)]
     FuturisticTimestamp(i64, i64),
 
+    #[error("the transaction {} spends one of its serial numbers more than once", _0)]
+    InternalDoubleSpend(String),
+
+    #[error("the transaction {} creates one of its commitments more than once", _0)]
+    InternalDuplicateCommitment(String),
+
     #[error("invalid block {:?}", _0)]
     InvalidBlock(Vec<u8>),
 
     #[error("invalid coinbase transaction")]
     InvalidCoinbaseTransaction,
 
+    #[error("the memory pool is disabled")]
+    MemoryPoolDisabled,
+
     #[error("block transactions do not hash to merkle root {:?}", _0)]
     MerkleRoot(String),
 
@@ -98,11 +110,17 @@ pub enum ConsensusError {
     #[error("{}", _0)]
     TransactionError(TransactionError),
 
+    #[error("the transaction has {} inputs, which exceeds the maximum of {}", _0, _1)]
+    TooManyTransactionInputs(usize, usize),
+
+    #[error("the transaction has {} outputs, which exceeds the maximum of {}", _0, _1)]
+    TooManyTransactionOutputs(usize, usize),
+
+    #[error("the transaction has {} bytes, which exceeds the maximum of {}", _0, _1)]
+    TransactionTooLarge(usize, usize),
+
     #[error("Transactions are spending more funds than they have available")]
     TransactionOverspending,
-
-    #[error("The block is already known")]
-    PreExistingBlock,
 }
 
 impl From<BlockError> for ConsensusError {
@@ -141,6 +159,12 @@ impl From<anyhow::Error> for ConsensusError {
     }
 }
 
+impl From<hex::FromHexError> for ConsensusError {
+    fn from(error: hex::FromHexError) -> Self {
+        ConsensusError::Crate("hex", format!("{:?}", error))
+    }
+}
+
 impl From<std::io::Error> for ConsensusError {
     fn from(error: std::io::Error) -> Self {
         ConsensusError::Crate("std::io", format!("{:?}", error))
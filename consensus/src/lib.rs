@@ -40,6 +40,9 @@ extern crate thiserror;
 #[macro_use]
 extern crate tracing;
 
+pub mod clock;
+pub use clock::*;
+
 pub mod consensus;
 pub use consensus::*;
 
@@ -57,30 +60,34 @@ pub use memory_pool::MemoryPool;
 pub mod parameters;
 pub use parameters::*;
 
+use error::ConsensusError;
 use snarkos_storage::Ledger;
 use snarkvm_dpc::{
     testnet1::{
         instantiated::{CommitmentMerkleParameters, Tx},
         transaction::amount::AleoAmount,
     },
+    Block,
     BlockHeader,
     BlockHeaderHash,
+    Transactions as DPCTransactions,
 };
+use snarkvm_posw::txids_to_roots;
 
 pub type MerkleTreeLedger<S> = Ledger<Tx, CommitmentMerkleParameters, S>;
 
-/// Calculate a block reward that halves every 4 years * 365 days * 24 hours * 100 blocks/hr = 3,504,000 blocks.
-pub fn get_block_reward(block_num: u32) -> AleoAmount {
-    let expected_blocks_per_hour: u32 = 100;
-    let num_years = 4;
-    let block_segments = num_years * 365 * 24 * expected_blocks_per_hour;
-
-    let aleo_denonimation = AleoAmount::COIN;
-    let initial_reward = 150i64 * aleo_denonimation;
+/// The reward for mining a block before the first halving, in the smallest denomination of ALEO.
+pub const INITIAL_BLOCK_REWARD: i64 = 150 * AleoAmount::COIN;
+/// The number of blocks after which the block reward halves: 4 years * 365 days * 24 hours * 100 blocks/hr.
+pub const BLOCK_REWARD_HALVING_INTERVAL: u32 = 4 * 365 * 24 * 100;
+/// The maximum number of times the block reward halves.
+pub const MAX_BLOCK_REWARD_HALVINGS: u32 = 2;
 
+/// Calculate a block reward that halves every `BLOCK_REWARD_HALVING_INTERVAL` = 3,504,000 blocks.
+pub fn get_block_reward(block_num: u32) -> AleoAmount {
     // The block reward halves at most 2 times - minimum is 37.5 ALEO after 8 years.
-    let num_halves = u32::min(block_num / block_segments, 2);
-    let reward = initial_reward / (2_u64.pow(num_halves)) as i64;
+    let num_halves = u32::min(block_num / BLOCK_REWARD_HALVING_INTERVAL, MAX_BLOCK_REWARD_HALVINGS);
+    let reward = INITIAL_BLOCK_REWARD / (2_u64.pow(num_halves)) as i64;
 
     AleoAmount::from_bytes(reward)
 }
@@ -88,3 +95,32 @@ pub fn get_block_reward(block_num: u32) -> AleoAmount {
 pub fn is_genesis(block_header: &BlockHeader) -> bool {
     block_header.previous_block_hash == BlockHeaderHash([0u8; 32])
 }
+
+/// Returns `true` if the value created by the coinbase transaction among the given block transactions doesn't exceed
+/// the block reward at the given height plus the fees paid by the other transactions, i.e. the block doesn't mint
+/// more than the monetary policy allows.
+pub fn has_valid_coinbase_value(transactions: &DPCTransactions<Tx>, block_height: u32) -> bool {
+    let mut coinbase_value = 0i64;
+    let mut total_fees = 0i64;
+
+    for transaction in transactions.iter() {
+        let value_balance = transaction.value_balance.0;
+
+        if value_balance.is_negative() {
+            coinbase_value = coinbase_value.saturating_sub(value_balance);
+        } else {
+            total_fees = total_fees.saturating_add(value_balance);
+        }
+    }
+
+    coinbase_value <= get_block_reward(block_height).0.saturating_add(total_fees)
+}
+
+/// Returns `true` if the Merkle root of the transactions of the given block matches the one in its header;
+/// the block hash only covers the header, so it doesn't attest to the transactions on its own.
+pub fn has_valid_merkle_root(block: &Block<Tx>) -> Result<bool, ConsensusError> {
+    let transaction_ids = block.transactions.to_transaction_ids()?;
+    let (merkle_root, _, _) = txids_to_roots(&transaction_ids);
+
+    Ok(merkle_root == block.header.merkle_root_hash)
+}
@@ -18,23 +18,228 @@
 //!
 //! `MemoryPool` keeps a vector of transactions seen by the miner.
 
-use crate::error::ConsensusError;
+use crate::{error::ConsensusError, Clock, SystemClock};
 use snarkos_storage::Ledger;
 use snarkvm_algorithms::traits::LoadableMerkleParameters;
-use snarkvm_dpc::{BlockHeader, LedgerScheme, Storage, TransactionScheme, Transactions as DPCTransactions};
+use snarkvm_dpc::{
+    testnet1::transaction::amount::AleoAmount,
+    BlockHeader,
+    LedgerScheme,
+    Storage,
+    TransactionScheme,
+    Transactions as DPCTransactions,
+};
 use snarkvm_utilities::{
     bytes::{FromBytes, ToBytes},
     has_duplicates,
     to_bytes,
 };
 
-use std::collections::HashMap;
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    fmt,
+    hash::Hasher,
+    mem,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[allow(deprecated)]
+use std::hash::SipHasher;
+
+/// The source a memory pool transaction was received from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TxOrigin {
+    /// The transaction was submitted to this node directly, e.g. via RPC.
+    Local,
+    /// The transaction was relayed to this node by the peer with the given address.
+    Peer(SocketAddr),
+}
+
+/// The priority class of a memory pool transaction; entries of a lower class are ordered behind
+/// all the entries of a higher one, regardless of their fee rates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PriorityClass {
+    /// A zero-fee transaction allowed by the network policy, e.g. a protocol message; it isn't subject
+    /// to the admission control fee floor, and is only mined when there's space left in a block.
+    Free,
+    /// A regular, fee-paying transaction.
+    Standard,
+}
 
-/// Stores a transaction and it's size in the memory pool.
+/// Stores a transaction, it's size, origin, priority class and the block height it's valid from in the memory pool.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Entry<T: TransactionScheme> {
     pub size_in_bytes: usize,
     pub transaction: T,
+    pub origin: TxOrigin,
+    pub priority_class: PriorityClass,
+    /// The block height the transaction is scheduled for; it's held back until the chain reaches it.
+    pub valid_from_height: Option<u32>,
+}
+
+impl<T: TransactionScheme<ValueBalance = AleoAmount>> Entry<T> {
+    /// Returns the fee paid by the transaction per byte of its size.
+    /// Entries with a higher fee rate have a higher priority in the memory pool.
+    pub fn fee_rate(&self) -> f64 {
+        self.transaction.value_balance().0 as f64 / self.size_in_bytes.max(1) as f64
+    }
+
+    /// Compares the priorities of two entries, i.e. their priority classes and then their fee rates.
+    pub fn cmp_priority(&self, other: &Self) -> Ordering {
+        self.priority_class.cmp(&other.priority_class).then_with(|| {
+            self.fee_rate()
+                .partial_cmp(&other.fee_rate())
+                .unwrap_or(Ordering::Equal)
+        })
+    }
+}
+
+/// Selects the entry that is evicted from a full memory pool to make room for a new one; entries of a lower
+/// priority class are always evicted before the ones of a higher class, and pinned entries are never evicted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EvictionPolicy {
+    /// Evicts the entry with the lowest fee rate, provided that the new entry has a higher one.
+    LowestFeeRate,
+    /// Evicts the entry that was inserted into the memory pool the earliest, provided that the new entry has
+    /// at least the same fee rate.
+    Oldest,
+    /// Evicts the entry with the largest size in bytes, provided that the new entry has at least the same fee rate.
+    Largest,
+}
+
+impl Default for EvictionPolicy {
+    fn default() -> Self {
+        Self::LowestFeeRate
+    }
+}
+
+/// Orders the memory pool entries of equal priority when they're selected for a block; any remaining ties are
+/// broken by the transaction ids, so that the selection is deterministic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TieBreaker {
+    /// Selects the entry that was inserted into the memory pool the earliest first.
+    Oldest,
+    /// Selects the entry with the smallest size in bytes first.
+    Smallest,
+    /// Selects the entry with the lowest transaction id first.
+    TransactionId,
+}
+
+impl Default for TieBreaker {
+    fn default() -> Self {
+        Self::Oldest
+    }
+}
+
+/// The method used to select the memory pool entries that are included in a new block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SelectionMode {
+    /// Selects the entries in the order of their priority, skipping the ones that don't fit.
+    Greedy,
+    /// Selects the set of entries collecting the most fees, as long as the memory pool holds at most
+    /// `MAX_OPTIMAL_SELECTION_TRANSACTIONS` transactions; larger pools are packed greedily.
+    Optimal,
+}
+
+impl Default for SelectionMode {
+    fn default() -> Self {
+        Self::Greedy
+    }
+}
+
+/// The reason a transaction wasn't admitted into the memory pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RejectionReason {
+    /// The transaction is blacklisted.
+    Blacklisted,
+    /// The transaction exceeds the maximum size, number of inputs or number of outputs.
+    ExceedsLimits,
+    /// The transaction spends one of its serial numbers more than once.
+    InternalDoubleSpend,
+    /// The transaction creates one of its commitments more than once.
+    InternalDuplicateCommitment,
+    /// The fee rate of the transaction is too low to be admitted under resource pressure.
+    InsufficientFee,
+    /// The transaction spends a serial number already spent in the ledger or by a memory pool transaction.
+    ConflictingSerialNumber,
+    /// The transaction creates a commitment already present in the ledger or in a memory pool transaction.
+    ConflictingCommitment,
+    /// The transaction has a memo already present in the ledger or in a memory pool transaction.
+    ConflictingMemo,
+    /// The memory pool is full and the transaction doesn't take precedence over any of its entries.
+    PoolFull,
+    /// The transaction conflicts with memory pool transactions it isn't allowed to replace.
+    ReplacementRejected,
+}
+
+impl fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Self::Blacklisted => "blacklisted",
+            Self::ExceedsLimits => "exceeds limits",
+            Self::InternalDoubleSpend => "internal double spend",
+            Self::InternalDuplicateCommitment => "internal duplicate commitment",
+            Self::InsufficientFee => "insufficient fee",
+            Self::ConflictingSerialNumber => "conflicting serial number",
+            Self::ConflictingCommitment => "conflicting commitment",
+            Self::ConflictingMemo => "conflicting memo",
+            Self::PoolFull => "memory pool full",
+            Self::ReplacementRejected => "replacement rejected",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+/// A transaction that wasn't admitted into the memory pool, along with the reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    pub transaction_id: Vec<u8>,
+    pub reason: RejectionReason,
+}
+
+/// The ids of the transactions in the memory pool at a point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemPoolSnapshot {
+    pub transaction_ids: HashSet<Vec<u8>>,
+}
+
+/// The changes to the memory pool since a snapshot was taken, with the transaction ids in ascending order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemPoolDiff {
+    /// The ids of the transactions that entered the memory pool.
+    pub added: Vec<Vec<u8>>,
+    /// The ids of the transactions that left the memory pool.
+    pub removed: Vec<Vec<u8>>,
+}
+
+/// Tightens the memory pool admission while the node is under resource pressure.
+#[derive(Clone)]
+pub struct AdmissionControl {
+    /// Returns the current resource pressure, e.g. the fraction of the available memory in use.
+    pub pressure: Arc<dyn Fn() -> f64 + Send + Sync>,
+    /// The pressure above which the raised fee rate floor applies.
+    pub pressure_threshold: f64,
+    /// The fee rate a transaction has to exceed in order to be admitted while under pressure.
+    pub min_fee_rate: f64,
+}
+
+impl AdmissionControl {
+    /// Returns `true` if a transaction with the given fee rate may be admitted at the current pressure.
+    pub fn admits(&self, fee_rate: f64) -> bool {
+        (self.pressure)() <= self.pressure_threshold || fee_rate > self.min_fee_rate
+    }
+}
+
+impl fmt::Debug for AdmissionControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdmissionControl")
+            .field("pressure_threshold", &self.pressure_threshold)
+            .field("min_fee_rate", &self.min_fee_rate)
+            .finish()
+    }
 }
 
 /// Stores transactions received by the server.
@@ -45,37 +250,286 @@ pub struct MemoryPool<T: TransactionScheme> {
     pub transactions: HashMap<Vec<u8>, Entry<T>>,
     /// The total size in bytes of the current memory pool.
     pub total_size_in_bytes: usize,
+    /// The maximum number of transactions in the memory pool.
+    pub max_transaction_count: usize,
+    /// The policy selecting the entry that is evicted when the memory pool is full.
+    pub eviction_policy: EvictionPolicy,
+    /// The rule ordering the entries of equal priority when they're selected for a block.
+    pub tie_breaker: TieBreaker,
+    /// The method used to select the entries included in a new block.
+    pub selection_mode: SelectionMode,
+    /// The maximum number of serial numbers (inputs) of a memory pool transaction.
+    pub max_transaction_inputs: usize,
+    /// The maximum number of commitments (outputs) of a memory pool transaction.
+    pub max_transaction_outputs: usize,
+    /// The maximum serialized size in bytes of a memory pool transaction.
+    pub max_transaction_size: usize,
+    /// The mapping of orphan transaction IDs to their corresponding transaction data. Orphan transactions
+    /// reference a ledger digest that is unknown to the local ledger, so they can't be verified yet.
+    pub orphans: HashMap<Vec<u8>, Entry<T>>,
+    /// The mapping of the ids of the transactions scheduled for a future block height to their transaction data.
+    pub scheduled: HashMap<Vec<u8>, Entry<T>>,
+    /// The optional admission control consulted before inserting new transactions.
+    pub admission_control: Option<AdmissionControl>,
+    /// The ids of the transactions that are never evicted to make room for other ones.
+    pub pinned: HashSet<Vec<u8>>,
+    /// The manual adjustments of the fees of the transactions that only affect the order they're selected for
+    /// a block in, set with `prioritise_transaction`.
+    pub fee_deltas: HashMap<Vec<u8>, i64>,
+    /// The ids of the transactions that are never admitted into the memory pool.
+    pub blacklist: HashSet<Vec<u8>>,
+    /// The mapping of the short ids of the transactions to their transaction ids, used to reconstruct compact blocks.
+    pub short_ids: HashMap<u64, Vec<u8>>,
+    /// The times the transactions were inserted into the memory pool at.
+    pub inserted_at: HashMap<Vec<u8>, Instant>,
+    /// The times the transactions were added to the orphan pool at.
+    pub orphaned_at: HashMap<Vec<u8>, Instant>,
+    /// The optional time after which unpinned transactions expire from the memory pool.
+    pub transaction_ttl: Option<Duration>,
+    /// The optional time after which the transactions whose ledger digest remains unknown are discarded from
+    /// the orphan pool.
+    pub orphan_ttl: Option<Duration>,
+    /// The most recent rejections of transactions, from the oldest to the newest one.
+    pub rejections: VecDeque<Rejection>,
+    /// The minimum time a transaction has to spend in the memory pool before it can be replaced by a conflicting one.
+    pub replacement_window: Duration,
+    /// The minimum percentage by which a replacement has to increase the fee rate of the transactions it replaces.
+    pub min_replacement_fee_increase: f64,
+    /// The source of the current time for the transaction expiries and replacements.
+    pub clock: Arc<dyn Clock>,
+    /// If `true`, the memory pool doesn't admit any transactions and isn't persisted, e.g. for the nodes that only
+    /// validate and relay blocks.
+    pub disabled: bool,
+    /// Indicates whether the memory pool is cleansed with `cleanse_with_block` whenever a block is committed;
+    /// if it isn't, only the transactions included in the block are removed from it.
+    pub auto_cleanse: bool,
+    /// Indicates whether the transactions changed since the memory pool was last stored.
+    dirty: bool,
+}
+
+/// Returns the short id of a transaction: the SipHash-2-4 of its id, used to identify it in a compact block.
+#[allow(deprecated)]
+pub fn short_id(transaction_id: &[u8]) -> u64 {
+    let mut hasher = SipHasher::new();
+    hasher.write(transaction_id);
+    hasher.finish()
 }
 
+/// Returns the lowest whole fee per byte that exceeds the given fee rate.
+fn fee_per_byte_above(fee_rate: f64) -> u64 {
+    if fee_rate < 0.0 { 0 } else { fee_rate.floor() as u64 + 1 }
+}
+
+/// The maximum number of transactions held in the orphan pool.
+pub const MAX_ORPHAN_TRANSACTIONS: usize = 100;
+
+/// The maximum number of transactions in the orphan pool that originate from a single peer.
+pub const MAX_ORPHAN_TRANSACTIONS_PER_PEER: usize = 10;
+
+/// The default maximum number of stored transactions reloaded into the memory pool.
+pub const MAX_RELOADED_TRANSACTIONS: usize = 10_000;
+
+/// The default maximum total size in bytes of the stored transactions reloaded into the memory pool.
+pub const MAX_RELOADED_BYTES: usize = 64 * 1024 * 1024;
+
+/// The maximum number of the most recent transaction rejections kept by the memory pool.
+pub const MAX_RECORDED_REJECTIONS: usize = 100;
+
+/// The maximum number of transactions held until the block height they're scheduled for.
+pub const MAX_SCHEDULED_TRANSACTIONS: usize = 100;
+
+/// The maximum number of memory pool transactions for which `SelectionMode::Optimal` searches for the most
+/// profitable block; the search is exponential in the worst case, so larger memory pools are packed greedily.
+pub const MAX_OPTIMAL_SELECTION_TRANSACTIONS: usize = 20;
+
+/// The default minimum time a transaction spends in the memory pool before it can be replaced.
+pub const DEFAULT_REPLACEMENT_WINDOW: Duration = Duration::from_secs(10);
+
+/// The default minimum percentage by which a replacement increases the fee rate of the transactions it replaces.
+pub const DEFAULT_MIN_REPLACEMENT_FEE_INCREASE: f64 = 10.0;
+
+/// The name of the counter of the submitted transactions that were already in the memory pool.
+pub const MEMPOOL_DUPLICATE_SUBMISSIONS: &str = "snarkos_mempool_duplicate_submissions_total";
+
+/// The ratio of the number of elements to the capacity of the memory pool collections below which they're shrunk
+/// by `MemoryPool::shrink_to_fit`.
+pub const COMPACTION_UTILIZATION_THRESHOLD: f64 = 0.25;
+
+/// The lower fee rate bounds of the buckets of the memory pool fee rate histogram.
+pub const FEE_RATE_HISTOGRAM_BUCKETS: [f64; 10] = [0.0, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0];
+
 const BLOCK_HEADER_SIZE: usize = BlockHeader::size();
 const COINBASE_TRANSACTION_SIZE: usize = 1490; // TODO Find the value for actual coinbase transaction size
 
-impl<T: TransactionScheme> MemoryPool<T> {
+impl<T: TransactionScheme<ValueBalance = AleoAmount>> MemoryPool<T> {
     /// Initialize a new memory pool with no transactions
     #[inline]
     pub fn new() -> Self {
         Self::default()
     }
 
-    /// Load the memory pool from previously stored state in storage
+    /// Initialize a new memory pool with no transactions that holds at most `max_transaction_count` of them.
+    #[inline]
+    pub fn with_max_transaction_count(max_transaction_count: usize) -> Self {
+        Self {
+            max_transaction_count,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the policy selecting the entry that is evicted when the memory pool is full.
+    #[inline]
+    pub fn with_eviction_policy(mut self, eviction_policy: EvictionPolicy) -> Self {
+        self.eviction_policy = eviction_policy;
+        self
+    }
+
+    /// Sets the rule ordering the entries of equal priority when they're selected for a block.
+    #[inline]
+    pub fn with_tie_breaker(mut self, tie_breaker: TieBreaker) -> Self {
+        self.tie_breaker = tie_breaker;
+        self
+    }
+
+    /// Sets the method used to select the entries included in a new block.
+    #[inline]
+    pub fn with_selection_mode(mut self, selection_mode: SelectionMode) -> Self {
+        self.selection_mode = selection_mode;
+        self
+    }
+
+    /// Sets the maximum numbers of inputs and outputs of the transactions admitted to the memory pool.
+    #[inline]
+    pub fn with_transaction_limits(mut self, max_inputs: usize, max_outputs: usize) -> Self {
+        self.max_transaction_inputs = max_inputs;
+        self.max_transaction_outputs = max_outputs;
+        self
+    }
+
+    /// Sets the maximum serialized size in bytes of the transactions admitted to the memory pool; it should be
+    /// below the maximum block size, as larger transactions could never be included in a block.
+    #[inline]
+    pub fn with_max_transaction_size(mut self, max_transaction_size: usize) -> Self {
+        self.max_transaction_size = max_transaction_size;
+        self
+    }
+
+    /// Sets the ids of the transactions that are never admitted into the memory pool.
+    #[inline]
+    pub fn with_blacklist<I: IntoIterator<Item = Vec<u8>>>(mut self, transaction_ids: I) -> Self {
+        self.blacklist = transaction_ids.into_iter().collect();
+        self
+    }
+
+    /// Sets the admission control consulted before inserting new transactions.
+    #[inline]
+    pub fn with_admission_control(mut self, admission_control: AdmissionControl) -> Self {
+        self.admission_control = Some(admission_control);
+        self
+    }
+
+    /// Sets the time after which unpinned transactions expire from the memory pool.
+    #[inline]
+    pub fn with_transaction_ttl(mut self, transaction_ttl: Duration) -> Self {
+        self.transaction_ttl = Some(transaction_ttl);
+        self
+    }
+
+    /// Sets the time after which the transactions whose ledger digest remains unknown are discarded from the
+    /// orphan pool.
+    #[inline]
+    pub fn with_orphan_ttl(mut self, orphan_ttl: Duration) -> Self {
+        self.orphan_ttl = Some(orphan_ttl);
+        self
+    }
+
+    /// Sets the minimum time a transaction has to spend in the memory pool before it can be replaced, and the
+    /// minimum percentage by which a replacement has to increase its fee rate.
+    #[inline]
+    pub fn with_replacement_policy(mut self, replacement_window: Duration, min_replacement_fee_increase: f64) -> Self {
+        self.replacement_window = replacement_window;
+        self.min_replacement_fee_increase = min_replacement_fee_increase;
+        self
+    }
+
+    /// Sets the source of the current time for the transaction expiries and replacements.
+    #[inline]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Sets whether the memory pool is disabled, i.e. it doesn't admit any transactions and isn't persisted.
+    #[inline]
+    pub fn with_disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    /// Sets whether the memory pool is cleansed with `cleanse_with_block` whenever a block is committed.
+    #[inline]
+    pub fn with_auto_cleanse(mut self, auto_cleanse: bool) -> Self {
+        self.auto_cleanse = auto_cleanse;
+        self
+    }
+
+    /// Load the memory pool from previously stored state in storage, reloading at most `MAX_RELOADED_TRANSACTIONS`
+    /// transactions of up to `MAX_RELOADED_BYTES` in total.
     pub fn from_storage<P: LoadableMerkleParameters, S: Storage>(
         storage: &Ledger<T, P, S>,
+    ) -> Result<Self, ConsensusError> {
+        Self::from_storage_with_limits(storage, MAX_RELOADED_TRANSACTIONS, MAX_RELOADED_BYTES)
+    }
+
+    /// Load the memory pool from previously stored state in storage, reloading at most `max_transactions`
+    /// transactions of up to `max_bytes` in total; the ones with the highest fee rates are kept.
+    pub fn from_storage_with_limits<P: LoadableMerkleParameters, S: Storage>(
+        storage: &Ledger<T, P, S>,
+        max_transactions: usize,
+        max_bytes: usize,
     ) -> Result<Self, ConsensusError> {
         let mut memory_pool = Self::new();
 
         if let Ok(Some(serialized_transactions)) = storage.get_memory_pool() {
             if let Ok(transaction_bytes) = DPCTransactions::<T>::read(&serialized_transactions[..]) {
-                for transaction in transaction_bytes.0 {
-                    let size = transaction.size();
-                    let entry = Entry {
+                let mut entries = transaction_bytes
+                    .0
+                    .into_iter()
+                    .map(|transaction| Entry {
+                        size_in_bytes: transaction.size(),
                         transaction,
-                        size_in_bytes: size,
-                    };
+                        origin: TxOrigin::Local,
+                        priority_class: PriorityClass::Standard,
+                        valid_from_height: None,
+                    })
+                    .collect::<Vec<_>>();
+                entries.sort_by(|a, b| b.cmp_priority(a));
+
+                let mut dropped_transactions = 0;
+                let mut dropped_bytes = 0;
+                for entry in entries {
+                    if memory_pool.transactions.len() >= max_transactions
+                        || memory_pool.total_size_in_bytes + entry.size_in_bytes > max_bytes
+                    {
+                        dropped_transactions += 1;
+                        dropped_bytes += entry.size_in_bytes;
+                        continue;
+                    }
                     memory_pool.insert(storage, entry)?;
                 }
+
+                if dropped_transactions != 0 {
+                    warn!(
+                        "Dropped {} stored memory pool transaction(s) ({} bytes) exceeding the reload limits",
+                        dropped_transactions, dropped_bytes
+                    );
+                }
             }
         }
 
+        // The loaded transactions are already stored.
+        memory_pool.dirty = false;
+
         Ok(memory_pool)
     }
 
@@ -85,6 +539,10 @@ impl<T: TransactionScheme> MemoryPool<T> {
         &self,
         storage: &Ledger<T, P, S>,
     ) -> Result<(), ConsensusError> {
+        if self.disabled {
+            return Ok(());
+        }
+
         let mut transactions = DPCTransactions::<T>::new();
 
         for (_transaction_id, entry) in self.transactions.iter() {
@@ -98,20 +556,199 @@ impl<T: TransactionScheme> MemoryPool<T> {
         Ok(())
     }
 
+    /// Store the memory pool state to the database if it changed since it was last stored;
+    /// returns `true` if it was stored.
+    pub fn store_if_dirty<P: LoadableMerkleParameters, S: Storage>(
+        &mut self,
+        storage: &Ledger<T, P, S>,
+    ) -> Result<bool, ConsensusError> {
+        if !self.dirty {
+            return Ok(false);
+        }
+
+        self.store(storage)?;
+        self.dirty = false;
+
+        Ok(true)
+    }
+
+    /// Returns `true` if the transactions changed since the memory pool was last stored.
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Exports the memory pool transactions as newline-delimited hex, ordered by transaction id.
+    pub fn export_hex(&self) -> Result<String, ConsensusError> {
+        let mut entries = self.transactions.iter().collect::<Vec<_>>();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut lines = Vec::with_capacity(entries.len());
+        for (_transaction_id, entry) in entries {
+            lines.push(hex::encode(to_bytes![entry.transaction]?));
+        }
+
+        Ok(lines.join("\n"))
+    }
+
+    /// Imports newline-delimited hex transactions produced by `export_hex` into the memory pool.
+    /// Each transaction is subject to the usual acceptance rules; returns the ids of the accepted ones.
+    pub fn import_hex<P: LoadableMerkleParameters, S: Storage>(
+        &mut self,
+        storage: &Ledger<T, P, S>,
+        hex_transactions: &str,
+    ) -> Result<Vec<Vec<u8>>, ConsensusError> {
+        let mut accepted = vec![];
+
+        for line in hex_transactions.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let transaction_bytes = hex::decode(line)?;
+            let entry = Entry {
+                size_in_bytes: transaction_bytes.len(),
+                transaction: T::read(&transaction_bytes[..])?,
+                origin: TxOrigin::Local,
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
+            };
+
+            if let Some(transaction_id) = self.insert(storage, entry)? {
+                accepted.push(transaction_id);
+            }
+        }
+
+        Ok(accepted)
+    }
+
+    /// Checks that the transaction neither spends any of its records twice nor creates any commitment twice;
+    /// the check is cheap, so it can precede the verification of the transaction.
+    pub fn check_internal_duplicates(transaction: &T) -> Result<(), ConsensusError> {
+        if has_duplicates(transaction.old_serial_numbers()) {
+            let transaction_id = transaction.transaction_id()?;
+            return Err(ConsensusError::InternalDoubleSpend(hex::encode(transaction_id)));
+        }
+
+        if has_duplicates(transaction.new_commitments()) {
+            let transaction_id = transaction.transaction_id()?;
+            return Err(ConsensusError::InternalDuplicateCommitment(hex::encode(transaction_id)));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the entry isn't blacklisted and that it doesn't exceed the size, input and output limits
+    /// of the memory pool, recording the rejection if it does.
+    fn check_limits(&mut self, entry: &Entry<T>) -> Result<(), ConsensusError> {
+        if let Err(error) = self.check_transaction_limits(&entry.transaction, entry.size_in_bytes) {
+            let reason = match error {
+                ConsensusError::BlacklistedTransaction(_) => RejectionReason::Blacklisted,
+                _ => RejectionReason::ExceedsLimits,
+            };
+            self.record_rejection(entry, reason);
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the given transaction, serialized in `size_in_bytes` bytes, isn't blacklisted and that it
+    /// doesn't exceed the size, input and output limits of the memory pool. This is cheap, so it's meant to be
+    /// called before the transaction is verified; `insert` applies it as well.
+    pub fn check_transaction_limits(&self, transaction: &T, size_in_bytes: usize) -> Result<(), ConsensusError> {
+        if !self.blacklist.is_empty() {
+            let transaction_id = transaction.transaction_id()?;
+            if self.blacklist.contains(&transaction_id[..]) {
+                return Err(ConsensusError::BlacklistedTransaction(hex::encode(transaction_id)));
+            }
+        }
+
+        if size_in_bytes > self.max_transaction_size {
+            return Err(ConsensusError::TransactionTooLarge(
+                size_in_bytes,
+                self.max_transaction_size,
+            ));
+        }
+
+        let inputs = transaction.old_serial_numbers().len();
+        if inputs > self.max_transaction_inputs {
+            return Err(ConsensusError::TooManyTransactionInputs(
+                inputs,
+                self.max_transaction_inputs,
+            ));
+        }
+
+        let outputs = transaction.new_commitments().len();
+        if outputs > self.max_transaction_outputs {
+            return Err(ConsensusError::TooManyTransactionOutputs(
+                outputs,
+                self.max_transaction_outputs,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the admission control admits the entry at the current resource pressure, recording the
+    /// rejection if it doesn't.
+    fn check_admission(&mut self, entry: &Entry<T>) -> bool {
+        if let Some(ref admission_control) = self.admission_control {
+            if entry.priority_class != PriorityClass::Free && !admission_control.admits(entry.fee_rate()) {
+                debug!("Rejecting a low-fee transaction due to resource pressure");
+                self.record_rejection(entry, RejectionReason::InsufficientFee);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Works like `check_internal_duplicates`, but also records the rejection of the entry.
+    fn check_entry_duplicates(&mut self, entry: &Entry<T>) -> Result<(), ConsensusError> {
+        if let Err(error) = Self::check_internal_duplicates(&entry.transaction) {
+            match error {
+                ConsensusError::InternalDoubleSpend(_) => {
+                    self.record_rejection(entry, RejectionReason::InternalDoubleSpend)
+                }
+                ConsensusError::InternalDuplicateCommitment(_) => {
+                    self.record_rejection(entry, RejectionReason::InternalDuplicateCommitment)
+                }
+                _ => {}
+            }
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
     /// Adds entry to memory pool if valid in the current ledger.
     pub fn insert<P: LoadableMerkleParameters, S: Storage>(
         &mut self,
         storage: &Ledger<T, P, S>,
         entry: Entry<T>,
     ) -> Result<Option<Vec<u8>>, ConsensusError> {
+        if self.disabled {
+            return Err(ConsensusError::MemoryPoolDisabled);
+        }
+
         let transaction_serial_numbers = entry.transaction.old_serial_numbers();
         let transaction_commitments = entry.transaction.new_commitments();
         let transaction_memo = entry.transaction.memorandum();
 
-        if has_duplicates(transaction_serial_numbers)
-            || has_duplicates(transaction_commitments)
-            || self.contains(&entry)
-        {
+        self.check_limits(&entry)?;
+
+        if self.contains(&entry) {
+            metrics::increment_counter!(MEMPOOL_DUPLICATE_SUBMISSIONS);
+            return Ok(None);
+        }
+
+        self.check_entry_duplicates(&entry)?;
+
+        // Hold the transactions scheduled for a future block height until the chain reaches it.
+        if let Some(valid_from_height) = entry.valid_from_height {
+            if valid_from_height > storage.get_current_block_height() {
+                return self.insert_scheduled(entry);
+            }
+        }
+
+        if !self.check_admission(&entry) {
             return Ok(None);
         }
 
@@ -127,282 +764,3130 @@ impl<T: TransactionScheme> MemoryPool<T> {
 
         for sn in transaction_serial_numbers {
             if storage.contains_sn(sn) || holding_serial_numbers.contains(&sn) {
+                self.record_rejection(&entry, RejectionReason::ConflictingSerialNumber);
                 return Ok(None);
             }
         }
 
         for cm in transaction_commitments {
             if storage.contains_cm(cm) || holding_commitments.contains(&cm) {
+                self.record_rejection(&entry, RejectionReason::ConflictingCommitment);
                 return Ok(None);
             }
         }
 
         if storage.contains_memo(transaction_memo) || holding_memos.contains(&transaction_memo) {
+            self.record_rejection(&entry, RejectionReason::ConflictingMemo);
             return Ok(None);
         }
 
         let transaction_id = entry.transaction.transaction_id()?.to_vec();
 
+        // Make room for the new entry by evicting the one selected by the eviction policy, unless the new entry
+        // would be evicted first; pinned entries are never evicted, while a pinned new entry takes precedence
+        // over any unpinned one.
+        if self.transactions.len() >= self.max_transaction_count {
+            let is_pinned = self.pinned.contains(&transaction_id);
+            match self.eviction_candidate() {
+                Some((evicted_id, evicted)) if is_pinned || self.is_evicted_for(evicted, &entry) => {
+                    let evicted_id = evicted_id.clone();
+                    self.remove_by_hash(&evicted_id)?;
+                }
+                _ => {
+                    self.record_rejection(&entry, RejectionReason::PoolFull);
+                    return Ok(None);
+                }
+            }
+        }
+
         self.total_size_in_bytes += entry.size_in_bytes;
+        self.short_ids.insert(short_id(&transaction_id), transaction_id.clone());
+        self.inserted_at.insert(transaction_id.clone(), self.clock.now());
         self.transactions.insert(transaction_id.clone(), entry);
+        self.dirty = true;
 
         Ok(Some(transaction_id))
     }
 
-    /// Cleanse the memory pool of outdated transactions.
-    #[inline]
-    pub fn cleanse<P: LoadableMerkleParameters, S: Storage>(
+    /// Adds entry to memory pool if valid in the current ledger, replacing the memory pool transactions that spend
+    /// any of its serial numbers, provided that they've spent at least `replacement_window` in the memory pool and
+    /// the entry increases their fee rates by at least `min_replacement_fee_increase` percent.
+    pub fn insert_with_rbf<P: LoadableMerkleParameters, S: Storage>(
         &mut self,
         storage: &Ledger<T, P, S>,
-    ) -> Result<(), ConsensusError> {
-        let mut new_memory_pool = Self::new();
+        entry: Entry<T>,
+    ) -> Result<Option<Vec<u8>>, ConsensusError> {
+        self.replace_by_fee(storage, entry)
+            .map(|inserted| inserted.map(|(transaction_id, _)| transaction_id))
+    }
 
-        for (_, entry) in self.clone().transactions.iter() {
-            new_memory_pool.insert(&storage, entry.clone())?;
+    /// Works like `insert_with_rbf`, but also returns the ids of the memory pool transactions the entry replaced.
+    pub fn replace_by_fee<P: LoadableMerkleParameters, S: Storage>(
+        &mut self,
+        storage: &Ledger<T, P, S>,
+        entry: Entry<T>,
+    ) -> Result<Option<(Vec<u8>, Vec<Vec<u8>>)>, ConsensusError> {
+        if self.contains(&entry) {
+            return self
+                .insert(storage, entry)
+                .map(|inserted| inserted.map(|transaction_id| (transaction_id, vec![])));
         }
 
-        self.total_size_in_bytes = new_memory_pool.total_size_in_bytes;
-        self.transactions = new_memory_pool.transactions;
+        // Run the cheap checks of `insert` before removing any of the transactions the entry conflicts with.
+        if self.disabled {
+            return Err(ConsensusError::MemoryPoolDisabled);
+        }
+        self.check_limits(&entry)?;
+        self.check_entry_duplicates(&entry)?;
+        let is_scheduled = entry.valid_from_height.map_or(false, |valid_from_height| {
+            valid_from_height > storage.get_current_block_height()
+        });
+        if !is_scheduled && !self.check_admission(&entry) {
+            return Ok(None);
+        }
 
-        Ok(())
-    }
+        let transaction_serial_numbers = entry.transaction.old_serial_numbers();
+        let conflicting = self
+            .transactions
+            .iter()
+            .filter(|(_, pooled)| {
+                pooled
+                    .transaction
+                    .old_serial_numbers()
+                    .iter()
+                    .any(|sn| transaction_serial_numbers.contains(sn))
+            })
+            .map(|(transaction_id, _)| transaction_id.clone())
+            .collect::<Vec<_>>();
+
+        let now = self.clock.now();
+        if let Some(transaction_id) = conflicting.iter().find(|id| !self.is_replaceable(id, &entry, now)) {
+            debug!(
+                "Rejecting a replacement of the memory pool transaction {}",
+                hex::encode(transaction_id)
+            );
+            self.record_rejection(&entry, RejectionReason::ReplacementRejected);
+            return Ok(None);
+        }
 
-    /// Removes transaction from memory pool or error.
-    #[inline]
-    pub fn remove(&mut self, entry: &Entry<T>) -> Result<Option<Vec<u8>>, ConsensusError> {
-        if self.contains(entry) {
-            self.total_size_in_bytes -= entry.size_in_bytes;
+        let mut replaced = Vec::with_capacity(conflicting.len());
+        for transaction_id in conflicting {
+            let inserted_at = self.inserted_at.get(&transaction_id).copied().unwrap_or(now);
+            let is_pinned = self.pinned.contains(&transaction_id);
+            let fee_delta = self.fee_deltas.get(&transaction_id).copied();
+            if let Some(replaced_entry) = self.remove_by_hash(&transaction_id)? {
+                replaced.push((transaction_id, replaced_entry, inserted_at, is_pinned, fee_delta));
+            }
+        }
 
-            let transaction_id = entry.transaction.transaction_id()?.to_vec();
+        let result = self.insert(storage, entry);
 
-            self.transactions.remove(&transaction_id);
+        // The replaced transactions are restored if the replacement wasn't accepted after all.
+        if !matches!(result, Ok(Some(_))) {
+            for (transaction_id, replaced_entry, inserted_at, is_pinned, fee_delta) in replaced {
+                self.total_size_in_bytes += replaced_entry.size_in_bytes;
+                self.short_ids.insert(short_id(&transaction_id), transaction_id.clone());
+                self.inserted_at.insert(transaction_id.clone(), inserted_at);
+                if is_pinned {
+                    self.pinned.insert(transaction_id.clone());
+                }
+                if let Some(fee_delta) = fee_delta {
+                    self.fee_deltas.insert(transaction_id.clone(), fee_delta);
+                }
+                self.transactions.insert(transaction_id, replaced_entry);
+            }
 
-            return Ok(Some(transaction_id));
+            return result.map(|_| None);
         }
 
-        Ok(None)
-    }
+        let replaced_ids = replaced
+            .into_iter()
+            .map(|(transaction_id, _, _, _, _)| transaction_id)
+            .collect();
 
-    /// Removes transaction from memory pool based on the transaction id.
-    #[inline]
-    pub fn remove_by_hash(&mut self, transaction_id: &[u8]) -> Result<Option<Entry<T>>, ConsensusError> {
-        match self.transactions.clone().get(transaction_id) {
-            Some(entry) => {
-                self.total_size_in_bytes -= entry.size_in_bytes;
-                self.transactions.remove(transaction_id);
+        result.map(|inserted| inserted.map(|transaction_id| (transaction_id, replaced_ids)))
+    }
 
-                Ok(Some(entry.clone()))
+    /// Records the rejection of the given entry for the given reason, forgetting the oldest recorded rejection if
+    /// there are too many of them.
+    fn record_rejection(&mut self, entry: &Entry<T>, reason: RejectionReason) {
+        if let Ok(transaction_id) = entry.transaction.transaction_id() {
+            if self.rejections.len() >= MAX_RECORDED_REJECTIONS {
+                self.rejections.pop_front();
             }
-            None => Ok(None),
+            self.rejections.push_back(Rejection {
+                transaction_id: transaction_id.to_vec(),
+                reason,
+            });
         }
     }
 
-    /// Returns whether or not the memory pool contains the entry.
-    #[inline]
-    pub fn contains(&self, entry: &Entry<T>) -> bool {
-        match &entry.transaction.transaction_id() {
-            Ok(transaction_id) => self.transactions.contains_key(&transaction_id.to_vec()),
-            Err(_) => false,
+    /// Returns `true` if the memory pool transaction with the given id may be replaced by the given entry.
+    fn is_replaceable(&self, transaction_id: &[u8], replacement: &Entry<T>, now: Instant) -> bool {
+        let pooled = match self.transactions.get(transaction_id) {
+            Some(pooled) => pooled,
+            None => return true,
+        };
+
+        if self.pinned.contains(transaction_id) {
+            return false;
         }
+
+        let time_in_pool = self
+            .inserted_at
+            .get(transaction_id)
+            .map(|inserted_at| now.saturating_duration_since(*inserted_at))
+            .unwrap_or_default();
+        let min_fee_rate = pooled.fee_rate() * (1.0 + self.min_replacement_fee_increase / 100.0);
+
+        time_in_pool >= self.replacement_window
+            && replacement.fee_rate() > pooled.fee_rate()
+            && replacement.fee_rate() >= min_fee_rate
     }
 
-    /// Get candidate transactions for a new block.
-    pub fn get_candidates<P: LoadableMerkleParameters, S: Storage>(
-        &self,
-        storage: &Ledger<T, P, S>,
-        max_size: usize,
+    /// Adds an entry to the orphan pool, unless it's already known or it fails the checks that don't depend on
+    /// the ledger. If the orphan pool is full even after the expired orphans are discarded, or the peer the entry
+    /// originates from already has `MAX_ORPHAN_TRANSACTIONS_PER_PEER` orphans, the oldest of those is evicted.
+    pub fn insert_orphan(&mut self, entry: Entry<T>) -> Result<Option<Vec<u8>>, ConsensusError> {
+        if self.disabled {
+            return Err(ConsensusError::MemoryPoolDisabled);
+        }
+
+        self.check_limits(&entry)?;
+
+        let transaction_id = entry.transaction.transaction_id()?.to_vec();
+
+        if self.transactions.contains_key(&transaction_id) || self.orphans.contains_key(&transaction_id) {
+            return Ok(None);
+        }
+
+        self.check_entry_duplicates(&entry)?;
+
+        if self.orphans.len() >= MAX_ORPHAN_TRANSACTIONS {
+            self.expire_orphans();
+        }
+
+        let peer_orphans = match entry.origin {
+            TxOrigin::Peer(_) => self
+                .orphans
+                .values()
+                .filter(|orphan| orphan.origin == entry.origin)
+                .count(),
+            _ => 0,
+        };
+
+        let evicted_id = if peer_orphans >= MAX_ORPHAN_TRANSACTIONS_PER_PEER {
+            self.oldest_orphan(|orphan| orphan.origin == entry.origin)
+        } else if self.orphans.len() >= MAX_ORPHAN_TRANSACTIONS {
+            self.oldest_orphan(|_| true)
+        } else {
+            None
+        };
+
+        if let Some(evicted_id) = evicted_id {
+            debug!("Evicting the orphan transaction {}", hex::encode(&evicted_id));
+            self.orphaned_at.remove(&evicted_id);
+            self.orphans.remove(&evicted_id);
+        }
+
+        self.orphaned_at.insert(transaction_id.clone(), self.clock.now());
+        self.orphans.insert(transaction_id.clone(), entry);
+
+        Ok(Some(transaction_id))
+    }
+
+    /// Returns the id of the orphan that satisfies the given predicate and has been in the orphan pool the longest.
+    fn oldest_orphan<F: Fn(&Entry<T>) -> bool>(&self, predicate: F) -> Option<Vec<u8>> {
+        self.orphans
+            .iter()
+            .filter(|(_, orphan)| predicate(orphan))
+            .min_by_key(|(transaction_id, _)| self.orphaned_at.get(*transaction_id))
+            .map(|(transaction_id, _)| transaction_id.clone())
+    }
+
+    /// Holds an entry until the block height it's scheduled for, unless it's already known or there are
+    /// too many scheduled entries.
+    fn insert_scheduled(&mut self, entry: Entry<T>) -> Result<Option<Vec<u8>>, ConsensusError> {
+        let transaction_id = entry.transaction.transaction_id()?.to_vec();
+
+        if self.transactions.contains_key(&transaction_id)
+            || self.scheduled.contains_key(&transaction_id)
+            || self.scheduled.len() >= MAX_SCHEDULED_TRANSACTIONS
+        {
+            return Ok(None);
+        }
+
+        self.scheduled.insert(transaction_id.clone(), entry);
+
+        Ok(Some(transaction_id))
+    }
+
+    /// Moves the scheduled entries whose block height the chain has reached into the memory pool, subject
+    /// to the usual acceptance rules; returns the ids of the accepted ones, while the rejected ones are dropped.
+    pub fn promote_scheduled<P: LoadableMerkleParameters, S: Storage>(
+        &mut self,
+        storage: &Ledger<T, P, S>,
+    ) -> Vec<Vec<u8>> {
+        let current_block_height = storage.get_current_block_height();
+
+        let due = self
+            .scheduled
+            .iter()
+            .filter(|(_, entry)| entry.valid_from_height.unwrap_or(0) <= current_block_height)
+            .map(|(transaction_id, _)| transaction_id.clone())
+            .collect::<Vec<_>>();
+
+        let mut promoted = vec![];
+        for transaction_id in due {
+            if let Some(entry) = self.scheduled.remove(&transaction_id) {
+                // A rejected entry doesn't prevent the others from being promoted.
+                match self.insert(storage, entry) {
+                    Ok(Some(transaction_id)) => promoted.push(transaction_id),
+                    Ok(None) => {}
+                    Err(error) => debug!("Dropping a scheduled transaction: {}", error),
+                }
+            }
+        }
+
+        promoted
+    }
+
+    /// Removes all the entries from the orphan pool and returns them.
+    pub fn take_orphans(&mut self) -> Vec<Entry<T>> {
+        self.take_orphans_if(|_| true)
+    }
+
+    /// Removes the entries that satisfy the given predicate from the orphan pool and returns them.
+    pub fn take_orphans_if<F: FnMut(&Entry<T>) -> bool>(&mut self, mut predicate: F) -> Vec<Entry<T>> {
+        let taken_ids = self
+            .orphans
+            .iter()
+            .filter(|(_, entry)| predicate(entry))
+            .map(|(transaction_id, _)| transaction_id.clone())
+            .collect::<Vec<_>>();
+
+        taken_ids
+            .into_iter()
+            .filter_map(|transaction_id| {
+                self.orphaned_at.remove(&transaction_id);
+                self.orphans.remove(&transaction_id)
+            })
+            .collect()
+    }
+
+    /// Discards the entries that have been in the orphan pool for longer than its orphan TTL; returns the ids
+    /// of the discarded transactions.
+    pub fn expire_orphans(&mut self) -> Vec<Vec<u8>> {
+        let orphan_ttl = match self.orphan_ttl {
+            Some(orphan_ttl) => orphan_ttl,
+            None => return vec![],
+        };
+        let now = self.clock.now();
+
+        let expired = self
+            .orphaned_at
+            .iter()
+            .filter(|(_, orphaned_at)| now.saturating_duration_since(**orphaned_at) > orphan_ttl)
+            .map(|(transaction_id, _)| transaction_id.clone())
+            .collect::<Vec<_>>();
+
+        for transaction_id in &expired {
+            self.orphaned_at.remove(transaction_id);
+            self.orphans.remove(transaction_id);
+        }
+
+        expired
+    }
+
+    /// Returns the unpinned entry selected by the eviction policy along with its transaction id.
+    fn eviction_candidate(&self) -> Option<(&Vec<u8>, &Entry<T>)> {
+        let unpinned = self
+            .transactions
+            .iter()
+            .filter(|(transaction_id, _)| !self.pinned.contains(*transaction_id));
+
+        match self.eviction_policy {
+            EvictionPolicy::LowestFeeRate => unpinned.min_by(|(_, a), (_, b)| a.cmp_priority(b)),
+            EvictionPolicy::Oldest => unpinned.min_by(|(id_a, a), (id_b, b)| {
+                a.priority_class
+                    .cmp(&b.priority_class)
+                    .then_with(|| self.inserted_at.get(*id_a).cmp(&self.inserted_at.get(*id_b)))
+            }),
+            EvictionPolicy::Largest => unpinned.min_by(|(_, a), (_, b)| {
+                a.priority_class
+                    .cmp(&b.priority_class)
+                    .then_with(|| b.size_in_bytes.cmp(&a.size_in_bytes))
+            }),
+        }
+    }
+
+    /// Returns `true` if the given eviction candidate may be evicted to make room for the new entry.
+    fn is_evicted_for(&self, evicted: &Entry<T>, entry: &Entry<T>) -> bool {
+        match self.eviction_policy {
+            EvictionPolicy::LowestFeeRate => evicted.cmp_priority(entry) == Ordering::Less,
+            // The candidate is selected regardless of its fee rate, so the new entry has to pay at least as much.
+            EvictionPolicy::Oldest | EvictionPolicy::Largest => {
+                evicted.priority_class <= entry.priority_class && entry.fee_rate() >= evicted.fee_rate()
+            }
+        }
+    }
+
+    /// Returns the minimum fee per byte a standard transaction currently needs to pay in order to enter
+    /// the memory pool, i.e. the lowest whole fee per byte that exceeds the raised fee rate floor under
+    /// resource pressure and, if the memory pool is full, that evicts the entry that would be evicted;
+    /// returns `u64::MAX` if no entry can be evicted.
+    pub fn current_min_fee_per_byte(&self) -> u64 {
+        let mut min_fee_per_byte = 0;
+
+        if let Some(ref admission_control) = self.admission_control {
+            if (admission_control.pressure)() > admission_control.pressure_threshold {
+                min_fee_per_byte = fee_per_byte_above(admission_control.min_fee_rate);
+            }
+        }
+
+        if self.transactions.len() >= self.max_transaction_count {
+            match self.eviction_candidate() {
+                Some((_, lowest)) if lowest.priority_class == PriorityClass::Free => {}
+                Some((_, lowest)) => {
+                    let lowest_fee_rate = lowest.fee_rate();
+                    let eviction_fee_per_byte = match self.eviction_policy {
+                        EvictionPolicy::LowestFeeRate => fee_per_byte_above(lowest_fee_rate),
+                        // Like in `is_evicted_for`, paying the candidate's fee rate is enough.
+                        EvictionPolicy::Oldest | EvictionPolicy::Largest => lowest_fee_rate.max(0.0).ceil() as u64,
+                    };
+                    min_fee_per_byte = min_fee_per_byte.max(eviction_fee_per_byte);
+                }
+                None => return u64::MAX,
+            }
+        }
+
+        min_fee_per_byte
+    }
+
+    /// Compares two memory pool entries along with their transaction ids in the order they're selected for a block
+    /// in, i.e. from the highest priority to the lowest, then by the tie-breaker and finally by the transaction ids.
+    fn cmp_selection_order(&self, (id_a, a): (&Vec<u8>, &Entry<T>), (id_b, b): (&Vec<u8>, &Entry<T>)) -> Ordering {
+        b.priority_class
+            .cmp(&a.priority_class)
+            .then_with(|| {
+                self.effective_fee_rate(id_b, b)
+                    .partial_cmp(&self.effective_fee_rate(id_a, a))
+                    .unwrap_or(Ordering::Equal)
+            })
+            .then_with(|| match self.tie_breaker {
+                TieBreaker::Oldest => self.inserted_at.get(id_a).cmp(&self.inserted_at.get(id_b)),
+                TieBreaker::Smallest => a.size_in_bytes.cmp(&b.size_in_bytes),
+                TieBreaker::TransactionId => Ordering::Equal,
+            })
+            .then_with(|| id_a.cmp(id_b))
+    }
+
+    /// Returns the fee rate of the given memory pool entry that it's selected for a block by, i.e. including the
+    /// manual adjustment of its fee set with `prioritise_transaction`.
+    pub fn effective_fee_rate(&self, transaction_id: &[u8], entry: &Entry<T>) -> f64 {
+        let fee_delta = self.fee_deltas.get(transaction_id).copied().unwrap_or(0);
+
+        entry.transaction.value_balance().0.saturating_add(fee_delta) as f64 / entry.size_in_bytes.max(1) as f64
+    }
+
+    /// Adjusts the fee of the transaction with the given id by the given delta when selecting the transactions
+    /// for a block, without changing the fee it actually pays; the adjustments accumulate until it's removed from
+    /// the memory pool. Returns `false` if it isn't in the memory pool.
+    pub fn prioritise_transaction(&mut self, transaction_id: &[u8], delta: i64) -> bool {
+        if !self.transactions.contains_key(transaction_id) {
+            return false;
+        }
+        let fee_delta = self.fee_deltas.entry(transaction_id.to_vec()).or_insert(0);
+        *fee_delta = fee_delta.saturating_add(delta);
+        true
+    }
+
+    /// Returns the memory pool entries along with their transaction ids, from the highest priority to the lowest;
+    /// entries of equal priority are ordered by the tie-breaker.
+    pub fn iter_by_fee(&self) -> impl Iterator<Item = (&Vec<u8>, &Entry<T>)> {
+        let mut entries = self.transactions.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| self.cmp_selection_order(*a, *b));
+        entries.into_iter()
+    }
+
+    /// Returns the `n` memory pool entries with the highest priority, from the highest to the lowest; entries of
+    /// equal priority are ordered by the tie-breaker. Unlike `iter_by_fee`, it doesn't sort the whole pool.
+    pub fn top_n_by_fee(&self, n: usize) -> Vec<&Entry<T>> {
+        if n == 0 {
+            return vec![];
+        }
+
+        // A bounded heap whose top is the lowest-priority entry kept so far.
+        let mut top = BinaryHeap::with_capacity(n.min(self.transactions.len()) + 1);
+        for (transaction_id, entry) in &self.transactions {
+            top.push(BySelectionOrder(self, transaction_id, entry));
+            if top.len() > n {
+                top.pop();
+            }
+        }
+
+        top.into_sorted_vec()
+            .into_iter()
+            .map(|BySelectionOrder(_, _, entry)| entry)
+            .collect()
+    }
+
+    /// Pins the transaction with the given id, so that it's never evicted to make room for other transactions;
+    /// it stays pinned until it's removed from the memory pool. Returns `false` if it isn't in the memory pool.
+    pub fn pin(&mut self, transaction_id: &[u8]) -> bool {
+        if !self.transactions.contains_key(transaction_id) {
+            return false;
+        }
+        self.pinned.insert(transaction_id.to_vec());
+        true
+    }
+
+    /// Unpins the transaction with the given id. Returns `false` if it wasn't pinned.
+    pub fn unpin(&mut self, transaction_id: &[u8]) -> bool {
+        self.pinned.remove(transaction_id)
+    }
+
+    /// Returns `true` if the transaction with the given id is pinned.
+    pub fn is_pinned(&self, transaction_id: &[u8]) -> bool {
+        self.pinned.contains(transaction_id)
+    }
+
+    /// Blacklists the transaction with the given id, so that it's never admitted into the memory pool, and
+    /// removes it if it's already there. Returns `false` if it was already blacklisted.
+    pub fn blacklist(&mut self, transaction_id: &[u8]) -> Result<bool, ConsensusError> {
+        self.remove_by_hash(transaction_id)?;
+        self.orphans.remove(transaction_id);
+        self.orphaned_at.remove(transaction_id);
+        self.scheduled.remove(transaction_id);
+
+        Ok(self.blacklist.insert(transaction_id.to_vec()))
+    }
+
+    /// Removes the transaction with the given id from the blacklist. Returns `false` if it wasn't blacklisted.
+    pub fn unblacklist(&mut self, transaction_id: &[u8]) -> bool {
+        self.blacklist.remove(transaction_id)
+    }
+
+    /// Returns `true` if the transaction with the given id is blacklisted.
+    pub fn is_blacklisted(&self, transaction_id: &[u8]) -> bool {
+        self.blacklist.contains(transaction_id)
+    }
+
+    /// Returns the total size in bytes of the transactions in each of the given fee rate buckets, described
+    /// by their ascending lower bounds. Transactions below the first bound are counted into the first bucket.
+    pub fn fee_rate_histogram(&self, bucket_bounds: &[f64]) -> Vec<(f64, usize)> {
+        let mut histogram = bucket_bounds.iter().map(|bound| (*bound, 0)).collect::<Vec<_>>();
+
+        for entry in self.transactions.values() {
+            let fee_rate = entry.fee_rate();
+            let bucket = bucket_bounds.iter().take_while(|bound| **bound <= fee_rate).count();
+
+            if let Some((_, size_in_bytes)) = histogram.get_mut(bucket.saturating_sub(1)) {
+                *size_in_bytes += entry.size_in_bytes;
+            }
+        }
+
+        histogram
+    }
+
+    /// Returns the median fee rate of the standard transactions in the memory pool, along with their number;
+    /// returns `None` if there are none.
+    pub fn median_fee_rate(&self) -> Option<(f64, usize)> {
+        let mut fee_rates = self
+            .transactions
+            .values()
+            .filter(|entry| entry.priority_class == PriorityClass::Standard)
+            .map(|entry| entry.fee_rate())
+            .collect::<Vec<_>>();
+        fee_rates.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        let count = fee_rates.len();
+        let median = match count {
+            0 => return None,
+            _ if count % 2 == 0 => (fee_rates[count / 2 - 1] + fee_rates[count / 2]) / 2.0,
+            _ => fee_rates[count / 2],
+        };
+
+        Some((median, count))
+    }
+
+    /// Returns a snapshot of the ids of the transactions currently in the memory pool.
+    pub fn snapshot(&self) -> MemPoolSnapshot {
+        MemPoolSnapshot {
+            transaction_ids: self.transactions.keys().cloned().collect(),
+        }
+    }
+
+    /// Returns the transactions added to and removed from the memory pool since the given snapshot was taken.
+    /// A transaction that left and re-entered the memory pool in the meantime isn't reported.
+    pub fn diff(&self, previous: &MemPoolSnapshot) -> MemPoolDiff {
+        let mut added = self
+            .transactions
+            .keys()
+            .filter(|transaction_id| !previous.transaction_ids.contains(*transaction_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut removed = previous
+            .transaction_ids
+            .iter()
+            .filter(|transaction_id| !self.transactions.contains_key(*transaction_id))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        added.sort_unstable();
+        removed.sort_unstable();
+
+        MemPoolDiff { added, removed }
+    }
+
+    /// Cleanse the memory pool of outdated transactions.
+    #[inline]
+    pub fn cleanse<P: LoadableMerkleParameters, S: Storage>(
+        &mut self,
+        storage: &Ledger<T, P, S>,
+    ) -> Result<(), ConsensusError> {
+        let mut new_memory_pool = Self::with_max_transaction_count(self.max_transaction_count);
+        new_memory_pool.pinned = self.pinned.clone();
+
+        for (_, entry) in self.clone().transactions.iter() {
+            new_memory_pool.insert(&storage, entry.clone())?;
+        }
+
+        // The outdated transactions are removed one by one, so that the auxiliary indexes stay consistent.
+        let outdated_transaction_ids = self
+            .transactions
+            .keys()
+            .filter(|transaction_id| !new_memory_pool.transactions.contains_key(*transaction_id))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for transaction_id in &outdated_transaction_ids {
+            self.remove_by_hash(transaction_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the transactions included in a newly committed block, along with the ones conflicting with it,
+    /// i.e. spending the same serial numbers or reusing its commitments or memos, and returns their ids.
+    pub fn cleanse_with_block(&mut self, transactions: &DPCTransactions<T>) -> Result<Vec<Vec<u8>>, ConsensusError> {
+        let invalid_transaction_ids = self
+            .transactions
+            .iter()
+            .filter(|(_, entry)| transactions.conflicts(&entry.transaction))
+            .map(|(transaction_id, _)| transaction_id.clone())
+            .collect::<Vec<_>>();
+
+        for transaction_id in &invalid_transaction_ids {
+            self.remove_by_hash(transaction_id)?;
+        }
+
+        Ok(invalid_transaction_ids)
+    }
+
+    /// Removes the transactions that are no longer valid as of the given block height, i.e. the ones conflicting
+    /// with the ledger state at that height, and returns their ids. It's meant to be called once after a batch
+    /// of blocks has been applied, instead of cleansing the memory pool after every block.
+    pub fn trim_to_height<P: LoadableMerkleParameters, S: Storage>(
+        &mut self,
+        storage: &Ledger<T, P, S>,
+        height: u32,
+    ) -> Result<Vec<Vec<u8>>, ConsensusError> {
+        let current_height = storage.get_current_block_height();
+        if current_height < height {
+            return Err(ConsensusError::Message(format!(
+                "can't trim the memory pool to height {}, the ledger is at height {}",
+                height, current_height
+            )));
+        }
+
+        let invalid_transaction_ids = self
+            .transactions
+            .iter()
+            .filter(|(_, entry)| storage.transaction_conflicts(&entry.transaction))
+            .map(|(transaction_id, _)| transaction_id.clone())
+            .collect::<Vec<_>>();
+
+        for transaction_id in &invalid_transaction_ids {
+            self.remove_by_hash(transaction_id)?;
+        }
+
+        Ok(invalid_transaction_ids)
+    }
+
+    /// Removes transaction from memory pool or error.
+    #[inline]
+    pub fn remove(&mut self, entry: &Entry<T>) -> Result<Option<Vec<u8>>, ConsensusError> {
+        if self.contains(entry) {
+            self.total_size_in_bytes -= entry.size_in_bytes;
+
+            let transaction_id = entry.transaction.transaction_id()?.to_vec();
+
+            self.transactions.remove(&transaction_id);
+            self.pinned.remove(&transaction_id);
+            self.fee_deltas.remove(&transaction_id);
+            self.inserted_at.remove(&transaction_id);
+            self.remove_short_id(&transaction_id);
+            self.dirty = true;
+
+            return Ok(Some(transaction_id));
+        }
+
+        Ok(None)
+    }
+
+    /// Removes transaction from memory pool based on the transaction id.
+    #[inline]
+    pub fn remove_by_hash(&mut self, transaction_id: &[u8]) -> Result<Option<Entry<T>>, ConsensusError> {
+        match self.transactions.clone().get(transaction_id) {
+            Some(entry) => {
+                self.total_size_in_bytes -= entry.size_in_bytes;
+                self.transactions.remove(transaction_id);
+                self.pinned.remove(transaction_id);
+                self.fee_deltas.remove(transaction_id);
+                self.inserted_at.remove(transaction_id);
+                self.remove_short_id(transaction_id);
+                self.dirty = true;
+
+                Ok(Some(entry.clone()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Removes the unpinned transactions that have been in the memory pool for longer than its transaction TTL;
+    /// returns the ids of the expired transactions.
+    pub fn expire(&mut self) -> Result<Vec<Vec<u8>>, ConsensusError> {
+        let transaction_ttl = match self.transaction_ttl {
+            Some(transaction_ttl) => transaction_ttl,
+            None => return Ok(vec![]),
+        };
+        let now = self.clock.now();
+
+        let expired = self
+            .inserted_at
+            .iter()
+            .filter(|(transaction_id, inserted_at)| {
+                !self.pinned.contains(*transaction_id) && now.saturating_duration_since(**inserted_at) > transaction_ttl
+            })
+            .map(|(transaction_id, _)| transaction_id.clone())
+            .collect::<Vec<_>>();
+
+        for transaction_id in &expired {
+            self.remove_by_hash(transaction_id)?;
+        }
+
+        Ok(expired)
+    }
+
+    /// Returns the ids of the unpinned transactions that will expire from the memory pool within the given window,
+    /// the ones expiring the soonest first; nothing expires unless the memory pool has a transaction TTL.
+    pub fn expiring_within(&self, window: Duration) -> Vec<Vec<u8>> {
+        let transaction_ttl = match self.transaction_ttl {
+            Some(transaction_ttl) => transaction_ttl,
+            None => return vec![],
+        };
+        let now = self.clock.now();
+
+        let mut expiring = self
+            .inserted_at
+            .iter()
+            .filter(|(transaction_id, inserted_at)| {
+                // A window too large to add to the age of a transaction covers its expiry as well.
+                !self.pinned.contains(*transaction_id)
+                    && match now.saturating_duration_since(**inserted_at).checked_add(window) {
+                        Some(age) => age > transaction_ttl,
+                        None => true,
+                    }
+            })
+            .collect::<Vec<_>>();
+        expiring.sort_by_key(|(_, inserted_at)| **inserted_at);
+
+        expiring
+            .into_iter()
+            .map(|(transaction_id, _)| transaction_id.clone())
+            .collect()
+    }
+
+    /// Returns the origin of the memory pool transaction with the given id.
+    #[inline]
+    pub fn origin(&self, transaction_id: &[u8]) -> Option<TxOrigin> {
+        self.transactions.get(transaction_id).map(|entry| entry.origin)
+    }
+
+    /// Returns whether or not the memory pool contains the entry.
+    #[inline]
+    pub fn contains(&self, entry: &Entry<T>) -> bool {
+        match &entry.transaction.transaction_id() {
+            Ok(transaction_id) => self.transactions.contains_key(&transaction_id.to_vec()),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns the ids of the memory pool transactions that share a serial number, a commitment or a memo with
+    /// the given transaction, in ascending order; the transaction itself isn't reported if it's in the memory pool.
+    pub fn conflicting_transactions(&self, transaction: &T) -> Vec<Vec<u8>> {
+        let transaction_id = transaction.transaction_id().ok().map(|id| id.to_vec());
+        let transaction_serial_numbers = transaction.old_serial_numbers();
+        let transaction_commitments = transaction.new_commitments();
+        let transaction_memo = transaction.memorandum();
+
+        let mut conflicting = self
+            .transactions
+            .iter()
+            .filter(|(pooled_id, _)| Some(*pooled_id) != transaction_id.as_ref())
+            .filter(|(_, pooled)| {
+                pooled
+                    .transaction
+                    .old_serial_numbers()
+                    .iter()
+                    .any(|sn| transaction_serial_numbers.contains(sn))
+                    || pooled
+                        .transaction
+                        .new_commitments()
+                        .iter()
+                        .any(|cm| transaction_commitments.contains(cm))
+                    || pooled.transaction.memorandum() == transaction_memo
+            })
+            .map(|(pooled_id, _)| pooled_id.clone())
+            .collect::<Vec<_>>();
+        conflicting.sort_unstable();
+
+        conflicting
+    }
+
+    /// Returns whether or not a memory pool transaction spends the given serial number.
+    pub fn contains_sn(&self, sn: &T::SerialNumber) -> bool {
+        self.transactions
+            .values()
+            .any(|entry| entry.transaction.old_serial_numbers().contains(sn))
+    }
+
+    /// Removes the short id of the given transaction from the index, unless it belongs to another transaction.
+    fn remove_short_id(&mut self, transaction_id: &[u8]) {
+        let short_id = short_id(transaction_id);
+        if self.short_ids.get(&short_id).map(|id| &id[..]) == Some(transaction_id) {
+            self.short_ids.remove(&short_id);
+        }
+    }
+
+    /// Returns the memory pool transactions with the given short ids, along with the indexes of the short ids
+    /// that don't match any of them, so that the transactions of a compact block can be reconstructed.
+    pub fn collect_by_short_ids(&self, short_ids: &[u64]) -> (Vec<T>, Vec<usize>) {
+        let mut transactions = Vec::with_capacity(short_ids.len());
+        let mut missing = vec![];
+
+        for (index, short_id) in short_ids.iter().enumerate() {
+            match self
+                .short_ids
+                .get(short_id)
+                .and_then(|transaction_id| self.transactions.get(transaction_id))
+            {
+                Some(entry) => transactions.push(entry.transaction.clone()),
+                None => missing.push(index),
+            }
+        }
+
+        (transactions, missing)
+    }
+
+    /// Recomputes the total size and the auxiliary sets of the memory pool from its `transactions`, e.g. after they
+    /// were mutated directly.
+    pub fn rebuild_indexes(&mut self) {
+        let transactions = &self.transactions;
+
+        self.total_size_in_bytes = transactions.values().map(|entry| entry.size_in_bytes).sum();
+        self.pinned
+            .retain(|transaction_id| transactions.contains_key(transaction_id));
+        self.fee_deltas
+            .retain(|transaction_id, _| transactions.contains_key(transaction_id));
+        self.orphans
+            .retain(|transaction_id, _| !transactions.contains_key(transaction_id));
+        let orphans = &self.orphans;
+        self.orphaned_at
+            .retain(|transaction_id, _| orphans.contains_key(transaction_id));
+        self.scheduled
+            .retain(|transaction_id, _| !transactions.contains_key(transaction_id));
+        self.short_ids = transactions
+            .keys()
+            .map(|transaction_id| (short_id(transaction_id), transaction_id.clone()))
+            .collect();
+        self.inserted_at
+            .retain(|transaction_id, _| transactions.contains_key(transaction_id));
+        let now = self.clock.now();
+        for transaction_id in transactions.keys() {
+            self.inserted_at.entry(transaction_id.clone()).or_insert(now);
+        }
+        for transaction_id in self.orphans.keys() {
+            self.orphaned_at.entry(transaction_id.clone()).or_insert(now);
+        }
+        self.dirty = true;
+    }
+
+    /// Shrinks the collections of the memory pool whose utilization dropped below `COMPACTION_UTILIZATION_THRESHOLD`,
+    /// reclaiming the memory they retained after a period of high churn; returns `true` if any of them was shrunk.
+    pub fn shrink_to_fit(&mut self) -> bool {
+        fn is_underutilized(len: usize, capacity: usize) -> bool {
+            capacity != 0 && (len as f64) < capacity as f64 * COMPACTION_UTILIZATION_THRESHOLD
+        }
+
+        let mut shrunk = false;
+
+        if is_underutilized(self.transactions.len(), self.transactions.capacity()) {
+            self.transactions.shrink_to_fit();
+            shrunk = true;
+        }
+        if is_underutilized(self.orphans.len(), self.orphans.capacity()) {
+            self.orphans.shrink_to_fit();
+            shrunk = true;
+        }
+        if is_underutilized(self.scheduled.len(), self.scheduled.capacity()) {
+            self.scheduled.shrink_to_fit();
+            shrunk = true;
+        }
+        if is_underutilized(self.pinned.len(), self.pinned.capacity()) {
+            self.pinned.shrink_to_fit();
+            shrunk = true;
+        }
+        if is_underutilized(self.fee_deltas.len(), self.fee_deltas.capacity()) {
+            self.fee_deltas.shrink_to_fit();
+            shrunk = true;
+        }
+        if is_underutilized(self.blacklist.len(), self.blacklist.capacity()) {
+            self.blacklist.shrink_to_fit();
+            shrunk = true;
+        }
+        if is_underutilized(self.short_ids.len(), self.short_ids.capacity()) {
+            self.short_ids.shrink_to_fit();
+            shrunk = true;
+        }
+        if is_underutilized(self.inserted_at.len(), self.inserted_at.capacity()) {
+            self.inserted_at.shrink_to_fit();
+            shrunk = true;
+        }
+        if is_underutilized(self.orphaned_at.len(), self.orphaned_at.capacity()) {
+            self.orphaned_at.shrink_to_fit();
+            shrunk = true;
+        }
+
+        shrunk
+    }
+
+    /// Returns an estimate of the memory used by the memory pool in bytes; unlike `total_size_in_bytes`, it also
+    /// accounts for the entries of its collections, including their unused capacity, and the transaction ids.
+    pub fn memory_footprint(&self) -> usize {
+        // Every bucket of a hash map holds a key-value pair and a control byte.
+        fn buckets<K, V>(capacity: usize) -> usize {
+            capacity * (mem::size_of::<K>() + mem::size_of::<V>() + 1)
+        }
+
+        fn ids<'a>(ids: impl Iterator<Item = &'a Vec<u8>>) -> usize {
+            ids.map(Vec::capacity).sum()
+        }
+
+        fn entries<T: TransactionScheme>(entries: &HashMap<Vec<u8>, Entry<T>>) -> usize {
+            buckets::<Vec<u8>, Entry<T>>(entries.capacity())
+                + ids(entries.keys())
+                + entries.values().map(|entry| entry.size_in_bytes).sum::<usize>()
+        }
+
+        mem::size_of::<Self>()
+            + entries(&self.transactions)
+            + entries(&self.orphans)
+            + entries(&self.scheduled)
+            + buckets::<Vec<u8>, ()>(self.pinned.capacity())
+            + ids(self.pinned.iter())
+            + buckets::<Vec<u8>, i64>(self.fee_deltas.capacity())
+            + ids(self.fee_deltas.keys())
+            + buckets::<Vec<u8>, ()>(self.blacklist.capacity())
+            + ids(self.blacklist.iter())
+            + buckets::<u64, Vec<u8>>(self.short_ids.capacity())
+            + ids(self.short_ids.values())
+            + buckets::<Vec<u8>, Instant>(self.inserted_at.capacity())
+            + ids(self.inserted_at.keys())
+            + buckets::<Vec<u8>, Instant>(self.orphaned_at.capacity())
+            + ids(self.orphaned_at.keys())
+            + self.rejections.capacity() * mem::size_of::<Rejection>()
+            + ids(self.rejections.iter().map(|rejection| &rejection.transaction_id))
+    }
+
+    /// Checks that the total size and the auxiliary sets of the memory pool agree with its `transactions`.
+    pub fn verify_consistency(&self) -> Result<(), ConsensusError> {
+        let total_size_in_bytes: usize = self.transactions.values().map(|entry| entry.size_in_bytes).sum();
+        if total_size_in_bytes != self.total_size_in_bytes {
+            return Err(ConsensusError::Message(format!(
+                "the memory pool total size is {} bytes, but its transactions take up {} bytes",
+                self.total_size_in_bytes, total_size_in_bytes
+            )));
+        }
+
+        for (transaction_id, entry) in &self.transactions {
+            if &entry.transaction.transaction_id()?.to_vec() != transaction_id {
+                return Err(ConsensusError::Message(format!(
+                    "the memory pool transaction {} is stored under a different id",
+                    hex::encode(transaction_id)
+                )));
+            }
+        }
+
+        if let Some(transaction_id) = self.pinned.iter().find(|id| !self.transactions.contains_key(*id)) {
+            return Err(ConsensusError::Message(format!(
+                "the pinned transaction {} isn't in the memory pool",
+                hex::encode(transaction_id)
+            )));
+        }
+
+        if let Some(transaction_id) = self
+            .transactions
+            .keys()
+            .find(|id| !self.short_ids.contains_key(&short_id(id)))
+        {
+            return Err(ConsensusError::Message(format!(
+                "the memory pool transaction {} is missing from the short id index",
+                hex::encode(transaction_id)
+            )));
+        }
+
+        if let Some(transaction_id) = self.short_ids.values().find(|id| !self.transactions.contains_key(*id)) {
+            return Err(ConsensusError::Message(format!(
+                "the short id of the transaction {} is indexed, but it isn't in the memory pool",
+                hex::encode(transaction_id)
+            )));
+        }
+
+        if self.inserted_at.len() != self.transactions.len()
+            || self.transactions.keys().any(|id| !self.inserted_at.contains_key(id))
+        {
+            return Err(ConsensusError::Message(
+                "the memory pool insertion times don't match its transactions".into(),
+            ));
+        }
+
+        if self.orphaned_at.len() != self.orphans.len()
+            || self.orphans.keys().any(|id| !self.orphaned_at.contains_key(id))
+        {
+            return Err(ConsensusError::Message(
+                "the orphan pool insertion times don't match its transactions".into(),
+            ));
+        }
+
+        if let Some(transaction_id) = self.orphans.keys().find(|id| self.transactions.contains_key(*id)) {
+            return Err(ConsensusError::Message(format!(
+                "the transaction {} is both in the memory pool and in the orphan pool",
+                hex::encode(transaction_id)
+            )));
+        }
+
+        if let Some(transaction_id) = self.scheduled.keys().find(|id| self.transactions.contains_key(*id)) {
+            return Err(ConsensusError::Message(format!(
+                "the transaction {} is both in the memory pool and scheduled for a future block height",
+                hex::encode(transaction_id)
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Get candidate transactions for a new block.
+    pub fn get_candidates<P: LoadableMerkleParameters, S: Storage>(
+        &self,
+        storage: &Ledger<T, P, S>,
+        max_size: usize,
     ) -> Result<DPCTransactions<T>, ConsensusError> {
         let max_size = max_size - (BLOCK_HEADER_SIZE + COINBASE_TRANSACTION_SIZE);
 
-        let mut block_size = 0;
-        let mut transactions = DPCTransactions::new();
+        let mut entries = self.iter_by_fee().map(|(_, entry)| entry).collect::<Vec<_>>();
+
+        if self.selection_mode == SelectionMode::Optimal && entries.len() <= MAX_OPTIMAL_SELECTION_TRANSACTIONS {
+            entries.retain(|entry| !storage.transaction_conflicts(&entry.transaction));
+
+            // Select the most profitable entries first and fill any space they leave with the remaining ones.
+            let selected = Self::most_profitable_selection(&entries, max_size);
+            let (selected, remaining): (Vec<_>, Vec<_>) = entries
+                .into_iter()
+                .enumerate()
+                .partition(|(index, _)| selected.contains(index));
+            entries = selected.into_iter().chain(remaining).map(|(_, entry)| entry).collect();
+        }
+
+        let mut block_size = 0;
+        let mut transactions = DPCTransactions::new();
+
+        for entry in entries {
+            if block_size + entry.size_in_bytes <= max_size {
+                if storage.transaction_conflicts(&entry.transaction) || transactions.conflicts(&entry.transaction) {
+                    continue;
+                }
+
+                block_size += entry.size_in_bytes;
+                transactions.push(entry.transaction.clone());
+            }
+        }
+
+        Ok(transactions)
+    }
+
+    /// Returns the indices of the non-conflicting entries that fit within the given size and collect the most fees.
+    /// The search explores every such subset, pruning the branches that can't beat the best one found so far.
+    fn most_profitable_selection(entries: &[&Entry<T>], max_size: usize) -> Vec<usize> {
+        struct Search<'a, T: TransactionScheme> {
+            entries: &'a [&'a Entry<T>],
+            fees: Vec<i64>,
+            remaining_fees: Vec<i64>,
+            max_size: usize,
+            best_fees: i64,
+            best: Vec<usize>,
+            // The serial numbers, commitments and memos of the currently selected entries.
+            serial_numbers: HashSet<&'a T::SerialNumber>,
+            commitments: HashSet<&'a T::Commitment>,
+            memos: HashSet<&'a T::Memorandum>,
+        }
+
+        impl<'a, T: TransactionScheme<ValueBalance = AleoAmount>> Search<'a, T> {
+            fn explore(&mut self, index: usize, size: usize, fees: i64, selected: &mut Vec<usize>) {
+                if fees > self.best_fees {
+                    self.best_fees = fees;
+                    self.best = selected.clone();
+                }
+
+                if index == self.entries.len() || fees + self.remaining_fees[index] <= self.best_fees {
+                    return;
+                }
+
+                let entries = self.entries;
+                let entry = entries[index];
+
+                if size + entry.size_in_bytes <= self.max_size && !self.conflicts(&entry.transaction, selected) {
+                    self.select(&entry.transaction);
+                    selected.push(index);
+                    self.explore(index + 1, size + entry.size_in_bytes, fees + self.fees[index], selected);
+                    selected.pop();
+                    self.deselect(&entry.transaction);
+                }
+
+                self.explore(index + 1, size, fees, selected);
+            }
+
+            /// Checks the transaction against the selected ones, following the rules of `DPCTransactions::conflicts`.
+            fn conflicts(&self, transaction: &T, selected: &[usize]) -> bool {
+                if let Some(first) = selected.first() {
+                    if self.entries[*first].transaction.network_id() != transaction.network_id() {
+                        return true;
+                    }
+                }
+
+                let mut serial_numbers = HashSet::new();
+                let mut commitments = HashSet::new();
+
+                self.memos.contains(transaction.memorandum())
+                    || transaction
+                        .old_serial_numbers()
+                        .iter()
+                        .any(|sn| self.serial_numbers.contains(sn) || !serial_numbers.insert(sn))
+                    || transaction
+                        .new_commitments()
+                        .iter()
+                        .any(|cm| self.commitments.contains(cm) || !commitments.insert(cm))
+            }
+
+            fn select(&mut self, transaction: &'a T) {
+                self.serial_numbers.extend(transaction.old_serial_numbers());
+                self.commitments.extend(transaction.new_commitments());
+                self.memos.insert(transaction.memorandum());
+            }
+
+            fn deselect(&mut self, transaction: &T) {
+                for sn in transaction.old_serial_numbers() {
+                    self.serial_numbers.remove(sn);
+                }
+                for cm in transaction.new_commitments() {
+                    self.commitments.remove(cm);
+                }
+                self.memos.remove(transaction.memorandum());
+            }
+        }
+
+        let fees = entries
+            .iter()
+            .map(|entry| entry.transaction.value_balance().0.max(0))
+            .collect::<Vec<_>>();
+        let mut remaining_fees = vec![0; entries.len() + 1];
+        for index in (0..entries.len()).rev() {
+            remaining_fees[index] = remaining_fees[index + 1] + fees[index];
+        }
+
+        let mut search = Search {
+            entries,
+            fees,
+            remaining_fees,
+            max_size,
+            best_fees: 0,
+            best: vec![],
+            serial_numbers: Default::default(),
+            commitments: Default::default(),
+            memos: Default::default(),
+        };
+        search.explore(0, 0, 0, &mut vec![]);
+
+        search.best
+    }
+
+    /// Get candidate transactions for a new block that pay at least the given fee per byte, so that a miner can
+    /// honor a fee rate floor directly.
+    pub fn candidates_at_feerate<P: LoadableMerkleParameters, S: Storage>(
+        &self,
+        storage: &Ledger<T, P, S>,
+        min_feerate: u64,
+        max_size: usize,
+    ) -> DPCTransactions<T> {
+        let max_size = max_size.saturating_sub(BLOCK_HEADER_SIZE + COINBASE_TRANSACTION_SIZE);
+
+        let mut block_size = 0;
+        let mut transactions = DPCTransactions::new();
+
+        for (_transaction_id, entry) in self.iter_by_fee() {
+            if entry.fee_rate() < min_feerate as f64 || block_size + entry.size_in_bytes > max_size {
+                continue;
+            }
+
+            if storage.transaction_conflicts(&entry.transaction) || transactions.conflicts(&entry.transaction) {
+                continue;
+            }
+
+            block_size += entry.size_in_bytes;
+            transactions.push(entry.transaction.clone());
+        }
+
+        transactions
+    }
+
+    /// Returns the ids of the transactions in the given block template that conflict with
+    /// an earlier transaction in the template, or that are no longer valid against the storage.
+    pub fn validate_template<P: LoadableMerkleParameters, S: Storage>(
+        &self,
+        storage: &Ledger<T, P, S>,
+        transactions: &DPCTransactions<T>,
+    ) -> Result<Vec<Vec<u8>>, ConsensusError> {
+        let mut valid_transactions = DPCTransactions::new();
+        let mut invalid_transaction_ids = vec![];
+
+        for transaction in transactions.iter() {
+            if storage.transaction_conflicts(transaction) || valid_transactions.conflicts(transaction) {
+                invalid_transaction_ids.push(transaction.transaction_id()?.to_vec());
+            } else {
+                valid_transactions.push(transaction.clone());
+            }
+        }
+
+        Ok(invalid_transaction_ids)
+    }
+}
+
+/// A memory pool entry along with its transaction id, ordered as the entries are selected for a block.
+struct BySelectionOrder<'a, T: TransactionScheme>(&'a MemoryPool<T>, &'a Vec<u8>, &'a Entry<T>);
+
+impl<T: TransactionScheme<ValueBalance = AleoAmount>> Ord for BySelectionOrder<'_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp_selection_order((self.1, self.2), (other.1, other.2))
+    }
+}
+
+impl<T: TransactionScheme<ValueBalance = AleoAmount>> PartialOrd for BySelectionOrder<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: TransactionScheme<ValueBalance = AleoAmount>> PartialEq for BySelectionOrder<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: TransactionScheme<ValueBalance = AleoAmount>> Eq for BySelectionOrder<'_, T> {}
+
+impl<T: TransactionScheme> Default for MemoryPool<T> {
+    fn default() -> Self {
+        Self {
+            total_size_in_bytes: 0,
+            max_transaction_count: usize::MAX,
+            eviction_policy: EvictionPolicy::default(),
+            tie_breaker: TieBreaker::default(),
+            selection_mode: SelectionMode::default(),
+            max_transaction_inputs: usize::MAX,
+            max_transaction_outputs: usize::MAX,
+            max_transaction_size: usize::MAX,
+            transactions: HashMap::<Vec<u8>, Entry<T>>::new(),
+            orphans: HashMap::<Vec<u8>, Entry<T>>::new(),
+            scheduled: HashMap::<Vec<u8>, Entry<T>>::new(),
+            admission_control: None,
+            pinned: HashSet::new(),
+            fee_deltas: HashMap::new(),
+            blacklist: HashSet::new(),
+            short_ids: HashMap::new(),
+            inserted_at: HashMap::new(),
+            orphaned_at: HashMap::new(),
+            transaction_ttl: None,
+            orphan_ttl: None,
+            rejections: VecDeque::new(),
+            replacement_window: DEFAULT_REPLACEMENT_WINDOW,
+            min_replacement_fee_increase: DEFAULT_MIN_REPLACEMENT_FEE_INCREASE,
+            clock: Arc::new(SystemClock),
+            disabled: false,
+            auto_cleanse: true,
+            dirty: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockClock;
+    use snarkos_testing::sync::*;
+    use snarkvm_dpc::{testnet1::instantiated::Tx, Block};
+
+    // MemoryPool tests use TRANSACTION_2 because memory pools shouldn't store coinbase transactions
+
+    /// Returns a local, standard entry of the given transaction that pays the given fee and has the given size.
+    fn test_entry(bytes: &[u8], fee: i64, size: usize) -> Entry<Tx> {
+        let mut transaction = Tx::read(bytes).unwrap();
+        transaction.value_balance = AleoAmount(fee);
+        Entry {
+            size_in_bytes: size,
+            transaction,
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        }
+    }
+
+    #[test]
+    fn push() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new();
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let size = TRANSACTION_2.len();
+
+        mem_pool
+            .insert(&blockchain, Entry {
+                size_in_bytes: size,
+                transaction: transaction.clone(),
+                origin: TxOrigin::Local,
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
+            })
+            .unwrap();
+
+        assert_eq!(size, mem_pool.total_size_in_bytes);
+        assert_eq!(1, mem_pool.transactions.len());
+
+        // Duplicate pushes don't do anything
+
+        mem_pool
+            .insert(&blockchain, Entry {
+                size_in_bytes: size,
+                transaction,
+                origin: TxOrigin::Local,
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
+            })
+            .unwrap();
+
+        assert_eq!(size, mem_pool.total_size_in_bytes);
+        assert_eq!(1, mem_pool.transactions.len());
+    }
+
+    #[test]
+    fn remove_entry() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new();
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let size = TRANSACTION_2.len();
+
+        let entry = Entry::<Tx> {
+            size_in_bytes: size,
+            transaction,
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+
+        mem_pool.insert(&blockchain, entry.clone()).unwrap();
+
+        assert_eq!(1, mem_pool.transactions.len());
+        assert_eq!(size, mem_pool.total_size_in_bytes);
+
+        mem_pool.remove(&entry).unwrap();
+
+        assert_eq!(0, mem_pool.transactions.len());
+        assert_eq!(0, mem_pool.total_size_in_bytes);
+    }
+
+    #[test]
+    fn remove_transaction_by_hash() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new();
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let size = TRANSACTION_2.len();
+
+        mem_pool
+            .insert(&blockchain, Entry {
+                size_in_bytes: size,
+                transaction: transaction.clone(),
+                origin: TxOrigin::Local,
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
+            })
+            .unwrap();
+
+        assert_eq!(1, mem_pool.transactions.len());
+        assert_eq!(size, mem_pool.total_size_in_bytes);
+
+        mem_pool
+            .remove_by_hash(&transaction.transaction_id().unwrap().to_vec())
+            .unwrap();
+
+        assert_eq!(0, mem_pool.transactions.len());
+        assert_eq!(0, mem_pool.total_size_in_bytes);
+    }
+
+    #[test]
+    fn get_candidates() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new();
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+
+        let size = to_bytes![transaction].unwrap().len();
+
+        let expected_transaction = transaction.clone();
+        mem_pool
+            .insert(&blockchain, Entry {
+                size_in_bytes: size,
+                transaction,
+                origin: TxOrigin::Local,
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
+            })
+            .unwrap();
+
+        let max_block_size = size + BLOCK_HEADER_SIZE + COINBASE_TRANSACTION_SIZE;
+
+        let candidates = mem_pool.get_candidates(&blockchain, max_block_size).unwrap();
+
+        assert!(candidates.contains(&expected_transaction));
+    }
+
+    #[test]
+    fn candidates_at_feerate() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let low_fee = test_entry(&TRANSACTION_1[..], 1, TRANSACTION_1.len());
+        let high_fee = test_entry(&TRANSACTION_2[..], 1_000_000, TRANSACTION_2.len());
+
+        let mut mem_pool = MemoryPool::new();
+        mem_pool.insert(&blockchain, low_fee.clone()).unwrap();
+        mem_pool.insert(&blockchain, high_fee.clone()).unwrap();
+        assert_eq!(2, mem_pool.transactions.len());
+
+        let max_block_size =
+            low_fee.size_in_bytes + high_fee.size_in_bytes + BLOCK_HEADER_SIZE + COINBASE_TRANSACTION_SIZE;
+
+        // Only the transaction at or above the floor is selected.
+        let floor = high_fee.fee_rate().floor() as u64;
+        assert!(low_fee.fee_rate() < floor as f64);
+        let candidates = mem_pool.candidates_at_feerate(&blockchain, floor, max_block_size);
+        assert_eq!(candidates.0, vec![high_fee.transaction.clone()]);
+
+        // Without a floor, both of them are selected, the higher-fee one first.
+        let candidates = mem_pool.candidates_at_feerate(&blockchain, 0, max_block_size);
+        assert_eq!(candidates.0, vec![high_fee.transaction.clone(), low_fee.transaction]);
+
+        // The size budget still applies.
+        let candidates = mem_pool.candidates_at_feerate(&blockchain, 0, max_block_size - 1);
+        assert_eq!(candidates.0, vec![high_fee.transaction.clone()]);
+
+        // Transactions conflicting with the ledger are skipped, even if the memory pool wasn't cleansed.
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        blockchain.insert_and_commit(&block_1).unwrap();
+        assert_eq!(2, mem_pool.transactions.len());
+        let candidates = mem_pool.candidates_at_feerate(&blockchain, 0, max_block_size);
+        assert_eq!(candidates.0, vec![high_fee.transaction]);
+    }
+
+    #[test]
+    fn optimal_selection() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let entry_with = |transaction_bytes: &[u8], memo: u64, fee: i64, size_in_bytes: usize| {
+            let mut entry = test_entry(transaction_bytes, fee, size_in_bytes);
+            entry.transaction.memorandum[..8].copy_from_slice(&memo.to_le_bytes());
+            entry
+        };
+
+        // The two spends of the first transaction's inputs conflict with each other; the greedy packer picks the
+        // one with the higher fee rate, which leaves no room for the second transaction.
+        let large = entry_with(&TRANSACTION_1[..], 1, 7_000, 6_000);
+        let small = entry_with(&TRANSACTION_1[..], 2, 5_000, 5_000);
+        let other = entry_with(&TRANSACTION_2[..], 3, 5_000, 5_000);
+        assert!(large.fee_rate() > small.fee_rate());
+
+        let max_block_size = 10_000 + BLOCK_HEADER_SIZE + COINBASE_TRANSACTION_SIZE;
+
+        let mut collected_fees = vec![];
+        for selection_mode in &[SelectionMode::Greedy, SelectionMode::Optimal] {
+            let mut mem_pool = MemoryPool::new().with_selection_mode(*selection_mode);
+            for entry in &[&large, &small, &other] {
+                let transaction_id = entry.transaction.transaction_id().unwrap().to_vec();
+                mem_pool.transactions.insert(transaction_id, (*entry).clone());
+            }
+            mem_pool.rebuild_indexes();
+
+            let candidates = mem_pool.get_candidates(&blockchain, max_block_size).unwrap();
+            collected_fees.push(
+                candidates
+                    .iter()
+                    .map(|transaction| transaction.value_balance.0)
+                    .sum::<i64>(),
+            );
+
+            if *selection_mode == SelectionMode::Optimal {
+                assert_eq!(2, candidates.0.len());
+                assert!(candidates.contains(&small.transaction) && candidates.contains(&other.transaction));
+            }
+        }
+
+        // The optimal packer collects strictly more fees than the greedy one.
+        assert_eq!(vec![7_000, 10_000], collected_fees);
+    }
+
+    #[test]
+    fn store_memory_pool() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new();
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        mem_pool
+            .insert(&blockchain, Entry {
+                size_in_bytes: TRANSACTION_2.len(),
+                transaction,
+                origin: TxOrigin::Local,
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
+            })
+            .unwrap();
+
+        assert_eq!(1, mem_pool.transactions.len());
+
+        mem_pool.store(&blockchain).unwrap();
+
+        let new_mem_pool = MemoryPool::from_storage(&blockchain).unwrap();
+
+        assert_eq!(mem_pool.total_size_in_bytes, new_mem_pool.total_size_in_bytes);
+    }
+
+    #[test]
+    fn from_storage_with_limits() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let low_fee = test_entry(&TRANSACTION_1[..], 1, TRANSACTION_1.len());
+        let high_fee = test_entry(&TRANSACTION_2[..], 1_000_000, TRANSACTION_2.len());
+
+        let mut mem_pool = MemoryPool::new();
+        mem_pool.insert(&blockchain, low_fee.clone()).unwrap();
+        mem_pool.insert(&blockchain, high_fee.clone()).unwrap();
+        mem_pool.store(&blockchain).unwrap();
+
+        // Without binding limits, everything is reloaded.
+        assert_eq!(2, MemoryPool::from_storage(&blockchain).unwrap().transactions.len());
+
+        // Only the highest-fee transaction fits within the transaction count limit.
+        let reloaded = MemoryPool::from_storage_with_limits(&blockchain, 1, usize::MAX).unwrap();
+        assert_eq!(1, reloaded.transactions.len());
+        assert!(reloaded.contains(&high_fee));
+
+        // Or within the size limit.
+        let reloaded = MemoryPool::from_storage_with_limits(&blockchain, usize::MAX, high_fee.size_in_bytes).unwrap();
+        assert_eq!(1, reloaded.transactions.len());
+        assert!(reloaded.contains(&high_fee));
+        assert_eq!(high_fee.size_in_bytes, reloaded.total_size_in_bytes);
+        assert!(!reloaded.is_dirty());
+    }
+
+    #[test]
+    fn store_memory_pool_if_dirty() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        // A memory pool loaded from storage has nothing new to store
+        let mut mem_pool = MemoryPool::from_storage(&blockchain).unwrap();
+        assert!(!mem_pool.store_if_dirty(&blockchain).unwrap());
+        assert_eq!(blockchain.get_memory_pool().unwrap(), None);
+
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        mem_pool.insert(&blockchain, entry.clone()).unwrap();
+        assert!(mem_pool.is_dirty());
+
+        // The modified memory pool is stored once
+        assert!(mem_pool.store_if_dirty(&blockchain).unwrap());
+        assert!(!mem_pool.is_dirty());
+        assert_eq!(MemoryPool::from_storage(&blockchain).unwrap().transactions.len(), 1);
+        assert!(!mem_pool.store_if_dirty(&blockchain).unwrap());
+
+        // A removal is stored as well
+        mem_pool.remove(&entry).unwrap();
+        assert!(mem_pool.store_if_dirty(&blockchain).unwrap());
+        assert!(MemoryPool::from_storage(&blockchain).unwrap().transactions.is_empty());
+    }
+
+    #[test]
+    fn cleanse_memory_pool() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new();
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        mem_pool
+            .insert(&blockchain, Entry {
+                size_in_bytes: TRANSACTION_2.len(),
+                transaction,
+                origin: TxOrigin::Local,
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
+            })
+            .unwrap();
+
+        assert_eq!(1, mem_pool.transactions.len());
+
+        mem_pool.store(&blockchain).unwrap();
+
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2 = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+
+        blockchain.insert_and_commit(&block_1).unwrap();
+        blockchain.insert_and_commit(&block_2).unwrap();
+
+        mem_pool.cleanse(&blockchain).unwrap();
+
+        assert_eq!(0, mem_pool.transactions.len());
+        assert_eq!(0, mem_pool.total_size_in_bytes);
+
+        // The auxiliary indexes are cleansed as well, and the removal is due to be stored
+        mem_pool.verify_consistency().unwrap();
+        assert!(mem_pool.store_if_dirty(&blockchain).unwrap());
+        assert!(MemoryPool::from_storage(&blockchain).unwrap().transactions.is_empty());
+    }
+
+    #[test]
+    fn trim_to_height() {
+        let new_mem_pool = |blockchain| {
+            let mut mem_pool = MemoryPool::new();
+            for transaction_bytes in [&TRANSACTION_1[..], &TRANSACTION_2[..]].iter() {
+                mem_pool
+                    .insert(blockchain, Entry {
+                        size_in_bytes: transaction_bytes.len(),
+                        transaction: Tx::read(*transaction_bytes).unwrap(),
+                        origin: TxOrigin::Local,
+                        priority_class: PriorityClass::Standard,
+                        valid_from_height: None,
+                    })
+                    .unwrap();
+            }
+            mem_pool
+        };
+        let blocks = [
+            Block::<Tx>::read(&BLOCK_1[..]).unwrap(),
+            Block::<Tx>::read(&BLOCK_2[..]).unwrap(),
+        ];
+
+        // Cleanse after every block
+        let cleansed_blockchain = FIXTURE_VK.ledger();
+        let mut cleansed_mem_pool = new_mem_pool(&cleansed_blockchain);
+        for block in blocks.iter() {
+            cleansed_blockchain.insert_and_commit(block).unwrap();
+            cleansed_mem_pool.cleanse(&cleansed_blockchain).unwrap();
+        }
+
+        // Trim once after all the blocks
+        let trimmed_blockchain = FIXTURE_VK.ledger();
+        let mut trimmed_mem_pool = new_mem_pool(&trimmed_blockchain);
+        assert_eq!(2, trimmed_mem_pool.transactions.len());
+        for block in blocks.iter() {
+            trimmed_blockchain.insert_and_commit(block).unwrap();
+        }
+
+        // The ledger hasn't reached the height yet
+        assert!(trimmed_mem_pool.trim_to_height(&trimmed_blockchain, 3).is_err());
+
+        let trimmed = trimmed_mem_pool.trim_to_height(&trimmed_blockchain, 2).unwrap();
+        assert_eq!(2 - trimmed_mem_pool.transactions.len(), trimmed.len());
+
+        let mut cleansed_ids = cleansed_mem_pool.transactions.keys().collect::<Vec<_>>();
+        let mut trimmed_ids = trimmed_mem_pool.transactions.keys().collect::<Vec<_>>();
+        cleansed_ids.sort();
+        trimmed_ids.sort();
+
+        assert_eq!(cleansed_ids, trimmed_ids);
+        assert_eq!(
+            cleansed_mem_pool.total_size_in_bytes,
+            trimmed_mem_pool.total_size_in_bytes
+        );
+    }
+
+    #[test]
+    fn cleanse_with_block() {
+        let blockchain = FIXTURE_VK.ledger();
+        let block = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+
+        let mut mem_pool = MemoryPool::new();
+        assert!(mem_pool.auto_cleanse);
+        assert!(!MemoryPool::<Tx>::new().with_auto_cleanse(false).auto_cleanse);
+
+        // A transaction spending the same serial numbers as one of the block's transactions
+        let mut conflicting = block.transactions.0.last().unwrap().clone();
+        conflicting.memorandum[..8].copy_from_slice(&[7u8; 8]);
+
+        let pooled = [&TRANSACTION_1[..], &TRANSACTION_2[..]]
+            .iter()
+            .map(|transaction_bytes| Tx::read(*transaction_bytes).unwrap())
+            .chain(block.transactions.0.iter().cloned())
+            .chain(std::iter::once(conflicting.clone()))
+            .collect::<Vec<_>>();
+        for transaction in pooled {
+            mem_pool
+                .transactions
+                .insert(transaction.transaction_id().unwrap().to_vec(), Entry {
+                    size_in_bytes: to_bytes![transaction].unwrap().len(),
+                    transaction,
+                    origin: TxOrigin::Local,
+                    priority_class: PriorityClass::Standard,
+                    valid_from_height: None,
+                });
+        }
+        mem_pool.rebuild_indexes();
+        let pooled_count = mem_pool.transactions.len();
+
+        blockchain.insert_and_commit(&block).unwrap();
+        let removed = mem_pool.cleanse_with_block(&block.transactions).unwrap();
+
+        // The confirmed transactions and the conflicting one are gone, and nothing left conflicts with the block
+        assert_eq!(pooled_count - mem_pool.transactions.len(), removed.len());
+        for transaction_id in block.transactions.to_transaction_ids().unwrap() {
+            assert!(!mem_pool.transactions.contains_key(&transaction_id[..]));
+        }
+        assert!(!mem_pool
+            .transactions
+            .contains_key(&conflicting.transaction_id().unwrap()[..]));
+        assert!(mem_pool
+            .transactions
+            .values()
+            .all(|entry| !block.transactions.conflicts(&entry.transaction)));
+        assert_eq!(
+            mem_pool.total_size_in_bytes,
+            mem_pool
+                .transactions
+                .values()
+                .map(|entry| entry.size_in_bytes)
+                .sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn disabled_memory_pool() {
+        let blockchain = FIXTURE_VK.ledger();
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+
+        // A disabled memory pool doesn't store any transactions
+        let mut mem_pool = MemoryPool::new().with_disabled(true);
+        assert!(matches!(
+            mem_pool.insert(&blockchain, entry.clone()),
+            Err(ConsensusError::MemoryPoolDisabled)
+        ));
+        assert!(matches!(
+            mem_pool.insert_orphan(entry.clone()),
+            Err(ConsensusError::MemoryPoolDisabled)
+        ));
+        assert!(mem_pool.transactions.is_empty());
+        assert!(mem_pool.orphans.is_empty());
+
+        // nor is it persisted over the one stored earlier
+        let mut stored_mem_pool = MemoryPool::new();
+        stored_mem_pool.insert(&blockchain, entry).unwrap().unwrap();
+        stored_mem_pool.store(&blockchain).unwrap();
+        mem_pool.store(&blockchain).unwrap();
+        assert_eq!(
+            1,
+            MemoryPool::<Tx>::from_storage(&blockchain).unwrap().transactions.len()
+        );
+
+        // Blocks are still committed, along with the memory pool upkeep that follows them
+        let block = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        blockchain.insert_and_commit(&block).unwrap();
+        assert!(mem_pool.cleanse_with_block(&block.transactions).unwrap().is_empty());
+        assert!(mem_pool.promote_scheduled(&blockchain).is_empty());
+        assert_eq!(1, blockchain.get_current_block_height());
+    }
+
+    #[test]
+    fn fee_rate_histogram() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new();
+        for transaction_bytes in [&TRANSACTION_1[..], &TRANSACTION_2[..]].iter() {
+            mem_pool
+                .insert(&blockchain, Entry {
+                    size_in_bytes: transaction_bytes.len(),
+                    transaction: Tx::read(*transaction_bytes).unwrap(),
+                    origin: TxOrigin::Local,
+                    priority_class: PriorityClass::Standard,
+                    valid_from_height: None,
+                })
+                .unwrap();
+        }
+
+        let histogram = mem_pool.fee_rate_histogram(&FEE_RATE_HISTOGRAM_BUCKETS);
+        assert_eq!(FEE_RATE_HISTOGRAM_BUCKETS.len(), histogram.len());
+
+        let histogram_size: usize = histogram.iter().map(|(_, size_in_bytes)| size_in_bytes).sum();
+        assert_eq!(mem_pool.total_size_in_bytes, histogram_size);
+
+        // Every entry lands in the bucket matching its fee rate
+        for entry in mem_pool.transactions.values() {
+            let (_, bucket_size) = histogram
+                .iter()
+                .rev()
+                .find(|(bound, _)| *bound <= entry.fee_rate())
+                .unwrap_or(&histogram[0]);
+            assert!(*bucket_size >= entry.size_in_bytes);
+        }
+
+        assert!(MemoryPool::<Tx>::new()
+            .fee_rate_histogram(&FEE_RATE_HISTOGRAM_BUCKETS)
+            .iter()
+            .all(|(_, size_in_bytes)| *size_in_bytes == 0));
+    }
+
+    #[test]
+    fn snapshot_diff() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let entry = |transaction_bytes: &[u8]| Entry {
+            size_in_bytes: transaction_bytes.len(),
+            transaction: Tx::read(transaction_bytes).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        let first = entry(&TRANSACTION_1[..]);
+        let second = entry(&TRANSACTION_2[..]);
+        let first_id = first.transaction.transaction_id().unwrap().to_vec();
+        let second_id = second.transaction.transaction_id().unwrap().to_vec();
+
+        let mut mem_pool = MemoryPool::new();
+        mem_pool.insert(&blockchain, first).unwrap();
+
+        let snapshot = mem_pool.snapshot();
+        assert_eq!(MemPoolDiff::default(), mem_pool.diff(&snapshot));
+
+        mem_pool.remove_by_hash(&first_id).unwrap();
+        mem_pool.insert(&blockchain, second).unwrap();
+
+        assert_eq!(
+            MemPoolDiff {
+                added: vec![second_id.clone()],
+                removed: vec![first_id],
+            },
+            mem_pool.diff(&snapshot)
+        );
+
+        // The diff is relative to the given snapshot only.
+        let snapshot = mem_pool.snapshot();
+        assert_eq!(MemPoolDiff::default(), mem_pool.diff(&snapshot));
+        assert!(snapshot.transaction_ids.contains(&second_id));
+    }
+
+    #[test]
+    fn pinned_transactions() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::with_max_transaction_count(1);
+
+        let entry_1 = Entry {
+            size_in_bytes: TRANSACTION_1.len(),
+            transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        let entry_2 = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        let (low, high) = if entry_1.fee_rate() < entry_2.fee_rate() {
+            (entry_1, entry_2)
+        } else {
+            (entry_2, entry_1)
+        };
+        let low_id = low.transaction.transaction_id().unwrap().to_vec();
+
+        // Only transactions in the memory pool can be pinned
+        assert!(!mem_pool.pin(&low_id));
+
+        mem_pool.insert(&blockchain, low.clone()).unwrap();
+        assert!(mem_pool.pin(&low_id));
+        assert!(mem_pool.is_pinned(&low_id));
+
+        // The pinned low-fee transaction isn't evicted to make room for a higher-fee one
+        assert!(mem_pool.insert(&blockchain, high.clone()).unwrap().is_none());
+        assert!(mem_pool.contains(&low));
+        assert!(!mem_pool.contains(&high));
+
+        // It survives cleansing as well
+        mem_pool.cleanse(&blockchain).unwrap();
+        assert!(mem_pool.contains(&low));
+        assert!(mem_pool.is_pinned(&low_id));
+
+        // Once unpinned, it's evicted as usual
+        assert!(mem_pool.unpin(&low_id));
+        assert!(mem_pool.insert(&blockchain, high.clone()).unwrap().is_some());
+        assert!(!mem_pool.contains(&low));
+        assert!(mem_pool.contains(&high));
+    }
+
+    #[test]
+    fn expire_transactions() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let clock = Arc::new(MockClock::new());
+        let mut mem_pool = MemoryPool::new()
+            .with_transaction_ttl(Duration::from_secs(60))
+            .with_clock(clock.clone());
+
+        let entry_1 = Entry {
+            size_in_bytes: TRANSACTION_1.len(),
+            transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        let entry_2 = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+
+        let id_1 = mem_pool.insert(&blockchain, entry_1.clone()).unwrap().unwrap();
+        clock.advance(Duration::from_secs(30));
+        mem_pool.insert(&blockchain, entry_2.clone()).unwrap().unwrap();
+
+        // Nothing expires before the TTL elapses
+        clock.advance(Duration::from_secs(30));
+        assert!(mem_pool.expire().unwrap().is_empty());
+
+        // Only the older transaction expires once it's past the TTL
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(mem_pool.expire().unwrap(), vec![id_1]);
+        assert!(!mem_pool.contains(&entry_1));
+        assert!(mem_pool.contains(&entry_2));
+        mem_pool.verify_consistency().unwrap();
+
+        // Pinned transactions never expire
+        let id_2 = entry_2.transaction.transaction_id().unwrap().to_vec();
+        assert!(mem_pool.pin(&id_2));
+        clock.advance(Duration::from_secs(3600));
+        assert!(mem_pool.expire().unwrap().is_empty());
+
+        assert!(mem_pool.unpin(&id_2));
+        assert_eq!(mem_pool.expire().unwrap(), vec![id_2]);
+        assert!(mem_pool.transactions.is_empty());
+        mem_pool.verify_consistency().unwrap();
+    }
+
+    #[test]
+    fn expiring_within() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let clock = Arc::new(MockClock::new());
+        let mut mem_pool = MemoryPool::new().with_clock(clock.clone());
+
+        let entry_1 = Entry {
+            size_in_bytes: TRANSACTION_1.len(),
+            transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        let entry_2 = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+
+        let id_1 = mem_pool.insert(&blockchain, entry_1).unwrap().unwrap();
+        clock.advance(Duration::from_secs(30));
+        let id_2 = mem_pool.insert(&blockchain, entry_2).unwrap().unwrap();
+
+        // Nothing expires without a TTL
+        assert!(mem_pool.expiring_within(Duration::from_secs(3600)).is_empty());
+
+        mem_pool.transaction_ttl = Some(Duration::from_secs(60));
+
+        // The older transaction expires in 30s, the newer one in 60s
+        assert!(mem_pool.expiring_within(Duration::from_secs(30)).is_empty());
+        assert_eq!(mem_pool.expiring_within(Duration::from_secs(31)), vec![id_1.clone()]);
+        assert_eq!(mem_pool.expiring_within(Duration::from_secs(61)), vec![
+            id_1.clone(),
+            id_2.clone()
+        ]);
+
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(mem_pool.expiring_within(Duration::from_secs(11)), vec![id_1.clone()]);
+
+        // Pinned transactions never expire
+        assert!(mem_pool.pin(&id_1));
+        assert_eq!(mem_pool.expiring_within(Duration::from_secs(3600)), vec![id_2.clone()]);
+
+        // A window too large to add to a transaction's age covers every transaction
+        assert_eq!(mem_pool.expiring_within(Duration::MAX), vec![id_2]);
+
+        // The reported transactions are the ones that eventually expire
+        clock.advance(Duration::from_secs(11));
+        assert!(mem_pool.unpin(&id_1));
+        assert_eq!(mem_pool.expire().unwrap(), vec![id_1]);
+    }
+
+    #[test]
+    fn validate_template() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new();
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        mem_pool
+            .insert(&blockchain, Entry {
+                size_in_bytes: TRANSACTION_2.len(),
+                transaction: transaction.clone(),
+                origin: TxOrigin::Local,
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
+            })
+            .unwrap();
+
+        let template = mem_pool.get_candidates(&blockchain, usize::MAX / 2).unwrap();
+        assert!(mem_pool.validate_template(&blockchain, &template).unwrap().is_empty());
+
+        // A transaction repeated within the template conflicts with its first occurrence
+
+        let mut duplicated = template.clone();
+        duplicated.push(transaction.clone());
+        assert_eq!(
+            vec![transaction.transaction_id().unwrap().to_vec()],
+            mem_pool.validate_template(&blockchain, &duplicated).unwrap()
+        );
+
+        // Once the transaction is committed, the template is stale
+
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2 = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+
+        blockchain.insert_and_commit(&block_1).unwrap();
+        blockchain.insert_and_commit(&block_2).unwrap();
+
+        assert_eq!(
+            vec![transaction.transaction_id().unwrap().to_vec()],
+            mem_pool.validate_template(&blockchain, &template).unwrap()
+        );
+    }
+
+    #[test]
+    fn collect_by_short_ids() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new();
+        let transaction_1 = Tx::read(&TRANSACTION_1[..]).unwrap();
+        let transaction_2 = Tx::read(&TRANSACTION_2[..]).unwrap();
+        mem_pool
+            .insert(&blockchain, Entry {
+                size_in_bytes: TRANSACTION_1.len(),
+                transaction: transaction_1.clone(),
+                origin: TxOrigin::Local,
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
+            })
+            .unwrap();
+
+        let short_id_1 = short_id(&transaction_1.transaction_id().unwrap());
+        let short_id_2 = short_id(&transaction_2.transaction_id().unwrap());
+        assert_ne!(short_id_1, short_id_2);
+
+        // Only the pooled transaction is matched
+        let short_ids = [short_id_2, short_id_1, 0];
+        let (transactions, missing) = mem_pool.collect_by_short_ids(&short_ids);
+        assert_eq!(transactions, vec![transaction_1.clone()]);
+        assert_eq!(missing, vec![0, 2]);
+
+        mem_pool
+            .insert(&blockchain, Entry {
+                size_in_bytes: TRANSACTION_2.len(),
+                transaction: transaction_2.clone(),
+                origin: TxOrigin::Local,
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
+            })
+            .unwrap();
+        mem_pool.verify_consistency().unwrap();
+
+        let (transactions, missing) = mem_pool.collect_by_short_ids(&short_ids);
+        assert_eq!(transactions, vec![transaction_2, transaction_1.clone()]);
+        assert_eq!(missing, vec![2]);
+
+        // Removed transactions are no longer matched
+        mem_pool
+            .remove_by_hash(&transaction_1.transaction_id().unwrap())
+            .unwrap();
+        mem_pool.verify_consistency().unwrap();
+
+        let (_, missing) = mem_pool.collect_by_short_ids(&short_ids);
+        assert_eq!(missing, vec![1, 2]);
+    }
+
+    #[test]
+    fn export_import_hex() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new();
+        for transaction_bytes in [&TRANSACTION_1[..], &TRANSACTION_2[..]].iter() {
+            mem_pool
+                .insert(&blockchain, Entry {
+                    size_in_bytes: transaction_bytes.len(),
+                    transaction: Tx::read(*transaction_bytes).unwrap(),
+                    origin: TxOrigin::Local,
+                    priority_class: PriorityClass::Standard,
+                    valid_from_height: None,
+                })
+                .unwrap();
+        }
+
+        let exported = mem_pool.export_hex().unwrap();
+        assert_eq!(2, exported.lines().count());
+
+        let mut new_mem_pool = MemoryPool::new();
+        let accepted = new_mem_pool.import_hex(&blockchain, &exported).unwrap();
+
+        assert_eq!(2, accepted.len());
+        assert_eq!(mem_pool.total_size_in_bytes, new_mem_pool.total_size_in_bytes);
+        for transaction_id in mem_pool.transactions.keys() {
+            assert!(new_mem_pool.transactions.contains_key(transaction_id));
+        }
+        assert_eq!(exported, new_mem_pool.export_hex().unwrap());
+
+        // Malformed input is reported rather than silently skipped.
+        assert!(new_mem_pool.import_hex(&blockchain, "not hex").is_err());
+    }
+
+    #[test]
+    fn orphan_pool() {
+        let mut mem_pool = MemoryPool::new();
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction,
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+
+        assert!(mem_pool.insert_orphan(entry.clone()).unwrap().is_some());
+        assert!(mem_pool.insert_orphan(entry.clone()).unwrap().is_none());
+        assert_eq!(1, mem_pool.orphans.len());
+        assert_eq!(0, mem_pool.transactions.len());
 
-        // TODO Change naive transaction selection
-        for (_transaction_id, entry) in self.transactions.iter() {
-            if block_size + entry.size_in_bytes <= max_size {
-                if storage.transaction_conflicts(&entry.transaction) || transactions.conflicts(&entry.transaction) {
-                    continue;
-                }
+        assert_eq!(vec![entry], mem_pool.take_orphans());
+        assert!(mem_pool.orphans.is_empty());
+    }
 
-                block_size += entry.size_in_bytes;
-                transactions.push(entry.transaction.clone());
+    #[test]
+    fn orphan_pool_limits() {
+        let clock = Arc::new(MockClock::new());
+        let mut mem_pool = MemoryPool::new().with_clock(clock.clone());
+
+        let orphan = |memo: u64, origin: TxOrigin| {
+            let mut transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+            transaction.memorandum[..8].copy_from_slice(&memo.to_le_bytes());
+            Entry {
+                size_in_bytes: TRANSACTION_2.len(),
+                transaction,
+                origin,
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
             }
-        }
+        };
+        let peer = |port: u16| TxOrigin::Peer(SocketAddr::from(([127, 0, 0, 1], port)));
 
-        Ok(transactions)
+        // A peer that exceeds its orphan limit has its oldest orphan evicted.
+        let mut first_ids = vec![];
+        for memo in 0..=MAX_ORPHAN_TRANSACTIONS_PER_PEER as u64 {
+            first_ids.push(mem_pool.insert_orphan(orphan(memo, peer(1))).unwrap().unwrap());
+            clock.advance(Duration::from_secs(1));
+        }
+        assert_eq!(MAX_ORPHAN_TRANSACTIONS_PER_PEER, mem_pool.orphans.len());
+        assert!(!mem_pool.orphans.contains_key(&first_ids[0]));
+        assert!(first_ids[1..].iter().all(|id| mem_pool.orphans.contains_key(id)));
+
+        // Once the orphan pool is full, the oldest orphan is evicted regardless of its origin.
+        let mut memo = 1_000;
+        for port in 2..(1 + MAX_ORPHAN_TRANSACTIONS / MAX_ORPHAN_TRANSACTIONS_PER_PEER) as u16 {
+            for _ in 0..MAX_ORPHAN_TRANSACTIONS_PER_PEER {
+                memo += 1;
+                mem_pool.insert_orphan(orphan(memo, peer(port))).unwrap().unwrap();
+                clock.advance(Duration::from_secs(1));
+            }
+        }
+        assert_eq!(MAX_ORPHAN_TRANSACTIONS, mem_pool.orphans.len());
+
+        let local_id = mem_pool.insert_orphan(orphan(3_000, TxOrigin::Local)).unwrap().unwrap();
+        assert_eq!(MAX_ORPHAN_TRANSACTIONS, mem_pool.orphans.len());
+        assert!(mem_pool.orphans.contains_key(&local_id));
+        assert!(!mem_pool.orphans.contains_key(&first_ids[1]));
+        mem_pool.verify_consistency().unwrap();
+
+        // The checks that don't depend on the ledger apply to orphans as well.
+        let blacklisted = orphan(2_000, peer(1));
+        let blacklisted_id = blacklisted.transaction.transaction_id().unwrap().to_vec();
+        let mut mem_pool = MemoryPool::new().with_blacklist(vec![blacklisted_id]);
+        assert!(matches!(
+            mem_pool.insert_orphan(blacklisted),
+            Err(ConsensusError::BlacklistedTransaction(_))
+        ));
+
+        let mut mem_pool = MemoryPool::new().with_max_transaction_size(TRANSACTION_2.len() - 1);
+        assert!(matches!(
+            mem_pool.insert_orphan(orphan(2_001, peer(1))),
+            Err(ConsensusError::TransactionTooLarge(..))
+        ));
+        assert!(mem_pool.orphans.is_empty());
     }
-}
 
-impl<T: TransactionScheme> Default for MemoryPool<T> {
-    fn default() -> Self {
-        Self {
-            total_size_in_bytes: 0,
-            transactions: HashMap::<Vec<u8>, Entry<T>>::new(),
-        }
+    #[test]
+    fn expire_orphans() {
+        let clock = Arc::new(MockClock::new());
+        let mut mem_pool = MemoryPool::new()
+            .with_orphan_ttl(Duration::from_secs(60))
+            .with_clock(clock.clone());
+
+        // The ledger digest of the orphan never becomes known.
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        let transaction_id = mem_pool.insert_orphan(entry).unwrap().unwrap();
+
+        // It isn't discarded before its TTL elapses
+        clock.advance(Duration::from_secs(60));
+        assert!(mem_pool.expire_orphans().is_empty());
+        assert_eq!(1, mem_pool.orphans.len());
+
+        // It's discarded once it's past its TTL
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(mem_pool.expire_orphans(), vec![transaction_id]);
+        assert!(mem_pool.orphans.is_empty());
+        mem_pool.verify_consistency().unwrap();
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use snarkos_testing::sync::*;
-    use snarkvm_dpc::{testnet1::instantiated::Tx, Block};
+    #[test]
+    fn scheduled_transactions() {
+        let blockchain = FIXTURE_VK.ledger();
 
-    // MemoryPool tests use TRANSACTION_2 because memory pools shouldn't store coinbase transactions
+        let mut mem_pool = MemoryPool::new();
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction,
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: Some(1),
+        };
+
+        // The transaction is held back until the chain reaches its block height
+        let transaction_id = mem_pool.insert(&blockchain, entry.clone()).unwrap().unwrap();
+        assert!(mem_pool.insert(&blockchain, entry.clone()).unwrap().is_none());
+        assert_eq!(1, mem_pool.scheduled.len());
+        assert!(!mem_pool.contains(&entry));
+        assert!(mem_pool
+            .get_candidates(&blockchain, usize::MAX / 2)
+            .unwrap()
+            .0
+            .is_empty());
+
+        assert!(mem_pool.promote_scheduled(&blockchain).is_empty());
+        assert_eq!(1, mem_pool.scheduled.len());
+
+        blockchain
+            .insert_and_commit(&Block::<Tx>::read(&BLOCK_1[..]).unwrap())
+            .unwrap();
+
+        // Once it's reached, the transaction becomes a candidate for the next block
+        assert_eq!(vec![transaction_id], mem_pool.promote_scheduled(&blockchain));
+        assert!(mem_pool.scheduled.is_empty());
+        assert!(mem_pool.contains(&entry));
+        assert_eq!(
+            vec![entry.transaction],
+            mem_pool.get_candidates(&blockchain, usize::MAX / 2).unwrap().0
+        );
+        mem_pool.verify_consistency().unwrap();
+    }
 
     #[test]
-    fn push() {
+    fn rebuild_indexes() {
         let blockchain = FIXTURE_VK.ledger();
 
         let mut mem_pool = MemoryPool::new();
         let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
-        let size = TRANSACTION_2.len();
+        let transaction_id = transaction.transaction_id().unwrap().to_vec();
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction,
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
 
-        mem_pool
-            .insert(&blockchain, Entry {
-                size_in_bytes: size,
-                transaction: transaction.clone(),
-            })
-            .unwrap();
+        mem_pool.insert(&blockchain, entry.clone()).unwrap();
+        mem_pool.pin(&transaction_id);
+        mem_pool.verify_consistency().unwrap();
 
-        assert_eq!(size, mem_pool.total_size_in_bytes);
-        assert_eq!(1, mem_pool.transactions.len());
+        // Mutating the transactions directly leaves the totals and the pinned set stale
+        mem_pool.transactions.clear();
+        assert!(mem_pool.verify_consistency().is_err());
 
-        // Duplicate pushes don't do anything
+        mem_pool.rebuild_indexes();
+        mem_pool.verify_consistency().unwrap();
+        assert_eq!(0, mem_pool.total_size_in_bytes);
+        assert!(!mem_pool.is_pinned(&transaction_id));
 
-        mem_pool
-            .insert(&blockchain, Entry {
-                size_in_bytes: size,
-                transaction,
-            })
-            .unwrap();
+        // A transaction that is both in the memory pool and in the orphan pool is no longer an orphan
+        mem_pool.transactions.insert(transaction_id.clone(), entry.clone());
+        mem_pool.orphans.insert(transaction_id, entry);
+        assert!(mem_pool.verify_consistency().is_err());
 
-        assert_eq!(size, mem_pool.total_size_in_bytes);
-        assert_eq!(1, mem_pool.transactions.len());
+        mem_pool.rebuild_indexes();
+        mem_pool.verify_consistency().unwrap();
+        assert_eq!(TRANSACTION_2.len(), mem_pool.total_size_in_bytes);
+        assert!(mem_pool.orphans.is_empty());
     }
 
     #[test]
-    fn remove_entry() {
+    fn memory_footprint() {
         let blockchain = FIXTURE_VK.ledger();
 
         let mut mem_pool = MemoryPool::new();
-        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
-        let size = TRANSACTION_2.len();
+        let empty_footprint = mem_pool.memory_footprint();
+        assert_eq!(0, mem_pool.total_size_in_bytes);
+        assert!(empty_footprint > 0);
+
+        let entries = [&TRANSACTION_1[..], &TRANSACTION_2[..]]
+            .iter()
+            .map(|transaction_bytes| Entry {
+                size_in_bytes: transaction_bytes.len(),
+                transaction: Tx::read(*transaction_bytes).unwrap(),
+                origin: TxOrigin::Local,
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
+            })
+            .collect::<Vec<_>>();
+        for entry in &entries {
+            mem_pool.insert(&blockchain, entry.clone()).unwrap().unwrap();
+        }
 
-        let entry = Entry::<Tx> {
-            size_in_bytes: size,
+        // Besides the transactions themselves, every entry takes up space in the collections and has its ids stored
+        // in several of them.
+        let footprint = mem_pool.memory_footprint();
+        let min_overhead_per_entry = mem::size_of::<Entry<Tx>>() + 3 * 32;
+        assert!(footprint > mem_pool.total_size_in_bytes + empty_footprint + 2 * min_overhead_per_entry);
+
+        // Removing a transaction releases its bytes, but not the capacity of the collections.
+        mem_pool.remove(&entries[0]).unwrap().unwrap();
+        let footprint_after_removal = mem_pool.memory_footprint();
+        assert_eq!(footprint - footprint_after_removal, entries[0].size_in_bytes + 3 * 32);
+    }
+
+    #[test]
+    fn shrink_to_fit() {
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
             transaction,
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
         };
 
-        mem_pool.insert(&blockchain, entry.clone()).unwrap();
+        let mut mem_pool = MemoryPool::new();
+        assert!(!mem_pool.shrink_to_fit());
+
+        // Fill the memory pool, then drain all but one of its entries
+        for i in 0..1024u32 {
+            let transaction_id = i.to_le_bytes().to_vec();
+            mem_pool.transactions.insert(transaction_id.clone(), entry.clone());
+            mem_pool.pinned.insert(transaction_id.clone());
+            mem_pool.fee_deltas.insert(transaction_id.clone(), 100);
+            mem_pool.blacklist.insert(transaction_id);
+        }
+        let transaction_id = 0u32.to_le_bytes().to_vec();
+        mem_pool.transactions.retain(|id, _| id == &transaction_id);
+        mem_pool.pinned.retain(|id| id == &transaction_id);
+        mem_pool.fee_deltas.retain(|id, _| id == &transaction_id);
+        mem_pool.blacklist.retain(|id| id == &transaction_id);
+
+        let capacity = mem_pool.transactions.capacity();
+        let pinned_capacity = mem_pool.pinned.capacity();
+        let fee_deltas_capacity = mem_pool.fee_deltas.capacity();
+        let blacklist_capacity = mem_pool.blacklist.capacity();
+        assert!(capacity >= 1024);
+
+        assert!(mem_pool.shrink_to_fit());
+        assert!(mem_pool.transactions.capacity() < capacity);
+        assert!(mem_pool.pinned.capacity() < pinned_capacity);
+        assert!(mem_pool.fee_deltas.capacity() < fee_deltas_capacity);
+        assert!(mem_pool.blacklist.capacity() < blacklist_capacity);
+        assert_eq!(1, mem_pool.transactions.len());
+        assert!(mem_pool.is_pinned(&transaction_id));
+        assert_eq!(Some(&100), mem_pool.fee_deltas.get(&transaction_id));
+        assert!(mem_pool.is_blacklisted(&transaction_id));
+
+        // The compacted collections are no longer underutilized
+        assert!(!mem_pool.shrink_to_fit());
+    }
+
+    #[test]
+    fn max_transaction_count() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::with_max_transaction_count(1);
+
+        let entry_1 = Entry {
+            size_in_bytes: TRANSACTION_1.len(),
+            transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        let entry_2 = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        let (low, high) = if entry_1.fee_rate() < entry_2.fee_rate() {
+            (entry_1, entry_2)
+        } else {
+            (entry_2, entry_1)
+        };
 
+        mem_pool.insert(&blockchain, low.clone()).unwrap();
         assert_eq!(1, mem_pool.transactions.len());
-        assert_eq!(size, mem_pool.total_size_in_bytes);
 
-        mem_pool.remove(&entry).unwrap();
+        // The higher-priority entry evicts the lower-priority one instead of growing the pool.
+        assert!(mem_pool.insert(&blockchain, high.clone()).unwrap().is_some());
+        assert_eq!(1, mem_pool.transactions.len());
+        assert!(mem_pool.contains(&high));
+        assert!(!mem_pool.contains(&low));
+        assert_eq!(high.size_in_bytes, mem_pool.total_size_in_bytes);
 
-        assert_eq!(0, mem_pool.transactions.len());
-        assert_eq!(0, mem_pool.total_size_in_bytes);
+        // A lower-priority entry isn't admitted into a full pool.
+        assert!(mem_pool.insert(&blockchain, low.clone()).unwrap().is_none());
+        assert_eq!(1, mem_pool.transactions.len());
+        assert!(mem_pool.contains(&high));
     }
 
     #[test]
-    fn remove_transaction_by_hash() {
+    fn eviction_policies() {
+        let clock = Arc::new(MockClock::new());
+        // The same contents for every policy: the oldest, the largest and the lowest fee rate entries all differ.
+        let contents = vec![
+            (b"oldest".to_vec(), test_entry(&TRANSACTION_2[..], 1_000, 1_000)),
+            (b"largest".to_vec(), test_entry(&TRANSACTION_2[..], 10_000, 5_000)),
+            (b"lowest_fee_rate".to_vec(), test_entry(&TRANSACTION_2[..], 100, 1_000)),
+        ];
+
+        let expected_victims = [
+            (EvictionPolicy::LowestFeeRate, b"lowest_fee_rate".to_vec()),
+            (EvictionPolicy::Oldest, b"oldest".to_vec()),
+            (EvictionPolicy::Largest, b"largest".to_vec()),
+        ];
+
+        for (eviction_policy, expected_victim) in expected_victims.iter() {
+            let mut mem_pool = MemoryPool::new()
+                .with_eviction_policy(*eviction_policy)
+                .with_clock(clock.clone());
+
+            for (transaction_id, entry) in contents.iter() {
+                mem_pool.inserted_at.insert(transaction_id.clone(), clock.now());
+                mem_pool.transactions.insert(transaction_id.clone(), entry.clone());
+                clock.advance(Duration::from_secs(1));
+            }
+
+            let (victim, _) = mem_pool.eviction_candidate().unwrap();
+            assert_eq!(expected_victim, victim, "unexpected victim for {:?}", eviction_policy);
+
+            // Pinned entries are never selected.
+            mem_pool.pin(expected_victim);
+            let (victim, _) = mem_pool.eviction_candidate().unwrap();
+            assert_ne!(expected_victim, victim);
+        }
+
+        assert_eq!(EvictionPolicy::LowestFeeRate, MemoryPool::<Tx>::new().eviction_policy);
+    }
+
+    #[test]
+    fn oldest_eviction_policy() {
         let blockchain = FIXTURE_VK.ledger();
 
+        let mut mem_pool = MemoryPool::with_max_transaction_count(1).with_eviction_policy(EvictionPolicy::Oldest);
+
+        let entry_1 = Entry {
+            size_in_bytes: TRANSACTION_1.len(),
+            transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        let entry_2 = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        let (low, high) = if entry_1.fee_rate() < entry_2.fee_rate() {
+            (entry_1, entry_2)
+        } else {
+            (entry_2, entry_1)
+        };
+
+        mem_pool.insert(&blockchain, low.clone()).unwrap();
+        assert!(mem_pool.current_min_fee_per_byte() as f64 > low.fee_rate());
+
+        // The new entry evicts the oldest one, as it pays at least the same fee rate.
+        assert!(mem_pool.insert(&blockchain, high.clone()).unwrap().is_some());
+        assert_eq!(1, mem_pool.transactions.len());
+        assert!(mem_pool.contains(&high));
+        assert!(!mem_pool.contains(&low));
+
+        // An entry paying a lower fee rate can't take the place of the oldest one.
+        assert!(mem_pool.insert(&blockchain, low.clone()).unwrap().is_none());
+        assert!(mem_pool.contains(&high));
+        assert!(!mem_pool.contains(&low));
+    }
+
+    #[test]
+    fn top_n_by_fee() {
+        let highest = test_entry(&TRANSACTION_2[..], 3_000, 1_000);
+        let tied_1 = test_entry(&TRANSACTION_2[..], 2_000, 1_000);
+        let tied_2 = test_entry(&TRANSACTION_2[..], 4_000, 2_000);
+        let lowest = test_entry(&TRANSACTION_2[..], 1_000, 1_000);
+        assert_eq!(tied_1.fee_rate(), tied_2.fee_rate());
+
+        let mut mem_pool = MemoryPool::new();
+        mem_pool.transactions.insert(b"d".to_vec(), lowest.clone());
+        mem_pool.transactions.insert(b"c".to_vec(), tied_2.clone());
+        mem_pool.transactions.insert(b"b".to_vec(), tied_1.clone());
+        mem_pool.transactions.insert(b"a".to_vec(), highest.clone());
+
+        assert!(mem_pool.top_n_by_fee(0).is_empty());
+        assert_eq!(vec![&highest], mem_pool.top_n_by_fee(1));
+
+        // The tie is broken by the transaction ids, so the result doesn't depend on the iteration order.
+        assert_eq!(vec![&highest, &tied_1], mem_pool.top_n_by_fee(2));
+
+        let all = vec![&highest, &tied_1, &tied_2, &lowest];
+        assert_eq!(all, mem_pool.top_n_by_fee(4));
+        assert_eq!(all, mem_pool.top_n_by_fee(10));
+        assert_eq!(all, mem_pool.top_n_by_fee(usize::MAX));
+    }
+
+    #[test]
+    fn prioritise_transaction() {
+        let entry_with = |fee: i64| test_entry(&TRANSACTION_2[..], fee, 1_000);
+
         let mut mem_pool = MemoryPool::new();
+        mem_pool.transactions.insert(b"high".to_vec(), entry_with(3_000));
+        mem_pool.transactions.insert(b"mid".to_vec(), entry_with(2_000));
+        mem_pool.transactions.insert(b"low".to_vec(), entry_with(1_000));
+        mem_pool.rebuild_indexes();
+        let order = |mem_pool: &MemoryPool<Tx>| mem_pool.iter_by_fee().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+
+        assert_eq!(order(&mem_pool), vec![
+            b"high".to_vec(),
+            b"mid".to_vec(),
+            b"low".to_vec()
+        ]);
+
+        // Boosting the low-fee transaction selects it ahead of the higher-fee ones without changing its fee
+        assert!(mem_pool.prioritise_transaction(b"low", 2_500));
+        assert_eq!(order(&mem_pool), vec![
+            b"low".to_vec(),
+            b"high".to_vec(),
+            b"mid".to_vec()
+        ]);
+        assert_eq!(mem_pool.transactions[&b"low".to_vec()].fee_rate(), 1.0);
+        assert_eq!(
+            mem_pool.effective_fee_rate(b"low", &mem_pool.transactions[&b"low".to_vec()]),
+            3.5
+        );
+
+        // The adjustments accumulate
+        assert!(mem_pool.prioritise_transaction(b"low", -1_000));
+        assert_eq!(order(&mem_pool), vec![
+            b"high".to_vec(),
+            b"low".to_vec(),
+            b"mid".to_vec()
+        ]);
+
+        // Transactions that aren't in the memory pool can't be prioritised
+        assert!(!mem_pool.prioritise_transaction(b"unknown", 1_000));
+        assert!(!mem_pool.fee_deltas.contains_key(&b"unknown".to_vec()));
+
+        // The adjustment is dropped along with the transaction
+        mem_pool.remove_by_hash(b"low").unwrap();
+        assert!(mem_pool.fee_deltas.is_empty());
+    }
+
+    #[test]
+    fn tie_breakers() {
+        let clock = Arc::new(MockClock::new());
+        // Entries of equal fee rates, inserted in an order that differs from both their sizes and their ids.
+        let contents = vec![
+            (b"b".to_vec(), test_entry(&TRANSACTION_2[..], 2_000, 1_000)),
+            (b"c".to_vec(), test_entry(&TRANSACTION_2[..], 1_000, 500)),
+            (b"a".to_vec(), test_entry(&TRANSACTION_2[..], 4_000, 2_000)),
+        ];
+
+        let expected_orders = [
+            (TieBreaker::Oldest, vec![b"b".to_vec(), b"c".to_vec(), b"a".to_vec()]),
+            (TieBreaker::Smallest, vec![b"c".to_vec(), b"b".to_vec(), b"a".to_vec()]),
+            (TieBreaker::TransactionId, vec![
+                b"a".to_vec(),
+                b"b".to_vec(),
+                b"c".to_vec(),
+            ]),
+        ];
+
+        for (tie_breaker, expected_order) in expected_orders.iter() {
+            let mut mem_pool = MemoryPool::new().with_tie_breaker(*tie_breaker);
+            for (transaction_id, entry) in &contents {
+                mem_pool.transactions.insert(transaction_id.clone(), entry.clone());
+                mem_pool.inserted_at.insert(transaction_id.clone(), clock.now());
+                clock.advance(Duration::from_secs(1));
+            }
+
+            let order = mem_pool.iter_by_fee().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+            assert_eq!(expected_order, &order);
+
+            let top = mem_pool.top_n_by_fee(2);
+            assert_eq!(
+                vec![
+                    &mem_pool.transactions[&expected_order[0]],
+                    &mem_pool.transactions[&expected_order[1]]
+                ],
+                top
+            );
+        }
+    }
+
+    #[test]
+    fn transaction_limits() {
+        let blockchain = FIXTURE_VK.ledger();
+
         let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
-        let size = TRANSACTION_2.len();
+        let num_inputs = transaction.old_serial_numbers().len();
+        let num_outputs = transaction.new_commitments().len();
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction,
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
 
-        mem_pool
-            .insert(&blockchain, Entry {
-                size_in_bytes: size,
-                transaction: transaction.clone(),
-            })
-            .unwrap();
+        // A transaction with more inputs than allowed is rejected with a distinct error.
+        let mut mem_pool = MemoryPool::new().with_transaction_limits(num_inputs - 1, num_outputs);
+        assert!(matches!(
+            mem_pool.check_transaction_limits(&entry.transaction, entry.size_in_bytes),
+            Err(ConsensusError::TooManyTransactionInputs(..))
+        ));
+        assert!(matches!(
+            mem_pool.insert(&blockchain, entry.clone()),
+            Err(ConsensusError::TooManyTransactionInputs(..))
+        ));
+        assert!(mem_pool.transactions.is_empty());
+
+        let mut mem_pool = MemoryPool::new().with_transaction_limits(num_inputs, num_outputs - 1);
+        assert!(matches!(
+            mem_pool.check_transaction_limits(&entry.transaction, entry.size_in_bytes),
+            Err(ConsensusError::TooManyTransactionOutputs(..))
+        ));
+        assert!(matches!(
+            mem_pool.insert(&blockchain, entry.clone()),
+            Err(ConsensusError::TooManyTransactionOutputs(..))
+        ));
+        assert!(mem_pool.transactions.is_empty());
+
+        let mut mem_pool = MemoryPool::new().with_transaction_limits(num_inputs, num_outputs);
+        assert!(mem_pool
+            .check_transaction_limits(&entry.transaction, entry.size_in_bytes)
+            .is_ok());
+        assert!(mem_pool.insert(&blockchain, entry).unwrap().is_some());
+    }
 
-        assert_eq!(1, mem_pool.transactions.len());
-        assert_eq!(size, mem_pool.total_size_in_bytes);
+    #[test]
+    fn internal_double_spend() {
+        let blockchain = FIXTURE_VK.ledger();
 
-        mem_pool
-            .remove_by_hash(&transaction.transaction_id().unwrap().to_vec())
-            .unwrap();
+        // A transaction spending its first serial number twice.
+        let mut transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        transaction.old_serial_numbers[1] = transaction.old_serial_numbers[0];
+        let transaction_id = transaction.transaction_id().unwrap().to_vec();
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction,
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
 
-        assert_eq!(0, mem_pool.transactions.len());
-        assert_eq!(0, mem_pool.total_size_in_bytes);
+        let mut mem_pool = MemoryPool::new();
+        assert!(matches!(
+            mem_pool.insert(&blockchain, entry.clone()),
+            Err(ConsensusError::InternalDoubleSpend(..))
+        ));
+        assert!(mem_pool.transactions.is_empty());
+        assert_eq!(mem_pool.rejections, vec![Rejection {
+            transaction_id,
+            reason: RejectionReason::InternalDoubleSpend,
+        }]);
+
+        // The check applies to the transactions scheduled for a future block height as well.
+        let scheduled = Entry {
+            valid_from_height: Some(10),
+            ..entry
+        };
+        assert!(matches!(
+            mem_pool.insert(&blockchain, scheduled),
+            Err(ConsensusError::InternalDoubleSpend(..))
+        ));
+        assert!(mem_pool.scheduled.is_empty());
     }
 
     #[test]
-    fn get_candidates() {
+    fn internal_duplicate_commitment() {
         let blockchain = FIXTURE_VK.ledger();
 
+        // A transaction creating its first commitment twice.
+        let mut transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        transaction.new_commitments[1] = transaction.new_commitments[0];
+        let transaction_id = transaction.transaction_id().unwrap().to_vec();
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction,
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+
         let mut mem_pool = MemoryPool::new();
-        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        assert!(matches!(
+            mem_pool.insert(&blockchain, entry),
+            Err(ConsensusError::InternalDuplicateCommitment(..))
+        ));
+        assert!(mem_pool.transactions.is_empty());
+        assert_eq!(mem_pool.rejections, vec![Rejection {
+            transaction_id,
+            reason: RejectionReason::InternalDuplicateCommitment,
+        }]);
+    }
 
-        let size = to_bytes![transaction].unwrap().len();
+    #[test]
+    fn max_transaction_size() {
+        let blockchain = FIXTURE_VK.ledger();
 
-        let expected_transaction = transaction.clone();
-        mem_pool
-            .insert(&blockchain, Entry {
-                size_in_bytes: size,
-                transaction,
-            })
-            .unwrap();
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
 
-        let max_block_size = size + BLOCK_HEADER_SIZE + COINBASE_TRANSACTION_SIZE;
+        // A transaction larger than allowed is rejected with a distinct error, regardless of its fee.
+        let mut mem_pool = MemoryPool::new().with_max_transaction_size(TRANSACTION_2.len() - 1);
+        assert!(matches!(
+            mem_pool.check_transaction_limits(&entry.transaction, entry.size_in_bytes),
+            Err(ConsensusError::TransactionTooLarge(..))
+        ));
+        assert!(matches!(
+            mem_pool.insert(&blockchain, entry.clone()),
+            Err(ConsensusError::TransactionTooLarge(size, max)) if size == TRANSACTION_2.len() && max == size - 1
+        ));
+        assert!(mem_pool.transactions.is_empty());
+        assert_eq!(0, mem_pool.total_size_in_bytes);
 
-        let candidates = mem_pool.get_candidates(&blockchain, max_block_size).unwrap();
+        // A transaction of exactly the maximum size is admitted.
+        let mut mem_pool = MemoryPool::new().with_max_transaction_size(TRANSACTION_2.len());
+        assert!(mem_pool
+            .check_transaction_limits(&entry.transaction, entry.size_in_bytes)
+            .is_ok());
+        assert!(mem_pool.insert(&blockchain, entry).unwrap().is_some());
+    }
 
-        assert!(candidates.contains(&expected_transaction));
+    #[test]
+    fn blacklisted_transactions() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_1.len(),
+            transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        let transaction_id = entry.transaction.transaction_id().unwrap().to_vec();
+
+        // A blacklisted transaction is rejected with a distinct error, even before it's verified.
+        let mut mem_pool = MemoryPool::new().with_blacklist(vec![transaction_id.clone()]);
+        assert!(mem_pool.is_blacklisted(&transaction_id));
+        assert!(matches!(
+            mem_pool.check_transaction_limits(&entry.transaction, entry.size_in_bytes),
+            Err(ConsensusError::BlacklistedTransaction(_))
+        ));
+        assert!(matches!(
+            mem_pool.insert(&blockchain, entry.clone()),
+            Err(ConsensusError::BlacklistedTransaction(id)) if id == hex::encode(&transaction_id)
+        ));
+        assert!(mem_pool.transactions.is_empty());
+
+        // Once it's removed from the blacklist, it's accepted.
+        assert!(mem_pool.unblacklist(&transaction_id));
+        assert!(!mem_pool.unblacklist(&transaction_id));
+        assert_eq!(
+            mem_pool.insert(&blockchain, entry.clone()).unwrap(),
+            Some(transaction_id.clone())
+        );
+
+        // Blacklisting a transaction that's already in the memory pool removes it.
+        assert!(mem_pool.blacklist(&transaction_id).unwrap());
+        assert!(!mem_pool.contains(&entry));
+        assert_eq!(0, mem_pool.total_size_in_bytes);
+        assert!(!mem_pool.blacklist(&transaction_id).unwrap());
     }
 
     #[test]
-    fn store_memory_pool() {
+    fn admission_control_under_pressure() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
         let blockchain = FIXTURE_VK.ledger();
 
-        let mut mem_pool = MemoryPool::new();
-        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
-        mem_pool
-            .insert(&blockchain, Entry {
-                size_in_bytes: TRANSACTION_2.len(),
-                transaction,
-            })
-            .unwrap();
+        let high_pressure = Arc::new(AtomicBool::new(true));
+        let pressure = high_pressure.clone();
+
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+
+        let mut mem_pool = MemoryPool::new().with_admission_control(AdmissionControl {
+            pressure: Arc::new(move || if pressure.load(Ordering::SeqCst) { 0.95 } else { 0.5 }),
+            pressure_threshold: 0.9,
+            min_fee_rate: entry.fee_rate(),
+        });
+
+        // The transaction doesn't exceed the raised fee rate floor.
+        assert!(mem_pool.insert(&blockchain, entry.clone()).unwrap().is_none());
+        assert_eq!(0, mem_pool.transactions.len());
 
+        // Once the pressure drops, the usual admission rules apply.
+        high_pressure.store(false, Ordering::SeqCst);
+        assert!(mem_pool.insert(&blockchain, entry).unwrap().is_some());
         assert_eq!(1, mem_pool.transactions.len());
+    }
 
-        mem_pool.store(&blockchain).unwrap();
+    #[test]
+    fn free_priority_class() {
+        let blockchain = FIXTURE_VK.ledger();
 
-        let new_mem_pool = MemoryPool::from_storage(&blockchain).unwrap();
+        let free = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Free,
+            valid_from_height: None,
+        };
+        let standard = Entry {
+            size_in_bytes: TRANSACTION_1.len(),
+            transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
 
-        assert_eq!(mem_pool.total_size_in_bytes, new_mem_pool.total_size_in_bytes);
+        // A policy-allowed zero-fee transaction isn't rejected by the fee rate floor.
+        let mut mem_pool = MemoryPool::new().with_admission_control(AdmissionControl {
+            pressure: Arc::new(|| 1.0),
+            pressure_threshold: 0.9,
+            min_fee_rate: f64::MAX,
+        });
+        assert!(mem_pool.insert(&blockchain, free.clone()).unwrap().is_some());
+
+        // It's ordered behind the fee-paying transactions.
+        mem_pool.admission_control = None;
+        assert!(mem_pool.insert(&blockchain, standard.clone()).unwrap().is_some());
+
+        let entries = mem_pool.iter_by_fee().map(|(_, entry)| entry).collect::<Vec<_>>();
+        assert_eq!(entries, vec![&standard, &free]);
+
+        // It's also the first one to be evicted from a full pool.
+        let mut mem_pool = MemoryPool::with_max_transaction_count(1);
+        mem_pool.insert(&blockchain, free.clone()).unwrap();
+        assert!(mem_pool.insert(&blockchain, standard.clone()).unwrap().is_some());
+        assert!(mem_pool.contains(&standard));
+        assert!(!mem_pool.contains(&free));
     }
 
     #[test]
-    fn cleanse_memory_pool() {
+    fn conflicting_transactions() {
         let blockchain = FIXTURE_VK.ledger();
 
         let mut mem_pool = MemoryPool::new();
-        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let mut transaction_ids = vec![];
+        for transaction_bytes in [&TRANSACTION_1[..], &TRANSACTION_2[..]].iter() {
+            let transaction_id = mem_pool
+                .insert(&blockchain, Entry {
+                    size_in_bytes: transaction_bytes.len(),
+                    transaction: Tx::read(*transaction_bytes).unwrap(),
+                    origin: TxOrigin::Local,
+                    priority_class: PriorityClass::Standard,
+                    valid_from_height: None,
+                })
+                .unwrap()
+                .unwrap();
+            transaction_ids.push(transaction_id);
+        }
+
+        // A double-spend of a pooled transaction, with a different memo (and thus id).
+        let mut double_spend = Tx::read(&TRANSACTION_2[..]).unwrap();
+        double_spend.memorandum[..8].copy_from_slice(&1u64.to_le_bytes());
+        assert_eq!(mem_pool.conflicting_transactions(&double_spend), vec![transaction_ids
+            [1]
+        .clone()]);
+
+        // A pooled transaction doesn't conflict with itself.
+        let pooled = Tx::read(&TRANSACTION_1[..]).unwrap();
+        assert!(mem_pool.conflicting_transactions(&pooled).is_empty());
+    }
+
+    #[test]
+    fn rejections() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let original = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        // A double-spend of the original transaction, with a different memo (and thus id).
+        let mut double_spend = original.clone();
+        double_spend.transaction.memorandum[..8].copy_from_slice(&1u64.to_le_bytes());
+        let double_spend_id = double_spend.transaction.transaction_id().unwrap().to_vec();
+
+        let mut mem_pool = MemoryPool::new();
+        mem_pool.insert(&blockchain, original).unwrap().unwrap();
+        assert!(mem_pool.rejections.is_empty());
+
+        assert!(mem_pool.insert(&blockchain, double_spend.clone()).unwrap().is_none());
+        assert_eq!(mem_pool.rejections, vec![Rejection {
+            transaction_id: double_spend_id,
+            reason: RejectionReason::ConflictingSerialNumber,
+        }]);
+
+        // Only the most recent rejections are kept.
+        for _ in 0..MAX_RECORDED_REJECTIONS {
+            mem_pool.insert(&blockchain, double_spend.clone()).unwrap();
+        }
+        assert_eq!(MAX_RECORDED_REJECTIONS, mem_pool.rejections.len());
+    }
+
+    #[test]
+    fn replace_by_fee() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        // Variants of a transaction spending the same serial numbers, with different fees and memos (and thus ids).
+        let entry_with_fee = |fee: i64| {
+            let mut entry = test_entry(&TRANSACTION_2[..], fee, TRANSACTION_2.len());
+            entry.transaction.memorandum[..8].copy_from_slice(&fee.to_le_bytes());
+            entry
+        };
+        let original = entry_with_fee(1000);
+        let small_increase = entry_with_fee(1050);
+        let large_increase = entry_with_fee(1200);
+
+        let clock = Arc::new(MockClock::new());
+        let mut mem_pool = MemoryPool::new()
+            .with_replacement_policy(Duration::from_secs(60), 10.0)
+            .with_clock(clock.clone());
+
+        assert!(mem_pool
+            .insert_with_rbf(&blockchain, original.clone())
+            .unwrap()
+            .is_some());
+
+        // A transaction can't be replaced before the end of its replacement window.
+        clock.advance(Duration::from_secs(30));
+        assert!(mem_pool
+            .insert_with_rbf(&blockchain, large_increase.clone())
+            .unwrap()
+            .is_none());
+        assert!(mem_pool.contains(&original));
+
+        // Afterwards, it can only be replaced with a sufficiently higher fee rate.
+        clock.advance(Duration::from_secs(30));
+        assert!(mem_pool
+            .insert_with_rbf(&blockchain, small_increase.clone())
+            .unwrap()
+            .is_none());
+        assert!(mem_pool.contains(&original));
+
+        assert!(mem_pool
+            .insert_with_rbf(&blockchain, large_increase.clone())
+            .unwrap()
+            .is_some());
+        assert!(!mem_pool.contains(&original));
+        assert!(mem_pool.contains(&large_increase));
+        assert_eq!(1, mem_pool.transactions.len());
+        mem_pool.verify_consistency().unwrap();
+
+        // The replacement starts a new replacement window.
+        assert!(mem_pool
+            .insert_with_rbf(&blockchain, entry_with_fee(2000))
+            .unwrap()
+            .is_none());
+
+        // The ids of the replaced transactions are reported alongside the id of the replacement.
+        clock.advance(Duration::from_secs(60));
+        let replacement = entry_with_fee(2000);
+        let (transaction_id, replaced_ids) = mem_pool
+            .replace_by_fee(&blockchain, replacement.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            replacement.transaction.transaction_id().unwrap().to_vec(),
+            transaction_id
+        );
+        assert_eq!(
+            vec![large_increase.transaction.transaction_id().unwrap().to_vec()],
+            replaced_ids
+        );
+
+        // A blacklisted transaction doesn't replace anything.
+        clock.advance(Duration::from_secs(60));
+        let blacklisted = entry_with_fee(3000);
         mem_pool
-            .insert(&blockchain, Entry {
-                size_in_bytes: TRANSACTION_2.len(),
-                transaction,
-            })
+            .blacklist(&blacklisted.transaction.transaction_id().unwrap())
             .unwrap();
+        assert!(matches!(
+            mem_pool.replace_by_fee(&blockchain, blacklisted),
+            Err(ConsensusError::BlacklistedTransaction(_))
+        ));
+        assert!(mem_pool.contains(&replacement));
+        mem_pool.verify_consistency().unwrap();
+
+        // A pinned transaction survives a rejected replacement, still pinned.
+        let replacement_id = replacement.transaction.transaction_id().unwrap();
+        assert!(mem_pool.pin(&replacement_id));
+        assert!(mem_pool
+            .replace_by_fee(&blockchain, entry_with_fee(4000))
+            .unwrap()
+            .is_none());
+        assert!(mem_pool.contains(&replacement));
+        assert!(mem_pool.is_pinned(&replacement_id));
+        mem_pool.verify_consistency().unwrap();
+
+        // A replacement rejected only after the conflicting transaction was removed restores it along with its
+        // manual fee adjustment; here, the replacement reuses the memo of another memory pool transaction.
+        assert!(mem_pool.unpin(&replacement_id));
+        assert!(mem_pool.prioritise_transaction(&replacement_id, 500));
+        let other = test_entry(&TRANSACTION_1[..], 1, TRANSACTION_1.len());
+        assert!(mem_pool.insert(&blockchain, other.clone()).unwrap().is_some());
+        let mut reused_memo = entry_with_fee(5000);
+        reused_memo.transaction.memorandum = other.transaction.memorandum;
+        assert!(mem_pool.replace_by_fee(&blockchain, reused_memo).unwrap().is_none());
+        assert!(mem_pool.contains(&replacement));
+        assert_eq!(Some(&500), mem_pool.fee_deltas.get(&replacement_id[..]));
+        mem_pool.verify_consistency().unwrap();
+    }
 
-        assert_eq!(1, mem_pool.transactions.len());
+    #[test]
+    fn current_min_fee_per_byte() {
+        let blockchain = FIXTURE_VK.ledger();
 
-        mem_pool.store(&blockchain).unwrap();
+        // An entry paying a whole fee of 10 per byte.
+        let entry = test_entry(&TRANSACTION_2[..], 10 * TRANSACTION_2.len() as i64, TRANSACTION_2.len());
+        assert_eq!(10.0, entry.fee_rate());
 
-        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
-        let block_2 = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+        // Any transaction may enter an empty pool.
+        let mut mem_pool = MemoryPool::with_max_transaction_count(1);
+        assert_eq!(0, mem_pool.current_min_fee_per_byte());
 
-        blockchain.insert_and_commit(&block_1).unwrap();
-        blockchain.insert_and_commit(&block_2).unwrap();
+        // Once it's full, a new transaction has to outbid the one that would be evicted.
+        mem_pool.insert(&blockchain, entry.clone()).unwrap();
+        assert_eq!(11, mem_pool.current_min_fee_per_byte());
 
-        mem_pool.cleanse(&blockchain).unwrap();
+        // Under the other eviction policies, matching its fee rate is enough.
+        for eviction_policy in [EvictionPolicy::Oldest, EvictionPolicy::Largest].iter() {
+            let mut mem_pool = MemoryPool::with_max_transaction_count(1).with_eviction_policy(*eviction_policy);
+            mem_pool.insert(&blockchain, entry.clone()).unwrap();
+            assert_eq!(10, mem_pool.current_min_fee_per_byte());
+        }
 
-        assert_eq!(0, mem_pool.transactions.len());
-        assert_eq!(0, mem_pool.total_size_in_bytes);
+        // Under resource pressure, the raised fee rate floor has to be exceeded.
+        mem_pool.admission_control = Some(AdmissionControl {
+            pressure: Arc::new(|| 1.0),
+            pressure_threshold: 0.9,
+            min_fee_rate: 20.0,
+        });
+        assert_eq!(21, mem_pool.current_min_fee_per_byte());
+
+        // Pinned entries can't be evicted, so nothing can enter a pool full of them.
+        let transaction_id = entry.transaction.transaction_id().unwrap();
+        mem_pool.pin(&transaction_id);
+        assert_eq!(u64::MAX, mem_pool.current_min_fee_per_byte());
     }
 }
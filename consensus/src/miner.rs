@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{error::ConsensusError, Consensus};
+use crate::{error::ConsensusError, BlockOutcome, Consensus};
 use snarkvm_algorithms::CRH;
 use snarkvm_dpc::{
     testnet1::{instantiated::*, Record as DPCRecord},
@@ -186,7 +186,12 @@ impl<S: Storage> Miner<S> {
 
         let block = Block { header, transactions };
 
-        self.consensus.receive_block(&block)?;
+        if let BlockOutcome::Invalid(reason) = self.consensus.receive_block(&block)? {
+            return Err(ConsensusError::Message(format!(
+                "the mined block is invalid: {}",
+                reason
+            )));
+        }
 
         // Store the non-dummy coinbase records.
         let mut records_to_store = vec![];
@@ -47,6 +47,8 @@ pub struct ConsensusParameters {
     pub max_nonce: u32,
     /// The anticipated number of seconds for finding a new block.
     pub target_block_time: i64,
+    /// The number of seconds a block timestamp is allowed to be ahead of this node's clock.
+    pub max_future_block_time_drift: i64,
     /// The PoSW sync verifier (read-only mode, no proving key loaded).
     pub verifier: PoswMarlin,
     /// The authorized inner SNARK IDs.
@@ -67,7 +69,7 @@ impl ConsensusParameters {
     /// Verify all fields in a block header.
     /// 1. The parent hash points to the tip of the chain.
     /// 2. Transactions hash to merkle root.
-    /// 3. The timestamp is less than 2 hours into the future.
+    /// 3. The timestamp is no further into the future than the allowed drift.
     /// 4. The timestamp is greater than parent timestamp.
     /// 5. The header is greater than or equal to target difficulty.
     /// 6. The nonce is within the limit.
@@ -80,8 +82,6 @@ impl ConsensusParameters {
     ) -> Result<(), ConsensusError> {
         let hash_result = header.to_difficulty_hash();
 
-        let now = Utc::now().timestamp();
-        let future_timelimit: i64 = now + TWO_HOURS_UNIX;
         let expected_difficulty = self.get_block_difficulty(parent_header, header.time);
 
         if parent_header.get_hash() != header.previous_block_hash {
@@ -95,11 +95,11 @@ impl ConsensusParameters {
             return Err(ConsensusError::PedersenMerkleRoot(
                 header.pedersen_merkle_root_hash.to_string(),
             ));
-        } else if header.time > future_timelimit {
-            return Err(ConsensusError::FuturisticTimestamp(future_timelimit, header.time));
-        } else if header.time < parent_header.time {
-            return Err(ConsensusError::TimestampInvalid(header.time, parent_header.time));
-        } else if hash_result > header.difficulty_target {
+        }
+
+        self.verify_timestamp(header, parent_header)?;
+
+        if hash_result > header.difficulty_target {
             return Err(ConsensusError::PowInvalid(header.difficulty_target, hash_result));
         } else if header.nonce >= self.max_nonce {
             return Err(ConsensusError::NonceInvalid(header.nonce, self.max_nonce));
@@ -120,6 +120,20 @@ impl ConsensusParameters {
         Ok(())
     }
 
+    /// Verify that the block timestamp is greater than the parent timestamp, and that it's
+    /// no further into the future than the allowed drift from this node's clock.
+    pub fn verify_timestamp(&self, header: &BlockHeader, parent_header: &BlockHeader) -> Result<(), ConsensusError> {
+        let future_timelimit: i64 = Utc::now().timestamp() + self.max_future_block_time_drift;
+
+        if header.time > future_timelimit {
+            Err(ConsensusError::FuturisticTimestamp(future_timelimit, header.time))
+        } else if header.time < parent_header.time {
+            Err(ConsensusError::TimestampInvalid(header.time, parent_header.time))
+        } else {
+            Ok(())
+        }
+    }
+
     // TODO (raychu86): Genericize this model to allow for generic programs.
     /// Generate the birth and death program proofs for a transaction for a given transaction kernel
     #[allow(clippy::type_complexity)]
@@ -177,10 +191,10 @@ impl ConsensusParameters {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::get_block_reward;
+    use crate::{get_block_reward, has_valid_coinbase_value};
     use rand::{thread_rng, Rng};
-    use snarkos_testing::sync::DATA;
-    use snarkvm_dpc::{BlockHeaderHash, PedersenMerkleRootHash};
+    use snarkos_testing::sync::{BLOCK_1, DATA};
+    use snarkvm_dpc::{testnet1::transaction::amount::AleoAmount, Block, BlockHeaderHash, PedersenMerkleRootHash};
 
     #[test]
     fn test_block_rewards() {
@@ -223,6 +237,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_coinbase_value() {
+        let block = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        assert!(has_valid_coinbase_value(&block.transactions, 1));
+
+        let coinbase_index = block
+            .transactions
+            .iter()
+            .position(|transaction| transaction.value_balance.is_negative())
+            .unwrap();
+
+        // A coinbase claiming a single unit more than the block reward plus the fees is rejected
+        let mut inflated = block.transactions.clone();
+        inflated.0[coinbase_index].value_balance = inflated.0[coinbase_index].value_balance.sub(AleoAmount(1));
+        assert!(!has_valid_coinbase_value(&inflated, 1));
+
+        // The block reward halves eventually, so the original coinbase becomes too large as well
+        assert!(!has_valid_coinbase_value(&block.transactions, u32::MAX));
+    }
+
     #[test]
     fn verify_header() {
         // mine a PoSW proof
@@ -232,6 +266,7 @@ mod tests {
             max_block_size: 1_000_000usize,
             max_nonce: std::u32::MAX - 1,
             target_block_time: 2i64, //unix seconds
+            max_future_block_time_drift: TWO_HOURS_UNIX,
             network_id: Network::Mainnet,
             verifier: posw,
             authorized_inner_snark_ids: vec![],
@@ -306,4 +341,31 @@ mod tests {
             .verify_header(&h2_err, &h1, &merkle_root_hash, &pedersen_merkle_root)
             .unwrap_err();
     }
+
+    #[test]
+    fn verify_timestamp_drift() {
+        let consensus = ConsensusParameters {
+            max_block_size: 1_000_000usize,
+            max_nonce: std::u32::MAX - 1,
+            target_block_time: 2i64, //unix seconds
+            max_future_block_time_drift: 60,
+            network_id: Network::Mainnet,
+            verifier: PoswMarlin::verify_only().unwrap(),
+            authorized_inner_snark_ids: vec![],
+        };
+
+        let h1 = DATA.block_1.header.clone();
+        let mut h2 = DATA.block_2.header.clone();
+
+        // within the allowed drift
+        h2.time = Utc::now().timestamp() + 30;
+        consensus.verify_timestamp(&h2, &h1).unwrap();
+
+        // beyond the allowed drift
+        h2.time = Utc::now().timestamp() + 90;
+        assert!(matches!(
+            consensus.verify_timestamp(&h2, &h1),
+            Err(ConsensusError::FuturisticTimestamp(..))
+        ));
+    }
 }
@@ -15,6 +15,7 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 mod consensus_sidechain {
+    use snarkos_consensus::BlockOutcome;
     use snarkos_testing::sync::*;
     use snarkvm_dpc::{testnet1::instantiated::Tx, Block};
     use snarkvm_utilities::bytes::FromBytes;
@@ -77,6 +78,67 @@ mod consensus_sidechain {
         assert_ne!(accepted, block_1_side);
     }
 
+    // Receive a canon, a duplicate, a sidechain and an invalid block, and check the outcome of each of them.
+    #[test]
+    fn block_outcomes() {
+        let consensus = snarkos_testing::sync::create_test_consensus();
+
+        let block_1_canon = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_1_side = Block::<Tx>::read(&ALTERNATIVE_BLOCK_1[..]).unwrap();
+
+        assert_eq!(
+            consensus.receive_block(&block_1_canon).unwrap(),
+            BlockOutcome::AcceptedToTip
+        );
+        assert_eq!(
+            consensus.receive_block(&block_1_canon).unwrap(),
+            BlockOutcome::Duplicate
+        );
+        assert_eq!(
+            consensus.receive_block(&block_1_side).unwrap(),
+            BlockOutcome::AcceptedAsSideChain
+        );
+
+        // Block 2 with the transactions of block 1 doesn't match its own header.
+        let mut block_2_invalid = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+        block_2_invalid.transactions = block_1_canon.transactions.clone();
+
+        assert!(matches!(
+            consensus.receive_block(&block_2_invalid).unwrap(),
+            BlockOutcome::Invalid(..)
+        ));
+        assert_eq!(consensus.ledger.get_current_block_height(), 1);
+    }
+
+    // Receive a batch of blocks with an invalid one, which is rejected as a whole, and then a valid batch.
+    #[test]
+    fn block_batch() {
+        let consensus = snarkos_testing::sync::create_test_consensus();
+
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2 = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+
+        // Block 2 with the transactions of block 1 doesn't match its own header.
+        let mut block_2_invalid = block_2.clone();
+        block_2_invalid.transactions = block_1.transactions.clone();
+
+        let outcomes = consensus
+            .receive_block_batch(&[block_1.clone(), block_2_invalid])
+            .unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert!(matches!(outcomes[1], BlockOutcome::Invalid(..)));
+
+        // The valid block 1 was reverted along with the batch.
+        assert_eq!(consensus.ledger.get_current_block_height(), 0);
+        assert!(!consensus.ledger.block_hash_exists(&block_1.header.get_hash()));
+
+        assert_eq!(consensus.receive_block_batch(&[block_1, block_2]).unwrap(), vec![
+            BlockOutcome::AcceptedToTip,
+            BlockOutcome::AcceptedToTip
+        ]);
+        assert_eq!(consensus.ledger.get_current_block_height(), 2);
+    }
+
     // Receive blocks from a sidechain that overtakes our current canonical chain.
     #[test]
     fn accept() {
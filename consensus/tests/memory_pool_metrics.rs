@@ -0,0 +1,71 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+// The metrics recorder is global, so these tests live in a dedicated binary.
+mod memory_pool_metrics {
+    use snarkos_consensus::memory_pool::{Entry, MemoryPool, PriorityClass, TxOrigin, MEMPOOL_DUPLICATE_SUBMISSIONS};
+    use snarkos_testing::sync::*;
+    use snarkvm_dpc::testnet1::instantiated::Tx;
+    use snarkvm_utilities::bytes::FromBytes;
+
+    use metrics::{GaugeValue, Key, Recorder, Unit};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    struct DuplicateSubmissions(AtomicU64);
+
+    impl Recorder for DuplicateSubmissions {
+        fn register_counter(&self, _key: &Key, _unit: Option<Unit>, _desc: Option<&'static str>) {}
+
+        fn register_gauge(&self, _key: &Key, _unit: Option<Unit>, _desc: Option<&'static str>) {}
+
+        fn register_histogram(&self, _key: &Key, _unit: Option<Unit>, _desc: Option<&'static str>) {}
+
+        fn increment_counter(&self, key: &Key, value: u64) {
+            if key.name() == MEMPOOL_DUPLICATE_SUBMISSIONS {
+                self.0.fetch_add(value, Ordering::SeqCst);
+            }
+        }
+
+        fn update_gauge(&self, _key: &Key, _value: GaugeValue) {}
+
+        fn record_histogram(&self, _key: &Key, _value: f64) {}
+    }
+
+    static RECORDER: DuplicateSubmissions = DuplicateSubmissions(AtomicU64::new(0));
+
+    #[test]
+    fn duplicate_submissions() {
+        metrics::set_recorder(&RECORDER).unwrap();
+
+        let blockchain = FIXTURE_VK.ledger();
+        let mut mem_pool = MemoryPool::new();
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+
+        // A new transaction isn't a duplicate submission
+        assert!(mem_pool.insert(&blockchain, entry.clone()).unwrap().is_some());
+        assert_eq!(0, RECORDER.0.load(Ordering::SeqCst));
+
+        // Submitting it again is
+        assert!(mem_pool.insert(&blockchain, entry).unwrap().is_none());
+        assert_eq!(1, RECORDER.0.load(Ordering::SeqCst));
+    }
+}
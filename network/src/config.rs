@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::NetworkError;
+use crate::{NetworkError, PeerEvictionStrategy};
 
 use parking_lot::RwLock;
 use std::{
@@ -27,10 +27,16 @@ use std::{
 pub struct Config {
     /// The pre-configured desired address of this node.
     pub desired_address: SocketAddr,
+    /// The addresses, e.g. of other network interfaces, this node accepts connections on besides the desired one.
+    additional_listen_addresses: Vec<SocketAddr>,
     /// The minimum number of peers required to maintain connections with.
     minimum_number_of_connected_peers: u16,
     /// The maximum number of peers permitted to maintain connections with.
     maximum_number_of_connected_peers: u16,
+    /// The number of connection slots reserved for the peers that have never been connected to before.
+    reserved_new_peer_slots: u16,
+    /// The strategy of choosing the peers to disconnect from when connected to more peers than permitted.
+    peer_eviction_strategy: PeerEvictionStrategy,
     /// The default bootnodes of the network.
     pub bootnodes: RwLock<Vec<SocketAddr>>,
     /// If `true`, initializes this node as a bootnode and forgoes connecting
@@ -38,6 +44,34 @@ pub struct Config {
     is_bootnode: bool,
     /// The interval between each peer sync.
     peer_sync_interval: Duration,
+    /// The maximum number of messages that can be queued in the inbound channel.
+    inbound_channel_capacity: usize,
+    /// The maximum number of inbound blocks that can be validated concurrently.
+    max_concurrent_block_validations: usize,
+    /// The interval between the checks for memory pool changes that need to be persisted.
+    mempool_persistence_interval: Duration,
+    /// The maximum number of peers shared at once in response to a `GetPeers` message.
+    shared_peer_count: usize,
+    /// The number of payloads of an unknown type a peer may send before it is penalized for each further one.
+    max_unknown_payloads: u32,
+    /// The number of bad events within the strike window that result in a failure being registered for a peer.
+    failure_strikes: u32,
+    /// The duration of the window within which the strikes against a peer are counted.
+    strike_window: Duration,
+    /// The maximum amount of time small outbound messages are held back in order to be coalesced.
+    outbound_coalesce_interval: Duration,
+    /// The size of the coalesced outbound messages above which they are written immediately.
+    outbound_coalesce_size: usize,
+    /// The maximum amount of time a peer may take to send the rest of a message once it has started sending it.
+    peer_read_timeout: Duration,
+    /// The maximum amount of time a write of the queued outbound messages to a peer may take.
+    peer_write_timeout: Duration,
+    /// The maximum amount of time an in-progress block sync round may take to finish once the node is shutting down.
+    sync_drain_timeout: Duration,
+    /// If `true`, the sync messages received during an earlier block sync round than the current one are ignored.
+    sync_round_protection: bool,
+    /// The maximum number of transactions and blocks queued to be relayed to a single peer.
+    relay_queue_depth: usize,
 }
 
 impl Config {
@@ -61,14 +95,131 @@ impl Config {
 
         Ok(Self {
             desired_address,
+            additional_listen_addresses: vec![],
             minimum_number_of_connected_peers,
             maximum_number_of_connected_peers,
+            reserved_new_peer_slots: crate::RESERVED_NEW_PEER_SLOTS,
+            // Bootnodes disconnect from random peers, while regular nodes from the least useful ones.
+            peer_eviction_strategy: if is_bootnode {
+                PeerEvictionStrategy::Random
+            } else {
+                PeerEvictionStrategy::LowestQuality
+            },
             bootnodes: RwLock::new(bootnodes),
             is_bootnode,
             peer_sync_interval,
+            inbound_channel_capacity: crate::INBOUND_CHANNEL_DEPTH,
+            max_concurrent_block_validations: crate::MAX_CONCURRENT_BLOCK_VALIDATIONS,
+            mempool_persistence_interval: Duration::from_secs(crate::MEMORY_POOL_PERSISTENCE_INTERVAL_SECS),
+            shared_peer_count: crate::SHARED_PEER_COUNT,
+            max_unknown_payloads: crate::MAX_UNKNOWN_PAYLOADS,
+            failure_strikes: crate::PEER_FAILURE_STRIKES,
+            strike_window: Duration::from_secs(crate::PEER_STRIKE_WINDOW_SECS),
+            outbound_coalesce_interval: Duration::from_millis(crate::OUTBOUND_COALESCE_INTERVAL_MILLIS),
+            outbound_coalesce_size: crate::OUTBOUND_COALESCE_SIZE,
+            peer_read_timeout: Duration::from_secs(crate::PEER_READ_TIMEOUT_SECS),
+            peer_write_timeout: Duration::from_secs(crate::PEER_WRITE_TIMEOUT_SECS),
+            sync_drain_timeout: Duration::from_secs(crate::SYNC_DRAIN_TIMEOUT_SECS),
+            sync_round_protection: true,
+            relay_queue_depth: crate::RELAY_QUEUE_DEPTH,
         })
     }
 
+    /// Sets the addresses, e.g. of other network interfaces, this node accepts connections on besides the desired one.
+    pub fn with_additional_listen_addresses(mut self, additional_listen_addresses: Vec<SocketAddr>) -> Self {
+        self.additional_listen_addresses = additional_listen_addresses;
+        self
+    }
+
+    /// Sets the maximum amount of time small outbound messages are held back in order to be coalesced
+    /// into a single write, and the size of the coalesced messages above which they are written immediately.
+    pub fn with_outbound_coalescing(mut self, interval: Duration, size: usize) -> Self {
+        self.outbound_coalesce_interval = interval;
+        self.outbound_coalesce_size = size;
+        self
+    }
+
+    /// Sets the maximum amount of time a peer may take to send the rest of a message once it has started
+    /// sending it, and the maximum amount of time a write to a peer may take.
+    pub fn with_peer_io_timeouts(mut self, read_timeout: Duration, write_timeout: Duration) -> Self {
+        self.peer_read_timeout = read_timeout;
+        self.peer_write_timeout = write_timeout;
+        self
+    }
+
+    /// Sets the maximum amount of time an in-progress block sync round may take to finish once the node is shutting down.
+    pub fn with_sync_drain_timeout(mut self, sync_drain_timeout: Duration) -> Self {
+        self.sync_drain_timeout = sync_drain_timeout;
+        self
+    }
+
+    /// Sets whether the sync messages received during an earlier block sync round than the current one are ignored.
+    pub fn with_sync_round_protection(mut self, sync_round_protection: bool) -> Self {
+        self.sync_round_protection = sync_round_protection;
+        self
+    }
+
+    /// Sets the number of connection slots reserved for the peers that have never been connected to before.
+    pub fn with_reserved_new_peer_slots(mut self, reserved_new_peer_slots: u16) -> Self {
+        self.reserved_new_peer_slots = reserved_new_peer_slots.min(self.maximum_number_of_connected_peers);
+        self
+    }
+
+    /// Sets the strategy of choosing the peers to disconnect from when connected to more peers than permitted.
+    pub fn with_peer_eviction_strategy(mut self, peer_eviction_strategy: PeerEvictionStrategy) -> Self {
+        self.peer_eviction_strategy = peer_eviction_strategy;
+        self
+    }
+
+    /// Sets the maximum number of messages that can be queued in the inbound channel.
+    pub fn with_inbound_channel_capacity(mut self, inbound_channel_capacity: usize) -> Self {
+        self.inbound_channel_capacity = inbound_channel_capacity.max(1);
+        self
+    }
+
+    /// Sets the maximum number of transactions and blocks queued to be relayed to a single peer.
+    pub fn with_relay_queue_depth(mut self, relay_queue_depth: usize) -> Self {
+        self.relay_queue_depth = relay_queue_depth.max(1);
+        self
+    }
+
+    /// Sets the maximum number of inbound blocks that can be validated concurrently.
+    pub fn with_max_concurrent_block_validations(mut self, max_concurrent_block_validations: usize) -> Self {
+        self.max_concurrent_block_validations = max_concurrent_block_validations.max(1);
+        self
+    }
+
+    /// Sets the interval between the checks for memory pool changes that need to be persisted.
+    pub fn with_mempool_persistence_interval(mut self, mempool_persistence_interval: Duration) -> Self {
+        self.mempool_persistence_interval = mempool_persistence_interval;
+        self
+    }
+
+    /// Sets the maximum number of peers shared at once in response to a `GetPeers` message.
+    pub fn with_shared_peer_count(mut self, shared_peer_count: usize) -> Self {
+        self.shared_peer_count = shared_peer_count;
+        self
+    }
+
+    /// Sets the number of payloads of an unknown type a peer may send before it is penalized for each further one.
+    pub fn with_max_unknown_payloads(mut self, max_unknown_payloads: u32) -> Self {
+        self.max_unknown_payloads = max_unknown_payloads;
+        self
+    }
+
+    /// Sets the number of bad events within the given window that result in a failure being registered for a peer.
+    pub fn with_failure_strikes(mut self, failure_strikes: u32, strike_window: Duration) -> Self {
+        self.failure_strikes = failure_strikes.max(1);
+        self.strike_window = strike_window;
+        self
+    }
+
+    /// Returns the addresses this node accepts connections on besides the desired one.
+    #[inline]
+    pub fn additional_listen_addresses(&self) -> &[SocketAddr] {
+        &self.additional_listen_addresses
+    }
+
     /// Returns the default bootnodes of the network.
     #[inline]
     pub fn bootnodes(&self) -> Vec<SocketAddr> {
@@ -93,8 +244,90 @@ impl Config {
         self.maximum_number_of_connected_peers
     }
 
+    /// Returns the number of connection slots reserved for the peers that have never been connected to before.
+    #[inline]
+    pub fn reserved_new_peer_slots(&self) -> u16 {
+        self.reserved_new_peer_slots
+    }
+
+    /// Returns the strategy of choosing the peers to disconnect from when connected to more peers than permitted.
+    #[inline]
+    pub fn peer_eviction_strategy(&self) -> PeerEvictionStrategy {
+        self.peer_eviction_strategy
+    }
+
     /// Returns the interval between each peer sync.
     pub fn peer_sync_interval(&self) -> Duration {
         self.peer_sync_interval
     }
+
+    /// Returns the maximum number of messages that can be queued in the inbound channel.
+    pub fn inbound_channel_capacity(&self) -> usize {
+        self.inbound_channel_capacity
+    }
+
+    /// Returns the maximum number of inbound blocks that can be validated concurrently.
+    pub fn max_concurrent_block_validations(&self) -> usize {
+        self.max_concurrent_block_validations
+    }
+
+    /// Returns the interval between the checks for memory pool changes that need to be persisted.
+    pub fn mempool_persistence_interval(&self) -> Duration {
+        self.mempool_persistence_interval
+    }
+
+    /// Returns the maximum number of peers shared at once in response to a `GetPeers` message.
+    pub fn shared_peer_count(&self) -> usize {
+        self.shared_peer_count
+    }
+
+    /// Returns the number of payloads of an unknown type a peer may send before it is penalized for each further one.
+    pub fn max_unknown_payloads(&self) -> u32 {
+        self.max_unknown_payloads
+    }
+
+    /// Returns the number of bad events within the strike window that result in a failure being registered for a peer.
+    pub fn failure_strikes(&self) -> u32 {
+        self.failure_strikes
+    }
+
+    /// Returns the duration of the window within which the strikes against a peer are counted.
+    pub fn strike_window(&self) -> Duration {
+        self.strike_window
+    }
+
+    /// Returns the maximum amount of time small outbound messages are held back in order to be coalesced.
+    pub fn outbound_coalesce_interval(&self) -> Duration {
+        self.outbound_coalesce_interval
+    }
+
+    /// Returns the size of the coalesced outbound messages above which they are written immediately.
+    pub fn outbound_coalesce_size(&self) -> usize {
+        self.outbound_coalesce_size
+    }
+
+    /// Returns the maximum amount of time a peer may take to send the rest of a message once it has started sending it.
+    pub fn peer_read_timeout(&self) -> Duration {
+        self.peer_read_timeout
+    }
+
+    /// Returns the maximum amount of time a write of the queued outbound messages to a peer may take.
+    pub fn peer_write_timeout(&self) -> Duration {
+        self.peer_write_timeout
+    }
+
+    /// Returns the maximum amount of time an in-progress block sync round may take to finish once the node is shutting down.
+    pub fn sync_drain_timeout(&self) -> Duration {
+        self.sync_drain_timeout
+    }
+
+    /// Returns `true` if the sync messages received during an earlier block sync round than the current one are ignored.
+    pub fn sync_round_protection(&self) -> bool {
+        self.sync_round_protection
+    }
+
+    /// Returns the maximum number of transactions and blocks queued to be relayed to a single peer.
+    pub fn relay_queue_depth(&self) -> usize {
+        self.relay_queue_depth
+    }
 }
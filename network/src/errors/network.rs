@@ -39,9 +39,11 @@ pub enum NetworkError {
     PeerBookMissingPeer,
     PeerCountInvalid,
     PeerIsDisconnected,
+    ReadTimeout,
     SelfConnectAttempt,
     SenderError(tokio::sync::mpsc::error::SendError<Message>),
     TooManyConnections,
+    WriteTimeout,
     OutboundChannelMissing,
     ReceiverFailedToParse,
     StorageError(StorageError),
@@ -59,7 +61,12 @@ impl NetworkError {
             ]
             .contains(&err.kind()),
             // other critical errors
-            Self::CapnProto(_) | Self::MessageTooBig(..) | Self::ZeroLengthMessage | Self::Noise(_) => true,
+            Self::CapnProto(_)
+            | Self::MessageTooBig(..)
+            | Self::ZeroLengthMessage
+            | Self::Noise(_)
+            | Self::ReadTimeout
+            | Self::WriteTimeout => true,
             _ => false,
         }
     }
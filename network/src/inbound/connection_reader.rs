@@ -17,9 +17,13 @@
 use crate::{errors::*, message::*};
 
 use parking_lot::Mutex;
-use tokio::{io::AsyncReadExt, net::tcp::OwnedReadHalf};
+use tokio::{
+    io::AsyncReadExt,
+    net::tcp::OwnedReadHalf,
+    time::{timeout_at, Instant},
+};
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 pub struct ConnReader {
     pub addr: SocketAddr,
@@ -27,6 +31,8 @@ pub struct ConnReader {
     buffer: Box<[u8]>,
     noise_buffer: Box<[u8]>,
     noise: Arc<Mutex<snow::TransportState>>,
+    /// The maximum amount of time the rest of a message may take to arrive once it has started arriving.
+    read_timeout: Duration,
 }
 
 impl ConnReader {
@@ -42,13 +48,27 @@ impl ConnReader {
             noise_buffer: vec![0u8; crate::NOISE_BUF_LEN].into(),
             buffer,
             noise,
+            read_timeout: Duration::from_secs(crate::PEER_READ_TIMEOUT_SECS),
         }
     }
 
-    /// Returns a message header read from an input stream.
-    pub async fn read_header(&mut self) -> Result<MessageHeader, NetworkError> {
+    /// Sets the maximum amount of time the rest of a message may take to arrive once it has started arriving.
+    pub fn with_read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = read_timeout;
+        self
+    }
+
+    /// Returns a message header read from an input stream, along with the deadline by which the rest of the
+    /// message has to arrive.
+    pub async fn read_header(&mut self) -> Result<(MessageHeader, Instant), NetworkError> {
         let mut header_arr = [0u8; 4];
-        self.reader.read_exact(&mut header_arr).await?;
+        // The peer may stay idle between messages, but once a message has started arriving, all of it must
+        // arrive in time.
+        self.reader.read_exact(&mut header_arr[..1]).await?;
+        let deadline = Instant::now() + self.read_timeout;
+        timeout_at(deadline, self.reader.read_exact(&mut header_arr[1..]))
+            .await
+            .map_err(|_| NetworkError::ReadTimeout)??;
         let header = MessageHeader::from(header_arr);
 
         if header.len as usize > crate::MAX_MESSAGE_SIZE {
@@ -56,13 +76,13 @@ impl ConnReader {
         } else if header.len == 0 {
             Err(NetworkError::ZeroLengthMessage)
         } else {
-            Ok(header)
+            Ok((header, deadline))
         }
     }
 
     /// Reads a message header + payload.
     pub async fn read_message(&mut self) -> Result<Message, NetworkError> {
-        let header = self.read_header().await?;
+        let (header, deadline) = self.read_header().await?;
         let len = header.len();
         let mut decrypted_len = 0;
         let mut processed_len = 0;
@@ -70,7 +90,9 @@ impl ConnReader {
         {
             while processed_len < len {
                 let chunk_len = std::cmp::min(crate::NOISE_BUF_LEN, len - processed_len);
-                self.reader.read_exact(&mut self.noise_buffer[..chunk_len]).await?;
+                timeout_at(deadline, self.reader.read_exact(&mut self.noise_buffer[..chunk_len]))
+                    .await
+                    .map_err(|_| NetworkError::ReadTimeout)??;
                 processed_len += chunk_len;
 
                 decrypted_len += self
@@ -16,7 +16,12 @@
 
 use crate::{errors::NetworkError, message::*, stats, Cache, ConnReader, ConnWriter, Node, Receiver, Sender, State};
 
-use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use parking_lot::Mutex;
 use snarkvm_dpc::Storage;
@@ -37,21 +42,41 @@ pub struct Inbound {
     pub(crate) sender: Sender,
     /// The consumer for receiving inbound messages to the server.
     receiver: Mutex<Option<Receiver>>,
+    /// The maximum number of messages that can be queued in the channel.
+    capacity: usize,
 }
 
 impl Default for Inbound {
     fn default() -> Self {
+        Self::new(crate::INBOUND_CHANNEL_DEPTH)
+    }
+}
+
+impl Inbound {
+    /// Creates a new inbound handler whose channel can queue up to the given number of messages.
+    pub fn new(capacity: usize) -> Self {
         // Initialize the sender and receiver.
-        let (sender, receiver) = tokio::sync::mpsc::channel(crate::INBOUND_CHANNEL_DEPTH);
+        let (sender, receiver) = tokio::sync::mpsc::channel(capacity);
 
         Self {
             sender,
             receiver: Mutex::new(Some(receiver)),
+            capacity,
         }
     }
-}
 
-impl Inbound {
+    /// Returns the maximum number of messages that can be queued in the inbound channel.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of messages currently queued in the inbound channel.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.capacity - self.sender.capacity()
+    }
+
     #[inline]
     pub(crate) fn take_receiver(&self) -> Receiver {
         self.receiver
@@ -62,7 +87,8 @@ impl Inbound {
 }
 
 impl<S: Storage + Send + Sync + 'static> Node<S> {
-    /// This method handles new inbound connection requests.
+    /// This method handles new inbound connection requests at the desired address, as well as at
+    /// any additional listen addresses.
     pub async fn listen(&self) -> Result<(), NetworkError> {
         let listener = TcpListener::bind(&self.config.desired_address).await?;
         let own_listener_address = listener.local_addr()?;
@@ -70,6 +96,28 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         self.set_local_address(own_listener_address);
         info!("Initializing listener for node ({:x})", self.id);
 
+        let mut listeners = vec![(listener, own_listener_address)];
+        for additional_address in self.config.additional_listen_addresses() {
+            let listener = TcpListener::bind(additional_address).await?;
+            let own_listener_address = listener.local_addr()?;
+            listeners.push((listener, own_listener_address));
+        }
+
+        let listen_addresses = listeners.iter().map(|(_, address)| *address).collect();
+        if self.listen_addresses.set(listen_addresses).is_err() {
+            panic!("listen addresses were set more than once!");
+        }
+
+        for (listener, own_listener_address) in listeners {
+            self.spawn_listener(listener, own_listener_address);
+        }
+
+        Ok(())
+    }
+
+    /// Accepts the connection requests received by the given listener; the peers that connect through it
+    /// are tagged with its address.
+    fn spawn_listener(&self, listener: TcpListener, own_listener_address: SocketAddr) {
         let node_clone = self.clone();
         let listener_handle = task::spawn(async move {
             info!("Listening for nodes at {}", own_listener_address);
@@ -95,6 +143,8 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
 
                             match handshake_result {
                                 Ok(Ok(remote_listener)) => {
+                                    node.set_accepted_inbound_connection();
+
                                     // Immediately send a ping to provide the peer with our block height.
                                     node.send_ping(remote_listener);
                                 }
@@ -121,8 +171,6 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         });
 
         self.register_task(listener_handle);
-
-        Ok(())
     }
 
     /// This method handles new inbound messages from a single connected node.
@@ -138,6 +186,11 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
                     error!("Unable to read message from {}: {}", reader.addr, error);
                     failure_count += 1;
 
+                    // A peer that stalls in the middle of a message is penalized.
+                    if matches!(error, NetworkError::ReadTimeout) {
+                        self.register_failure(reader.addr);
+                    }
+
                     // Determine if we should disconnect.
                     let disconnect_from_peer = error.is_fatal() || failure_count >= 10;
 
@@ -171,6 +224,14 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
                     _ => {}
                 }
 
+                // Sync messages are tagged with the current block sync round, so that the ones
+                // still queued once a new round starts can be told apart.
+                let message = if matches!(message.payload, Payload::Sync(..) | Payload::SyncBlock(..)) {
+                    message.with_sync_round(self.block_sync_round())
+                } else {
+                    message
+                };
+
                 // Messages are queued in a single tokio MPSC receiver.
                 self.route(message)
             }
@@ -182,10 +243,14 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         receiver: &mut Receiver,
         cache: &mut Cache,
     ) -> Result<(), NetworkError> {
-        let Message { direction, payload } = receiver.recv().await.ok_or(NetworkError::ReceiverFailedToParse)?;
+        let message = receiver.recv().await.ok_or(NetworkError::ReceiverFailedToParse)?;
+        let received_at = Instant::now();
 
         metrics::decrement_gauge!(stats::QUEUES_INBOUND, 1.0);
 
+        let is_stale_sync_message = self.is_stale_sync_message(&message);
+        let Message { direction, payload, .. } = message;
+
         let source = if let Direction::Inbound(addr) = direction {
             addr
         } else {
@@ -194,12 +259,47 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
 
         self.peer_book.register_message(source);
 
+        // The sync messages from an earlier block sync round mustn't affect the current one.
+        if is_stale_sync_message {
+            trace!(
+                "Ignoring a {} from {} received during an earlier block sync round",
+                payload,
+                source
+            );
+            return Ok(());
+        }
+
+        // A `SyncBlock` that wasn't requested from its source is treated like a block announcement, so
+        // that it can't affect the progress of a block sync round.
+        let payload = match payload {
+            Payload::SyncBlock(block) if !self.peer_book.is_expecting_sync_blocks(source) => {
+                trace!("Received an unsolicited sync block from {}", source);
+                Payload::Block(block)
+            }
+            payload => payload,
+        };
+
+        // While shutting down, the node only finishes the block sync round in progress.
+        if self.is_shutting_down() && !matches!(payload, Payload::SyncBlock(..)) {
+            return Ok(());
+        }
+
         // Check if the message hasn't already been processed recently if it's a `Block`.
         // The node should also reject them while syncing, as it is bound to receive them later.
         if matches!(payload, Payload::Block(..)) && (self.state() == State::Syncing || cache.contains(&payload)) {
             return Ok(());
         }
 
+        // Record the processing time per payload type, so that slow handlers can be told apart.
+        let payload_type = payload.to_string();
+        let result = self.inner_dispatch_payload(source, payload).await;
+        metrics::histogram!(stats::INBOUND_PROCESSING_TIME, received_at.elapsed(), "payload" => payload_type);
+
+        result
+    }
+
+    /// Routes a single inbound payload to its handler.
+    async fn inner_dispatch_payload(&self, source: SocketAddr, payload: Payload) -> Result<(), NetworkError> {
         match payload {
             Payload::Transaction(transaction) => {
                 metrics::increment_counter!(stats::INBOUND_TRANSACTIONS);
@@ -208,22 +308,37 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
                     self.received_memory_pool_transaction(source, transaction)?;
                 }
             }
+            Payload::TransactionReplacement(replaced_ids, transaction) => {
+                metrics::increment_counter!(stats::INBOUND_TRANSACTIONS);
+
+                if self.sync().is_some() {
+                    self.received_transaction_replacement(source, replaced_ids, transaction)?;
+                }
+            }
             Payload::Block(block) => {
                 metrics::increment_counter!(stats::INBOUND_BLOCKS);
 
                 if self.sync().is_some() {
-                    self.received_block(source, block, true)?;
+                    self.received_new_block(source, block).await?;
                 }
             }
             Payload::SyncBlock(block) => {
                 metrics::increment_counter!(stats::INBOUND_SYNCBLOCKS);
 
                 if self.sync().is_some() {
-                    self.received_block(source, block, false)?;
-
-                    // Update the peer and possibly finish the sync process.
-                    if self.peer_book.got_sync_block(source) {
+                    // Process the block and possibly finish the sync process.
+                    if self.received_sync_block(source, block)? {
                         self.finished_syncing_blocks();
+
+                        // Remove the memory pool transactions invalidated by the whole batch of sync blocks.
+                        let sync = self.expect_sync();
+                        if let Err(e) = sync
+                            .memory_pool()
+                            .lock()
+                            .trim_to_height(sync.storage(), sync.current_block_height())
+                        {
+                            debug!("Failed to trim the memory pool after syncing blocks: {}", e);
+                        }
                     }
                 }
             }
@@ -245,7 +360,7 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
                 metrics::increment_counter!(stats::INBOUND_MEMORYPOOL);
 
                 if self.sync().is_some() {
-                    self.received_memory_pool(mempool)?;
+                    self.received_memory_pool(source, mempool)?;
                 }
             }
             Payload::GetSync(getsync) => {
@@ -259,10 +374,21 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
                 metrics::increment_counter!(stats::INBOUND_SYNCS);
 
                 if self.sync().is_some() {
-                    if sync.is_empty() {
+                    if self.peer_book.received_sync_hashes(source) > self.expect_sync().max_sync_hash_packets() {
+                        // Discard the block hashes of a peer that sends more of them than requested,
+                        // and exclude it from the ongoing sync round.
+                        warn!("{} sent more block hash packets than requested", source);
+                        self.register_failure(source);
+                        self.peer_book.expecting_sync_blocks(source, 0);
+
+                        if self.is_syncing_blocks() && self.expect_sync().sync_peer() == Some(source) {
+                            self.set_state(State::Idle);
+                        }
+                    } else if sync.is_empty() {
                         // An empty `Sync` is unexpected, as `GetSync` requests are only
-                        // sent to peers that declare a greater block height.
-                        self.peer_book.register_failure(source);
+                        // sent to peers that declare a greater block height; another peer may be asked instead.
+                        self.gather_more_sync_hashes(source, 0);
+                        self.register_failure(source);
                         warn!("{} doesn't have sync blocks to share", source);
                     } else if self.peer_book.expecting_sync_blocks(source, sync.len()) {
                         trace!("Received {} sync block hashes from {}", sync.len(), source);
@@ -280,10 +406,10 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
 
                 self.process_inbound_peers(peers);
             }
-            Payload::Ping(block_height) => {
+            Payload::Ping(block_height, min_fee_per_byte) => {
                 metrics::increment_counter!(stats::INBOUND_PINGS);
 
-                self.peer_book.received_ping(source, block_height);
+                self.peer_book.received_ping(source, block_height, min_fee_per_byte);
             }
             Payload::Pong => {
                 metrics::increment_counter!(stats::INBOUND_PONGS);
@@ -292,6 +418,13 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
             Payload::Unknown => {
                 metrics::increment_counter!(stats::INBOUND_UNKNOWN);
                 warn!("Unknown payload received; this could indicate that the client you're using is out-of-date");
+
+                // Tolerate a few unknown payloads, as they may come from a more recent client, but
+                // penalize the peers that keep sending them.
+                if self.peer_book.received_unknown_payload(source) > self.config.max_unknown_payloads() {
+                    warn!("{} sent too many payloads of an unknown type", source);
+                    self.register_failure(source);
+                }
             }
         }
 
@@ -366,7 +499,20 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         // the remote listening address
         let remote_listener = SocketAddr::from((remote_address.ip(), peer_version.listening_port));
 
-        self.set_connected(remote_address, remote_listener, noise, buffer, reader, writer)?;
+        // the remaining connection slots may be reserved for new peers
+        if !self.has_slot_for(remote_listener) {
+            return Err(NetworkError::TooManyConnections);
+        }
+
+        self.set_connected(
+            remote_address,
+            remote_listener,
+            Some(own_listener_address),
+            noise,
+            buffer,
+            reader,
+            writer,
+        )?;
 
         metrics::increment_counter!(stats::HANDSHAKES_SUCCESSES_RESP);
 
@@ -390,3 +536,159 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use snarkos_storage::LedgerStorage;
+    use std::sync::atomic::Ordering;
+
+    #[tokio::test]
+    async fn inbound_channel_depth() {
+        let config = Config::new(
+            "127.0.0.1:0".parse().unwrap(),
+            1,
+            10,
+            vec![],
+            false,
+            Duration::from_secs(10),
+        )
+        .unwrap()
+        .with_inbound_channel_capacity(4);
+        // The node's services aren't started, so the routed messages remain queued.
+        let node = Node::<LedgerStorage>::new(config).await.unwrap();
+        let source = "127.0.0.1:4141".parse().unwrap();
+
+        assert_eq!(node.inbound.capacity(), 4);
+        assert_eq!(node.inbound.depth(), 0);
+
+        for expected_depth in 1..=4 {
+            node.route(Message::new(Direction::Inbound(source), Payload::GetPeers));
+            assert_eq!(node.inbound.depth(), expected_depth);
+        }
+
+        // Messages routed into a full channel are dropped.
+        node.route(Message::new(Direction::Inbound(source), Payload::GetPeers));
+        assert_eq!(node.inbound.depth(), 4);
+    }
+
+    #[tokio::test]
+    async fn unknown_payload_penalty() {
+        let config = Config::new(
+            "127.0.0.1:0".parse().unwrap(),
+            1,
+            10,
+            vec![],
+            false,
+            Duration::from_secs(10),
+        )
+        .unwrap()
+        .with_max_unknown_payloads(2);
+        let node = Node::<LedgerStorage>::new(config).await.unwrap();
+        let source = "127.0.0.1:4141".parse().unwrap();
+        node.peer_book.set_connected(source, None);
+        let quality = node.peer_book.get_peer(source, true).unwrap().quality;
+
+        // A packed `Payload` with a discriminant that's not in the schema.
+        let payload = Payload::deserialize(&[0x10, 0x03, 0x50, 0x01, 0x01, 0x03, 0xFF, 0xFF, 0x00, 0x00]).unwrap();
+        assert_eq!(payload, Payload::Unknown);
+
+        let mut receiver = node.inbound.take_receiver();
+        let mut cache = Cache::default();
+
+        // The first few unknown payloads are tolerated.
+        for _ in 0..2 {
+            node.route(Message::new(Direction::Inbound(source), payload.clone()));
+            node.process_incoming_messages(&mut receiver, &mut cache).await.unwrap();
+        }
+        assert_eq!(quality.unknown_payloads.load(Ordering::SeqCst), 2);
+        assert_eq!(quality.failures.load(Ordering::SeqCst), 0);
+
+        // Each one over the limit is penalized.
+        for expected_failures in 1..=2 {
+            node.route(Message::new(Direction::Inbound(source), payload.clone()));
+            node.process_incoming_messages(&mut receiver, &mut cache).await.unwrap();
+            assert_eq!(quality.failures.load(Ordering::SeqCst), expected_failures);
+        }
+    }
+    #[tokio::test]
+    async fn failure_strikes() {
+        let config = Config::new(
+            "127.0.0.1:0".parse().unwrap(),
+            1,
+            10,
+            vec![],
+            false,
+            Duration::from_secs(10),
+        )
+        .unwrap()
+        .with_max_unknown_payloads(0)
+        .with_failure_strikes(3, Duration::from_secs(60));
+        let node = Node::<LedgerStorage>::new(config).await.unwrap();
+        let source = "127.0.0.1:4141".parse().unwrap();
+        node.peer_book.set_connected(source, None);
+        let quality = node.peer_book.get_peer(source, true).unwrap().quality;
+
+        let mut receiver = node.inbound.take_receiver();
+        let mut cache = Cache::default();
+
+        // Every unknown payload is a bad event, but only every third one within the window is a failure.
+        for expected_failures in &[0, 0, 1, 1, 1, 2] {
+            node.route(Message::new(Direction::Inbound(source), Payload::Unknown));
+            node.process_incoming_messages(&mut receiver, &mut cache).await.unwrap();
+            assert_eq!(quality.failures.load(Ordering::SeqCst), *expected_failures);
+        }
+    }
+
+    #[tokio::test]
+    async fn stale_sync_messages() {
+        let config = Config::new(
+            "127.0.0.1:0".parse().unwrap(),
+            1,
+            10,
+            vec![],
+            false,
+            Duration::from_secs(10),
+        )
+        .unwrap();
+        let node = Node::<LedgerStorage>::new(config).await.unwrap();
+        let source = "127.0.0.1:4141".parse().unwrap();
+        node.peer_book.set_connected(source, None);
+
+        // A sync block received during the current block sync round is processed.
+        node.register_block_sync_attempt(source, 1);
+        let sync_block = Message::new(Direction::Inbound(source), Payload::SyncBlock(vec![]));
+        let current_sync_block = sync_block.clone().with_sync_round(node.block_sync_round());
+        assert!(!node.is_stale_sync_message(&current_sync_block));
+
+        // Once a new round starts, it's stale and ignored.
+        node.register_block_sync_attempt(source, 1);
+        assert!(node.is_stale_sync_message(&current_sync_block));
+
+        let mut receiver = node.inbound.take_receiver();
+        let mut cache = Cache::default();
+        node.route(current_sync_block);
+        node.process_incoming_messages(&mut receiver, &mut cache).await.unwrap();
+        assert!(node.is_syncing_blocks());
+
+        // Messages that weren't tagged with a round are never stale.
+        assert!(!node.is_stale_sync_message(&sync_block));
+
+        // The protection can be disabled.
+        let config = Config::new(
+            "127.0.0.1:0".parse().unwrap(),
+            1,
+            10,
+            vec![],
+            false,
+            Duration::from_secs(10),
+        )
+        .unwrap()
+        .with_sync_round_protection(false);
+        let node = Node::<LedgerStorage>::new(config).await.unwrap();
+        let stale_sync_block = sync_block.with_sync_round(node.block_sync_round());
+        node.register_block_sync_attempt(source, 1);
+        assert!(!node.is_stale_sync_message(&stale_sync_block));
+    }
+}
@@ -56,9 +56,32 @@ pub use sync::*;
 
 /// The maximum number of block hashes that can be requested or provided in a single batch.
 pub const MAX_BLOCK_SYNC_COUNT: u32 = 64;
+/// The maximum number of block locator hashes accepted in a single `GetSync`; the locators built by
+/// `get_block_locator_hashes` hold fewer than that for any 32-bit block height.
+pub const MAX_BLOCK_LOCATOR_HASHES: usize = 64;
+/// The maximum number of the most recent block hashes advertised by a peer that are remembered.
+pub const MAX_ADVERTISED_BLOCKS: usize = 256;
 /// The maximum amount of time allowed to process a single batch of sync blocks. It should be aligned
 /// with `MAX_BLOCK_SYNC_COUNT`.
 pub const BLOCK_SYNC_EXPIRATION_SECS: u8 = 30;
+/// The default maximum number of attempts to reconnect to a sync peer that disconnected while it was ahead.
+pub const MAX_SYNC_PEER_RECONNECT_ATTEMPTS: u8 = 3;
+/// The default maximum number of block hash packets a peer may send in response to a single `GetSync`.
+pub const MAX_SYNC_HASH_PACKETS: u32 = 1;
+/// The default maximum number of peers asked for block hashes in a single block sync round.
+pub const MAX_SYNC_HASH_ROUNDS: u8 = 2;
+/// The default maximum number of block hashes in a segment of a block sync round downloaded from a single peer.
+pub const SYNC_SEGMENT_SIZE: usize = 16;
+/// The default maximum number of blocks served to a single peer per second, after an initial burst of as many.
+pub const MAX_SERVED_BLOCKS_PER_SECOND: u32 = 64;
+/// The maximum amount of time a block served to a peer can be held back for; the blocks requested beyond
+/// that aren't served. It should be aligned with `BLOCK_SYNC_EXPIRATION_SECS`.
+pub const MAX_BLOCK_SERVING_DELAY_SECS: u64 = 30;
+/// The default number of blocks a peer may claim to be ahead of the node beyond what the time elapsed since the
+/// node's latest block can account for.
+pub const MAX_SYNC_HEIGHT_LEAD: u32 = 1024;
+/// The maximum number of peers from a single /24 (IPv4) or /48 (IPv6) network that sync blocks are downloaded from at once.
+pub const MAX_SYNC_PEERS_PER_SUBNET: usize = 2;
 
 /// The noise handshake pattern.
 pub const HANDSHAKE_PATTERN: &str = "Noise_XXpsk3_25519_ChaChaPoly_SHA256";
@@ -78,16 +101,61 @@ pub const HANDSHAKE_PEER_TIMEOUT_SECS: u8 = 5;
 /// The amount of time after which a peer will be considered inactive an disconnected from if they have
 /// not sent any messages in the meantime.
 pub const MAX_PEER_INACTIVITY_SECS: u8 = 30;
-
+/// The default maximum amount of time a peer may take to send the rest of a message once it has started sending it.
+pub const PEER_READ_TIMEOUT_SECS: u64 = 10;
+/// The default maximum amount of time a write of the queued outbound messages to a peer may take.
+pub const PEER_WRITE_TIMEOUT_SECS: u64 = 10;
+/// The default maximum amount of time an in-progress block sync round may take to finish once the node is shutting down.
+pub const SYNC_DRAIN_TIMEOUT_SECS: u64 = 10;
+
+/// The round-trip time of a `Ping` above which a peer is disconnected from.
+pub const MAX_PEER_RTT_MS: u64 = 1500;
+/// The number of failures at which a peer is disconnected from.
+pub const MAX_PEER_FAILURES: u32 = 3;
+
+/// The age at which a fee rate hint shared by a peer is no longer taken into account.
+pub const MAX_FEE_RATE_HINT_AGE_SECS: u64 = 1800;
+/// The highest fee rate hint taken from a peer; the higher ones are clamped to it.
+pub const MAX_FEE_RATE_HINT: u64 = 1_000_000;
+
+/// The upper bound of the random delay applied to each scheduled `Ping`, so that pings to different
+/// peers are spread out over time instead of being sent in bursts.
+pub const PING_JITTER_MILLIS: u64 = 500;
+
+/// The interval between the compactions of the memory pool collections.
+pub const MEMORY_POOL_COMPACTION_INTERVAL_SECS: u64 = 60;
+/// The default interval between the checks for memory pool changes that need to be persisted.
+pub const MEMORY_POOL_PERSISTENCE_INTERVAL_SECS: u64 = 30;
+
+/// The maximum number of transaction ids a `TransactionReplacement` may list as replaced; a transaction can only
+/// conflict with as many memory pool transactions as it has serial numbers and a memo.
+pub const MAX_REPLACED_TRANSACTIONS: usize = 16;
 /// The maximum size of a message that can be transmitted in the network.
 pub const MAX_MESSAGE_SIZE: usize = 8 * 1024 * 1024; // 8MiB
-/// The maximum number of peers shared at once in response to a `GetPeers` message.
+/// The default maximum number of peers shared at once in response to a `GetPeers` message.
 pub const SHARED_PEER_COUNT: usize = 25;
+/// The default number of bad events within the strike window that result in a failure being registered for a peer.
+pub const PEER_FAILURE_STRIKES: u32 = 1;
+/// The default duration of the window within which the strikes against a peer are counted.
+pub const PEER_STRIKE_WINDOW_SECS: u64 = 60;
+/// The default number of payloads of an unknown type a peer may send before it is penalized for each further one.
+pub const MAX_UNKNOWN_PAYLOADS: u32 = 10;
+
+/// The default number of connection slots reserved for the peers that have never been connected to before.
+pub const RESERVED_NEW_PEER_SLOTS: u16 = 0;
 
 /// The depth of the common inbound channel.
 pub const INBOUND_CHANNEL_DEPTH: usize = 16 * 1024;
+/// The default maximum number of inbound blocks that can be validated concurrently.
+pub const MAX_CONCURRENT_BLOCK_VALIDATIONS: usize = 4;
 /// The depth of the per-connection outbound channels.
 pub const OUTBOUND_CHANNEL_DEPTH: usize = 1024;
+/// The default maximum number of transactions and blocks queued to be relayed to a single peer.
+pub const RELAY_QUEUE_DEPTH: usize = 256;
+/// The default maximum amount of time small outbound messages are held back in order to be coalesced into a single write.
+pub const OUTBOUND_COALESCE_INTERVAL_MILLIS: u64 = 5;
+/// The default size of the coalesced outbound messages above which they are written immediately.
+pub const OUTBOUND_COALESCE_SIZE: usize = 64 * 1024;
 
 /// The version of the network protocol; it can be incremented in order to force users to update.
 /// FIXME: probably doesn't need to be a u64, could also be more informative than just a number
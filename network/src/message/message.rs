@@ -38,11 +38,23 @@ impl fmt::Display for Direction {
 pub struct Message {
     pub direction: Direction,
     pub payload: Payload,
+    /// The block sync round an inbound sync message was received in.
+    pub sync_round: Option<u64>,
 }
 
 impl Message {
     pub fn new(direction: Direction, payload: Payload) -> Self {
-        Self { direction, payload }
+        Self {
+            direction,
+            payload,
+            sync_round: None,
+        }
+    }
+
+    /// Tags the message with the block sync round it was received in.
+    pub fn with_sync_round(mut self, sync_round: u64) -> Self {
+        self.sync_round = Some(sync_round);
+        self
     }
 
     pub fn receiver(&self) -> SocketAddr {
@@ -77,7 +89,7 @@ pub enum Payload {
     #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/peers.md"))]
     Peers(Vec<SocketAddr>),
     #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/ping.md"))]
-    Ping(BlockHeight),
+    Ping(BlockHeight, u64),
     #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/pong.md"))]
     Pong,
     #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/sync.md"))]
@@ -86,6 +98,11 @@ pub enum Payload {
     SyncBlock(Vec<u8>),
     #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/transaction.md"))]
     Transaction(Vec<u8>),
+    #[cfg_attr(
+        nightly,
+        doc(include = "../../documentation/network_messages/transaction_replacement.md")
+    )]
+    TransactionReplacement(Vec<Vec<u8>>, Vec<u8>),
 
     // a placeholder indicating the introduction of a new payload type; used for forward compatibility
     #[doc(hidden)]
@@ -107,6 +124,7 @@ impl fmt::Display for Payload {
             Self::Sync(..) => "sync",
             Self::SyncBlock(..) => "syncblock",
             Self::Transaction(..) => "transaction",
+            Self::TransactionReplacement(..) => "txreplacement",
             Self::Unknown => "unknown",
         };
 
@@ -1162,6 +1162,163 @@ pub mod transaction {
   }
 }
 
+pub mod transaction_replacement {
+  #[derive(Copy, Clone)]
+  pub struct Owned(());
+  impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl <'a> ::capnp::traits::OwnedStruct<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl ::capnp::traits::Pipelined for Owned { type Pipeline = Pipeline; }
+
+  #[derive(Clone, Copy)]
+  pub struct Reader<'a> { reader: ::capnp::private::layout::StructReader<'a> }
+
+  impl <'a,> ::capnp::traits::HasTypeId for Reader<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructReader<'a> for Reader<'a,>  {
+    fn new(reader: ::capnp::private::layout::StructReader<'a>) -> Reader<'a,> {
+      Reader { reader,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerReader<'a> for Reader<'a,>  {
+    fn get_from_pointer(reader: &::capnp::private::layout::PointerReader<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Reader<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructReader::new(reader.get_struct(default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::IntoInternalStructReader<'a> for Reader<'a,>  {
+    fn into_internal_struct_reader(self) -> ::capnp::private::layout::StructReader<'a> {
+      self.reader
+    }
+  }
+
+  impl <'a,> ::capnp::traits::Imbue<'a> for Reader<'a,>  {
+    fn imbue(&mut self, cap_table: &'a ::capnp::private::layout::CapTable) {
+      self.reader.imbue(::capnp::private::layout::CapTableReader::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> Reader<'a,>  {
+    pub fn reborrow(&self) -> Reader<'_,> {
+      Reader { .. *self }
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.reader.total_size()
+    }
+    #[inline]
+    pub fn get_replaced_ids(self) -> ::capnp::Result<::capnp::data_list::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+    }
+    pub fn has_replaced_ids(&self) -> bool {
+      !self.reader.get_pointer_field(0).is_null()
+    }
+    #[inline]
+    pub fn get_transaction(self) -> ::capnp::Result<::capnp::data::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(1), ::core::option::Option::None)
+    }
+    pub fn has_transaction(&self) -> bool {
+      !self.reader.get_pointer_field(1).is_null()
+    }
+  }
+
+  pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
+  impl <'a,> ::capnp::traits::HasStructSize for Builder<'a,>  {
+    #[inline]
+    fn struct_size() -> ::capnp::private::layout::StructSize { _private::STRUCT_SIZE }
+  }
+  impl <'a,> ::capnp::traits::HasTypeId for Builder<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructBuilder<'a> for Builder<'a,>  {
+    fn new(builder: ::capnp::private::layout::StructBuilder<'a>) -> Builder<'a, > {
+      Builder { builder,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::ImbueMut<'a> for Builder<'a,>  {
+    fn imbue_mut(&mut self, cap_table: &'a mut ::capnp::private::layout::CapTable) {
+      self.builder.imbue(::capnp::private::layout::CapTableBuilder::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerBuilder<'a> for Builder<'a,>  {
+    fn init_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, _size: u32) -> Builder<'a,> {
+      ::capnp::traits::FromStructBuilder::new(builder.init_struct(_private::STRUCT_SIZE))
+    }
+    fn get_from_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Builder<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructBuilder::new(builder.get_struct(_private::STRUCT_SIZE, default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::SetPointerBuilder for Reader<'a,>  {
+    fn set_pointer_builder<'b>(pointer: ::capnp::private::layout::PointerBuilder<'b>, value: Reader<'a,>, canonicalize: bool) -> ::capnp::Result<()> { pointer.set_struct(&value.reader, canonicalize) }
+  }
+
+  impl <'a,> Builder<'a,>  {
+    pub fn into_reader(self) -> Reader<'a,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+    pub fn reborrow(&mut self) -> Builder<'_,> {
+      Builder { .. *self }
+    }
+    pub fn reborrow_as_reader(&self) -> Reader<'_,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.builder.into_reader().total_size()
+    }
+    #[inline]
+    pub fn get_replaced_ids(self) -> ::capnp::Result<::capnp::data_list::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+    }
+    #[inline]
+    pub fn set_replaced_ids(&mut self, value: ::capnp::data_list::Reader<'a>) -> ::capnp::Result<()> {
+      ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+    }
+    #[inline]
+    pub fn init_replaced_ids(self, size: u32) -> ::capnp::data_list::Builder<'a> {
+      ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), size)
+    }
+    pub fn has_replaced_ids(&self) -> bool {
+      !self.builder.get_pointer_field(0).is_null()
+    }
+    #[inline]
+    pub fn get_transaction(self) -> ::capnp::Result<::capnp::data::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(1), ::core::option::Option::None)
+    }
+    #[inline]
+    pub fn set_transaction(&mut self, value: ::capnp::data::Reader<'_>)  {
+      self.builder.get_pointer_field(1).set_data(value);
+    }
+    #[inline]
+    pub fn init_transaction(self, size: u32) -> ::capnp::data::Builder<'a> {
+      self.builder.get_pointer_field(1).init_data(size)
+    }
+    pub fn has_transaction(&self) -> bool {
+      !self.builder.get_pointer_field(1).is_null()
+    }
+  }
+
+  pub struct Pipeline { _typeless: ::capnp::any_pointer::Pipeline }
+  impl ::capnp::capability::FromTypelessPipeline for Pipeline {
+    fn new(typeless: ::capnp::any_pointer::Pipeline) -> Pipeline {
+      Pipeline { _typeless: typeless,  }
+    }
+  }
+  impl Pipeline  {
+  }
+  mod _private {
+    use capnp::private::layout;
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 2 };
+    pub const TYPE_ID: u64 = 0xf5ba_ca9d_c616_388a;
+  }
+}
+
 pub mod block {
   #[derive(Copy, Clone)]
   pub struct Owned(());
@@ -1347,6 +1504,10 @@ pub mod ping {
     pub fn get_block_height(self) -> u32 {
       self.reader.get_data_field::<u32>(0)
     }
+    #[inline]
+    pub fn get_min_fee_per_byte(self) -> u64 {
+      self.reader.get_data_field::<u64>(1)
+    }
   }
 
   pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
@@ -1405,6 +1566,14 @@ pub mod ping {
     pub fn set_block_height(&mut self, value: u32)  {
       self.builder.set_data_field::<u32>(0, value);
     }
+    #[inline]
+    pub fn get_min_fee_per_byte(self) -> u64 {
+      self.builder.get_data_field::<u64>(1)
+    }
+    #[inline]
+    pub fn set_min_fee_per_byte(&mut self, value: u64)  {
+      self.builder.set_data_field::<u64>(1, value);
+    }
   }
 
   pub struct Pipeline { _typeless: ::capnp::any_pointer::Pipeline }
@@ -1417,7 +1586,7 @@ pub mod ping {
   }
   mod _private {
     use capnp::private::layout;
-    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 1, pointers: 0 };
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 2, pointers: 0 };
     pub const TYPE_ID: u64 = 0x87ca_2c1e_0607_67fe;
   }
 }
@@ -1924,7 +2093,7 @@ pub mod payload {
   }
 
   pub mod payload_type {
-    pub use self::Which::{Block,GetBlocks,GetMemoryPool,GetPeers,GetSync,MemoryPool,Peers,Ping,Pong,Sync,SyncBlock,Transaction};
+    pub use self::Which::{Block,GetBlocks,GetMemoryPool,GetPeers,GetSync,MemoryPool,Peers,Ping,Pong,Sync,SyncBlock,Transaction,TransactionReplacement};
 
     #[derive(Copy, Clone)]
     pub struct Owned(());
@@ -2019,6 +2188,10 @@ pub mod payload {
         if self.reader.get_data_field::<u16>(0) != 11 { return false; }
         !self.reader.get_pointer_field(0).is_null()
       }
+      pub fn has_transaction_replacement(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 12 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
       #[inline]
       pub fn which(self) -> ::core::result::Result<WhichReader<'a,>, ::capnp::NotInSchema> {
         match self.reader.get_data_field::<u16>(0) {
@@ -2082,6 +2255,11 @@ pub mod payload {
               ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
             ))
           }
+          12 => {
+            ::core::result::Result::Ok(TransactionReplacement(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
           x => ::core::result::Result::Err(::capnp::NotInSchema(x))
         }
       }
@@ -2304,6 +2482,20 @@ pub mod payload {
         !self.builder.get_pointer_field(0).is_null()
       }
       #[inline]
+      pub fn set_transaction_replacement(&mut self, value: crate::payload_capnp::transaction_replacement::Reader<'_>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 12);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_transaction_replacement(self, ) -> crate::payload_capnp::transaction_replacement::Builder<'a> {
+        self.builder.set_data_field::<u16>(0, 12);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+      }
+      pub fn has_transaction_replacement(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 12 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
       pub fn which(self) -> ::core::result::Result<WhichBuilder<'a,>, ::capnp::NotInSchema> {
         match self.builder.get_data_field::<u16>(0) {
           0 => {
@@ -2366,6 +2558,11 @@ pub mod payload {
               ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
             ))
           }
+          12 => {
+            ::core::result::Result::Ok(TransactionReplacement(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
           x => ::core::result::Result::Err(::capnp::NotInSchema(x))
         }
       }
@@ -2384,7 +2581,7 @@ pub mod payload {
       pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 1, pointers: 1 };
       pub const TYPE_ID: u64 = 0xb8b4_27fe_5891_d61c;
     }
-    pub enum Which<A0,A1,A2,A3,A4,A5,A6,A7,A8,A9,A10,A11> {
+    pub enum Which<A0,A1,A2,A3,A4,A5,A6,A7,A8,A9,A10,A11,A12> {
       Block(A0),
       GetBlocks(A1),
       GetMemoryPool(A2),
@@ -2397,9 +2594,10 @@ pub mod payload {
       Sync(A9),
       SyncBlock(A10),
       Transaction(A11),
+      TransactionReplacement(A12),
     }
-    pub type WhichReader<'a,> = Which<::capnp::Result<crate::payload_capnp::block::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::get_memory_pool::Reader<'a>>,::capnp::Result<crate::payload_capnp::get_peers::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::socket_addr::Owned>>,::capnp::Result<crate::payload_capnp::ping::Reader<'a>>,::capnp::Result<crate::payload_capnp::pong::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::block::Reader<'a>>,::capnp::Result<crate::payload_capnp::transaction::Reader<'a>>>;
-    pub type WhichBuilder<'a,> = Which<::capnp::Result<crate::payload_capnp::block::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::get_memory_pool::Builder<'a>>,::capnp::Result<crate::payload_capnp::get_peers::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::socket_addr::Owned>>,::capnp::Result<crate::payload_capnp::ping::Builder<'a>>,::capnp::Result<crate::payload_capnp::pong::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::block::Builder<'a>>,::capnp::Result<crate::payload_capnp::transaction::Builder<'a>>>;
+    pub type WhichReader<'a,> = Which<::capnp::Result<crate::payload_capnp::block::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::get_memory_pool::Reader<'a>>,::capnp::Result<crate::payload_capnp::get_peers::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::socket_addr::Owned>>,::capnp::Result<crate::payload_capnp::ping::Reader<'a>>,::capnp::Result<crate::payload_capnp::pong::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::block::Reader<'a>>,::capnp::Result<crate::payload_capnp::transaction::Reader<'a>>,::capnp::Result<crate::payload_capnp::transaction_replacement::Reader<'a>>>;
+    pub type WhichBuilder<'a,> = Which<::capnp::Result<crate::payload_capnp::block::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::get_memory_pool::Builder<'a>>,::capnp::Result<crate::payload_capnp::get_peers::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::socket_addr::Owned>>,::capnp::Result<crate::payload_capnp::ping::Builder<'a>>,::capnp::Result<crate::payload_capnp::pong::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::block::Builder<'a>>,::capnp::Result<crate::payload_capnp::transaction::Builder<'a>>,::capnp::Result<crate::payload_capnp::transaction_replacement::Builder<'a>>>;
   }
 }
 
@@ -26,6 +26,7 @@ use payload_capnp::{
     },
     socket_addr,
     transaction,
+    transaction_replacement,
     version,
 };
 
@@ -89,11 +90,17 @@ impl Payload {
             payload_type::Which::GetSync(hashes) => Ok(Payload::GetSync(deserialize_block_hashes(hashes?)?)),
             payload_type::Which::MemoryPool(txs) => deserialize_transactions(txs?),
             payload_type::Which::Peers(peers) => Ok(Payload::Peers(deserialize_addresses(peers?)?)),
-            payload_type::Which::Ping(ping) => Ok(Payload::Ping(ping?.get_block_height())),
+            payload_type::Which::Ping(ping) => {
+                let ping = ping?;
+                Ok(Payload::Ping(ping.get_block_height(), ping.get_min_fee_per_byte()))
+            }
             payload_type::Which::Pong(_) => Ok(Payload::Pong),
             payload_type::Which::Sync(hashes) => Ok(Payload::Sync(deserialize_block_hashes(hashes?)?)),
             payload_type::Which::SyncBlock(block) => deserialize_block(block?, true),
             payload_type::Which::Transaction(tx) => Ok(Payload::Transaction(tx?.get_data()?.to_vec())),
+            payload_type::Which::TransactionReplacement(replacement) => {
+                deserialize_transaction_replacement(replacement?)
+            }
         }
     }
 
@@ -164,9 +171,10 @@ impl Payload {
                         }
                     }
                 }
-                Payload::Ping(block_height) => {
+                Payload::Ping(block_height, min_fee_per_byte) => {
                     let mut builder = builder.init_ping();
                     builder.set_block_height(*block_height);
+                    builder.set_min_fee_per_byte(*min_fee_per_byte);
                 }
                 Payload::Pong => {
                     let mut builder = builder.init_pong();
@@ -187,6 +195,16 @@ impl Payload {
                     let mut builder = builder.init_transaction();
                     builder.set_data(&bytes);
                 }
+                Payload::TransactionReplacement(replaced_ids, bytes) => {
+                    let mut builder = builder.init_transaction_replacement();
+                    {
+                        let mut ids_builder = builder.reborrow().init_replaced_ids(replaced_ids.len() as u32);
+                        for (i, id) in replaced_ids.iter().enumerate() {
+                            ids_builder.set(i as u32, id);
+                        }
+                    }
+                    builder.set_transaction(bytes);
+                }
                 _ => unreachable!(),
             }
         }
@@ -283,6 +301,20 @@ fn deserialize_transactions(txs: Transactions<'_>) -> capnp::Result<Payload> {
     Ok(Payload::MemoryPool(vec))
 }
 
+fn deserialize_transaction_replacement(replacement: transaction_replacement::Reader<'_>) -> capnp::Result<Payload> {
+    let replaced_ids = replacement.get_replaced_ids()?;
+    let mut vec = Vec::with_capacity(replaced_ids.len() as usize);
+
+    for id in replaced_ids.iter() {
+        vec.push(id?.to_vec());
+    }
+
+    Ok(Payload::TransactionReplacement(
+        vec,
+        replacement.get_transaction()?.to_vec(),
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -305,7 +337,8 @@ mod tests {
             Payload::Block(blob.clone()),
             Payload::MemoryPool(vec![blob.clone(); 10]),
             Payload::SyncBlock(blob.clone()),
-            Payload::Transaction(blob),
+            Payload::Transaction(blob.clone()),
+            Payload::TransactionReplacement(vec![vec![1u8; 32], vec![2u8; 32]], blob),
         ] {
             assert_eq!(
                 Payload::deserialize(&Payload::serialize(payload).unwrap()).unwrap(),
@@ -354,7 +387,7 @@ mod tests {
     #[test]
     fn serialize_deserialize_ping() {
         for i in 0u8..255 {
-            let payload = Payload::Ping(i as u32);
+            let payload = Payload::Ping(i as u32, i as u64 * 1_000);
 
             assert_eq!(
                 Payload::deserialize(&Payload::serialize(&payload).unwrap()).unwrap(),
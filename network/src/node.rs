@@ -18,20 +18,21 @@ use crate::*;
 use snarkvm_dpc::Storage;
 
 use chrono::{DateTime, Utc};
-use metrics::{register_counter, register_gauge};
+use metrics::{register_counter, register_gauge, register_histogram};
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
-use rand::{seq::SliceRandom, thread_rng, Rng};
+use rand::{thread_rng, Rng};
 use std::{
     net::SocketAddr,
     ops::Deref,
     sync::{
-        atomic::{AtomicBool, AtomicU8, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
         Arc,
     },
     thread,
+    time::Duration,
 };
-use tokio::{task, time::sleep};
+use tokio::{sync::watch, task, time::sleep};
 
 #[cfg(feature = "prometheus")]
 use metrics_exporter_prometheus::PrometheusBuilder;
@@ -55,6 +56,8 @@ pub struct InnerNode<S: Storage> {
     state: StateCode,
     /// The local address of this node.
     pub local_address: OnceCell<SocketAddr>,
+    /// All the addresses this node listens at, starting with the local address.
+    pub listen_addresses: OnceCell<Vec<SocketAddr>>,
     /// The pre-configured parameters of this node.
     pub config: Config,
     /// The inbound handler of this node.
@@ -67,12 +70,24 @@ pub struct InnerNode<S: Storage> {
     pub sync: OnceCell<Arc<Sync<S>>>,
     /// The node's start-up timestamp.
     pub launched: DateTime<Utc>,
+    /// The sender of the updates to the current block height of this node.
+    block_height_sender: watch::Sender<u32>,
+    /// A receiver of the updates to the current block height of this node, kept so that they can always be sent.
+    block_height_receiver: watch::Receiver<u32>,
+    /// The validator of the new blocks received from peers, available once the services are started.
+    pub(crate) block_validator: OnceCell<OrderedValidator<ValidatedBlock>>,
     /// The tasks spawned by the node.
     tasks: Mutex<Vec<task::JoinHandle<()>>>,
     /// The threads spawned by the node.
     threads: Mutex<Vec<thread::JoinHandle<()>>>,
     /// An indicator of whether the node is shutting down.
     shutting_down: AtomicBool,
+    /// An indicator of whether the node has completed a block sync round.
+    completed_block_sync: AtomicBool,
+    /// The identifier of the latest block sync round.
+    block_sync_round: AtomicU64,
+    /// An indicator of whether the node has accepted an inbound connection, i.e. whether it's reachable.
+    accepted_inbound_connection: AtomicBool,
 }
 
 impl<S: Storage> Drop for InnerNode<S> {
@@ -136,19 +151,28 @@ impl<S: Storage> Node<S> {
 impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
     /// Creates a new instance of `Node`.
     pub async fn new(config: Config) -> Result<Self, NetworkError> {
+        let (block_height_sender, block_height_receiver) = watch::channel(0);
+
         Ok(Self(Arc::new(InnerNode {
             id: thread_rng().gen(),
             state: Default::default(),
             local_address: Default::default(),
+            listen_addresses: Default::default(),
+            inbound: Inbound::new(config.inbound_channel_capacity()),
             config,
-            inbound: Default::default(),
             outbound: Default::default(),
             peer_book: Default::default(),
             sync: Default::default(),
             launched: Utc::now(),
+            block_height_sender,
+            block_height_receiver,
+            block_validator: Default::default(),
             tasks: Default::default(),
             threads: Default::default(),
             shutting_down: Default::default(),
+            completed_block_sync: Default::default(),
+            block_sync_round: Default::default(),
+            accepted_inbound_connection: Default::default(),
         })))
     }
 
@@ -156,6 +180,37 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
         if self.sync.set(Arc::new(sync)).is_err() {
             panic!("sync was set more than once!");
         }
+
+        // The node can already be at some non-zero height.
+        self.update_best_block_height();
+    }
+
+    /// Returns a receiver of the current block height of the node, which is updated whenever a block is committed.
+    pub fn best_block_height(&self) -> watch::Receiver<u32> {
+        self.block_height_receiver.clone()
+    }
+
+    /// Notifies the receivers of the best block height if it changed since the last update.
+    pub(crate) fn update_best_block_height(&self) {
+        if let Some(sync) = self.sync() {
+            let block_height = sync.current_block_height();
+
+            if *self.block_height_receiver.borrow() != block_height {
+                // The node holds a receiver, so the channel can't be closed.
+                let _ = self.block_height_sender.send(block_height);
+            }
+        }
+    }
+
+    /// Checks whether none of the connected peers advertises a higher block height than the node's own,
+    /// i.e. whether a block mined by the node wouldn't be built on a stale tip.
+    pub fn is_chain_leader(&self) -> bool {
+        let block_height = self.sync().map(|sync| sync.current_block_height()).unwrap_or(0);
+
+        match self.peer_book.best_height_peer() {
+            Some((_, best_peer_height)) => best_peer_height <= block_height,
+            None => true,
+        }
     }
 
     /// Returns a reference to the sync objects.
@@ -261,56 +316,68 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
             self.register_task(sync_mempool_task);
 
             let node_clone = self.clone();
-            let block_sync_interval = node_clone.expect_sync().block_sync_interval();
-            let sync_block_task = task::spawn(async move {
-                loop {
-                    let is_syncing_blocks = node_clone.is_syncing_blocks();
-                    let is_sync_expired = node_clone.expect_sync().has_block_sync_expired();
-
-                    // if the node is not currently syncing blocks or an earlier sync attempt has expired,
-                    // consider syncing blocks with a peer who has a longer chain
-                    if !is_syncing_blocks || is_sync_expired {
-                        // if the node's state is `Syncing`, change it to `Idle`, as it means the
-                        // previous attempt has expired - the peer has disconnected or was too slow
-                        // to deliver the batch of sync blocks
-                        if is_syncing_blocks {
-                            debug!("An unfinished block sync has expired.");
-                            node_clone.set_state(State::Idle);
-                        }
+            let (block_validator, commit_blocks_task) =
+                OrderedValidator::new(self.config.max_concurrent_block_validations(), move |block| {
+                    node_clone.commit_validated_block(block)
+                });
+            if self.block_validator.set(block_validator).is_err() {
+                panic!("the block validator was set more than once!");
+            }
+            self.register_task(commit_blocks_task);
 
-                        let mut prospect_sync_nodes = Vec::new();
-                        let my_height = node_clone.expect_sync().current_block_height();
+            let node_clone = self.clone();
+            let compact_mempool_task = task::spawn(async move {
+                loop {
+                    sleep(Duration::from_secs(crate::MEMORY_POOL_COMPACTION_INTERVAL_SECS)).await;
 
-                        // Pick a random peer of all the connected ones that claim
-                        // to have a longer chain.
-                        for (peer, info) in node_clone.peer_book.connected_peers().iter() {
-                            // Fetch the current block height of this connected peer.
-                            let peer_block_height = info.block_height();
+                    let mut memory_pool = node_clone.expect_sync().memory_pool().lock();
 
-                            if peer_block_height > my_height + 1 {
-                                prospect_sync_nodes.push((*peer, peer_block_height));
-                            }
+                    match memory_pool.expire() {
+                        Ok(expired) if !expired.is_empty() => {
+                            debug!("Expired {} transaction(s) from the memory pool", expired.len())
                         }
+                        Ok(_) => {}
+                        Err(error) => warn!("Failed to expire memory pool transactions: {}", error),
+                    }
 
-                        let random_sync_peer = prospect_sync_nodes.choose(&mut rand::thread_rng());
-                        if let Some((sync_node, peer_height)) = random_sync_peer {
-                            // Log the sync job as a trace.
-                            trace!(
-                                "Preparing to sync from {} with a block height of {} (mine: {}, {} peers with a greater height)",
-                                sync_node,
-                                peer_height,
-                                my_height,
-                                prospect_sync_nodes.len()
-                            );
-
-                            // Cancel any possibly ongoing sync attempts.
-                            node_clone.peer_book.cancel_any_unfinished_syncing();
-
-                            // Begin a new sync attempt.
-                            node_clone.register_block_sync_attempt();
-                            node_clone.update_blocks(*sync_node);
-                        }
+                    let expired_orphans = memory_pool.expire_orphans();
+                    if !expired_orphans.is_empty() {
+                        debug!(
+                            "Discarded {} expired transaction(s) from the orphan pool",
+                            expired_orphans.len()
+                        );
+                    }
+
+                    // Reclaim the memory retained by the memory pool after a period of high churn.
+                    if memory_pool.shrink_to_fit() {
+                        trace!("Compacted the memory pool");
                     }
+                }
+            });
+            self.register_task(compact_mempool_task);
+
+            let node_clone = self.clone();
+            let mempool_persistence_interval = self.config.mempool_persistence_interval();
+            let persist_mempool_task = task::spawn(async move {
+                loop {
+                    sleep(mempool_persistence_interval).await;
+
+                    // Only write the memory pool to the database if it changed since it was last stored.
+                    let sync = node_clone.expect_sync();
+                    match sync.memory_pool().lock().store_if_dirty(sync.storage()) {
+                        Ok(true) => trace!("Stored the memory pool"),
+                        Ok(false) => {}
+                        Err(error) => debug!("Failed to store memory pool transactions in database {}", error),
+                    }
+                }
+            });
+            self.register_task(persist_mempool_task);
+
+            let node_clone = self.clone();
+            let block_sync_interval = node_clone.expect_sync().block_sync_interval();
+            let sync_block_task = task::spawn(async move {
+                loop {
+                    node_clone.run_block_sync();
 
                     sleep(block_sync_interval).await;
                 }
@@ -319,8 +386,27 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
         }
     }
 
+    /// Shuts the node down once the block sync round in progress, if any, has processed the blocks it requested
+    /// and the already received messages are handled, or once the sync drain timeout elapses; meanwhile, no new
+    /// sync rounds are started and only the requested sync blocks are processed.
+    pub async fn shut_down_gracefully(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+
+        let drain_deadline = tokio::time::Instant::now() + self.config.sync_drain_timeout();
+        while (self.is_syncing_blocks() || self.inbound.depth() != 0) && tokio::time::Instant::now() < drain_deadline {
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        if self.is_syncing_blocks() {
+            warn!("Shutting down before the block sync round has finished");
+        }
+
+        self.shut_down();
+    }
+
     pub fn shut_down(&self) {
         debug!("Shutting down");
+        self.shutting_down.store(true, Ordering::SeqCst);
 
         for addr in self.connected_peers() {
             self.disconnect_from_peer(addr);
@@ -333,6 +419,13 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
         for handle in self.tasks.lock().drain(..).rev() {
             handle.abort();
         }
+
+        // The periodic persistence task is aborted by now, so store any memory pool changes made since it last ran.
+        if let Some(sync) = self.sync() {
+            if let Err(error) = sync.memory_pool().lock().store_if_dirty(sync.storage()) {
+                error!("Failed to store memory pool transactions in database {}", error);
+            }
+        }
     }
 
     pub fn register_task(&self, handle: task::JoinHandle<()>) {
@@ -348,11 +441,65 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
         self.local_address.get().copied()
     }
 
+    /// Returns all the addresses this node listens at, starting with the local address.
+    #[inline]
+    pub fn listen_addresses(&self) -> &[SocketAddr] {
+        self.listen_addresses
+            .get()
+            .map(|addresses| &addresses[..])
+            .unwrap_or(&[])
+    }
+
     #[inline]
     pub fn is_shutting_down(&self) -> bool {
         self.shutting_down.load(Ordering::Relaxed)
     }
 
+    /// Checks whether the node has completed at least one block sync round since it was started.
+    #[inline]
+    pub fn has_completed_block_sync(&self) -> bool {
+        self.completed_block_sync.load(Ordering::Relaxed)
+    }
+
+    /// Registers that the node has completed a block sync round.
+    #[inline]
+    pub(crate) fn set_completed_block_sync(&self) {
+        self.completed_block_sync.store(true, Ordering::Relaxed);
+    }
+
+    /// Checks whether the node has accepted at least one inbound connection since it was started.
+    #[inline]
+    pub fn has_accepted_inbound_connection(&self) -> bool {
+        self.accepted_inbound_connection.load(Ordering::Relaxed)
+    }
+
+    /// Registers that the node has accepted an inbound connection.
+    #[inline]
+    pub(crate) fn set_accepted_inbound_connection(&self) {
+        self.accepted_inbound_connection.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the identifier of the latest block sync round.
+    #[inline]
+    pub fn block_sync_round(&self) -> u64 {
+        self.block_sync_round.load(Ordering::SeqCst)
+    }
+
+    /// Registers the start of a new block sync round.
+    #[inline]
+    pub(crate) fn start_block_sync_round(&self) {
+        self.block_sync_round.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if the given message is a sync message received during an earlier block sync round
+    /// than the current one, which should be ignored.
+    pub fn is_stale_sync_message(&self, message: &Message) -> bool {
+        match message.sync_round {
+            Some(sync_round) => self.config.sync_round_protection() && sync_round != self.block_sync_round(),
+            None => false,
+        }
+    }
+
     /// Sets the local address of the node to the given value.
     #[inline]
     pub fn set_local_address(&self, addr: SocketAddr) {
@@ -401,6 +548,7 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
         register_counter!(crate::INBOUND_SYNCBLOCKS);
         register_counter!(crate::INBOUND_TRANSACTIONS);
         register_counter!(crate::INBOUND_UNKNOWN);
+        register_histogram!(crate::INBOUND_PROCESSING_TIME);
 
         register_counter!(crate::OUTBOUND_ALL_SUCCESSES);
         register_counter!(crate::OUTBOUND_ALL_FAILURES);
@@ -427,6 +575,7 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
         register_counter!(crate::MISC_DUPLICATE_BLOCKS);
         register_counter!(crate::MISC_DUPLICATE_SYNC_BLOCKS);
         register_counter!(crate::MISC_RPC_REQUESTS);
+        register_counter!(snarkos_consensus::memory_pool::MEMPOOL_DUPLICATE_SUBMISSIONS);
 
         // The node can already be at some non-zero height.
         if let Some(sync) = self.sync() {
@@ -17,9 +17,9 @@
 use crate::{errors::NetworkError, message::*};
 
 use parking_lot::Mutex;
-use tokio::{io::AsyncWriteExt, net::tcp::OwnedWriteHalf};
+use tokio::{io::AsyncWriteExt, net::tcp::OwnedWriteHalf, time::timeout};
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 /// A channel for writing messages to a peer.
 /// The write stream is protected by an Arc + Mutex to enable cloning.
@@ -29,6 +29,12 @@ pub struct ConnWriter {
     pub writer: OwnedWriteHalf,
     buffer: Box<[u8]>,
     noise: Arc<Mutex<snow::TransportState>>,
+    /// The encrypted messages queued to be written to the stream in a single write.
+    pending: Vec<u8>,
+    /// The number of writes to the stream so far.
+    write_count: u64,
+    /// The maximum amount of time a single write to the stream may take.
+    write_timeout: Duration,
 }
 
 impl ConnWriter {
@@ -43,11 +49,26 @@ impl ConnWriter {
             writer,
             buffer,
             noise,
+            pending: Vec::new(),
+            write_count: 0,
+            write_timeout: Duration::from_secs(crate::PEER_WRITE_TIMEOUT_SECS),
         }
     }
 
-    /// Writes a message consisting of a header and payload.
+    /// Sets the maximum amount of time a single write to the stream may take.
+    pub fn with_write_timeout(mut self, write_timeout: Duration) -> Self {
+        self.write_timeout = write_timeout;
+        self
+    }
+
+    /// Writes a message consisting of a header and payload, along with any previously queued messages.
     pub async fn write_message(&mut self, payload: &Payload) -> Result<(), NetworkError> {
+        self.queue_message(payload)?;
+        self.flush().await
+    }
+
+    /// Queues a message consisting of a header and payload, to be written to the stream on the next flush.
+    pub fn queue_message(&mut self, payload: &Payload) -> Result<(), NetworkError> {
         let serialized_payload = Payload::serialize(payload)?;
 
         {
@@ -69,16 +90,44 @@ impl ConnWriter {
             }
 
             let header = MessageHeader::from(encrypted_len);
-            self.writer.write_all(&header.as_bytes()[..]).await?;
-            self.writer.write_all(&self.buffer[..encrypted_len]).await?;
+            self.pending.extend_from_slice(&header.as_bytes()[..]);
+            self.pending.extend_from_slice(&self.buffer[..encrypted_len]);
         }
 
         // If message is a `SyncBlock` message, log it as a trace.
         match payload {
-            Payload::SyncBlock(_) => trace!("Sent a '{}' message to {}", payload, self.addr),
-            _ => debug!("Sent a '{}' message to {}", payload, self.addr),
+            Payload::SyncBlock(_) => trace!("Queued a '{}' message for {}", payload, self.addr),
+            _ => debug!("Queued a '{}' message for {}", payload, self.addr),
         }
 
         Ok(())
     }
+
+    /// Writes all the queued messages to the stream at once.
+    pub async fn flush(&mut self) -> Result<(), NetworkError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        // The queued messages are discarded even if the write fails, as the stream is broken then.
+        let pending = std::mem::take(&mut self.pending);
+        self.write_count += 1;
+        timeout(self.write_timeout, self.writer.write_all(&pending))
+            .await
+            .map_err(|_| NetworkError::WriteTimeout)??;
+
+        trace!("Sent {}B of queued messages to {}", pending.len(), self.addr);
+
+        Ok(())
+    }
+
+    /// Returns the size of the messages queued to be written on the next flush.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns the number of writes to the stream so far.
+    pub fn write_count(&self) -> u64 {
+        self.write_count
+    }
 }
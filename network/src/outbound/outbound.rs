@@ -18,10 +18,20 @@ use crate::{stats, ConnWriter, Direction, Message, NetworkError, Node, Payload};
 
 use snarkvm_dpc::Storage;
 
-use std::{collections::HashMap, net::SocketAddr};
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::Arc,
+};
 
-use parking_lot::RwLock;
-use tokio::sync::mpsc::{error::TrySendError, Receiver, Sender};
+use parking_lot::{Mutex, RwLock};
+use tokio::{
+    sync::{
+        mpsc::{error::TrySendError, Receiver, Sender},
+        Notify,
+    },
+    time::{timeout_at, Instant},
+};
 
 /// The map of remote addresses to their active write channels.
 type Channels = HashMap<SocketAddr, Sender<Message>>;
@@ -31,6 +41,64 @@ type Channels = HashMap<SocketAddr, Sender<Message>>;
 pub struct Outbound {
     /// The map of remote addresses to their active write channels.
     pub(crate) channels: RwLock<Channels>,
+    /// The map of remote addresses to the queues of the transactions and blocks relayed to them.
+    pub(crate) relay_queues: RwLock<HashMap<SocketAddr, Arc<RelayQueue>>>,
+}
+
+/// A bounded queue of the transactions and blocks relayed to a single peer; once it's full, the oldest ones
+/// are dropped, so that the backlog of a slow peer doesn't hold up the relays to the other peers.
+#[derive(Debug)]
+pub struct RelayQueue {
+    /// The queued relayed messages, from the oldest to the newest.
+    messages: Mutex<VecDeque<Message>>,
+    /// The maximum number of queued relayed messages.
+    capacity: usize,
+    /// Wakes up the writer of the connection once a relayed message is queued.
+    notify: Notify,
+}
+
+impl RelayQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            messages: Default::default(),
+            capacity: capacity.max(1),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Queues a relayed message; returns `true` if the oldest queued one was dropped to make room for it.
+    pub fn push(&self, message: Message) -> bool {
+        let dropped = {
+            let mut messages = self.messages.lock();
+            let dropped = messages.len() >= self.capacity && messages.pop_front().is_some();
+            messages.push_back(message);
+            dropped
+        };
+        self.notify.notify_one();
+
+        dropped
+    }
+
+    /// Waits for the oldest queued relayed message and removes it from the queue.
+    pub async fn pop(&self) -> Message {
+        loop {
+            let notified = self.notify.notified();
+            if let Some(message) = self.messages.lock().pop_front() {
+                return message;
+            }
+            notified.await;
+        }
+    }
+
+    /// Returns the number of queued relayed messages.
+    pub fn len(&self) -> usize {
+        self.messages.lock().len()
+    }
+
+    /// Returns `true` if there are no queued relayed messages.
+    pub fn is_empty(&self) -> bool {
+        self.messages.lock().is_empty()
+    }
 }
 
 impl Outbound {
@@ -86,37 +154,161 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         }
     }
 
+    /// Queues the given transaction or block to be relayed to the address associated with it; if the peer's
+    /// relay queue is full, its oldest queued relay is dropped rather than holding up the relays to other peers.
+    pub fn relay_message(&self, message: Message) {
+        let target_addr = message.receiver();
+        let relay_queue = self.outbound.relay_queues.read().get(&target_addr).cloned();
+
+        match relay_queue {
+            Some(relay_queue) => {
+                if relay_queue.push(message) {
+                    debug!("The relay queue for {} is full; dropped the oldest relay", target_addr);
+                    self.peer_book.register_dropped_relay(target_addr);
+                }
+            }
+            None => {
+                warn!("Failed to relay a {}: peer is disconnected", message);
+                metrics::increment_counter!(stats::OUTBOUND_ALL_FAILURES);
+            }
+        }
+    }
+
+    /// Returns the number of transactions and blocks queued to be relayed to the given peer.
+    pub fn queued_relays(&self, remote_address: SocketAddr) -> usize {
+        self.outbound
+            .relay_queues
+            .read()
+            .get(&remote_address)
+            .map(|relay_queue| relay_queue.len())
+            .unwrap_or(0)
+    }
+
     pub fn send_ping(&self, remote_address: SocketAddr) {
         // Consider peering tests that don't use the sync layer.
-        let current_block_height = if let Some(ref sync) = self.sync() {
-            sync.current_block_height()
+        let (current_block_height, min_fee_per_byte) = if let Some(ref sync) = self.sync() {
+            // A memory pool that can't admit any more standard transactions doesn't share a fee rate hint.
+            let min_fee_per_byte = match sync.memory_pool().lock().current_min_fee_per_byte() {
+                u64::MAX => 0,
+                min_fee_per_byte => min_fee_per_byte,
+            };
+            (sync.current_block_height(), min_fee_per_byte)
         } else {
-            0
+            (0, 0)
         };
 
         self.peer_book.sending_ping(remote_address);
 
         self.send_request(Message::new(
             Direction::Outbound(remote_address),
-            Payload::Ping(current_block_height),
+            Payload::Ping(current_block_height, min_fee_per_byte),
         ));
     }
 
     /// This method handles new outbound messages to a single connected node.
-    pub async fn listen_for_outbound_messages(&self, mut receiver: Receiver<Message>, writer: &mut ConnWriter) {
-        // Read the next message queued to be sent.
-        while let Some(message) = receiver.recv().await {
-            metrics::decrement_gauge!(stats::QUEUES_OUTBOUND, 1.0);
-
-            match writer.write_message(&message.payload).await {
-                Ok(_) => {
-                    metrics::increment_counter!(stats::OUTBOUND_ALL_SUCCESSES);
+    ///
+    /// Small messages are coalesced into a single write until the coalescing interval elapses or their
+    /// combined size exceeds the coalescing size; large and latency-sensitive messages are written immediately.
+    /// The messages sent directly to the node take precedence over the relayed ones.
+    pub async fn listen_for_outbound_messages(
+        &self,
+        mut receiver: Receiver<Message>,
+        relay_queue: Arc<RelayQueue>,
+        writer: &mut ConnWriter,
+    ) {
+        let coalesce_interval = self.config.outbound_coalesce_interval();
+        let coalesce_size = self.config.outbound_coalesce_size();
+
+        // The number of queued messages and the time by which they need to be written.
+        let mut queued_messages = 0;
+        let mut flush_deadline = Instant::now();
+
+        loop {
+            // Read the next message queued to be sent; wait for it only until the deadline if there are queued messages.
+            let next_message = async {
+                tokio::select! {
+                    biased;
+                    message = receiver.recv() => message.map(|message| (message, false)),
+                    message = relay_queue.pop() => Some((message, true)),
                 }
-                Err(error) => {
-                    warn!("Failed to send a {}: {}", message, error);
-                    metrics::increment_counter!(stats::OUTBOUND_ALL_FAILURES);
+            };
+            let message = if queued_messages == 0 {
+                next_message.await
+            } else {
+                match timeout_at(flush_deadline, next_message).await {
+                    Ok(message) => message,
+                    Err(_) => {
+                        if !self.flush_outbound_messages(writer, &mut queued_messages).await {
+                            break;
+                        }
+                        continue;
+                    }
+                }
+            };
+
+            let message = match message {
+                Some((message, is_relayed)) => {
+                    if !is_relayed {
+                        metrics::decrement_gauge!(stats::QUEUES_OUTBOUND, 1.0);
+                    }
+                    message
                 }
+                None => {
+                    self.flush_outbound_messages(writer, &mut queued_messages).await;
+                    break;
+                }
+            };
+
+            if let Err(error) = writer.queue_message(&message.payload) {
+                warn!("Failed to send a {}: {}", message, error);
+                metrics::increment_counter!(stats::OUTBOUND_ALL_FAILURES);
+                continue;
+            }
+
+            if queued_messages == 0 {
+                flush_deadline = Instant::now() + coalesce_interval;
+            }
+            queued_messages += 1;
+
+            // Pings and Pongs aren't held back in order not to skew latency calculation.
+            let is_latency_sensitive = matches!(message.payload, Payload::Ping(..) | Payload::Pong);
+            if (is_latency_sensitive || writer.pending_len() >= coalesce_size)
+                && !self.flush_outbound_messages(writer, &mut queued_messages).await
+            {
+                break;
             }
         }
     }
+
+    /// Writes all the queued outbound messages at once; returns `false` if the write failed in a way
+    /// that resulted in the peer being disconnected from.
+    async fn flush_outbound_messages(&self, writer: &mut ConnWriter, queued_messages: &mut u64) -> bool {
+        let is_connection_usable = match writer.flush().await {
+            Ok(_) => {
+                metrics::counter!(stats::OUTBOUND_ALL_SUCCESSES, *queued_messages);
+                true
+            }
+            Err(error) => {
+                warn!(
+                    "Failed to send {} queued messages to {}: {}",
+                    queued_messages, writer.addr, error
+                );
+                metrics::counter!(stats::OUTBOUND_ALL_FAILURES, *queued_messages);
+
+                if error.is_fatal() {
+                    // A peer that doesn't accept the messages in time is penalized.
+                    if matches!(error, NetworkError::WriteTimeout) {
+                        self.register_failure(writer.addr);
+                    }
+                    warn!("Disconnecting from {} (unreliable)", writer.addr);
+                    self.disconnect_from_peer(writer.addr);
+                }
+
+                !error.is_fatal()
+            }
+        };
+        *queued_messages = 0;
+
+        is_connection_usable
+    }
 }
@@ -21,17 +21,30 @@ use crate::{
 };
 use snarkos_storage::{BlockHeight, Ledger};
 use snarkvm_algorithms::traits::LoadableMerkleParameters;
-use snarkvm_dpc::{Storage, TransactionScheme};
+use snarkvm_dpc::{BlockHeaderHash, Storage, TransactionScheme};
 
 use parking_lot::RwLock;
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 use std::{
+    cmp,
     collections::{HashMap, HashSet},
     net::SocketAddr,
     sync::{atomic::Ordering, Arc},
-    time::Instant,
+    time::{Duration, Instant},
 };
 
+/// The strategy of choosing the peers to disconnect from when connected to more peers than permitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerEvictionStrategy {
+    /// Disconnect from random peers.
+    Random,
+    /// Disconnect from the most recently connected peers.
+    MostRecentlyConnected,
+    /// Disconnect from the peers with the lowest composite quality score.
+    LowestQuality,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct SerializedPeerBook(Vec<PeerInfo>);
 
@@ -223,6 +236,15 @@ impl PeerBook {
         }
     }
 
+    ///
+    /// Tags the given connected peer with the listen address of this node it connected through.
+    ///
+    pub fn set_local_interface(&self, address: SocketAddr, local_interface: Option<SocketAddr>) {
+        if let Some(peer_info) = self.connected_peers.write().get_mut(&address) {
+            peer_info.set_local_interface(local_interface);
+        }
+    }
+
     ///
     /// Removes the given address from the connecting and connected peers in this `PeerBook`,
     /// and adds the given address to the disconnected peers in this `PeerBook`.
@@ -322,6 +344,79 @@ impl PeerBook {
         }
     }
 
+    ///
+    /// Returns the `SocketAddr` and block height of the connected peer with the highest
+    /// advertised block height, or `None` if there are no connected peers.
+    ///
+    pub fn best_height_peer(&self) -> Option<(SocketAddr, BlockHeight)> {
+        self.connected_peers()
+            .iter()
+            .map(|(&socket_address, peer_info)| (socket_address, peer_info.block_height()))
+            .max_by_key(|&(_, block_height)| block_height)
+    }
+
+    ///
+    /// Returns the addresses of the connected peers that likely have the block with the given hash, i.e. the ones
+    /// that have advertised or sent it, followed by the ones whose advertised block height is at least the given
+    /// height of the block, if it's known; the latter are ordered from the highest block height to the lowest.
+    ///
+    pub fn peers_with_block(&self, hash: &BlockHeaderHash, height: Option<BlockHeight>) -> Vec<SocketAddr> {
+        let mut peers = self
+            .connected_peers()
+            .into_iter()
+            .filter_map(|(socket_address, peer_info)| {
+                let has_advertised_block = peer_info.quality.has_advertised_block(hash);
+                let block_height = peer_info.block_height();
+
+                if has_advertised_block || height.map(|height| block_height >= height).unwrap_or(false) {
+                    Some((socket_address, has_advertised_block, block_height))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        peers.sort_unstable_by_key(|&(socket_address, has_advertised_block, block_height)| {
+            (
+                cmp::Reverse(has_advertised_block),
+                cmp::Reverse(block_height),
+                socket_address,
+            )
+        });
+
+        peers.into_iter().map(|(socket_address, _, _)| socket_address).collect()
+    }
+
+    ///
+    /// Returns the addresses of the given number of connected peers to disconnect from, chosen
+    /// using the given strategy.
+    ///
+    pub fn peers_to_evict(&self, count: usize, strategy: PeerEvictionStrategy) -> Vec<SocketAddr> {
+        let mut candidates = self.connected_peers().into_iter().collect::<Vec<_>>();
+
+        match strategy {
+            PeerEvictionStrategy::Random => candidates.shuffle(&mut rand::thread_rng()),
+            PeerEvictionStrategy::MostRecentlyConnected => {
+                candidates.sort_unstable_by_key(|(_, peer_info)| cmp::Reverse(peer_info.last_connected()))
+            }
+            PeerEvictionStrategy::LowestQuality => {
+                let best_block_height = candidates
+                    .iter()
+                    .map(|(_, peer_info)| peer_info.block_height())
+                    .max()
+                    .unwrap_or(0);
+                candidates.sort_by(|(_, a), (_, b)| {
+                    a.quality
+                        .score(best_block_height)
+                        .partial_cmp(&b.quality.score(best_block_height))
+                        .unwrap_or(cmp::Ordering::Equal)
+                });
+            }
+        }
+
+        candidates.into_iter().take(count).map(|(addr, _)| addr).collect()
+    }
+
     ///
     /// Updates the last seen timestamp of this peer to the current time.
     ///
@@ -347,22 +442,64 @@ impl PeerBook {
     }
 
     /// Handles an incoming `Ping` message.
-    pub fn received_ping(&self, source: SocketAddr, block_height: BlockHeight) {
+    pub fn received_ping(&self, source: SocketAddr, block_height: BlockHeight, min_fee_per_byte: u64) {
         if let Some(ref quality) = self.peer_quality(source) {
             quality.block_height.store(block_height, Ordering::SeqCst);
+
+            // A zero fee rate doesn't carry any information, e.g. the peer's memory pool isn't under pressure.
+            if min_fee_per_byte > 0 {
+                let min_fee_per_byte = min_fee_per_byte.min(crate::MAX_FEE_RATE_HINT);
+                *quality.fee_rate_hint.lock() = Some((min_fee_per_byte, Instant::now()));
+            }
         } else {
             warn!("Tried updating block height of a peer that's not connected: {}", source);
         }
     }
 
+    /// Returns the median of the recent fee rate hints shared by the connected peers, so that a few peers sharing
+    /// outlandish hints can't skew it; returns `None` if there are no sufficiently recent hints.
+    pub fn fee_rate_hint(&self, now: Instant) -> Option<f64> {
+        let max_age = Duration::from_secs(crate::MAX_FEE_RATE_HINT_AGE_SECS);
+
+        let mut fee_rates = self
+            .connected_peers
+            .read()
+            .values()
+            .filter_map(|peer| *peer.quality.fee_rate_hint.lock())
+            .filter(|(_, received)| now.saturating_duration_since(*received) <= max_age)
+            .map(|(fee_rate, _)| fee_rate.min(crate::MAX_FEE_RATE_HINT))
+            .collect::<Vec<_>>();
+        fee_rates.sort_unstable();
+
+        let count = fee_rates.len();
+        match count {
+            0 => None,
+            _ if count % 2 == 0 => Some((fee_rates[count / 2 - 1] as f64 + fee_rates[count / 2] as f64) / 2.0),
+            _ => Some(fee_rates[count / 2] as f64),
+        }
+    }
+
+    /// Records that the given peer has the block with the given hash.
+    pub fn advertised_block(&self, source: SocketAddr, hash: BlockHeaderHash) {
+        if let Some(quality) = self.peer_quality(source) {
+            quality.advertised_block(hash);
+        } else {
+            trace!(
+                "Tried recording a block advertised by a peer that's not connected: {}",
+                source
+            );
+        }
+    }
+
     /// Handles an incoming `Pong` message.
     pub fn received_pong(&self, source: SocketAddr) {
         if let Some(quality) = self.peer_quality(source) {
             if quality.expecting_pong.load(Ordering::SeqCst) {
                 let ping_sent = quality.last_ping_sent.lock().unwrap();
-                let rtt = ping_sent.elapsed().as_millis() as u64;
-                trace!("RTT for {} is {}ms", source, rtt);
-                quality.rtt_ms.store(rtt, Ordering::SeqCst);
+                let rtt = ping_sent.elapsed();
+                trace!("RTT for {} is {}us", source, rtt.as_micros());
+                quality.rtt_ms.store(rtt.as_millis() as u64, Ordering::SeqCst);
+                quality.rtt_us.store(rtt.as_micros() as u64, Ordering::SeqCst);
                 quality.expecting_pong.store(false, Ordering::SeqCst);
             } else {
                 quality.failures.fetch_add(1, Ordering::Relaxed);
@@ -373,6 +510,40 @@ impl PeerBook {
         }
     }
 
+    /// Registers that a `GetSync` is about to be sent to the given peer.
+    pub fn sending_get_sync(&self, target: SocketAddr) {
+        if let Some(quality) = self.peer_quality(target) {
+            quality.sync_hash_packets.store(0, Ordering::SeqCst);
+        } else {
+            trace!("Tried to send a GetSync to a peer that's not connected: {}", target);
+        }
+    }
+
+    /// Registers the receipt of a block hash packet from a peer; returns the number of such packets
+    /// received from it since the last `GetSync` sent to it.
+    pub fn received_sync_hashes(&self, source: SocketAddr) -> u32 {
+        if let Some(quality) = self.peer_quality(source) {
+            quality.sync_hash_packets.fetch_add(1, Ordering::SeqCst) + 1
+        } else {
+            trace!("Received block hashes from a peer that's not connected: {}", source);
+            0
+        }
+    }
+
+    /// Registers the receipt of a payload of an unknown or unsupported type from a peer; returns the
+    /// number of such payloads received from it so far.
+    pub fn received_unknown_payload(&self, source: SocketAddr) -> u32 {
+        if let Some(quality) = self.peer_quality(source) {
+            quality.unknown_payloads.fetch_add(1, Ordering::SeqCst) + 1
+        } else {
+            trace!(
+                "Received an unknown payload from a peer that's not connected: {}",
+                source
+            );
+            0
+        }
+    }
+
     /// Registers that the given number of blocks is expected as part of syncing with a peer.
     pub fn expecting_sync_blocks(&self, addr: SocketAddr, count: usize) -> bool {
         if let Some(ref pq) = self.peer_quality(addr) {
@@ -384,6 +555,13 @@ impl PeerBook {
         }
     }
 
+    /// Returns `true` if the node has requested sync blocks from a peer and is still waiting for some of them.
+    pub fn is_expecting_sync_blocks(&self, addr: SocketAddr) -> bool {
+        self.peer_quality(addr)
+            .map(|pq| pq.remaining_sync_blocks.load(Ordering::SeqCst) != 0)
+            .unwrap_or(false)
+    }
+
     /// Registers the receipt of a sync block from a peer; returns `true` when finished syncing.
     pub fn got_sync_block(&self, addr: SocketAddr) -> bool {
         if let Some(ref pq) = self.peer_quality(addr) {
@@ -412,6 +590,41 @@ impl PeerBook {
         }
     }
 
+    /// Registers a bad event related to a peer; returns `true` if it was the one that brought the number
+    /// of strikes within the strike window to `max_strikes`, in which case the strikes are reset.
+    pub fn register_strike(&self, addr: SocketAddr, max_strikes: u32, window: Duration) -> bool {
+        if let Some(pq) = self.peer_quality(addr) {
+            let mut first_strike = pq.first_strike.lock();
+
+            // Strikes from an expired window are forgiven.
+            let now = Instant::now();
+            if first_strike
+                .map(|first| now.duration_since(first) > window)
+                .unwrap_or(true)
+            {
+                *first_strike = Some(now);
+                pq.strikes.store(0, Ordering::SeqCst);
+            }
+
+            if pq.strikes.fetch_add(1, Ordering::SeqCst) + 1 >= max_strikes {
+                *first_strike = None;
+                pq.strikes.store(0, Ordering::SeqCst);
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    /// Registers that the oldest transaction or block queued to be relayed to a peer was dropped.
+    pub fn register_dropped_relay(&self, addr: SocketAddr) {
+        if let Some(pq) = self.peer_quality(addr) {
+            pq.dropped_relays.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
     /// Registers a non-critical failure related to a peer.
     pub fn register_failure(&self, addr: SocketAddr) {
         if let Some(pq) = self.peer_quality(addr) {
@@ -513,4 +726,127 @@ mod tests {
         assert_eq!(false, peer_book.is_connecting(remote_address));
         assert_eq!(true, peer_book.is_connected(remote_address));
     }
+
+    #[test]
+    fn test_best_height_peer() {
+        let peer_book = PeerBook::default();
+        assert_eq!(None, peer_book.best_height_peer());
+
+        let heights = [(4031, 5), (4032, 12), (4033, 7)];
+        for &(port, block_height) in heights.iter() {
+            let remote_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), port));
+            peer_book.set_connected(remote_address, None);
+            peer_book.received_ping(remote_address, block_height, 0);
+        }
+
+        let best_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4032));
+        assert_eq!(Some((best_address, 12)), peer_book.best_height_peer());
+
+        // Disconnected peers aren't considered
+        peer_book.set_disconnected(best_address);
+        let next_best_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4033));
+        assert_eq!(Some((next_best_address, 7)), peer_book.best_height_peer());
+    }
+
+    #[test]
+    fn test_fee_rate_hint() {
+        let peer_book = PeerBook::default();
+        let address = |port| SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), port));
+        assert_eq!(None, peer_book.fee_rate_hint(Instant::now()));
+
+        // A zero fee rate isn't a hint.
+        for &(port, min_fee_per_byte) in [(4031, 10), (4032, 30), (4033, 0)].iter() {
+            peer_book.set_connected(address(port), None);
+            peer_book.received_ping(address(port), 0, min_fee_per_byte);
+        }
+        let now = Instant::now();
+        assert_eq!(Some(20.0), peer_book.fee_rate_hint(now));
+
+        // An outlandish hint doesn't skew the median, and is clamped.
+        peer_book.set_connected(address(4034), None);
+        peer_book.received_ping(address(4034), 0, u64::MAX);
+        assert_eq!(
+            Some(crate::MAX_FEE_RATE_HINT),
+            peer_book
+                .get_peer(address(4034), true)
+                .unwrap()
+                .quality
+                .fee_rate_hint
+                .lock()
+                .map(|(fee_rate, _)| fee_rate)
+        );
+        assert_eq!(Some(30.0), peer_book.fee_rate_hint(now));
+
+        // Stale hints age out.
+        let later = now + Duration::from_secs(crate::MAX_FEE_RATE_HINT_AGE_SECS + 1);
+        assert_eq!(None, peer_book.fee_rate_hint(later));
+    }
+
+    #[test]
+    fn test_peers_with_block() {
+        let peer_book = PeerBook::default();
+        let address = |port| SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), port));
+        let hash = BlockHeaderHash([1u8; 32]);
+        let other_hash = BlockHeaderHash([2u8; 32]);
+
+        let heights = [(4031, 5), (4032, 12), (4033, 7), (4034, 10)];
+        for &(port, block_height) in heights.iter() {
+            peer_book.set_connected(address(port), None);
+            peer_book.received_ping(address(port), block_height, 0);
+        }
+        peer_book.advertised_block(address(4031), hash.clone());
+        peer_book.advertised_block(address(4033), other_hash.clone());
+
+        // Without the height of the block, only the peers that advertised it are known to have it.
+        assert_eq!(vec![address(4031)], peer_book.peers_with_block(&hash, None));
+        assert_eq!(vec![address(4033)], peer_book.peers_with_block(&other_hash, None));
+
+        // The peers that advertised the block come first, followed by the ones that are high enough.
+        assert_eq!(
+            vec![address(4031), address(4032), address(4034)],
+            peer_book.peers_with_block(&hash, Some(10))
+        );
+
+        // Disconnected peers aren't considered.
+        peer_book.set_disconnected(address(4031));
+        assert!(peer_book.peers_with_block(&hash, None).is_empty());
+        assert_eq!(vec![address(4032)], peer_book.peers_with_block(&hash, Some(11)));
+    }
+
+    #[test]
+    fn test_peers_to_evict_lowest_quality() {
+        let peer_book = PeerBook::default();
+
+        // (port, block height, rtt in ms, failures)
+        let peers = [
+            (4031, 100, 50, 0),
+            (4032, 100, 900, 1),
+            (4033, 40, 200, 0),
+            (4034, 95, 100, 0),
+        ];
+        for &(port, block_height, rtt_ms, failures) in peers.iter() {
+            let remote_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), port));
+            peer_book.set_connected(remote_address, None);
+            peer_book.received_ping(remote_address, block_height, 0);
+
+            let quality = peer_book.get_peer(remote_address, true).unwrap().quality;
+            quality.rtt_ms.store(rtt_ms, Ordering::SeqCst);
+            quality.failures.store(failures, Ordering::SeqCst);
+        }
+
+        let address = |port| SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), port));
+
+        // The slow, unreliable peer is the worst one, followed by the one that lags behind the most.
+        assert_eq!(
+            vec![address(4032)],
+            peer_book.peers_to_evict(1, PeerEvictionStrategy::LowestQuality)
+        );
+        assert_eq!(
+            vec![address(4032), address(4033)],
+            peer_book.peers_to_evict(2, PeerEvictionStrategy::LowestQuality)
+        );
+
+        // No more peers than are connected can be chosen.
+        assert_eq!(4, peer_book.peers_to_evict(10, PeerEvictionStrategy::Random).len());
+    }
 }
@@ -15,6 +15,7 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use snarkos_storage::BlockHeight;
+use snarkvm_dpc::BlockHeaderHash;
 
 use chrono::{DateTime, Utc};
 use parking_lot::{Mutex, RwLock};
@@ -22,6 +23,7 @@ use serde::{Deserialize, Serialize};
 use tokio::task;
 
 use std::{
+    collections::VecDeque,
     net::SocketAddr,
     sync::{
         atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
@@ -49,12 +51,28 @@ pub struct PeerQuality {
     pub last_ping_sent: Mutex<Option<Instant>>,
     /// The time it took to send a `Ping` to the peer and for it to respond with a `Pong`.
     pub rtt_ms: AtomicU64,
+    /// The same round-trip time as `rtt_ms`, measured in microseconds.
+    pub rtt_us: AtomicU64,
     /// The number of failures associated with the peer; grounds for dismissal.
     pub failures: AtomicU32,
+    /// The number of bad events associated with the peer in the current strike window.
+    pub strikes: AtomicU32,
+    /// The timestamp of the first strike in the current strike window.
+    pub first_strike: Mutex<Option<Instant>>,
     /// The number of remaining blocks to sync with.
     pub remaining_sync_blocks: AtomicU32,
+    /// The number of block hash packets received from the peer since the last `GetSync` sent to it.
+    pub sync_hash_packets: AtomicU32,
+    /// The number of payloads of an unknown or unsupported type received from the peer.
+    pub unknown_payloads: AtomicU32,
     /// The number of messages received from the peer.
     pub num_messages_received: AtomicU64,
+    /// The number of transactions and blocks that weren't relayed to the peer because its relay queue was full.
+    pub dropped_relays: AtomicU64,
+    /// The most recent hashes of the blocks the peer has advertised or sent, from the oldest to the newest.
+    pub advertised_blocks: Mutex<VecDeque<BlockHeaderHash>>,
+    /// The minimum fee per byte of the peer's memory pool it shared last, along with the time it was received.
+    pub fee_rate_hint: Mutex<Option<(u64, Instant)>>,
 }
 
 impl PeerQuality {
@@ -69,6 +87,37 @@ impl PeerQuality {
             true
         }
     }
+
+    /// Returns the composite quality score of the peer, given the highest block height among the connected
+    /// peers; `0.0` is the best score. The round-trip time, the failures and the lag behind the best block
+    /// height lower the score in proportion to the value at which each of them would result in a disconnect.
+    pub fn score(&self, best_block_height: BlockHeight) -> f64 {
+        let rtt = self.rtt_ms.load(Ordering::Relaxed) as f64 / crate::MAX_PEER_RTT_MS as f64;
+        let failures = self.failures.load(Ordering::Relaxed) as f64 / crate::MAX_PEER_FAILURES as f64;
+        let lag = best_block_height.saturating_sub(self.block_height.load(Ordering::Relaxed)) as f64
+            / best_block_height.max(1) as f64;
+
+        -(rtt + failures + lag)
+    }
+
+    /// Records that the peer has the block with the given hash, forgetting the oldest recorded hash if there are
+    /// more than `MAX_ADVERTISED_BLOCKS` of them.
+    pub fn advertised_block(&self, hash: BlockHeaderHash) {
+        let mut advertised_blocks = self.advertised_blocks.lock();
+        if advertised_blocks.contains(&hash) {
+            return;
+        }
+
+        if advertised_blocks.len() >= crate::MAX_ADVERTISED_BLOCKS {
+            advertised_blocks.pop_front();
+        }
+        advertised_blocks.push_back(hash);
+    }
+
+    /// Returns `true` if the peer has advertised or sent the block with the given hash.
+    pub fn has_advertised_block(&self, hash: &BlockHeaderHash) -> bool {
+        self.advertised_blocks.lock().contains(hash)
+    }
 }
 
 /// A data structure containing information about a peer.
@@ -84,6 +133,9 @@ pub struct PeerInfo {
     last_disconnected: Option<DateTime<Utc>>,
     /// The number of times we have connected to this peer.
     connected_count: u64,
+    /// The listen address of this node the peer connected through, if it's an inbound peer.
+    #[serde(skip)]
+    local_interface: Option<SocketAddr>,
     /// The quality of the connection with the peer.
     #[serde(skip)]
     pub quality: Arc<PeerQuality>,
@@ -106,6 +158,7 @@ impl PeerInfo {
             last_connected: None,
             last_disconnected: None,
             connected_count: 0,
+            local_interface: None,
             quality: Default::default(),
             tasks: Default::default(),
         }
@@ -159,6 +212,21 @@ impl PeerInfo {
         self.connected_count
     }
 
+    ///
+    /// Returns the listen address of this node the peer connected through, if it's an inbound peer.
+    ///
+    #[inline]
+    pub fn local_interface(&self) -> Option<SocketAddr> {
+        self.local_interface
+    }
+
+    ///
+    /// Sets the listen address of this node the peer connected through.
+    ///
+    pub(crate) fn set_local_interface(&mut self, local_interface: Option<SocketAddr>) {
+        self.local_interface = local_interface;
+    }
+
     ///
     /// Updates the peer to connected.
     ///
@@ -180,6 +248,7 @@ impl PeerInfo {
     ///
     pub(crate) fn set_disconnected(&mut self) {
         self.last_disconnected = Some(Utc::now());
+        self.local_interface = None;
         self.quality.expecting_pong.store(false, Ordering::SeqCst);
         self.quality.remaining_sync_blocks.store(0, Ordering::SeqCst);
 
@@ -14,7 +14,18 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{message::*, stats, ConnReader, ConnWriter, NetworkError, Node, SerializedPeerBook, Version};
+use crate::{
+    message::*,
+    stats,
+    ConnReader,
+    ConnWriter,
+    NetworkError,
+    Node,
+    RelayQueue,
+    SerializedPeerBook,
+    Version,
+    PING_JITTER_MILLIS,
+};
 use snarkvm_dpc::Storage;
 
 use std::{
@@ -25,7 +36,7 @@ use std::{
 };
 
 use parking_lot::Mutex;
-use rand::seq::IteratorRandom;
+use rand::{seq::IteratorRandom, Rng};
 use snow::HandshakeState;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
@@ -35,8 +46,14 @@ use tokio::{
     },
     sync::mpsc::channel,
     task,
+    time::sleep,
 };
 
+/// Returns a random delay of up to `PING_JITTER_MILLIS` to apply before sending a scheduled `Ping`.
+pub(crate) fn ping_jitter<R: Rng>(rng: &mut R) -> Duration {
+    Duration::from_millis(rng.gen_range(0..=PING_JITTER_MILLIS))
+}
+
 impl<S: Storage> Node<S> {
     /// Obtain a list of addresses of connected peers for this node.
     pub(crate) fn connected_peers(&self) -> Vec<SocketAddr> {
@@ -72,8 +89,8 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
             .filter(|(addr, _)| !bootnodes.contains(addr)) // Skip this check if the peer is a bootnode.
             .map(|(addr, info)| (*addr, &info.quality))
         {
-            if peer_quality.rtt_ms.load(Ordering::Relaxed) > 1500
-                || peer_quality.failures.load(Ordering::Relaxed) >= 3
+            if peer_quality.rtt_ms.load(Ordering::Relaxed) > crate::MAX_PEER_RTT_MS
+                || peer_quality.failures.load(Ordering::Relaxed) >= crate::MAX_PEER_FAILURES
                 || peer_quality.is_inactive(now)
             {
                 warn!("Peer {} has a low quality score; disconnecting.", addr);
@@ -101,23 +118,11 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
                 number_to_disconnect
             );
 
-            let mut connected = self
+            for addr in self
                 .peer_book
-                .connected_peers()
-                .iter()
-                .map(|(addr, info)| (*addr, info.last_connected()))
-                .collect::<Vec<_>>();
-
-            // Bootnodes will disconnect from random peers...
-            if !self.config.is_bootnode() {
-                // ...while regular peers from the most recently connected.
-                connected.sort_unstable_by_key(|(_, last_connected)| *last_connected);
-            }
-
-            for _ in 0..number_to_disconnect {
-                if let Some((addr, _)) = connected.pop() {
-                    self.disconnect_from_peer(addr);
-                }
+                .peers_to_evict(number_to_disconnect, self.config.peer_eviction_strategy())
+            {
+                self.disconnect_from_peer(addr);
             }
         }
 
@@ -132,14 +137,16 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         let own_address = self.local_address().unwrap();
 
         // Don't connect if maximum number of connections has been reached.
-        if !self.can_connect() {
+        if !self.can_connect() || !self.has_slot_for(remote_address) {
             return Err(NetworkError::TooManyConnections);
         }
 
-        if remote_address == own_address
-            || ((remote_address.ip().is_unspecified() || remote_address.ip().is_loopback())
-                && remote_address.port() == own_address.port())
-        {
+        let is_own_address = |own_address: &SocketAddr| {
+            remote_address == *own_address
+                || ((remote_address.ip().is_unspecified() || remote_address.ip().is_loopback())
+                    && remote_address.port() == own_address.port())
+        };
+        if is_own_address(&own_address) || self.listen_addresses().iter().any(is_own_address) {
             return Err(NetworkError::SelfConnectAttempt);
         }
         if self.peer_book.is_connecting(remote_address) {
@@ -207,7 +214,7 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
             trace!("sent s, se, psk (XX handshake part 3/3) to {}", remote_address);
 
             // The remote_listener is the same as remote_address when initiating a connection.
-            node.set_connected(remote_address, remote_address, noise, buffer, reader, writer)?;
+            node.set_connected(remote_address, remote_address, None, noise, buffer, reader, writer)?;
 
             metrics::increment_counter!(stats::HANDSHAKES_SUCCESSES_INIT);
 
@@ -356,6 +363,31 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         }
     }
 
+    ///
+    /// Attempts to reconnect to a sync peer that disconnected while it was ahead of this node.
+    ///
+    pub(crate) fn reconnect_to_sync_peer(&self, remote_address: SocketAddr) {
+        debug!("Attempting to reconnect to sync peer {}", remote_address);
+
+        let node = self.clone();
+        task::spawn(async move {
+            match node.initiate_connection(remote_address).await {
+                Err(NetworkError::PeerAlreadyConnecting) | Err(NetworkError::PeerAlreadyConnected) => {
+                    // no issue here, already connecting
+                }
+                Err(e @ NetworkError::TooManyConnections) | Err(e @ NetworkError::SelfConnectAttempt) => {
+                    warn!("Couldn't reconnect to sync peer {}: {}", remote_address, e);
+                    // the connection hasn't been established, no need to disconnect
+                }
+                Err(e) => {
+                    warn!("Couldn't reconnect to sync peer {}: {}", remote_address, e);
+                    node.disconnect_from_peer(remote_address);
+                }
+                Ok(_) => {}
+            }
+        });
+    }
+
     /// Broadcasts a `GetPeers` message to all connected peers to request for more peers.
     fn broadcast_getpeers_requests(&self) {
         // Check that this node is not a bootnode.
@@ -378,24 +410,24 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         }
     }
 
-    /// Broadcasts a `Ping` message to all connected peers.
+    /// Broadcasts a `Ping` message to all connected peers; each one is sent after a random delay
+    /// so that the resulting ping/pong traffic doesn't arrive in bursts.
     fn broadcast_pings(&self) {
         trace!("Broadcasting `Ping` messages");
 
-        // Consider peering tests that don't use the sync layer.
-        let current_block_height = if let Some(ref sync) = self.sync() {
-            sync.current_block_height()
-        } else {
-            0
-        };
-
+        let mut rng = rand::thread_rng();
         for remote_address in self.connected_peers() {
-            self.peer_book.sending_ping(remote_address);
+            let delay = ping_jitter(&mut rng);
+            let node = self.clone();
+
+            task::spawn(async move {
+                sleep(delay).await;
 
-            self.send_request(Message::new(
-                Direction::Outbound(remote_address),
-                Payload::Ping(current_block_height),
-            ));
+                // The peer could have disconnected in the meantime.
+                if node.peer_book.is_connected(remote_address) {
+                    node.send_ping(remote_address);
+                }
+            });
         }
     }
 
@@ -432,23 +464,30 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         // If the peer was truly disconnected, remove its channel and advise.
         if was_connected {
             self.outbound.channels.write().remove(&remote_address);
+            self.outbound.relay_queues.write().remove(&remote_address);
             trace!("Disconnected from {}", remote_address);
         }
     }
 
+    /// Starts the tasks handling the connection with the given peer and marks it as connected; the `local_interface`
+    /// is the listen address an inbound peer connected through, and is `None` for outbound connections.
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     pub fn set_connected(
         &self,
         remote_address: SocketAddr,
         remote_listener: SocketAddr,
+        local_interface: Option<SocketAddr>,
         noise: HandshakeState,
         buffer: Box<[u8]>,
         reader: OwnedReadHalf,
         writer: OwnedWriteHalf,
     ) -> Result<(), NetworkError> {
         let noise = Arc::new(Mutex::new(noise.into_transport_mode()?));
-        let mut reader = ConnReader::new(remote_listener, reader, buffer.clone(), Arc::clone(&noise));
-        let mut writer = ConnWriter::new(remote_listener, writer, buffer, noise);
+        let mut reader = ConnReader::new(remote_listener, reader, buffer.clone(), Arc::clone(&noise))
+            .with_read_timeout(self.config.peer_read_timeout());
+        let mut writer = ConnWriter::new(remote_listener, writer, buffer, noise)
+            .with_write_timeout(self.config.peer_write_timeout());
 
         // Create a channel dedicated to sending messages to the connection.
         let (sender, receiver) = channel(crate::OUTBOUND_CHANNEL_DEPTH);
@@ -463,6 +502,13 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
             error!("The outbound channel for {} had already existed!", remote_listener);
         }
 
+        // Create a bounded queue dedicated to relaying transactions and blocks to the connection.
+        let relay_queue = Arc::new(RelayQueue::new(self.config.relay_queue_depth()));
+        self.outbound
+            .relay_queues
+            .write()
+            .insert(remote_listener, Arc::clone(&relay_queue));
+
         // Listen for inbound messages.
         let node = self.clone();
         let peer_reading_task = tokio::spawn(async move {
@@ -472,11 +518,13 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         // Listen for outbound messages.
         let node = self.clone();
         let peer_writing_task = tokio::spawn(async move {
-            node.listen_for_outbound_messages(receiver, &mut writer).await;
+            node.listen_for_outbound_messages(receiver, relay_queue, &mut writer)
+                .await;
         });
 
         // Mark the peer as connected.
         self.peer_book.set_connected(remote_address, Some(remote_listener));
+        self.peer_book.set_local_interface(remote_listener, local_interface);
 
         if let Some(peer) = self.peer_book.get_peer(remote_listener, true) {
             peer.register_task(peer_reading_task, true);
@@ -494,7 +542,8 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
     }
 
     pub(crate) fn send_peers(&self, remote_address: SocketAddr) {
-        // Broadcast the sanitized list of connected peers back to the requesting peer.
+        // Broadcast a random subset of the sanitized list of connected peers back to the requesting peer, so that
+        // a single request can neither be used to amplify traffic nor to learn all of the node's connections.
         let peers = self
             .peer_book
             .connected_peers()
@@ -502,7 +551,7 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
             .map(|(k, _)| k)
             .filter(|&addr| *addr != remote_address)
             .copied()
-            .choose_multiple(&mut rand::thread_rng(), crate::SHARED_PEER_COUNT);
+            .choose_multiple(&mut rand::thread_rng(), self.config.shared_peer_count());
 
         self.send_request(Message::new(Direction::Outbound(remote_address), Payload::Peers(peers)));
     }
@@ -537,4 +586,44 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
             true
         }
     }
+
+    /// Registers a strike against the given peer, and a failure once it accumulates the configured number of
+    /// strikes within the strike window, so that transient glitches don't get it disconnected.
+    pub(crate) fn register_failure(&self, remote_address: SocketAddr) {
+        if self.peer_book.register_strike(
+            remote_address,
+            self.config.failure_strikes(),
+            self.config.strike_window(),
+        ) {
+            self.peer_book.register_failure(remote_address);
+        }
+    }
+
+    /// Returns `true` if the given peer may take one of the free connection slots; the slots reserved
+    /// for new peers are only available to the peers that have never been connected to before.
+    pub fn has_slot_for(&self, remote_address: SocketAddr) -> bool {
+        let reserved_slots = self.config.reserved_new_peer_slots() as usize;
+        let is_new_peer = self
+            .peer_book
+            .get_peer(remote_address, false)
+            .map(|peer_info| peer_info.connected_count() == 0)
+            .unwrap_or(true);
+
+        if reserved_slots == 0 || is_new_peer {
+            return true;
+        }
+
+        let num_connected = self.peer_book.number_of_connected_peers() as usize;
+        let max_peers = self.config.maximum_number_of_connected_peers() as usize;
+
+        if num_connected >= max_peers.saturating_sub(reserved_slots) {
+            warn!(
+                "Only the {} connection slots reserved for new peers are left; rejecting {}",
+                reserved_slots, remote_address
+            );
+            false
+        } else {
+            true
+        }
+    }
 }
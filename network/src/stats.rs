@@ -15,6 +15,7 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use metrics::{GaugeValue, Key, Recorder, Unit};
+use snarkos_consensus::memory_pool::MEMPOOL_DUPLICATE_SUBMISSIONS;
 
 use std::sync::atomic::{AtomicU64, Ordering};
 
@@ -33,6 +34,7 @@ pub const INBOUND_SYNCS: &str = "snarkos_inbound_syncs_total";
 pub const INBOUND_SYNCBLOCKS: &str = "snarkos_inbound_syncblocks_total";
 pub const INBOUND_TRANSACTIONS: &str = "snarkos_inbound_transactions_total";
 pub const INBOUND_UNKNOWN: &str = "snarkos_inbound_unknown_total";
+pub const INBOUND_PROCESSING_TIME: &str = "snarkos_inbound_processing_time_seconds";
 
 pub const OUTBOUND_ALL_SUCCESSES: &str = "snarkos_outbound_all_successes_total";
 pub const OUTBOUND_ALL_FAILURES: &str = "snarkos_outbound_all_failures_total";
@@ -243,6 +245,8 @@ pub struct MiscStats {
     pub duplicate_sync_blocks: AtomicU64,
     /// The number of RPC requests received.
     pub rpc_requests: AtomicU64,
+    /// The number of submitted transactions that were already in the memory pool.
+    pub duplicate_transactions: AtomicU64,
 }
 
 impl MiscStats {
@@ -252,6 +256,7 @@ impl MiscStats {
             duplicate_blocks: AtomicU64::new(0),
             duplicate_sync_blocks: AtomicU64::new(0),
             rpc_requests: AtomicU64::new(0),
+            duplicate_transactions: AtomicU64::new(0),
         }
     }
 }
@@ -304,6 +309,7 @@ impl Recorder for Stats {
             MISC_DUPLICATE_BLOCKS => self.misc.duplicate_blocks.fetch_add(value, Ordering::Relaxed),
             MISC_DUPLICATE_SYNC_BLOCKS => self.misc.duplicate_sync_blocks.fetch_add(value, Ordering::Relaxed),
             MISC_RPC_REQUESTS => self.misc.rpc_requests.fetch_add(value, Ordering::Relaxed),
+            MEMPOOL_DUPLICATE_SUBMISSIONS => self.misc.duplicate_transactions.fetch_add(value, Ordering::Relaxed),
             _ => {
                 error!("Metrics key {} wasn't assigned an operation and won't work!", key);
                 0
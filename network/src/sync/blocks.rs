@@ -15,10 +15,30 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{message::*, stats, NetworkError, Node};
-use snarkos_consensus::error::ConsensusError;
-use snarkvm_dpc::{Block, BlockHeaderHash, Storage};
-
-use std::net::SocketAddr;
+use snarkos_consensus::{error::ConsensusError, BlockOutcome};
+use snarkos_storage::BlockHeight;
+use snarkvm_dpc::{testnet1::instantiated::Tx, Block, BlockHeader, BlockHeaderHash, Storage};
+use snarkvm_utilities::FromBytes;
+
+use rand::seq::SliceRandom;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+use tokio::{task, time::sleep};
+
+/// A new block received from a peer, along with the outcome of its concurrent validation.
+pub struct ValidatedBlock {
+    /// The peer that sent the block.
+    source: SocketAddr,
+    /// The serialized block.
+    bytes: Vec<u8>,
+    /// The deserialized block, along with whether its transactions were verified.
+    block: Result<(Block<Tx>, bool), NetworkError>,
+    /// The canon tip the block's transactions were verified against, if it could be determined.
+    verified_at_tip: Option<BlockHeaderHash>,
+}
 
 impl<S: Storage + Send + std::marker::Sync + 'static> Node<S> {
     ///
@@ -35,6 +55,8 @@ impl<S: Storage + Send + std::marker::Sync + 'static> Node<S> {
 
         info!("Updating blocks from {}", sync_node);
 
+        self.peer_book.sending_get_sync(sync_node);
+
         // Send a GetSync to the selected sync node.
         self.send_request(Message::new(
             Direction::Outbound(sync_node),
@@ -50,8 +72,8 @@ impl<S: Storage + Send + std::marker::Sync + 'static> Node<S> {
 
         for remote_address in self.connected_peers() {
             if remote_address != block_miner {
-                // Send a `Block` message to the connected peer.
-                self.send_request(Message::new(
+                // Relay a `Block` message to the connected peer.
+                self.relay_message(Message::new(
                     Direction::Outbound(remote_address),
                     Payload::Block(block_bytes.clone()),
                 ));
@@ -59,67 +81,149 @@ impl<S: Storage + Send + std::marker::Sync + 'static> Node<S> {
         }
     }
 
-    /// A peer has sent us a new block to process.
-    pub(crate) fn received_block(
+    /// A peer has sent us a new block to process; its transactions are verified concurrently with
+    /// the ones of other new blocks, but the blocks are committed in the order they were received in.
+    /// If too many blocks are already awaiting validation, it waits until there's room for another one.
+    pub(crate) async fn received_new_block(
         &self,
         remote_address: SocketAddr,
         block: Vec<u8>,
-        is_block_new: bool,
     ) -> Result<(), NetworkError> {
-        let block_size = block.len();
+        let block_validator = match self.block_validator.get() {
+            Some(block_validator) => block_validator,
+            None => return self.received_block(remote_address, block, true).map(|_| ()),
+        };
+
+        let consensus = self.expect_sync().consensus.clone();
         let max_block_size = self.expect_sync().max_block_size();
 
-        if block_size > max_block_size {
-            error!(
-                "Received block from {} that is too big ({} > {})",
-                remote_address, block_size, max_block_size
-            );
-            return Err(NetworkError::ConsensusError(ConsensusError::BlockTooLarge(
-                block_size,
-                max_block_size,
-            )));
-        }
+        block_validator
+            .submit(move || {
+                // The tip is read before the transactions are verified, so that a later change can be detected.
+                let verified_at_tip = consensus
+                    .ledger
+                    .get_block_hash(consensus.ledger.get_current_block_height())
+                    .ok();
+
+                let block_struct = deserialize_block(remote_address, &block, max_block_size).map(|block_struct| {
+                    // The transactions that fail to verify are verified again once the block is committed,
+                    // as they may depend on the blocks that preceded it.
+                    let transactions_verified =
+                        matches!(consensus.verify_transactions(&block_struct.transactions.0), Ok(true));
+                    (block_struct, transactions_verified)
+                });
+
+                ValidatedBlock {
+                    source: remote_address,
+                    bytes: block,
+                    block: block_struct,
+                    verified_at_tip,
+                }
+            })
+            .await;
 
-        let block_struct = match Block::deserialize(&block) {
-            Ok(block) => block,
+        Ok(())
+    }
+
+    /// Commits a new block whose transactions were verified concurrently.
+    pub(crate) fn commit_validated_block(&self, validated_block: ValidatedBlock) {
+        let ValidatedBlock {
+            source,
+            bytes,
+            block,
+            verified_at_tip,
+        } = validated_block;
+
+        let block_outcome = block.and_then(|(block_struct, transactions_verified)| {
+            let sync = self.expect_sync();
+            let consensus = &sync.consensus;
+
+            // The verification only holds if the block still extends the ledger it was verified against;
+            // otherwise, e.g. if the blocks queued before it changed the tip, it's verified again.
+            let canon_tip = sync.storage().get_block_hash(sync.current_block_height())?;
+            let block_outcome = if transactions_verified
+                && is_verification_current(
+                    &block_struct.header.previous_block_hash,
+                    verified_at_tip.as_ref(),
+                    &canon_tip,
+                ) {
+                consensus.receive_verified_block(&block_struct)?
+            } else {
+                consensus.receive_block(&block_struct)?
+            };
+
+            Ok((block_struct.header.get_hash(), block_outcome))
+        });
+
+        match block_outcome {
+            Ok((block_hash, block_outcome)) => self.processed_block(source, bytes, block_hash, true, &block_outcome),
             Err(error) => {
-                error!(
-                    "Failed to deserialize received block from {}: {}",
-                    remote_address, error
-                );
-                return Err(error.into());
+                metrics::increment_counter!(stats::INBOUND_ALL_FAILURES);
+                error!("Failed to process a block from {}: {}", source, error);
             }
-        };
+        }
+    }
 
-        info!(
-            "Received block from {} of epoch {} with hash {:?}",
+    /// A peer has sent us a block to process; returns the outcome of processing it.
+    pub(crate) fn received_block(
+        &self,
+        remote_address: SocketAddr,
+        block: Vec<u8>,
+        is_block_new: bool,
+    ) -> Result<BlockOutcome, NetworkError> {
+        let block_struct = deserialize_block(remote_address, &block, self.expect_sync().max_block_size())?;
+
+        // Verify the block and insert it into the storage.
+        let block_outcome = self.expect_sync().consensus.receive_block(&block_struct)?;
+
+        self.processed_block(
             remote_address,
-            block_struct.header.time,
-            hex::encode(block_struct.header.get_hash().0)
+            block,
+            block_struct.header.get_hash(),
+            is_block_new,
+            &block_outcome,
         );
 
-        // Verify the block and insert it into the storage.
-        let block_validity = self.expect_sync().consensus.receive_block(&block_struct);
+        Ok(block_outcome)
+    }
 
-        if let Err(ConsensusError::PreExistingBlock) = block_validity {
-            if is_block_new {
-                metrics::increment_counter!(stats::MISC_DUPLICATE_BLOCKS);
-            } else {
-                metrics::increment_counter!(stats::MISC_DUPLICATE_SYNC_BLOCKS);
-            }
+    /// Updates the node's state after a received block was processed.
+    fn processed_block(
+        &self,
+        remote_address: SocketAddr,
+        block: Vec<u8>,
+        block_hash: BlockHeaderHash,
+        is_block_new: bool,
+        block_outcome: &BlockOutcome,
+    ) {
+        if !matches!(block_outcome, BlockOutcome::Invalid(_)) {
+            self.peer_book.advertised_block(remote_address, block_hash);
         }
 
-        if block_validity.is_ok() {
-            // This is a non-sync Block, send it to our peers.
-            if is_block_new {
-                self.propagate_block(block, remote_address);
-            } else {
-                // If it's a valid SyncBlock, bump block height.
-                metrics::increment_counter!(stats::MISC_BLOCK_HEIGHT);
+        match block_outcome {
+            BlockOutcome::AcceptedToTip | BlockOutcome::AcceptedAsSideChain => {
+                self.update_best_block_height();
+
+                // This is a non-sync Block, send it to our peers.
+                if is_block_new {
+                    self.propagate_block(block, remote_address);
+                } else {
+                    // If it's a valid SyncBlock, bump block height.
+                    metrics::increment_counter!(stats::MISC_BLOCK_HEIGHT);
+                }
+            }
+            BlockOutcome::Duplicate => {
+                if is_block_new {
+                    metrics::increment_counter!(stats::MISC_DUPLICATE_BLOCKS);
+                } else {
+                    metrics::increment_counter!(stats::MISC_DUPLICATE_SYNC_BLOCKS);
+                }
+            }
+            BlockOutcome::Invalid(reason) => {
+                warn!("Received an invalid block from {}: {}", remote_address, reason);
+                self.register_failure(remote_address);
             }
         }
-
-        Ok(())
     }
 
     /// A peer has requested a block.
@@ -128,27 +232,82 @@ impl<S: Storage + Send + std::marker::Sync + 'static> Node<S> {
         remote_address: SocketAddr,
         header_hashes: Vec<BlockHeaderHash>,
     ) -> Result<(), NetworkError> {
-        for hash in header_hashes.into_iter().take(crate::MAX_BLOCK_SYNC_COUNT as usize) {
-            let block = self.expect_sync().storage().get_block(&hash)?;
+        // Only serve up to the maximum number of blocks, and penalize a peer that requests more of them.
+        let max_requested_blocks = self.expect_sync().max_requested_blocks() as usize;
+        if header_hashes.len() > max_requested_blocks {
+            warn!(
+                "{} requested {} blocks, more than the maximum of {}",
+                remote_address,
+                header_hashes.len(),
+                max_requested_blocks
+            );
+            self.register_failure(remote_address);
+        }
+
+        for hash in header_hashes.into_iter().take(max_requested_blocks) {
+            // Send a `SyncBlock` message to the connected peer, holding it back if the peer exceeded its serving rate;
+            // the blocks that would be held back for too long aren't served at all.
+            let delay = match self.expect_sync().reserve_block_serving(remote_address) {
+                Some(delay) => delay,
+                None => {
+                    debug!(
+                        "Not serving the remaining blocks requested by {}, as it has too many pending",
+                        remote_address
+                    );
+                    break;
+                }
+            };
 
-            // Send a `SyncBlock` message to the connected peer.
-            self.send_request(Message::new(
-                Direction::Outbound(remote_address),
-                Payload::SyncBlock(block.serialize()?),
-            ));
+            if delay == Duration::from_secs(0) {
+                self.send_sync_block(remote_address, &hash)?;
+            } else {
+                // The block is only loaded once it's due, so that the pending blocks aren't held in memory.
+                let node = self.clone();
+                task::spawn(async move {
+                    sleep(delay).await;
+                    if let Err(e) = node.send_sync_block(remote_address, &hash) {
+                        debug!("Failed to serve a block to {}: {}", remote_address, e);
+                    }
+                });
+            }
         }
 
         Ok(())
     }
 
+    /// Sends the block with the given hash to the given peer in a `SyncBlock` message.
+    fn send_sync_block(&self, remote_address: SocketAddr, hash: &BlockHeaderHash) -> Result<(), NetworkError> {
+        let block = self.expect_sync().storage().get_block(hash)?;
+        self.send_request(Message::new(
+            Direction::Outbound(remote_address),
+            Payload::SyncBlock(block.serialize()?),
+        ));
+
+        Ok(())
+    }
+
     /// A peer has requested our chain state to sync with.
     pub(crate) fn received_get_sync(
         &self,
         remote_address: SocketAddr,
         block_locator_hashes: Vec<BlockHeaderHash>,
     ) -> Result<(), NetworkError> {
+        // Don't look up an excessive number of block locator hashes, and penalize a peer that sends them.
+        if block_locator_hashes.len() > crate::MAX_BLOCK_LOCATOR_HASHES {
+            warn!(
+                "{} sent {} block locator hashes, more than the maximum of {}",
+                remote_address,
+                block_locator_hashes.len(),
+                crate::MAX_BLOCK_LOCATOR_HASHES
+            );
+            self.register_failure(remote_address);
+            return Ok(());
+        }
+
         let sync = {
-            let storage = self.expect_sync().storage();
+            let sync = self.expect_sync();
+            let storage = sync.storage();
+            let max_served_sync_hashes = sync.max_served_sync_hashes();
 
             let latest_shared_hash = storage.get_latest_shared_hash(block_locator_hashes)?;
             let current_height = storage.get_current_block_height();
@@ -157,10 +316,10 @@ impl<S: Storage + Send + std::marker::Sync + 'static> Node<S> {
                 if height < current_height {
                     let mut max_height = current_height;
 
-                    // if the requester is behind more than MAX_BLOCK_SYNC_COUNT blocks
-                    if current_height > height + crate::MAX_BLOCK_SYNC_COUNT {
-                        // send no more than MAX_BLOCK_SYNC_COUNT
-                        max_height = height + crate::MAX_BLOCK_SYNC_COUNT;
+                    // if the requester is behind more than the maximum number of served sync hashes,
+                    // send no more than that; it asks for the following ones in its next sync round
+                    if current_height - height > max_served_sync_hashes {
+                        max_height = height + max_served_sync_hashes;
                     }
 
                     let mut block_hashes = Vec::with_capacity((max_height - height) as usize);
@@ -186,17 +345,430 @@ impl<S: Storage + Send + std::marker::Sync + 'static> Node<S> {
     }
 
     /// A peer has sent us their chain state.
-    pub(crate) fn received_sync(&self, remote_address: SocketAddr, block_hashes: Vec<BlockHeaderHash>) {
+    pub(crate) fn received_sync(&self, remote_address: SocketAddr, mut block_hashes: Vec<BlockHeaderHash>) {
         // If empty sync is no-op as chain states match
         if !block_hashes.is_empty() {
-            for batch in block_hashes.chunks(crate::MAX_BLOCK_SYNC_COUNT as usize) {
-                // GetBlocks for each block hash: fire and forget, relying on block locator hashes to
-                // detect missing blocks and divergence in chain for now.
-                self.send_request(Message::new(
-                    Direction::Outbound(remote_address),
-                    Payload::GetBlocks(batch.to_vec()),
-                ));
+            for hash in &block_hashes {
+                self.peer_book.advertised_block(remote_address, hash.clone());
+            }
+
+            let sync = self.expect_sync();
+            let received_hash_count = block_hashes.len();
+
+            // Only the blocks that weren't requested from the peers asked earlier in this round are requested.
+            sync.retain_unrequested_sync_hashes(&mut block_hashes);
+            if block_hashes.len() != received_hash_count {
+                self.peer_book.expecting_sync_blocks(remote_address, block_hashes.len());
+            }
+            self.gather_more_sync_hashes(remote_address, received_hash_count);
+            if block_hashes.is_empty() {
+                return;
+            }
+
+            let segment_peers = self.sync_segment_peers(remote_address, block_hashes.len());
+            let segments = block_hashes
+                .chunks(sync.sync_segment_size())
+                .zip(segment_peers.iter().cycle());
+
+            if segment_peers.len() > 1 {
+                // The segments are downloaded concurrently, so the blocks need to be stitched back together
+                // in the order of the chain before they are processed.
+                sync.segments().start(&block_hashes);
+
+                let mut expected_sync_blocks = HashMap::with_capacity(segment_peers.len());
+                for (segment, peer) in segments.clone() {
+                    *expected_sync_blocks.entry(*peer).or_insert(0) += segment.len();
+                }
+                for (peer, count) in expected_sync_blocks {
+                    self.peer_book.expecting_sync_blocks(peer, count);
+                }
+
+                debug!(
+                    "Downloading {} sync blocks in segments of {} from {} peers",
+                    block_hashes.len(),
+                    sync.sync_segment_size(),
+                    segment_peers.len()
+                );
+            } else {
+                sync.segments().clear();
+            }
+
+            for (segment, peer) in segments {
+                for batch in segment.chunks(crate::MAX_BLOCK_SYNC_COUNT as usize) {
+                    // GetBlocks for each block hash: fire and forget, relying on block locator hashes to
+                    // detect missing blocks and divergence in chain for now.
+                    self.send_request(Message::new(
+                        Direction::Outbound(*peer),
+                        Payload::GetBlocks(batch.to_vec()),
+                    ));
+                }
             }
         }
     }
+
+    /// Asks another peer with a longer chain for block hashes if the given peer, asked for them in the ongoing
+    /// block sync round, provided fewer of them than its block height suggests; the number of peers asked in
+    /// a single round is bounded, and a round whose blocks are downloaded in segments isn't extended.
+    pub(crate) fn gather_more_sync_hashes(&self, source: SocketAddr, received_hash_count: usize) {
+        let sync = self.expect_sync();
+        if !self.is_syncing_blocks() || !sync.is_sync_hash_peer(source) || sync.segments().is_active() {
+            return;
+        }
+
+        let my_height = sync.current_block_height();
+        let source_height = match self.peer_book.get_peer(source, true) {
+            Some(peer_info) => peer_info.block_height(),
+            None => return,
+        };
+        let expected_hash_count = source_height.saturating_sub(my_height).min(crate::MAX_BLOCK_SYNC_COUNT) as usize;
+        if received_hash_count >= expected_hash_count {
+            return;
+        }
+
+        // Prefer the peer with the longest chain among the ones that weren't asked yet.
+        let next_peer = self
+            .peer_book
+            .connected_peers()
+            .iter()
+            .filter(|(peer, info)| info.block_height() > my_height + 1 && !sync.is_sync_hash_peer(**peer))
+            .max_by_key(|(_, info)| info.block_height())
+            .map(|(peer, _)| *peer);
+
+        if let Some(next_peer) = next_peer {
+            if sync.register_sync_hash_peer(next_peer) {
+                debug!(
+                    "{} provided {} of the {} expected block hashes; asking {} for more",
+                    source, received_hash_count, expected_hash_count, next_peer
+                );
+                self.update_blocks(next_peer);
+            }
+        }
+    }
+
+    /// Returns the peers to download the segments of the given number of sync blocks from, starting
+    /// with the peer that provided their hashes; only the peers with a sufficient block height are included.
+    fn sync_segment_peers(&self, sync_peer: SocketAddr, sync_block_count: usize) -> Vec<SocketAddr> {
+        let sync = self.expect_sync();
+        let segment_count = (sync_block_count + sync.sync_segment_size() - 1) / sync.sync_segment_size();
+        let min_block_height = sync.current_block_height() + sync_block_count as BlockHeight;
+
+        let mut candidates = self
+            .peer_book
+            .connected_peers()
+            .iter()
+            .filter(|(peer, info)| **peer != sync_peer && info.block_height() >= min_block_height)
+            .map(|(peer, _)| *peer)
+            .collect::<Vec<_>>();
+
+        // The candidates are ordered before they're shuffled, so that the choice depends only on the rng.
+        candidates.sort_unstable();
+        candidates.shuffle(&mut *sync.rng());
+
+        select_diverse_peers(sync_peer, &candidates, segment_count, crate::MAX_SYNC_PEERS_PER_SUBNET)
+    }
+
+    /// A peer has sent us a block requested during a block sync round; returns `true` when the round is finished.
+    pub(crate) fn received_sync_block(&self, remote_address: SocketAddr, block: Vec<u8>) -> Result<bool, NetworkError> {
+        let segments = self.expect_sync().segments();
+
+        if !segments.is_active() {
+            if let Some(batch) = self.expect_sync().buffer_sync_block(remote_address, block) {
+                self.commit_sync_blocks(batch)?;
+            }
+
+            // Update the peer and possibly finish the sync process, committing any blocks held back.
+            let is_finished = self.peer_book.got_sync_block(remote_address);
+            if is_finished {
+                let batch = self.expect_sync().take_pending_sync_blocks();
+                if !batch.is_empty() {
+                    self.commit_sync_blocks(batch)?;
+                }
+            }
+
+            return Ok(is_finished);
+        }
+
+        let block_hash = BlockHeader::read(&block[..])?.get_hash();
+        if !segments.is_expected(&block_hash) {
+            debug!("Received an unexpected sync block from {}", remote_address);
+            return Ok(false);
+        }
+
+        // The block is only matched with its hash, so its transactions are checked before it's held back; a
+        // tampered block would otherwise only be detected once its predecessors are processed, abandoning the round.
+        if !snarkos_consensus::has_valid_merkle_root(&Block::deserialize(&block)?)? {
+            warn!(
+                "Received a sync block with a mismatched Merkle root from {}",
+                remote_address
+            );
+            self.register_failure(remote_address);
+            return Ok(false);
+        }
+        self.peer_book.got_sync_block(remote_address);
+
+        // Process the blocks whose predecessors have all been processed already.
+        let ready_blocks = segments
+            .insert(block_hash, remote_address, block)
+            .into_iter()
+            .map(|(_, source, block)| (source, block))
+            .collect::<Vec<_>>();
+        for batch in ready_blocks.chunks(self.expect_sync().sync_commit_batch_size()) {
+            match self.commit_sync_blocks(batch.to_vec()) {
+                // The rest of the round builds on an invalid block, so it's abandoned.
+                Ok(false) => {
+                    segments.clear();
+                    break;
+                }
+                Ok(true) => {}
+                Err(e) => {
+                    segments.clear();
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(!segments.is_active())
+    }
+
+    /// Commits a batch of sync blocks; the ones extending the canon chain are committed together, either all or none
+    /// of them, and the others are processed one by one. Returns `false` if one of them is invalid.
+    fn commit_sync_blocks(&self, batch: Vec<(SocketAddr, Vec<u8>)>) -> Result<bool, NetworkError> {
+        if let [(source, block)] = &batch[..] {
+            let block_outcome = self.received_block(*source, block.clone(), false)?;
+            return Ok(!matches!(block_outcome, BlockOutcome::Invalid(_)));
+        }
+
+        let sync = self.expect_sync();
+        let max_block_size = sync.max_block_size();
+        let mut blocks = batch
+            .into_iter()
+            .map(|(source, bytes)| {
+                deserialize_block(source, &bytes, max_block_size).map(|block| Some((source, bytes, block)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The blocks aren't necessarily received in order, so the ones extending the canon chain are ordered first;
+        // the others, e.g. the ones from a side chain, are processed on their own afterwards.
+        let links = blocks
+            .iter()
+            .flatten()
+            .map(|(_, _, block)| (block.header.previous_block_hash.clone(), block.header.get_hash()))
+            .collect::<Vec<_>>();
+        let canon_tip = sync.storage().get_block_hash(sync.current_block_height())?;
+        let (chain, chain_blocks): (Vec<_>, Vec<_>) = chain_order(canon_tip, &links)
+            .into_iter()
+            .filter_map(|index| blocks[index].take())
+            .map(|(source, bytes, block)| ((source, bytes), block))
+            .unzip();
+
+        if !chain_blocks.is_empty() {
+            let block_outcomes = sync.consensus.receive_block_batch(&chain_blocks)?;
+
+            // None of the blocks were committed if one of them is invalid, so only that one is processed further.
+            let is_valid = !matches!(block_outcomes.last(), Some(BlockOutcome::Invalid(_)));
+            let processed = if is_valid { 0 } else { block_outcomes.len() - 1 };
+            for (((source, bytes), block), block_outcome) in
+                chain.into_iter().zip(chain_blocks).zip(block_outcomes).skip(processed)
+            {
+                self.processed_block(source, bytes, block.header.get_hash(), false, &block_outcome);
+            }
+
+            if !is_valid {
+                return Ok(false);
+            }
+        }
+
+        for (source, bytes, _) in blocks.into_iter().flatten() {
+            if let BlockOutcome::Invalid(_) = self.received_block(source, bytes, false)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Returns whether the transactions of a block with the given parent, verified against the ledger with the
+/// given tip, are still verified with regard to the current canon tip.
+fn is_verification_current(
+    previous_block_hash: &BlockHeaderHash,
+    verified_at_tip: Option<&BlockHeaderHash>,
+    canon_tip: &BlockHeaderHash,
+) -> bool {
+    verified_at_tip == Some(canon_tip) && previous_block_hash == canon_tip
+}
+
+/// Returns the indices of the blocks, given as the hashes of their parents and their own, that form a chain
+/// extending the given tip, in the order of the chain.
+fn chain_order(tip: BlockHeaderHash, links: &[(BlockHeaderHash, BlockHeaderHash)]) -> Vec<usize> {
+    let mut children = HashMap::with_capacity(links.len());
+    for (index, (parent_hash, _)) in links.iter().enumerate() {
+        children.entry(parent_hash).or_insert(index);
+    }
+
+    let mut order = vec![];
+    let mut tip = tip;
+    while let Some(index) = children.remove(&tip) {
+        order.push(index);
+        tip = links[index].1.clone();
+    }
+
+    order
+}
+
+/// Returns the /24 network of an IPv4 address or the /48 network of an IPv6 one.
+fn subnet(addr: &SocketAddr) -> IpAddr {
+    match addr.ip() {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            IpAddr::from([a, b, c, 0])
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            IpAddr::from([segments[0], segments[1], segments[2], 0, 0, 0, 0, 0])
+        }
+    }
+}
+
+/// Selects up to `count` peers, starting with `first`, so that they span as many distinct subnets as possible;
+/// the candidates from the subnets that are already represented are only picked if there are no others left,
+/// and no more than `max_per_subnet` peers are picked from a single subnet.
+fn select_diverse_peers(
+    first: SocketAddr,
+    candidates: &[SocketAddr],
+    count: usize,
+    max_per_subnet: usize,
+) -> Vec<SocketAddr> {
+    let mut selected = vec![first];
+    let mut subnet_counts = HashMap::new();
+    subnet_counts.insert(subnet(&first), 1);
+
+    // Prefer the candidates from new subnets, then fill the remaining spots up to the per-subnet limit.
+    for max_count in &[1, max_per_subnet] {
+        for candidate in candidates {
+            if selected.len() >= count {
+                return selected;
+            }
+
+            let subnet_count = subnet_counts.entry(subnet(candidate)).or_insert(0);
+            if *subnet_count < *max_count && !selected.contains(candidate) {
+                *subnet_count += 1;
+                selected.push(*candidate);
+            }
+        }
+    }
+
+    selected
+}
+
+/// Checks the size of a block received from a peer and deserializes it.
+fn deserialize_block(
+    remote_address: SocketAddr,
+    block: &[u8],
+    max_block_size: usize,
+) -> Result<Block<Tx>, NetworkError> {
+    let block_size = block.len();
+
+    if block_size > max_block_size {
+        error!(
+            "Received block from {} that is too big ({} > {})",
+            remote_address, block_size, max_block_size
+        );
+        return Err(NetworkError::ConsensusError(ConsensusError::BlockTooLarge(
+            block_size,
+            max_block_size,
+        )));
+    }
+
+    let block_struct = match Block::deserialize(block) {
+        Ok(block) => block,
+        Err(error) => {
+            error!(
+                "Failed to deserialize received block from {}: {}",
+                remote_address, error
+            );
+            return Err(error.into());
+        }
+    };
+
+    info!(
+        "Received block from {} of epoch {} with hash {:?}",
+        remote_address,
+        block_struct.header.time,
+        hex::encode(block_struct.header.get_hash().0)
+    );
+
+    Ok(block_struct)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sync_peers_prefer_distinct_subnets() {
+        let sync_peer: SocketAddr = "10.0.0.1:4131".parse().unwrap();
+        let candidates = [
+            "10.0.0.2:4131",
+            "10.0.0.3:4131",
+            "10.0.0.4:4131",
+            "10.0.1.1:4131",
+            "192.168.1.1:4131",
+        ]
+        .iter()
+        .map(|addr| addr.parse().unwrap())
+        .collect::<Vec<SocketAddr>>();
+
+        // The peers from other subnets are picked before the ones sharing the sync peer's subnet.
+        let selected = select_diverse_peers(sync_peer, &candidates, 3, 2);
+        assert_eq!(selected, vec![sync_peer, candidates[3], candidates[4]]);
+
+        // The remaining spots are filled with peers from the represented subnets, up to the per-subnet limit.
+        let selected = select_diverse_peers(sync_peer, &candidates, 6, 2);
+        assert_eq!(selected, vec![sync_peer, candidates[3], candidates[4], candidates[0]]);
+
+        // IPv6 peers are grouped by their /48 network.
+        let sync_peer: SocketAddr = "[2001:db8:1::1]:4131".parse().unwrap();
+        let candidates = ["[2001:db8:1:ff::1]:4131", "[2001:db8:2::1]:4131"]
+            .iter()
+            .map(|addr| addr.parse().unwrap())
+            .collect::<Vec<SocketAddr>>();
+        let selected = select_diverse_peers(sync_peer, &candidates, 2, 1);
+        assert_eq!(selected, vec![sync_peer, candidates[1]]);
+    }
+
+    #[test]
+    fn sync_blocks_chained_from_tip() {
+        let hash = |i: u8| BlockHeaderHash([i; 32]);
+
+        // Blocks 1 to 3 received out of order, along with a fork of block 1 and an orphan.
+        let links = vec![
+            (hash(2), hash(3)),
+            (hash(0), hash(1)),
+            (hash(8), hash(9)),
+            (hash(1), hash(2)),
+            (hash(0), hash(4)),
+        ];
+        assert_eq!(chain_order(hash(0), &links), vec![1, 3, 0]);
+
+        // None of the blocks extend an unrelated tip.
+        assert!(chain_order(hash(5), &links).is_empty());
+    }
+
+    #[test]
+    fn verification_invalidated_by_tip_change() {
+        let hash = |i: u8| BlockHeaderHash([i; 32]);
+
+        // A block extending the tip it was verified against, which is still the canon tip.
+        assert!(is_verification_current(&hash(1), Some(&hash(1)), &hash(1)));
+
+        // A queued block committed after the blocks before it moved the tip, e.g. to another fork.
+        assert!(!is_verification_current(&hash(1), Some(&hash(1)), &hash(2)));
+
+        // A block from a side chain, verified against a tip it doesn't extend.
+        assert!(!is_verification_current(&hash(3), Some(&hash(1)), &hash(1)));
+
+        // A block verified against an unknown tip.
+        assert!(!is_verification_current(&hash(1), None, &hash(1)));
+    }
 }
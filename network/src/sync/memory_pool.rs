@@ -15,8 +15,8 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{message::*, NetworkError, Node};
-use snarkos_consensus::memory_pool::Entry;
-use snarkvm_dpc::{testnet1::instantiated::Tx, Storage};
+use snarkos_consensus::memory_pool::{Entry, PriorityClass, TxOrigin};
+use snarkvm_dpc::{testnet1::instantiated::Tx, LedgerScheme, Storage};
 use snarkvm_utilities::{
     bytes::{FromBytes, ToBytes},
     to_bytes,
@@ -29,6 +29,11 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
     /// Triggers the memory pool sync with a selected peer.
     ///
     pub fn update_memory_pool(&self, sync_node: Option<SocketAddr>) {
+        // A node without a memory pool has no use for the transactions of its peers.
+        if self.sync().map(|sync| sync.memory_pool().lock().disabled) == Some(true) {
+            return;
+        }
+
         if let Some(sync_node) = sync_node {
             info!("Updating memory pool from {}", sync_node);
 
@@ -39,39 +44,82 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
     }
 
     ///
-    /// Broadcast memory pool transaction to connected peers.
+    /// Broadcast memory pool transaction to connected peers; if it replaced other memory pool
+    /// transactions, their ids are broadcast along with it.
     ///
-    pub(crate) fn propagate_memory_pool_transaction(&self, transaction_bytes: Vec<u8>, transaction_sender: SocketAddr) {
+    pub(crate) fn propagate_memory_pool_transaction(
+        &self,
+        transaction_bytes: Vec<u8>,
+        origin: TxOrigin,
+        replaced_ids: Vec<Vec<u8>>,
+    ) {
         debug!("Propagating a memory pool transaction to connected peers");
 
         let local_address = self.local_address().unwrap();
 
+        let payload = if replaced_ids.is_empty() {
+            Payload::Transaction(transaction_bytes)
+        } else {
+            Payload::TransactionReplacement(replaced_ids, transaction_bytes)
+        };
+
         for remote_address in self.connected_peers() {
-            if remote_address != transaction_sender && remote_address != local_address {
-                // Send a `Transaction` message to the connected peer.
-                self.send_request(Message::new(
-                    Direction::Outbound(remote_address),
-                    Payload::Transaction(transaction_bytes.clone()),
-                ));
+            // Don't echo the transaction back to the peer it originated from.
+            if origin != TxOrigin::Peer(remote_address) && remote_address != local_address {
+                // Relay a `Transaction` or a `TransactionReplacement` message to the connected peer.
+                self.relay_message(Message::new(Direction::Outbound(remote_address), payload.clone()));
             }
         }
     }
 
     ///
-    /// Verifies a received memory pool transaction, adds it to the memory pool,
-    /// and propagates it to peers.
+    /// Verifies a received memory pool transaction, adds it to the memory pool, replacing the
+    /// conflicting transactions it pays a sufficiently higher fee than, and propagates it to peers.
     ///
     pub(crate) fn received_memory_pool_transaction(
         &self,
         source: SocketAddr,
         transaction: Vec<u8>,
     ) -> Result<(), NetworkError> {
+        // Without a memory pool there's nothing to verify the transaction for.
+        if self.expect_sync().memory_pool().lock().disabled {
+            return Ok(());
+        }
+
         if let Ok(tx) = Tx::read(&*transaction) {
+            // Verifying a transaction is expensive, so reject the blacklisted ones and the ones exceeding the
+            // memory pool limits first.
+            let limits = self
+                .expect_sync()
+                .memory_pool()
+                .lock()
+                .check_transaction_limits(&tx, transaction.len());
+            if let Err(error) = limits {
+                debug!("Rejected a transaction before verifying it: {}", error);
+                return Ok(());
+            }
+
             let insertion = {
                 let storage = self.expect_sync().storage();
 
                 if !self.expect_sync().consensus.verify_transaction(&tx)? {
-                    error!("Received a transaction that was invalid");
+                    // The transaction could have been created against a ledger state that isn't known yet.
+                    if !storage.validate_digest(&tx.ledger_digest) && !tx.value_balance.is_negative() {
+                        debug!("Received an orphan transaction; holding it until its ledger digest is known");
+
+                        let entry = Entry::<Tx> {
+                            size_in_bytes: transaction.len(),
+                            transaction: tx,
+                            origin: TxOrigin::Peer(source),
+                            priority_class: PriorityClass::Standard,
+                            valid_from_height: None,
+                        };
+                        if let Err(error) = self.expect_sync().memory_pool().lock().insert_orphan(entry) {
+                            debug!("Rejected an orphan transaction: {}", error);
+                        }
+                    } else {
+                        error!("Received a transaction that was invalid");
+                    }
                     return Ok(());
                 }
 
@@ -83,24 +131,62 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
                 let entry = Entry::<Tx> {
                     size_in_bytes: transaction.len(),
                     transaction: tx,
+                    origin: TxOrigin::Peer(source),
+                    priority_class: PriorityClass::Standard,
+                    valid_from_height: None,
                 };
 
-                self.expect_sync().memory_pool().lock().insert(storage, entry)
+                self.expect_sync().memory_pool().lock().replace_by_fee(storage, entry)
             };
 
-            if let Ok(inserted) = insertion {
-                if inserted.is_some() {
+            if let Ok(Some((_, replaced_ids))) = insertion {
+                if replaced_ids.is_empty() {
                     info!("Transaction added to memory pool.");
-                    self.propagate_memory_pool_transaction(transaction, source);
+                } else {
+                    info!(
+                        "Transaction added to memory pool, replacing {} transaction(s).",
+                        replaced_ids.len()
+                    );
                 }
+                self.propagate_memory_pool_transaction(transaction, TxOrigin::Peer(source), replaced_ids);
             }
         }
 
         Ok(())
     }
 
+    ///
+    /// Handles a transaction that replaced the given memory pool transactions of a peer; it replaces
+    /// the local ones too if it's valid and pays a sufficiently higher fee than them.
+    ///
+    pub(crate) fn received_transaction_replacement(
+        &self,
+        source: SocketAddr,
+        replaced_ids: Vec<Vec<u8>>,
+        transaction: Vec<u8>,
+    ) -> Result<(), NetworkError> {
+        // The local conflicts of the replacement are determined independently, but a peer that claims
+        // an implausible number of replaced transactions is penalized.
+        if replaced_ids.len() > crate::MAX_REPLACED_TRANSACTIONS {
+            warn!(
+                "{} sent a transaction replacing {} transactions, more than the maximum of {}",
+                source,
+                replaced_ids.len(),
+                crate::MAX_REPLACED_TRANSACTIONS
+            );
+            self.register_failure(source);
+            return Ok(());
+        }
+
+        self.received_memory_pool_transaction(source, transaction)
+    }
+
     /// A peer has requested our memory pool transactions.
     pub(crate) fn received_get_memory_pool(&self, remote_address: SocketAddr) {
+        if self.expect_sync().memory_pool().lock().disabled {
+            return;
+        }
+
         // TODO (howardwu): This should have been written with Rayon - it is easily parallelizable.
         let transactions = {
             let mut txs = vec![];
@@ -125,8 +211,16 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
     }
 
     /// A peer has sent us their memory pool transactions.
-    pub(crate) fn received_memory_pool(&self, transactions: Vec<Vec<u8>>) -> Result<(), NetworkError> {
+    pub(crate) fn received_memory_pool(
+        &self,
+        source: SocketAddr,
+        transactions: Vec<Vec<u8>>,
+    ) -> Result<(), NetworkError> {
         let mut memory_pool = self.expect_sync().memory_pool().lock();
+        if memory_pool.disabled {
+            return Ok(());
+        }
+
         let storage = self.expect_sync().storage();
 
         for transaction_bytes in transactions {
@@ -134,6 +228,9 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
             let entry = Entry::<Tx> {
                 size_in_bytes: transaction_bytes.len(),
                 transaction,
+                origin: TxOrigin::Peer(source),
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
             };
 
             if let Ok(Some(txid)) = memory_pool.insert(&storage, entry) {
@@ -144,15 +241,11 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
             }
         }
 
-        // Cleanse and store transactions once batch has been received.
+        // Cleanse transactions once batch has been received; they are stored periodically.
         debug!("Cleansing memory pool transactions in database");
         memory_pool
             .cleanse(&storage)
             .unwrap_or_else(|error| debug!("Failed to cleanse memory pool transactions in database {}", error));
-        debug!("Storing memory pool transactions in database");
-        memory_pool
-            .store(&storage)
-            .unwrap_or_else(|error| debug!("Failed to store memory pool transaction in database {}", error));
 
         Ok(())
     }
@@ -99,6 +99,7 @@ impl<S: Storage + Send + Sync + 'static> MinerInstance<S> {
                 }
 
                 metrics::increment_counter!(stats::MISC_BLOCKS_MINED);
+                self.node.update_best_block_height();
 
                 info!("Mined a new block: {:?}", hex::encode(block.header.get_hash().0));
 
@@ -24,5 +24,17 @@ pub use memory_pool::*;
 pub mod miner;
 pub use miner::*;
 
+pub mod progress;
+pub use progress::*;
+
+pub mod segments;
+pub use segments::*;
+
+pub mod serving;
+pub use serving::*;
+
 pub mod sync;
 pub use sync::*;
+
+pub mod validation;
+pub use validation::*;
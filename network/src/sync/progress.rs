@@ -0,0 +1,52 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_storage::BlockHeight;
+
+use parking_lot::RwLock;
+use std::time::Instant;
+
+/// Tracks the progress of the current block sync round.
+#[derive(Debug, Default)]
+pub struct SyncProgress {
+    /// The time the current sync round was started at, along with the block height at that time.
+    round_start: RwLock<Option<(Instant, BlockHeight)>>,
+}
+
+impl SyncProgress {
+    /// Registers the start of a new sync round at the given block height and time.
+    pub fn start_round(&self, block_height: BlockHeight, now: Instant) {
+        *self.round_start.write() = Some((now, block_height));
+    }
+
+    /// Returns the average number of blocks per second imported since the start of the current
+    /// sync round until `now`, or `0.0` if no sync round has been started yet.
+    pub fn blocks_per_second(&self, current_block_height: BlockHeight, now: Instant) -> f64 {
+        match *self.round_start.read() {
+            Some((started_at, start_height)) => {
+                let elapsed = now.saturating_duration_since(started_at).as_secs_f64();
+                let imported_blocks = current_block_height.saturating_sub(start_height);
+
+                if elapsed > 0.0 {
+                    imported_blocks as f64 / elapsed
+                } else {
+                    0.0
+                }
+            }
+            None => 0.0,
+        }
+    }
+}
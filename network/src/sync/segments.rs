@@ -0,0 +1,99 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkvm_dpc::BlockHeaderHash;
+
+use parking_lot::Mutex;
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+};
+
+/// Tracks a block sync round whose block hashes were split into segments that are downloaded
+/// concurrently from different peers. Blocks that arrive ahead of their predecessors are buffered,
+/// so that they are always processed in the order of the chain.
+#[derive(Debug, Default)]
+pub struct SyncSegments {
+    inner: Mutex<SegmentsInner>,
+}
+
+#[derive(Debug, Default)]
+struct SegmentsInner {
+    /// The hashes of the blocks that haven't been processed yet, in the order of the chain.
+    remaining: VecDeque<BlockHeaderHash>,
+    /// The blocks that have arrived ahead of their predecessors, along with the peers that sent them.
+    buffered: HashMap<BlockHeaderHash, (SocketAddr, Vec<u8>)>,
+}
+
+impl SyncSegments {
+    /// Starts tracking a segmented download of the given block hashes, discarding any previous one.
+    pub fn start(&self, block_hashes: &[BlockHeaderHash]) {
+        let mut inner = self.inner.lock();
+        inner.remaining = block_hashes.iter().cloned().collect();
+        inner.buffered.clear();
+    }
+
+    /// Stops tracking the current segmented download, if there is one.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock();
+        inner.remaining.clear();
+        inner.buffered.clear();
+    }
+
+    /// Returns `true` if a segmented download has blocks that haven't been processed yet.
+    pub fn is_active(&self) -> bool {
+        !self.inner.lock().remaining.is_empty()
+    }
+
+    /// Returns `true` if the block with the given hash is part of the current segmented download
+    /// and it hasn't been processed yet.
+    pub fn is_expected(&self, block_hash: &BlockHeaderHash) -> bool {
+        self.inner.lock().remaining.contains(block_hash)
+    }
+
+    /// Buffers the given block and returns all the blocks that can now be processed, in the order of the chain.
+    pub fn insert(
+        &self,
+        block_hash: BlockHeaderHash,
+        source: SocketAddr,
+        block: Vec<u8>,
+    ) -> Vec<(BlockHeaderHash, SocketAddr, Vec<u8>)> {
+        let mut inner = self.inner.lock();
+
+        if !inner.remaining.contains(&block_hash) {
+            return vec![];
+        }
+        inner.buffered.insert(block_hash, (source, block));
+
+        let mut ready = vec![];
+        while let Some(next_hash) = inner.remaining.front().cloned() {
+            match inner.buffered.remove(&next_hash) {
+                Some((source, block)) => {
+                    inner.remaining.pop_front();
+                    ready.push((next_hash, source, block));
+                }
+                None => break,
+            }
+        }
+
+        ready
+    }
+
+    /// Returns the number of blocks that arrived ahead of their predecessors and are awaiting processing.
+    pub fn buffered(&self) -> usize {
+        self.inner.lock().buffered.len()
+    }
+}
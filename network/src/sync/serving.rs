@@ -0,0 +1,130 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+/// Paces the blocks served to each peer in response to its `GetBlocks`, so that a single peer
+/// can't drain the upload bandwidth of the node.
+#[derive(Debug, Default)]
+pub struct BlockServingLimiter {
+    /// The time at which the serving allowance of each peer is fully replenished.
+    replenished_at: Mutex<HashMap<SocketAddr, Instant>>,
+}
+
+impl BlockServingLimiter {
+    /// Reserves the serving of a block to the given peer at `now`; a peer is served up to `blocks_per_second`
+    /// blocks at once, and then one every `1 / blocks_per_second` seconds. Returns how long serving the block
+    /// has to be delayed, or `None` if the delay would exceed `max_delay`, in which case nothing is reserved.
+    pub fn reserve(
+        &self,
+        peer: SocketAddr,
+        blocks_per_second: u32,
+        max_delay: Duration,
+        now: Instant,
+    ) -> Option<Duration> {
+        let interval = Duration::from_secs(1) / blocks_per_second.max(1);
+        let mut replenished_at = self.replenished_at.lock();
+
+        // The peers whose allowance is already replenished are indistinguishable from new ones.
+        replenished_at.retain(|_, replenished_at| *replenished_at > now);
+
+        let peer_replenished_at = replenished_at.get(&peer).copied().unwrap_or(now);
+        let slot = match peer_replenished_at.checked_sub(Duration::from_secs(1) - interval) {
+            Some(slot) if slot > now => slot,
+            _ => now,
+        };
+        if slot - now > max_delay {
+            return None;
+        }
+        replenished_at.insert(peer, peer_replenished_at + interval);
+
+        Some(slot - now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serving_rate_is_capped() {
+        let limiter = BlockServingLimiter::default();
+        let peer: SocketAddr = "127.0.0.1:4141".parse().unwrap();
+        let other_peer: SocketAddr = "127.0.0.1:4142".parse().unwrap();
+        let max_delay = Duration::from_secs(60);
+        let now = Instant::now();
+
+        // A burst of up to one second's worth of blocks is served immediately
+        for _ in 0..10 {
+            assert_eq!(limiter.reserve(peer, 10, max_delay, now), Some(Duration::from_secs(0)));
+        }
+
+        // The following ones are spaced out at the configured rate
+        for i in 1..=20 {
+            assert_eq!(
+                limiter.reserve(peer, 10, max_delay, now),
+                Some(Duration::from_millis(100 * i))
+            );
+        }
+
+        // Other peers have their own allowance
+        assert_eq!(
+            limiter.reserve(other_peer, 10, max_delay, now),
+            Some(Duration::from_secs(0))
+        );
+
+        // The allowance is replenished over time
+        let later = now + Duration::from_secs(4);
+        for _ in 0..10 {
+            assert_eq!(
+                limiter.reserve(peer, 10, max_delay, later),
+                Some(Duration::from_secs(0))
+            );
+        }
+        assert_eq!(
+            limiter.reserve(peer, 10, max_delay, later),
+            Some(Duration::from_millis(100))
+        );
+    }
+
+    #[test]
+    fn serving_delay_is_capped() {
+        let limiter = BlockServingLimiter::default();
+        let peer: SocketAddr = "127.0.0.1:4141".parse().unwrap();
+        let max_delay = Duration::from_secs(1);
+        let now = Instant::now();
+
+        // The burst and the blocks delayed by up to a second are reserved
+        for _ in 0..20 {
+            assert!(limiter.reserve(peer, 10, max_delay, now).is_some());
+        }
+
+        // The ones delayed by more than that are rejected, without taking up the peer's allowance
+        for _ in 0..5 {
+            assert_eq!(limiter.reserve(peer, 10, max_delay, now), None);
+        }
+        let later = now + Duration::from_millis(100);
+        assert_eq!(
+            limiter.reserve(peer, 10, max_delay, later),
+            Some(Duration::from_secs(1))
+        );
+    }
+}
@@ -14,19 +14,24 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Node, State};
-use snarkos_consensus::{ConsensusParameters, MemoryPool, MerkleTreeLedger};
+use crate::{BlockServingLimiter, Node, State, SyncProgress, SyncSegments};
+use snarkos_consensus::{Clock, ConsensusParameters, MemoryPool, MerkleTreeLedger, SystemClock};
 use snarkos_storage::BlockHeight;
 use snarkvm_dpc::{
     testnet1::{
         instantiated::{Components, Tx},
         parameters::PublicParameters,
     },
+    BlockHeaderHash,
     Storage,
 };
 
-use parking_lot::{Mutex, RwLock};
+use chrono::Utc;
+use parking_lot::{Mutex, MutexGuard, RwLock};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -43,6 +48,52 @@ pub struct Sync<S: Storage> {
     mempool_sync_interval: Duration,
     /// The last time a block sync was initiated.
     last_block_sync: RwLock<Option<Instant>>,
+    /// The progress of the current block sync round.
+    progress: SyncProgress,
+    /// The peer the latest block sync round was started with, along with its block height at that time.
+    sync_peer: RwLock<Option<(SocketAddr, BlockHeight)>>,
+    /// The number of attempts made to reconnect to sync peers that disconnected while they were ahead.
+    sync_peer_reconnect_attempts: Mutex<HashMap<SocketAddr, u8>>,
+    /// The maximum number of attempts to reconnect to a sync peer before falling back to other peers.
+    max_sync_peer_reconnect_attempts: u8,
+    /// If `true`, the peer that completed the latest block sync round is preferred for the next one.
+    sync_peer_stickiness: bool,
+    /// The peer that completed the latest block sync round, unless a later round failed.
+    sticky_sync_peer: RwLock<Option<SocketAddr>>,
+    /// The maximum number of block hash packets a peer may send in response to a single `GetSync`.
+    max_sync_hash_packets: u32,
+    /// The maximum number of peers asked for block hashes in a single block sync round.
+    max_sync_hash_rounds: u8,
+    /// The peers asked for block hashes in the current block sync round.
+    sync_hash_peers: Mutex<HashSet<SocketAddr>>,
+    /// The block hashes requested from the peers in the current block sync round.
+    requested_sync_hashes: Mutex<HashSet<BlockHeaderHash>>,
+    /// The maximum number of blocks a peer may request in a single `GetBlocks`.
+    max_requested_blocks: u32,
+    /// The maximum number of block hashes sent in response to a single `GetSync`; a peer that is further behind
+    /// receives the following ones in its next block sync rounds.
+    max_served_sync_hashes: u32,
+    /// The maximum number of block hashes in a segment of a block sync round downloaded from a single peer.
+    sync_segment_size: usize,
+    /// The segmented download of the current block sync round.
+    segments: SyncSegments,
+    /// The number of sync blocks committed to the storage at once, all or nothing.
+    sync_commit_batch_size: usize,
+    /// The number of blocks a peer may claim to be ahead of the node beyond what the time elapsed since the
+    /// node's latest block can account for.
+    max_sync_height_lead: u32,
+    /// The sync blocks received in the current block sync round that weren't committed yet, along with their sources.
+    pending_sync_blocks: Mutex<Vec<(SocketAddr, Vec<u8>)>>,
+    /// The maximum number of blocks served to a single peer per second, after an initial burst of as many.
+    max_served_blocks_per_second: u32,
+    /// The pacing of the blocks served to the peers.
+    serving_limiter: BlockServingLimiter,
+    /// Ensures that only a single block sync round is being started at a time.
+    round_guard: Mutex<()>,
+    /// The source of randomness for the selection of the sync peers.
+    rng: Mutex<StdRng>,
+    /// The source of the current time for the block sync timeouts.
+    clock: Arc<dyn Clock>,
 }
 
 impl<S: Storage> Sync<S> {
@@ -59,9 +110,115 @@ impl<S: Storage> Sync<S> {
             block_sync_interval,
             mempool_sync_interval,
             last_block_sync: Default::default(),
+            progress: Default::default(),
+            sync_peer: Default::default(),
+            sync_peer_reconnect_attempts: Default::default(),
+            max_sync_peer_reconnect_attempts: crate::MAX_SYNC_PEER_RECONNECT_ATTEMPTS,
+            sync_peer_stickiness: true,
+            sticky_sync_peer: Default::default(),
+            max_sync_hash_packets: crate::MAX_SYNC_HASH_PACKETS,
+            max_sync_hash_rounds: crate::MAX_SYNC_HASH_ROUNDS,
+            sync_hash_peers: Default::default(),
+            requested_sync_hashes: Default::default(),
+            max_requested_blocks: crate::MAX_BLOCK_SYNC_COUNT,
+            max_served_sync_hashes: crate::MAX_BLOCK_SYNC_COUNT,
+            sync_segment_size: crate::SYNC_SEGMENT_SIZE,
+            segments: Default::default(),
+            sync_commit_batch_size: 1,
+            max_sync_height_lead: crate::MAX_SYNC_HEIGHT_LEAD,
+            pending_sync_blocks: Default::default(),
+            max_served_blocks_per_second: crate::MAX_SERVED_BLOCKS_PER_SECOND,
+            serving_limiter: Default::default(),
+            round_guard: Default::default(),
+            rng: Mutex::new(StdRng::from_entropy()),
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Sets the maximum number of attempts to reconnect to a sync peer before falling back to other peers.
+    pub fn with_max_sync_peer_reconnect_attempts(mut self, max_sync_peer_reconnect_attempts: u8) -> Self {
+        self.max_sync_peer_reconnect_attempts = max_sync_peer_reconnect_attempts;
+        self
+    }
+
+    /// Sets whether the peer that completed the latest block sync round is preferred for the next one.
+    pub fn with_sync_peer_stickiness(mut self, sync_peer_stickiness: bool) -> Self {
+        self.sync_peer_stickiness = sync_peer_stickiness;
+        self
+    }
+
+    /// Sets the maximum number of block hash packets a peer may send in response to a single `GetSync`.
+    pub fn with_max_sync_hash_packets(mut self, max_sync_hash_packets: u32) -> Self {
+        self.max_sync_hash_packets = max_sync_hash_packets;
+        self
+    }
+
+    /// Sets the maximum number of peers asked for block hashes in a single block sync round.
+    pub fn with_max_sync_hash_rounds(mut self, max_sync_hash_rounds: u8) -> Self {
+        self.max_sync_hash_rounds = max_sync_hash_rounds.max(1);
+        self
+    }
+
+    /// Sets the maximum number of blocks a peer may request in a single `GetBlocks`.
+    pub fn with_max_requested_blocks(mut self, max_requested_blocks: u32) -> Self {
+        self.max_requested_blocks = max_requested_blocks;
+        self
+    }
+
+    /// Sets the maximum number of block hashes sent in response to a single `GetSync`.
+    pub fn with_max_served_sync_hashes(mut self, max_served_sync_hashes: u32) -> Self {
+        self.max_served_sync_hashes = max_served_sync_hashes.max(1);
+        self
+    }
+
+    /// Sets the maximum number of block hashes in a segment of a block sync round downloaded from a single peer.
+    pub fn with_sync_segment_size(mut self, sync_segment_size: usize) -> Self {
+        self.sync_segment_size = sync_segment_size.max(1);
+        self
+    }
+
+    /// Sets the number of sync blocks committed to the storage at once, all or nothing.
+    pub fn with_sync_commit_batch_size(mut self, sync_commit_batch_size: usize) -> Self {
+        self.sync_commit_batch_size = sync_commit_batch_size.max(1);
+        self
+    }
+
+    /// Sets the number of blocks a peer may claim to be ahead of the node beyond what the time elapsed since the
+    /// node's latest block can account for.
+    pub fn with_max_sync_height_lead(mut self, max_sync_height_lead: u32) -> Self {
+        self.max_sync_height_lead = max_sync_height_lead;
+        self
+    }
+
+    /// Sets the maximum number of blocks served to a single peer per second, after an initial burst of as many.
+    pub fn with_max_served_blocks_per_second(mut self, max_served_blocks_per_second: u32) -> Self {
+        self.max_served_blocks_per_second = max_served_blocks_per_second.max(1);
+        self
+    }
+
+    /// Seeds the source of randomness for the selection of the sync peers, making it reproducible.
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng = Mutex::new(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    /// Sets the source of the current time for the block sync timeouts.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns the source of the current time for the block sync timeouts.
+    #[inline]
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Returns the source of randomness for the selection of the sync peers.
+    pub(crate) fn rng(&self) -> MutexGuard<'_, StdRng> {
+        self.rng.lock()
+    }
+
     /// Returns a reference to the storage system of this node.
     #[inline]
     pub fn storage(&self) -> &MerkleTreeLedger<S> {
@@ -98,10 +255,157 @@ impl<S: Storage> Sync<S> {
         self.consensus.ledger.get_current_block_height()
     }
 
+    /// Returns a reference to the progress of the current block sync round.
+    #[inline]
+    pub fn progress(&self) -> &SyncProgress {
+        &self.progress
+    }
+
+    /// Returns the address of the peer the latest block sync round was started with.
+    pub fn sync_peer(&self) -> Option<SocketAddr> {
+        self.sync_peer.read().map(|(address, _)| address)
+    }
+
+    /// Returns the peer that completed the latest block sync round and is preferred for the next one, if any.
+    pub fn sticky_sync_peer(&self) -> Option<SocketAddr> {
+        if self.sync_peer_stickiness {
+            *self.sticky_sync_peer.read()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of attempts made to reconnect to the given sync peer.
+    pub fn sync_peer_reconnect_attempts(&self, address: SocketAddr) -> u8 {
+        self.sync_peer_reconnect_attempts
+            .lock()
+            .get(&address)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns the maximum number of block hash packets a peer may send in response to a single `GetSync`.
+    pub fn max_sync_hash_packets(&self) -> u32 {
+        self.max_sync_hash_packets
+    }
+
+    /// Returns the maximum number of peers asked for block hashes in a single block sync round.
+    pub fn max_sync_hash_rounds(&self) -> u8 {
+        self.max_sync_hash_rounds
+    }
+
+    /// Returns `true` if the given peer was asked for block hashes in the current block sync round.
+    pub fn is_sync_hash_peer(&self, peer: SocketAddr) -> bool {
+        self.sync_hash_peers.lock().contains(&peer)
+    }
+
+    /// Registers that the given peer is about to be asked for block hashes in the current block sync round;
+    /// returns `false` if the maximum number of peers was asked already.
+    pub(crate) fn register_sync_hash_peer(&self, peer: SocketAddr) -> bool {
+        let mut sync_hash_peers = self.sync_hash_peers.lock();
+        if sync_hash_peers.len() >= self.max_sync_hash_rounds as usize {
+            return false;
+        }
+        sync_hash_peers.insert(peer)
+    }
+
+    /// Removes the block hashes that were already requested in the current block sync round from the given
+    /// ones, and registers the remaining ones as requested.
+    pub(crate) fn retain_unrequested_sync_hashes(&self, block_hashes: &mut Vec<BlockHeaderHash>) {
+        let mut requested_sync_hashes = self.requested_sync_hashes.lock();
+        block_hashes.retain(|hash| requested_sync_hashes.insert(hash.clone()));
+    }
+
+    /// Returns the maximum number of blocks a peer may request in a single `GetBlocks`.
+    pub fn max_requested_blocks(&self) -> u32 {
+        self.max_requested_blocks
+    }
+
+    /// Returns the maximum number of block hashes sent in response to a single `GetSync`.
+    pub fn max_served_sync_hashes(&self) -> u32 {
+        self.max_served_sync_hashes
+    }
+
+    /// Returns the maximum number of blocks served to a single peer per second, after an initial burst of as many.
+    pub fn max_served_blocks_per_second(&self) -> u32 {
+        self.max_served_blocks_per_second
+    }
+
+    /// Returns the number of sync blocks committed to the storage at once, all or nothing.
+    pub fn sync_commit_batch_size(&self) -> usize {
+        self.sync_commit_batch_size
+    }
+
+    /// Returns the number of blocks a peer may claim to be ahead of the node beyond what the time elapsed since
+    /// the node's latest block can account for.
+    pub fn max_sync_height_lead(&self) -> u32 {
+        self.max_sync_height_lead
+    }
+
+    /// Returns the greatest block height a peer may plausibly advertise: the node's own height, the number of
+    /// blocks that could have been mined since its latest one, and the configured lead.
+    pub fn max_plausible_block_height(&self) -> BlockHeight {
+        let my_height = self.current_block_height();
+        let target_block_time = self.consensus_parameters().target_block_time.max(1);
+
+        let elapsed_blocks = self
+            .storage()
+            .get_block_hash(my_height)
+            .and_then(|hash| self.storage().get_block_header(&hash))
+            .map(|header| (Utc::now().timestamp() - header.time).max(0) / target_block_time)
+            .unwrap_or(0);
+
+        my_height
+            .saturating_add(self.max_sync_height_lead)
+            .saturating_add(elapsed_blocks.min(u32::MAX as i64) as u32)
+    }
+
+    /// Holds back a received sync block until a whole batch of them can be committed; returns the batch
+    /// once it's complete.
+    pub(crate) fn buffer_sync_block(&self, source: SocketAddr, block: Vec<u8>) -> Option<Vec<(SocketAddr, Vec<u8>)>> {
+        let mut pending_sync_blocks = self.pending_sync_blocks.lock();
+        pending_sync_blocks.push((source, block));
+
+        if pending_sync_blocks.len() >= self.sync_commit_batch_size {
+            Some(std::mem::take(&mut *pending_sync_blocks))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the sync blocks that weren't committed yet, e.g. once the block sync round is finished.
+    pub(crate) fn take_pending_sync_blocks(&self) -> Vec<(SocketAddr, Vec<u8>)> {
+        std::mem::take(&mut *self.pending_sync_blocks.lock())
+    }
+
+    /// Reserves the serving of a block to the given peer; returns how long serving it has to be delayed
+    /// in order to keep within the maximum serving rate, or `None` if the peer's backlog of blocks to serve
+    /// is already too long.
+    pub fn reserve_block_serving(&self, peer: SocketAddr) -> Option<Duration> {
+        self.serving_limiter.reserve(
+            peer,
+            self.max_served_blocks_per_second,
+            Duration::from_secs(crate::MAX_BLOCK_SERVING_DELAY_SECS),
+            self.clock.now(),
+        )
+    }
+
+    /// Returns the maximum number of block hashes in a segment of a block sync round downloaded from a single peer.
+    pub fn sync_segment_size(&self) -> usize {
+        self.sync_segment_size
+    }
+
+    /// Returns a reference to the segmented download of the current block sync round.
+    #[inline]
+    pub fn segments(&self) -> &SyncSegments {
+        &self.segments
+    }
+
     /// Checks whether any previous sync attempt has expired.
     pub fn has_block_sync_expired(&self) -> bool {
-        if let Some(ref timestamp) = *self.last_block_sync.read() {
-            timestamp.elapsed() > Duration::from_secs(crate::BLOCK_SYNC_EXPIRATION_SECS as u64)
+        if let Some(timestamp) = *self.last_block_sync.read() {
+            self.clock.now().saturating_duration_since(timestamp)
+                > Duration::from_secs(crate::BLOCK_SYNC_EXPIRATION_SECS as u64)
         } else {
             // this means it's the very first sync attempt
             true
@@ -131,14 +435,177 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
 
     /// Register that the node is no longer syncing blocks.
     pub fn finished_syncing_blocks(&self) {
+        // The sync peer has proven useful again, so it may be reconnected to in the future
+        // and it's preferred for the next block sync round.
+        if let Some(sync) = self.sync() {
+            if let Some(sync_peer) = sync.sync_peer() {
+                sync.sync_peer_reconnect_attempts.lock().remove(&sync_peer);
+                *sync.sticky_sync_peer.write() = Some(sync_peer);
+            }
+        }
+        self.set_completed_block_sync();
         self.set_state(State::Idle);
     }
 
-    /// Register that the node attempted to sync blocks.
-    pub fn register_block_sync_attempt(&self) {
+    /// Starts a block sync round with a peer that has a longer chain, unless the node is already syncing blocks
+    /// and its earlier sync attempt hasn't expired nor lost its peer; returns `true` if a round was started.
+    pub fn run_block_sync(&self) -> bool {
+        let sync = match self.sync() {
+            Some(sync) => sync,
+            None => return false,
+        };
+
+        // No new block sync rounds are started while the node is shutting down.
+        if self.is_shutting_down() {
+            return false;
+        }
+
+        // Another block sync round is just being started.
+        let _round_guard = match sync.round_guard.try_lock() {
+            Some(round_guard) => round_guard,
+            None => return false,
+        };
+
+        let is_syncing_blocks = self.is_syncing_blocks();
+        let is_sync_expired = sync.has_block_sync_expired();
+        let has_lost_sync_peer = self.has_lost_sync_peer();
+
+        // if the node is not currently syncing blocks, an earlier sync attempt has expired or
+        // its peer has disconnected, consider syncing blocks with a peer who has a longer chain
+        if is_syncing_blocks && !is_sync_expired && !has_lost_sync_peer {
+            return false;
+        }
+
+        // if the node's state is `Syncing`, change it to `Idle`, as it means the
+        // previous attempt has expired - the peer has disconnected or was too slow
+        // to deliver the batch of sync blocks
+        if is_syncing_blocks {
+            debug!("An unfinished block sync has expired.");
+            sync.sticky_sync_peer.write().take();
+            self.set_state(State::Idle);
+        }
+
+        // if the previous sync peer disconnected while it was ahead, attempt to reconnect
+        // to it before falling back to other peers
+        if let Some(sync_peer) = self.sync_peer_to_reconnect() {
+            self.reconnect_to_sync_peer(sync_peer);
+            return false;
+        }
+
+        let mut prospect_sync_nodes = Vec::new();
+        let my_height = sync.current_block_height();
+        let max_plausible_height = sync.max_plausible_block_height();
+
+        // Pick a random peer of all the connected ones that claim
+        // to have a longer chain.
+        for (peer, info) in self.peer_book.connected_peers().iter() {
+            // Fetch the current block height of this connected peer.
+            let peer_block_height = info.block_height();
+
+            // A peer claiming an implausibly long chain could otherwise always be chosen to sync from.
+            if peer_block_height > max_plausible_height {
+                warn!(
+                    "Peer {} advertised an implausible block height of {} (mine: {}, max: {})",
+                    peer, peer_block_height, my_height, max_plausible_height
+                );
+                self.register_failure(*peer);
+                continue;
+            }
+
+            if peer_block_height > my_height + 1 {
+                prospect_sync_nodes.push((*peer, peer_block_height));
+            }
+        }
+        // The peers are ordered, so that the choice depends only on the rng.
+        prospect_sync_nodes.sort_unstable();
+
+        // Prefer a sync peer that was just reconnected to, then the one that completed the latest
+        // block sync round, otherwise pick a random one.
+        let reconnected_sync_peer = sync.sync_peer().and_then(|sync_peer| {
+            prospect_sync_nodes
+                .iter()
+                .find(|(peer, _)| *peer == sync_peer && sync.sync_peer_reconnect_attempts(sync_peer) != 0)
+        });
+        let sticky_sync_peer = || {
+            sync.sticky_sync_peer()
+                .and_then(|sticky_sync_peer| prospect_sync_nodes.iter().find(|(peer, _)| *peer == sticky_sync_peer))
+        };
+        let random_sync_peer = reconnected_sync_peer
+            .or_else(sticky_sync_peer)
+            .or_else(|| prospect_sync_nodes.choose(&mut *sync.rng()));
+
+        match random_sync_peer {
+            Some((sync_node, peer_height)) => {
+                // Log the sync job as a trace.
+                trace!(
+                    "Preparing to sync from {} with a block height of {} (mine: {}, {} peers with a greater height)",
+                    sync_node,
+                    peer_height,
+                    my_height,
+                    prospect_sync_nodes.len()
+                );
+
+                // Cancel any possibly ongoing sync attempts.
+                self.peer_book.cancel_any_unfinished_syncing();
+
+                // Begin a new sync attempt.
+                self.register_block_sync_attempt(*sync_node, *peer_height);
+                self.update_blocks(*sync_node);
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Register that the node attempted to sync blocks with the given peer at the given block height.
+    pub fn register_block_sync_attempt(&self, sync_peer: SocketAddr, sync_peer_height: BlockHeight) {
+        self.start_block_sync_round();
+
         if let Some(sync) = self.sync() {
-            *sync.last_block_sync.write() = Some(Instant::now());
+            *sync.last_block_sync.write() = Some(sync.clock.now());
+            *sync.sync_peer.write() = Some((sync_peer, sync_peer_height));
+            *sync.sync_hash_peers.lock() = std::iter::once(sync_peer).collect();
+            sync.requested_sync_hashes.lock().clear();
+            sync.segments.clear();
+            sync.pending_sync_blocks.lock().clear();
+
+            // A new sync round begins unless the node is already syncing.
+            if !self.is_syncing_blocks() {
+                sync.progress.start_round(sync.current_block_height(), sync.clock.now());
+            }
         }
         self.set_state(State::Syncing);
     }
+
+    /// Returns `true` if the peer of the ongoing block sync round is no longer connected.
+    pub fn has_lost_sync_peer(&self) -> bool {
+        match self.sync().and_then(|sync| sync.sync_peer()) {
+            Some(sync_peer) => self.is_syncing_blocks() && !self.peer_book.is_connected(sync_peer),
+            None => false,
+        }
+    }
+
+    /// Returns the address of the latest sync peer if it disconnected while it was ahead of this node
+    /// and it hasn't run out of reconnect attempts; the attempt is registered as part of the call.
+    pub fn sync_peer_to_reconnect(&self) -> Option<SocketAddr> {
+        let sync = self.sync()?;
+        let (sync_peer, sync_peer_height) = (*sync.sync_peer.read())?;
+
+        if self.peer_book.is_connected(sync_peer)
+            || self.peer_book.is_connecting(sync_peer)
+            || sync_peer_height <= sync.current_block_height() + 1
+        {
+            return None;
+        }
+
+        let mut reconnect_attempts = sync.sync_peer_reconnect_attempts.lock();
+        let attempts = reconnect_attempts.entry(sync_peer).or_insert(0);
+        if *attempts >= sync.max_sync_peer_reconnect_attempts {
+            return None;
+        }
+        *attempts += 1;
+
+        Some(sync_peer)
+    }
 }
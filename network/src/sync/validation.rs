@@ -0,0 +1,226 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use tokio::{
+    sync::{mpsc, oneshot, Semaphore},
+    task,
+};
+
+/// Validates inbound blocks concurrently, up to a given limit, while committing them one at a
+/// time in the order in which they were received; the number of pending validations is bounded too.
+pub struct OrderedValidator<T> {
+    /// The permits for the concurrent validations.
+    validation_slots: Arc<Semaphore>,
+    /// The pending validation results, in the order of submission.
+    pending: mpsc::Sender<oneshot::Receiver<T>>,
+}
+
+impl<T: Send + 'static> OrderedValidator<T> {
+    /// Creates a new `OrderedValidator` that runs up to `max_concurrency` validations at once, and
+    /// a task that passes their results to `commit` in the order of submission. No more than
+    /// `max_concurrency` validations are queued behind the one whose result is awaited.
+    pub fn new<C>(max_concurrency: usize, mut commit: C) -> (Self, task::JoinHandle<()>)
+    where
+        C: FnMut(T) + Send + 'static,
+    {
+        let max_concurrency = max_concurrency.max(1);
+        let (pending, mut pending_receiver) = mpsc::channel::<oneshot::Receiver<T>>(max_concurrency);
+
+        let commit_task = task::spawn(async move {
+            while let Some(validation) = pending_receiver.recv().await {
+                // A validation that panicked doesn't hold up the ones that follow it.
+                if let Ok(validated) = validation.await {
+                    commit(validated);
+                }
+            }
+        });
+
+        let validator = Self {
+            validation_slots: Arc::new(Semaphore::new(max_concurrency)),
+            pending,
+        };
+
+        (validator, commit_task)
+    }
+
+    /// Queues a validation, waiting for room in the queue if it's full; its result is committed once
+    /// all the previously submitted ones are.
+    pub async fn submit<F>(&self, validate: F)
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (result_sender, result_receiver) = oneshot::channel();
+
+        if self.pending.send(result_receiver).await.is_err() {
+            error!("Failed to queue a block validation: the commit task has stopped");
+            return;
+        }
+
+        let validation_slots = self.validation_slots.clone();
+        task::spawn(async move {
+            // The semaphore is never closed.
+            let _permit = validation_slots.acquire_owned().await.unwrap();
+
+            if let Ok(validated) = task::spawn_blocking(validate).await {
+                let _ = result_sender.send(validated);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Barrier,
+        },
+        thread,
+        time::Duration,
+    };
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn concurrent_validation_ordered_commit() {
+        const NUM_BLOCKS: usize = 4;
+
+        let committed = Arc::new(Mutex::new(Vec::new()));
+        let validated = Arc::new(Mutex::new(Vec::new()));
+        let (commit_sender, mut commit_receiver) = mpsc::unbounded_channel();
+
+        let committed_clone = committed.clone();
+        let (validator, _commit_task) = OrderedValidator::new(NUM_BLOCKS, move |block: usize| {
+            committed_clone.lock().push(block);
+            commit_sender.send(()).unwrap();
+        });
+
+        // All the validations must be running at the same time in order to pass the barrier.
+        let barrier = Arc::new(Barrier::new(NUM_BLOCKS));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        for block in 0..NUM_BLOCKS {
+            let barrier = barrier.clone();
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            let validated = validated.clone();
+
+            validator
+                .submit(move || {
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(current, Ordering::SeqCst);
+                    barrier.wait();
+
+                    // The later blocks finish their validation first.
+                    thread::sleep(Duration::from_millis(50 * (NUM_BLOCKS - block) as u64));
+                    validated.lock().push(block);
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                    block
+                })
+                .await;
+        }
+
+        for _ in 0..NUM_BLOCKS {
+            tokio::time::timeout(Duration::from_secs(5), commit_receiver.recv())
+                .await
+                .unwrap()
+                .unwrap();
+        }
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), NUM_BLOCKS);
+        assert_eq!(*validated.lock(), (0..NUM_BLOCKS).rev().collect::<Vec<_>>());
+        assert_eq!(*committed.lock(), (0..NUM_BLOCKS).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn bounded_validation() {
+        let (commit_sender, mut commit_receiver) = mpsc::unbounded_channel();
+        let (validator, _commit_task) = OrderedValidator::new(2, move |_| commit_sender.send(()).unwrap());
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..6 {
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+
+            validator
+                .submit(move || {
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(current, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(20));
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                })
+                .await;
+        }
+
+        for _ in 0..6 {
+            tokio::time::timeout(Duration::from_secs(5), commit_receiver.recv())
+                .await
+                .unwrap()
+                .unwrap();
+        }
+
+        assert!(max_in_flight.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[tokio::test]
+    async fn bounded_queue() {
+        let (commit_sender, mut commit_receiver) = mpsc::unbounded_channel();
+        let (validator, _commit_task) = OrderedValidator::new(1, move |_| commit_sender.send(()).unwrap());
+
+        // The validations don't finish until they're released.
+        let (release_sender, release_receiver) = std::sync::mpsc::channel::<()>();
+        let release_receiver = Arc::new(Mutex::new(release_receiver));
+
+        // One validation is awaited by the commit task, and another one is queued behind it.
+        for _ in 0..2 {
+            let release_receiver = release_receiver.clone();
+            validator.submit(move || release_receiver.lock().recv().unwrap()).await;
+        }
+
+        // Further submissions wait for room in the queue.
+        let release_receiver_clone = release_receiver.clone();
+        let submission = validator.submit(move || release_receiver_clone.lock().recv().unwrap());
+        assert!(tokio::time::timeout(Duration::from_millis(100), submission)
+            .await
+            .is_err());
+
+        for _ in 0..2 {
+            release_sender.send(()).unwrap();
+            tokio::time::timeout(Duration::from_secs(5), commit_receiver.recv())
+                .await
+                .unwrap()
+                .unwrap();
+        }
+
+        // Once the queue is drained, new validations are accepted again.
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            validator.submit(move || release_receiver.lock().recv().unwrap()),
+        )
+        .await
+        .unwrap();
+        release_sender.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), commit_receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -21,7 +21,7 @@ use snarkos_testing::{
     network::{handshaken_node_and_peer, spawn_2_fake_nodes, test_node, TestSetup},
     wait_until,
 };
-use tokio::{io::AsyncWriteExt, net::TcpStream};
+use tokio::{io::AsyncWriteExt, net::TcpStream, time::sleep};
 
 use std::{
     net::SocketAddr,
@@ -29,6 +29,7 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    time::Duration,
 };
 
 pub const ITERATIONS: usize = 1000;
@@ -80,6 +81,53 @@ async fn fuzzing_zeroes_post_handshake() {
     wait_until!(1, node.peer_book.number_of_connected_peers() == 0);
 }
 
+#[tokio::test]
+async fn stalled_mid_message_post_handshake() {
+    let node_setup = TestSetup {
+        consensus_setup: None,
+        is_bootnode: true,
+        peer_read_timeout: 1,
+        ..Default::default()
+    };
+    let (node, mut fake_node) = handshaken_node_and_peer(node_setup).await;
+    wait_until!(1, node.peer_book.number_of_connected_peers() == 1);
+    let quality = node.peer_book.connected_peers().into_iter().next().unwrap().1.quality;
+
+    // a peer that stays idle between messages is not affected by the read timeout
+    sleep(Duration::from_secs(2)).await;
+    assert_eq!(node.peer_book.number_of_connected_peers(), 1);
+
+    // announce a 64B message, but only send a part of it and stall
+    fake_node.write_bytes(&64u32.to_be_bytes()).await;
+    fake_node.write_bytes(&[0u8; 16]).await;
+
+    // the read times out and the peer is penalized and disconnected from
+    wait_until!(3, node.peer_book.number_of_connected_peers() == 0);
+    assert_eq!(quality.failures.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn trickled_message_post_handshake() {
+    let node_setup = TestSetup {
+        consensus_setup: None,
+        is_bootnode: true,
+        peer_read_timeout: 2,
+        ..Default::default()
+    };
+    let (node, mut fake_node) = handshaken_node_and_peer(node_setup).await;
+    wait_until!(1, node.peer_book.number_of_connected_peers() == 1);
+
+    // start a 64B message, and only complete its header shortly before the read timeout
+    let header = 64u32.to_be_bytes();
+    fake_node.write_bytes(&header[..1]).await;
+    sleep(Duration::from_millis(1500)).await;
+    fake_node.write_bytes(&header[1..]).await;
+    fake_node.write_bytes(&[0u8; 16]).await;
+
+    // the timeout covers the whole message, so it doesn't start over for the payload
+    wait_until!(1, node.peer_book.number_of_connected_peers() == 0);
+}
+
 #[tokio::test]
 async fn fuzzing_valid_header_pre_handshake() {
     // tracing_subscriber::fmt::init();
@@ -349,7 +397,7 @@ async fn fuzzing_corrupted_payloads_with_bodies_pre_handshake() {
         Payload::SyncBlock(blob.clone()),
         Payload::Transaction(blob.clone()),
         Payload::Peers(addrs.clone()),
-        Payload::Ping(thread_rng().gen()),
+        Payload::Ping(thread_rng().gen(), thread_rng().gen()),
     ] {
         let serialized = Payload::serialize(payload).unwrap();
 
@@ -408,7 +456,7 @@ async fn fuzzing_corrupted_payloads_with_bodies_post_handshake() {
         Payload::SyncBlock(blob.clone()),
         Payload::Transaction(blob.clone()),
         Payload::Peers(addrs.clone()),
-        Payload::Ping(thread_rng().gen()),
+        Payload::Ping(thread_rng().gen(), thread_rng().gen()),
     ] {
         let serialized = Payload::serialize(payload).unwrap();
 
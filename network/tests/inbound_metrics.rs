@@ -0,0 +1,69 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+// The metrics recorder is global, so these tests live in a dedicated binary.
+mod inbound_metrics {
+    use snarkos_network::{message::*, INBOUND_PROCESSING_TIME};
+    use snarkos_testing::{
+        network::{handshaken_node_and_peer, TestSetup},
+        wait_until,
+    };
+
+    use metrics::{GaugeValue, Key, Recorder, Unit};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    struct GetPeersProcessingTimes(AtomicU64);
+
+    impl Recorder for GetPeersProcessingTimes {
+        fn register_counter(&self, _key: &Key, _unit: Option<Unit>, _desc: Option<&'static str>) {}
+
+        fn register_gauge(&self, _key: &Key, _unit: Option<Unit>, _desc: Option<&'static str>) {}
+
+        fn register_histogram(&self, _key: &Key, _unit: Option<Unit>, _desc: Option<&'static str>) {}
+
+        fn increment_counter(&self, _key: &Key, _value: u64) {}
+
+        fn update_gauge(&self, _key: &Key, _value: GaugeValue) {}
+
+        fn record_histogram(&self, key: &Key, _value: f64) {
+            if key.name() == INBOUND_PROCESSING_TIME
+                && key
+                    .labels()
+                    .any(|label| label.key() == "payload" && label.value() == "getpeers")
+            {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    static RECORDER: GetPeersProcessingTimes = GetPeersProcessingTimes(AtomicU64::new(0));
+
+    #[tokio::test]
+    async fn processing_time() {
+        metrics::set_recorder(&RECORDER).unwrap();
+
+        let setup = TestSetup {
+            consensus_setup: None,
+            ..Default::default()
+        };
+        let (_node, mut peer) = handshaken_node_and_peer(setup).await;
+        assert_eq!(0, RECORDER.0.load(Ordering::SeqCst));
+
+        // A processed `GetPeers` records its processing time
+        peer.write_message(&Payload::GetPeers).await;
+        wait_until!(5, RECORDER.0.load(Ordering::SeqCst) == 1);
+    }
+}
@@ -16,10 +16,15 @@
 
 use snarkos_network::message::*;
 use snarkos_testing::{
-    network::{handshaken_node_and_peer, random_bound_address, test_node, TestSetup},
+    network::{handshaken_node_and_peer, handshaken_peer, random_bound_address, test_node, FakeNode, TestSetup},
     wait_until,
 };
 
+use std::{
+    collections::{BTreeSet, HashSet},
+    time::{Duration, Instant},
+};
+
 #[tokio::test]
 async fn peer_initiator_side() {
     let setup = TestSetup {
@@ -66,6 +71,113 @@ async fn peer_responder_side() {
     assert!(matches!(payload, Payload::Peers(..)));
 }
 
+#[tokio::test]
+async fn scheduled_pings_are_jittered() {
+    let setup = TestSetup {
+        consensus_setup: None,
+        peer_sync_interval: 1,
+        ..Default::default()
+    };
+    let (_node, mut peer) = handshaken_node_and_peer(setup).await;
+
+    // record the arrival times of the node's pings, answering each of them
+    let mut arrival_times = vec![];
+    while arrival_times.len() < 8 {
+        if let Payload::Ping(..) = peer.read_payload().await.unwrap() {
+            arrival_times.push(Instant::now());
+            peer.write_message(&Payload::Pong).await;
+        }
+    }
+
+    // the first ping may precede the schedule, so only the ones after it are considered
+    let peer_sync_interval = Duration::from_secs(1);
+    let jitter_bound = Duration::from_millis(snarkos_network::PING_JITTER_MILLIS);
+    let slack = Duration::from_millis(250);
+    let intervals = arrival_times[1..]
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .collect::<Vec<_>>();
+
+    // the intervals between the pings stay within the jitter bound of the peer sync interval...
+    for interval in &intervals {
+        assert!(*interval >= peer_sync_interval - jitter_bound);
+        assert!(*interval <= peer_sync_interval + jitter_bound + slack);
+    }
+
+    // ...but they're not all equal to it, as they would be without the jitter
+    let deviation = Duration::from_millis(30);
+    assert!(intervals.iter().any(|interval| {
+        *interval > peer_sync_interval + deviation || *interval + deviation < peer_sync_interval
+    }));
+}
+
+#[tokio::test]
+async fn shared_peer_count() {
+    let setup = TestSetup {
+        consensus_setup: None,
+        shared_peer_count: 2,
+        ..Default::default()
+    };
+    let (node, mut peer) = handshaken_node_and_peer(setup).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // connect a few more peers, so that there are more of them than the node shares at once
+    let mut other_peers = Vec::new();
+    for _ in 0..5 {
+        other_peers.push(handshaken_peer(node.local_address().unwrap()).await);
+    }
+    wait_until!(5, node.peer_book.number_of_connected_peers() == 6);
+
+    // the shared addresses never exceed the cap, and a random subset of them is shared each time
+    let mut shared_peer_sets = HashSet::new();
+    for _ in 0..10 {
+        peer.write_message(&Payload::GetPeers).await;
+
+        let addrs = loop {
+            match peer.read_payload().await.unwrap() {
+                Payload::Peers(addrs) => break addrs,
+                _ => continue,
+            }
+        };
+        assert!(addrs.len() <= 2);
+        assert!(!addrs.contains(&peer.addr()));
+
+        shared_peer_sets.insert(addrs.into_iter().collect::<BTreeSet<_>>());
+    }
+    assert!(shared_peer_sets.len() > 1);
+}
+
+#[tokio::test]
+async fn reserved_new_peer_slots() {
+    let setup = TestSetup {
+        consensus_setup: None,
+        max_peers: 2,
+        reserved_new_peer_slots: 1,
+        ..Default::default()
+    };
+    let (node, mut peer_a) = handshaken_node_and_peer(setup).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer_a.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // the only non-reserved slot is taken, so it's not available to peers that have connected before
+    wait_until!(5, node.peer_book.number_of_connected_peers() == 1);
+    assert!(!node.has_slot_for(peer_a.addr()));
+
+    // a brand-new peer can still connect into the reserved slot
+    let (new_addr, _) = random_bound_address().await;
+    assert!(node.has_slot_for(new_addr));
+
+    let mut peer_b = handshaken_peer(node.local_address().unwrap()).await;
+    let payload = peer_b.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+    wait_until!(5, node.peer_book.number_of_connected_peers() == 2);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn triangle() {
     let setup = |bootnodes| TestSetup {
@@ -96,3 +208,36 @@ async fn triangle() {
     // Make sure C connects to A => peer propagation works.
     wait_until!(5, triangle_is_formed());
 }
+
+#[tokio::test]
+async fn multiple_listen_addresses() {
+    let setup = TestSetup {
+        consensus_setup: None,
+        additional_listen_addresses: vec!["127.0.0.1:0".parse().unwrap()],
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+
+    // The node listens at its local address first, and at the additional one second.
+    let listen_addresses = node.listen_addresses().to_vec();
+    assert_eq!(2, listen_addresses.len());
+    assert_eq!(Some(listen_addresses[0]), node.local_address());
+    assert_ne!(listen_addresses[0], listen_addresses[1]);
+
+    // Peers are accepted at both of the addresses.
+    let peer_0 = handshaken_peer(listen_addresses[0]).await;
+    let peer_1 = handshaken_peer(listen_addresses[1]).await;
+    wait_until!(
+        5,
+        node.peer_book.is_connected(peer_0.addr()) && node.peer_book.is_connected(peer_1.addr())
+    );
+
+    // Each of the peers is tagged with the address it connected through.
+    let local_interface = |peer: &FakeNode| {
+        node.peer_book
+            .get_peer(peer.addr(), true)
+            .and_then(|peer_info| peer_info.local_interface())
+    };
+    assert_eq!(Some(listen_addresses[0]), local_interface(&peer_0));
+    assert_eq!(Some(listen_addresses[1]), local_interface(&peer_1));
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use snarkos_consensus::{Consensus, ConsensusParameters, MerkleTreeLedger};
+use snarkos_consensus::{Consensus, ConsensusParameters, MerkleTreeLedger, TWO_HOURS_UNIX};
 use snarkos_storage::{Ledger, LedgerStorage};
 use snarkvm_algorithms::{merkle_tree::MerkleTree, traits::LoadableMerkleParameters, MerkleParameters, CRH};
 use snarkvm_dpc::{
@@ -66,6 +66,8 @@ fn empty_ledger<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage>(
         storage,
         cm_merkle_tree: RwLock::new(cm_merkle_tree),
         ledger_parameters: parameters,
+        degraded: Default::default(),
+        last_write_probe: Default::default(),
         _transaction: PhantomData,
     })
 }
@@ -77,6 +79,7 @@ pub fn generate<S: Storage>(recipient: &str, value: u64, network_id: u8, file_na
         max_block_size: 1_000_000_000usize,
         max_nonce: u32::max_value(),
         target_block_time: 10i64,
+        max_future_block_time_drift: TWO_HOURS_UNIX,
         network_id: Network::from_network_id(network_id),
         verifier: PoswMarlin::verify_only().expect("could not instantiate PoSW verifier"),
         authorized_inner_snark_ids: vec![],
@@ -18,11 +18,12 @@
 
 use crate::{
     rpc_trait::RpcFunctions,
-    rpc_types::{Meta, RpcCredentials},
+    rpc_types::{Meta, RpcCredentials, RttUnit, SignedResponse},
     RpcImpl,
 };
 use snarkos_consensus::MerkleTreeLedger;
 use snarkos_network::Node;
+use snarkos_toolkit::{account::PrivateKey, signature::SignaturePublicKey};
 use snarkvm_dpc::Storage;
 
 use hyper::{
@@ -34,17 +35,30 @@ use hyper::{
 use json_rpc_types as jrt;
 use jsonrpc_core::Params;
 use serde::Serialize;
-use tokio::task;
+use tokio::{sync::Semaphore, task, time::timeout};
+use tracing::Instrument;
 
-use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+use std::{
+    convert::Infallible,
+    fs,
+    future::Future,
+    net::SocketAddr,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-const METHODS_EXPECTING_PARAMS: [&str; 14] = [
+const METHODS_EXPECTING_PARAMS: [&str; 20] = [
     // public
     "getblock",
     "getblockhash",
     "getrawtransaction",
+    "getrawtransactions",
+    "getspendstatus",
     "gettransactioninfo",
     "decoderawtransaction",
+    "decoderawblock",
     "sendtransaction",
     "validaterawtransaction",
     // private
@@ -55,8 +69,47 @@ const METHODS_EXPECTING_PARAMS: [&str; 14] = [
     "decoderecord",
     "decryptrecord",
     "disconnect",
+    "blacklisttransaction",
+    "unblacklisttransaction",
+    "prioritisetransaction",
+];
+
+/// The methods whose handlers are CPU-heavy; the number of their concurrently executed requests is bounded.
+const EXPENSIVE_METHODS: [&str; 7] = [
+    "decoderawblock",
+    "getblocktemplate",
+    "sendtransaction",
+    "validaterawtransaction",
+    "createrawtransaction",
+    "createtransactionkernel",
+    "createtransaction",
 ];
 
+/// Bounds the number of concurrently executed RPC requests; the excess ones are queued for up to
+/// the queue timeout, after which they're rejected.
+#[derive(Clone)]
+pub struct ConcurrencyLimit {
+    semaphore: Arc<Semaphore>,
+    queue_timeout: Duration,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(max_concurrent_requests: usize, queue_timeout: Duration) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent_requests)),
+            queue_timeout,
+        }
+    }
+
+    /// Runs the given request handler once fewer than the maximum number of requests are executed;
+    /// returns `None` if that didn't happen within the queue timeout.
+    pub async fn run<F: Future>(&self, handler: F) -> Option<F::Output> {
+        let _permit = timeout(self.queue_timeout, self.semaphore.acquire()).await.ok()?.ok()?;
+
+        Some(handler.await)
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn start_rpc_server<S: Storage + Send + Sync + 'static>(
     rpc_addr: SocketAddr,
@@ -64,17 +117,43 @@ pub fn start_rpc_server<S: Storage + Send + Sync + 'static>(
     node_server: Node<S>,
     username: Option<String>,
     password: Option<String>,
+    slow_request_threshold: Duration,
+    signing_key_file: Option<PathBuf>,
+    expensive_request_limit: ConcurrencyLimit,
+    min_chain_query_peers: u16,
 ) -> task::JoinHandle<()> {
     let credentials = match (username, password) {
         (Some(username), Some(password)) => Some(RpcCredentials { username, password }),
         _ => None,
     };
 
-    let rpc_impl = RpcImpl::new(secondary_storage, credentials, node_server);
+    // If a signing key file is provided, the successful responses are signed with the key it contains.
+    let signing_key = signing_key_file.map(|path| {
+        let signing_key = fs::read_to_string(&path).expect("Couldn't read the RPC signing key file!");
+        let signing_key = PrivateKey::from_str(signing_key.trim()).expect("Invalid RPC signing key!");
+        let public_key = SignaturePublicKey::from(&signing_key).expect("Invalid RPC signing key!");
+        info!("Signing RPC responses; the signature public key is {}", public_key);
+
+        Arc::new(signing_key)
+    });
+
+    let rpc_impl = RpcImpl::new(secondary_storage, credentials, node_server, min_chain_query_peers);
 
     let service = make_service_fn(move |_conn| {
         let rpc = rpc_impl.clone();
-        async move { Ok::<_, Infallible>(service_fn(move |req| handle_rpc(rpc.clone(), req))) }
+        let signing_key = signing_key.clone();
+        let expensive_request_limit = expensive_request_limit.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                handle_rpc(
+                    rpc.clone(),
+                    req,
+                    slow_request_threshold,
+                    signing_key.clone(),
+                    expensive_request_limit.clone(),
+                )
+            }))
+        }
     });
 
     let server = Server::bind(&rpc_addr).serve(service);
@@ -84,9 +163,32 @@ pub fn start_rpc_server<S: Storage + Send + Sync + 'static>(
     })
 }
 
+/// Measures the duration of the given RPC request handler and logs a warning if it exceeds
+/// `slow_request_threshold`. Returns the output of the handler and whether it was slow.
+pub async fn time_request<F: Future>(method: &str, slow_request_threshold: Duration, handler: F) -> (F::Output, bool) {
+    let start = Instant::now();
+    let output = handler.await;
+    let elapsed = start.elapsed();
+
+    let is_slow = elapsed > slow_request_threshold;
+    if is_slow {
+        warn!(
+            "Slow RPC request: `{}` took {}ms (threshold: {}ms)",
+            method,
+            elapsed.as_millis(),
+            slow_request_threshold.as_millis()
+        );
+    }
+
+    (output, is_slow)
+}
+
 async fn handle_rpc<S: Storage + Send + Sync + 'static>(
     rpc: RpcImpl<S>,
     req: hyper::Request<Body>,
+    slow_request_threshold: Duration,
+    signing_key: Option<Arc<PrivateKey>>,
+    expensive_request_limit: ConcurrencyLimit,
 ) -> Result<hyper::Response<Body>, Infallible> {
     // Register the request in the metrics.
     metrics::increment_counter!(snarkos_network::MISC_RPC_REQUESTS);
@@ -140,151 +242,289 @@ async fn handle_rpc<S: Storage + Send + Sync + 'static>(
         }
     };
 
-    // Handle the request method.
-    let response = match &*req.method {
-        // public
-        "getblock" => {
-            let result = rpc
-                .get_block(params[0].as_str().unwrap_or("").into())
-                .map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
-        "getblockcount" => {
-            let result = rpc.get_block_count().map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
-        "getbestblockhash" => {
-            let result = rpc.get_best_block_hash().map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
-        "getblockhash" => match serde_json::from_value::<u32>(params.remove(0)) {
-            Ok(height) => {
-                let result = rpc.get_block_hash(height).map_err(convert_crate_err);
+    // Handle the request method within a span, measuring its duration.
+    let span = info_span!("rpc_request", method = %req.method);
+    let handler = async {
+        match &*req.method {
+            // public
+            "getblock" => {
+                let result = rpc
+                    .get_block(params[0].as_str().unwrap_or("").into())
+                    .map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getblockcount" => {
+                let result = rpc.get_block_count().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getbestblockhash" => {
+                let result = rpc.get_best_block_hash().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getbestchaininfo" => {
+                let result = rpc.get_best_chain_info().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getblockhash" => match serde_json::from_value::<u32>(params.remove(0)) {
+                Ok(height) => {
+                    let result = rpc.get_block_hash(height).map_err(convert_crate_err);
+                    result_to_response(&req, result)
+                }
+                Err(_) => {
+                    let err = jrt::Error::with_custom_msg(jrt::ErrorCode::ParseError, "Invalid block height!");
+                    jrt::Response::error(jrt::Version::V2, err, req.id.clone())
+                }
+            },
+            "getrawtransaction" => {
+                let result = rpc
+                    .get_raw_transaction(params[0].as_str().unwrap_or("").into())
+                    .map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getrawtransactions" => match serde_json::from_value::<Vec<String>>(params.remove(0)) {
+                Ok(transaction_ids) => {
+                    let result = rpc.get_raw_transactions(transaction_ids).map_err(convert_crate_err);
+                    result_to_response(&req, result)
+                }
+                Err(_) => {
+                    let err = jrt::Error::with_custom_msg(jrt::ErrorCode::ParseError, "Invalid transaction ids!");
+                    jrt::Response::error(jrt::Version::V2, err, req.id.clone())
+                }
+            },
+            "gettransactioninfo" => {
+                let result = rpc
+                    .get_transaction_info(params[0].as_str().unwrap_or("").into())
+                    .map_err(convert_crate_err);
                 result_to_response(&req, result)
             }
-            Err(_) => {
-                let err = jrt::Error::with_custom_msg(jrt::ErrorCode::ParseError, "Invalid block height!");
+            "decoderawtransaction" => {
+                let result = rpc
+                    .decode_raw_transaction(params[0].as_str().unwrap_or("").into())
+                    .map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "decoderawblock" => {
+                let result = rpc
+                    .decode_raw_block(params[0].as_str().unwrap_or("").into())
+                    .map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "sendtransaction" => {
+                let result = rpc
+                    .send_raw_transaction(params[0].as_str().unwrap_or("").into())
+                    .map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "validaterawtransaction" => {
+                let result = rpc
+                    .validate_raw_transaction(params[0].as_str().unwrap_or("").into())
+                    .map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getconnectioncount" => {
+                let result = rpc.get_connection_count().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getconsensusparams" => {
+                let result = rpc.get_consensus_params().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getpeerinfo" => {
+                // The unit of the round-trip times is an optional param.
+                let rtt_unit = match &req.params {
+                    Some(Params::Array(arr)) if !arr.is_empty() => serde_json::from_value::<RttUnit>(arr[0].clone())
+                        .map(Some)
+                        .map_err(|_| ()),
+                    _ => Ok(None),
+                };
+                match rtt_unit {
+                    Ok(rtt_unit) => {
+                        let result = rpc.get_peer_info(rtt_unit).map_err(convert_crate_err);
+                        result_to_response(&req, result)
+                    }
+                    Err(_) => {
+                        let err = jrt::Error::with_custom_msg(jrt::ErrorCode::ParseError, "Invalid RTT unit!");
+                        jrt::Response::error(jrt::Version::V2, err, req.id.clone())
+                    }
+                }
+            }
+            "getmempoolinfo" => {
+                let result = rpc.get_mempool_info().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getnodeinfo" => {
+                let result = rpc.get_node_info().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getnetworkinfo" => {
+                let result = rpc.get_network_info().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getnodestats" => {
+                let result = rpc.get_node_stats().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getblocktemplate" => {
+                let result = rpc.get_block_template().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getorphantxs" => {
+                let result = rpc.get_orphan_txs().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getrejectedtransactions" => {
+                let result = rpc.get_rejected_transactions().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getspendstatus" => {
+                let result = rpc
+                    .get_spend_status(params[0].as_str().unwrap_or("").into())
+                    .map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getsyncstatus" => {
+                let result = rpc.get_sync_status().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "estimatefee" => {
+                let result = rpc.estimate_fee().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            "getblocklocator" => {
+                let result = rpc.get_block_locator().map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            // private
+            "createaccount" => {
+                let result = rpc
+                    .create_account_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            "createrawtransaction" => {
+                let result = rpc
+                    .create_raw_transaction_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            "createtransactionkernel" => {
+                let result = rpc
+                    .create_transaction_kernel_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            "createtransaction" => {
+                let result = rpc
+                    .create_transaction_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            "getrecordcommitments" => {
+                let result = rpc
+                    .get_record_commitments_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            "getrawrecord" => {
+                let result = rpc
+                    .get_raw_record_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            "decoderecord" => {
+                let result = rpc
+                    .decode_record_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            "decryptrecord" => {
+                let result = rpc
+                    .decrypt_record_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            "disconnect" => {
+                let result = rpc
+                    .disconnect_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            "clearorphantxs" => {
+                let result = rpc
+                    .clear_orphan_txs_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            "syncnow" => {
+                let result = rpc
+                    .sync_now_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            "blacklisttransaction" => {
+                let result = rpc
+                    .blacklist_transaction_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            "unblacklisttransaction" => {
+                let result = rpc
+                    .unblacklist_transaction_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            "prioritisetransaction" => {
+                let result = rpc
+                    .prioritise_transaction_protected(Params::Array(params), meta)
+                    .await
+                    .map_err(convert_core_err);
+                result_to_response(&req, result)
+            }
+            _ => {
+                let err = jrt::Error::from_code(jrt::ErrorCode::MethodNotFound);
                 jrt::Response::error(jrt::Version::V2, err, req.id.clone())
             }
-        },
-        "getrawtransaction" => {
-            let result = rpc
-                .get_raw_transaction(params[0].as_str().unwrap_or("").into())
-                .map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
-        "gettransactioninfo" => {
-            let result = rpc
-                .get_transaction_info(params[0].as_str().unwrap_or("").into())
-                .map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
-        "decoderawtransaction" => {
-            let result = rpc
-                .decode_raw_transaction(params[0].as_str().unwrap_or("").into())
-                .map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
-        "sendtransaction" => {
-            let result = rpc
-                .send_raw_transaction(params[0].as_str().unwrap_or("").into())
-                .map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
-        "validaterawtransaction" => {
-            let result = rpc
-                .validate_raw_transaction(params[0].as_str().unwrap_or("").into())
-                .map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
-        "getconnectioncount" => {
-            let result = rpc.get_connection_count().map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
-        "getpeerinfo" => {
-            let result = rpc.get_peer_info().map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
-        "getnodeinfo" => {
-            let result = rpc.get_node_info().map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
-        "getnodestats" => {
-            let result = rpc.get_node_stats().map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
-        "getblocktemplate" => {
-            let result = rpc.get_block_template().map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
-        // private
-        "createaccount" => {
-            let result = rpc
-                .create_account_protected(Params::Array(params), meta)
-                .await
-                .map_err(convert_core_err);
-            result_to_response(&req, result)
-        }
-        "createrawtransaction" => {
-            let result = rpc
-                .create_raw_transaction_protected(Params::Array(params), meta)
-                .await
-                .map_err(convert_core_err);
-            result_to_response(&req, result)
-        }
-        "createtransactionkernel" => {
-            let result = rpc
-                .create_transaction_kernel_protected(Params::Array(params), meta)
-                .await
-                .map_err(convert_core_err);
-            result_to_response(&req, result)
-        }
-        "createtransaction" => {
-            let result = rpc
-                .create_transaction_protected(Params::Array(params), meta)
-                .await
-                .map_err(convert_core_err);
-            result_to_response(&req, result)
         }
-        "getrecordcommitments" => {
-            let result = rpc
-                .get_record_commitments_protected(Params::Array(params), meta)
-                .await
-                .map_err(convert_core_err);
-            result_to_response(&req, result)
-        }
-        "getrawrecord" => {
-            let result = rpc
-                .get_raw_record_protected(Params::Array(params), meta)
-                .await
-                .map_err(convert_core_err);
-            result_to_response(&req, result)
-        }
-        "decoderecord" => {
-            let result = rpc
-                .decode_record_protected(Params::Array(params), meta)
-                .await
-                .map_err(convert_core_err);
-            result_to_response(&req, result)
-        }
-        "decryptrecord" => {
-            let result = rpc
-                .decrypt_record_protected(Params::Array(params), meta)
-                .await
-                .map_err(convert_core_err);
-            result_to_response(&req, result)
-        }
-        "disconnect" => {
-            let result = rpc
-                .disconnect_protected(Params::Array(params), meta)
-                .await
-                .map_err(convert_core_err);
-            result_to_response(&req, result)
+    };
+    let handler = time_request(&req.method, slow_request_threshold, handler.instrument(span));
+
+    // The expensive requests are queued if too many of them are already being executed.
+    let response = if EXPENSIVE_METHODS.contains(&&*req.method) {
+        match expensive_request_limit.run(handler).await {
+            Some((response, _)) => response,
+            None => {
+                warn!(
+                    "Rejecting an RPC request: `{}`; too many concurrent requests",
+                    req.method
+                );
+                let err = jrt::Error::with_custom_msg(jrt::ErrorCode::ServerError(0), "Too many concurrent requests");
+                jrt::Response::error(jrt::Version::V2, err, req.id.clone())
+            }
         }
-        _ => {
-            let err = jrt::Error::from_code(jrt::ErrorCode::MethodNotFound);
-            jrt::Response::error(jrt::Version::V2, err, req.id.clone())
+    } else {
+        handler.await.0
+    };
+
+    // Sign the response if the node is configured to do so.
+    let response = match signing_key {
+        Some(signing_key) => {
+            let height = rpc.storage.get_current_block_height();
+            sign_response(&signing_key, &req, height, response)
         }
+        None => response,
     };
 
     // Serialize the response object.
@@ -333,3 +573,120 @@ fn result_to_response<T: Serialize>(
         Err(err) => jrt::Response::error(jrt::Version::V2, err, request.id.clone()),
     }
 }
+
+/// Replaces the result of a successful response with a `SignedResponse` binding the result to the request
+/// it answers and to the given height of the canon chain.
+fn sign_response(
+    signing_key: &PrivateKey,
+    request: &jrt::Request<Params>,
+    height: u32,
+    response: jrt::Response<serde_json::Value, ()>,
+) -> jrt::Response<serde_json::Value, ()> {
+    let jrt::Response { jsonrpc, payload, id } = response;
+    let payload = payload.and_then(|result| {
+        let params = serde_json::to_value(&request.params).unwrap_or_default();
+        let request_id = serde_json::to_value(&request.id).unwrap_or_default();
+        let signed_response = SignedResponse::sign(
+            signing_key,
+            String::from(&*request.method),
+            params,
+            request_id,
+            height,
+            result,
+        )
+        .map_err(convert_crate_err)?;
+        Ok(serde_json::to_value(&signed_response).unwrap_or_default())
+    });
+
+    jrt::Response { jsonrpc, payload, id }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkos_storage::LedgerStorage;
+    use snarkos_testing::{
+        network::{test_config, ConsensusSetup, TestSetup},
+        sync::*,
+    };
+
+    use serde_json::{json, Value};
+
+    async fn initialize_test_rpc(ledger: Arc<MerkleTreeLedger<LedgerStorage>>) -> RpcImpl<LedgerStorage> {
+        let environment = test_config(TestSetup::default());
+        let mut node = Node::new(environment).await.unwrap();
+        let consensus_setup = ConsensusSetup::default();
+        let consensus = Arc::new(create_test_consensus_from_ledger(ledger.clone()));
+
+        let node_consensus = snarkos_network::Sync::new(
+            consensus,
+            consensus_setup.is_miner,
+            Duration::from_secs(consensus_setup.block_sync_interval),
+            Duration::from_secs(consensus_setup.tx_sync_interval),
+        );
+        node.set_sync(node_consensus);
+
+        RpcImpl::new(ledger, None, node, 0)
+    }
+
+    #[tokio::test]
+    async fn signed_getblock_is_bound_to_its_request() {
+        let ledger = Arc::new(FIXTURE_VK.ledger());
+        let rpc = initialize_test_rpc(ledger.clone()).await;
+
+        let signing_key = PrivateKey::from_str("APrivateKey1tvv5YV1dipNiku2My8jMkqpqCyYKvR5Jq4y2mtjw7s77Zpn").unwrap();
+        let public_key = SignaturePublicKey::from(&signing_key).unwrap();
+
+        // Send a getblock request through the custom server's request handler.
+        let block_hash = hex::encode(GENESIS_BLOCK_HEADER_HASH.to_vec());
+        let request = json!({ "jsonrpc": "2.0", "id": 7, "method": "getblock", "params": [block_hash] });
+        let request = hyper::Request::new(Body::from(serde_json::to_vec(&request).unwrap()));
+
+        let response = handle_rpc(
+            rpc,
+            request,
+            Duration::from_secs(1),
+            Some(Arc::new(signing_key)),
+            ConcurrencyLimit::new(1, Duration::from_secs(1)),
+        )
+        .await
+        .unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let response: Value = serde_json::from_slice(&body).unwrap();
+
+        // The signed response carries the request it answers and the current height.
+        let signed_response: SignedResponse = serde_json::from_value(response["result"].clone()).unwrap();
+        assert_eq!(signed_response.method, "getblock");
+        assert_eq!(signed_response.params, json!([block_hash]));
+        assert_eq!(signed_response.id, json!(7));
+        assert_eq!(signed_response.height, ledger.get_current_block_height());
+        assert_eq!(signed_response.result["hash"], json!(block_hash));
+        assert!(signed_response.verify(&public_key).unwrap());
+
+        // Changing any of the signed fields invalidates the signature.
+        let mut tampered_response = signed_response.clone();
+        tampered_response.method = "getblockhash".into();
+        assert!(!tampered_response.verify(&public_key).unwrap());
+
+        let mut tampered_response = signed_response.clone();
+        tampered_response.params = json!(["00"]);
+        assert!(!tampered_response.verify(&public_key).unwrap());
+
+        let mut tampered_response = signed_response.clone();
+        tampered_response.id = json!(8);
+        assert!(!tampered_response.verify(&public_key).unwrap());
+
+        let mut tampered_response = signed_response.clone();
+        tampered_response.height += 1;
+        assert!(!tampered_response.verify(&public_key).unwrap());
+
+        let mut tampered_response = signed_response.clone();
+        tampered_response.result["nonce"] = json!(signed_response.result["nonce"].as_u64().unwrap() + 1);
+        assert!(!tampered_response.verify(&public_key).unwrap());
+
+        // Neither does a response verified against another key.
+        let other_key = PrivateKey::new(&mut rand::thread_rng()).unwrap();
+        let other_public_key = SignaturePublicKey::from(&other_key).unwrap();
+        assert!(!signed_response.verify(&other_public_key).unwrap());
+    }
+}
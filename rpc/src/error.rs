@@ -40,9 +40,15 @@ pub enum RpcError {
     #[error("{}", _0)]
     DPCError(DPCError),
 
+    #[error("not ready to serve chain queries: {} of {} required peers connected", _0, _1)]
+    InsufficientPeers(u16, u16),
+
     #[error("invalid block hash: {}", _0)]
     InvalidBlockHash(String),
 
+    #[error("invalid serial number: {}", _0)]
+    InvalidSerialNumber(String),
+
     #[error("invalid metadata: {}", _0)]
     InvalidMetadata(String),
 
@@ -55,6 +61,9 @@ pub enum RpcError {
     #[error("{}", _0)]
     StorageError(StorageError),
 
+    #[error("too many transaction ids: {} (max: {})", _0, _1)]
+    TooManyTransactionIds(usize, usize),
+
     #[error("{}", _0)]
     TransactionError(TransactionError),
 }
@@ -155,6 +164,18 @@ impl From<snarkos_toolkit::errors::PrivateKeyError> for RpcError {
     }
 }
 
+impl From<snarkos_toolkit::errors::SignatureError> for RpcError {
+    fn from(error: snarkos_toolkit::errors::SignatureError) -> Self {
+        RpcError::Crate("snarkos_toolkit::errors::signature", format!("{:?}", error))
+    }
+}
+
+impl From<serde_json::Error> for RpcError {
+    fn from(error: serde_json::Error) -> Self {
+        RpcError::Crate("serde_json", format!("{:?}", error))
+    }
+}
+
 impl From<std::boxed::Box<dyn std::any::Any + std::marker::Send>> for RpcError {
     fn from(error: std::boxed::Box<dyn std::any::Any + std::marker::Send>) -> Self {
         RpcError::Crate("std::boxed::Box", format!("{:?}", error))
@@ -19,20 +19,35 @@
 //! See [RpcFunctions](../trait.RpcFunctions.html) for documentation of public endpoints.
 
 use crate::{error::RpcError, rpc_trait::RpcFunctions, rpc_types::*};
-use snarkos_consensus::{get_block_reward, memory_pool::Entry, ConsensusParameters, MemoryPool, MerkleTreeLedger};
+use snarkos_consensus::{
+    get_block_reward,
+    memory_pool::{Entry, PriorityClass, TxOrigin, FEE_RATE_HISTOGRAM_BUCKETS},
+    ConsensusParameters,
+    MemoryPool,
+    MerkleTreeLedger,
+    BLOCK_REWARD_HALVING_INTERVAL,
+    INITIAL_BLOCK_REWARD,
+    MAX_BLOCK_REWARD_HALVINGS,
+};
 use snarkos_network::{Node, Sync, NODE_STATS};
 use snarkvm_dpc::{
     testnet1::{
         instantiated::{Components, Tx},
         parameters::PublicParameters,
+        transaction::amount::AleoAmount,
     },
+    Block,
+    BlockHeader,
     BlockHeaderHash,
+    LedgerScheme,
     Storage,
     TransactionScheme,
+    Transactions as DPCTransactions,
 };
 use snarkvm_utilities::{
     bytes::{FromBytes, ToBytes},
     to_bytes,
+    CanonicalDeserialize,
     CanonicalSerialize,
 };
 
@@ -40,10 +55,19 @@ use chrono::Utc;
 use parking_lot::Mutex;
 
 use std::{
+    collections::BTreeMap,
     ops::Deref,
     sync::{atomic::Ordering, Arc},
+    time::Instant,
 };
 
+/// The number of standard memory pool transactions from which `estimatefee` relies on the memory pool alone;
+/// below that, the fee rate hints shared by the peers make up for the missing transactions.
+pub const MIN_LOCAL_FEE_SAMPLES: usize = 10;
+
+/// The maximum number of transactions that can be requested in a single `getrawtransactions` call.
+pub const MAX_RAW_TRANSACTIONS_PER_REQUEST: usize = 100;
+
 /// Implements JSON-RPC HTTP endpoint functions for a node.
 /// The constructor is given Arc::clone() copies of all needed node components.
 #[derive(Derivative)]
@@ -67,18 +91,39 @@ pub struct RpcInner<S: Storage> {
 
     /// A clone of the network Node
     pub(crate) node: Node<S>,
+
+    /// The number of connected peers required to serve chain queries before the node completes a block sync round
+    pub(crate) min_chain_query_peers: u16,
 }
 
 impl<S: Storage + Send + core::marker::Sync + 'static> RpcImpl<S> {
     /// Creates a new struct for calling public and private RPC endpoints.
-    pub fn new(storage: Arc<MerkleTreeLedger<S>>, credentials: Option<RpcCredentials>, node: Node<S>) -> Self {
+    pub fn new(
+        storage: Arc<MerkleTreeLedger<S>>,
+        credentials: Option<RpcCredentials>,
+        node: Node<S>,
+        min_chain_query_peers: u16,
+    ) -> Self {
         Self(Arc::new(RpcInner {
             storage,
             credentials,
             node,
+            min_chain_query_peers,
         }))
     }
 
+    /// Checks whether the node can serve chain queries, i.e. whether it has either completed a block sync round
+    /// or is connected to enough peers for its view of the chain not to be considered stale.
+    pub fn ensure_ready_for_chain_queries(&self) -> Result<(), RpcError> {
+        let connected_peers = self.node.peer_book.number_of_connected_peers();
+
+        if connected_peers >= self.min_chain_query_peers || self.node.has_completed_block_sync() {
+            Ok(())
+        } else {
+            Err(RpcError::InsufficientPeers(connected_peers, self.min_chain_query_peers))
+        }
+    }
+
     pub fn sync_handler(&self) -> Result<&Arc<Sync<S>>, RpcError> {
         self.node.sync().ok_or(RpcError::NoConsensus)
     }
@@ -118,7 +163,7 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
         };
 
         let confirmations = match height {
-            Some(block_height) => storage.get_current_block_height() - block_height,
+            Some(block_height) => storage.get_current_block_height().saturating_sub(block_height),
             None => 0,
         };
 
@@ -150,6 +195,8 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
 
     /// Returns the number of blocks in the canonical chain.
     fn get_block_count(&self) -> Result<u32, RpcError> {
+        self.ensure_ready_for_chain_queries()?;
+
         let storage = &self.storage;
         storage.catch_up_secondary(false)?;
         Ok(storage.get_block_count())
@@ -157,6 +204,8 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
 
     /// Returns the block hash of the head of the canonical chain.
     fn get_best_block_hash(&self) -> Result<String, RpcError> {
+        self.ensure_ready_for_chain_queries()?;
+
         let storage = &self.storage;
         storage.catch_up_secondary(false)?;
         let best_block_hash = storage.get_block_hash(storage.get_current_block_height())?;
@@ -164,6 +213,20 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
         Ok(hex::encode(&best_block_hash.0))
     }
 
+    /// Returns the block hash and height of the head of the canonical chain, along with its median time past.
+    fn get_best_chain_info(&self) -> Result<BestChainInfo, RpcError> {
+        let storage = &self.storage;
+        storage.catch_up_secondary(false)?;
+        let height = storage.get_current_block_height();
+        let best_block_hash = storage.get_block_hash(height)?;
+
+        Ok(BestChainInfo {
+            best_block_hash: hex::encode(best_block_hash.0),
+            height,
+            median_time_past: storage.get_median_time_past()?,
+        })
+    }
+
     /// Returns the block hash of the index specified if it exists in the canonical chain.
     fn get_block_hash(&self, block_height: u32) -> Result<String, RpcError> {
         let storage = &self.storage;
@@ -182,6 +245,38 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
         ))
     }
 
+    /// Returns the hex encoded bytes of the transactions with the given ids, looked up in the ledger and then in
+    /// the memory pool; the unknown transactions map to `null`.
+    fn get_raw_transactions(&self, transaction_ids: Vec<String>) -> Result<BTreeMap<String, Option<String>>, RpcError> {
+        if transaction_ids.len() > MAX_RAW_TRANSACTIONS_PER_REQUEST {
+            return Err(RpcError::TooManyTransactionIds(
+                transaction_ids.len(),
+                MAX_RAW_TRANSACTIONS_PER_REQUEST,
+            ));
+        }
+
+        let storage = &self.storage;
+        storage.catch_up_secondary(false)?;
+        let memory_pool = self.memory_pool()?.lock();
+
+        let mut raw_transactions = BTreeMap::new();
+        for transaction_id in transaction_ids {
+            let id = hex::decode(&transaction_id)?;
+
+            let transaction = match storage.get_transaction(&id)? {
+                Some(transaction) => Some(to_bytes![transaction]?),
+                None => match memory_pool.transactions.get(&id) {
+                    Some(entry) => Some(to_bytes![entry.transaction]?),
+                    None => None,
+                },
+            };
+
+            raw_transactions.insert(transaction_id, transaction.map(hex::encode));
+        }
+
+        Ok(raw_transactions)
+    }
+
     /// Returns information about a transaction from a transaction id.
     fn get_transaction_info(&self, transaction_id: String) -> Result<TransactionInfo, RpcError> {
         let transaction_bytes = self.get_raw_transaction(transaction_id)?;
@@ -223,11 +318,26 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
 
         let transaction_id = transaction.transaction_id()?;
         let storage = &self.storage;
-        let block_number = match storage.get_transaction_location(&transaction_id.to_vec())? {
+        let block_location = storage.get_transaction_location(&transaction_id.to_vec())?;
+        let block_number = match &block_location {
             Some(block_location) => Some(storage.get_block_number(&BlockHeaderHash(block_location.block_hash))?),
             None => None,
         };
 
+        // Unlike the confirmations of `getblock`, these count the containing block itself, so a transaction
+        // in the latest block has 1 confirmation; one whose block is no longer canon has none.
+        let confirmations = match (&block_location, block_number) {
+            (Some(block_location), Some(block_height))
+                if storage.is_canon(&BlockHeaderHash(block_location.block_hash)) =>
+            {
+                storage
+                    .get_current_block_height()
+                    .saturating_sub(block_height)
+                    .saturating_add(1)
+            }
+            _ => 0,
+        };
+
         let transaction_metadata = TransactionMetadata { block_number };
 
         Ok(TransactionInfo {
@@ -245,6 +355,40 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
             signatures,
             encrypted_records,
             transaction_metadata,
+            confirmations,
+        })
+    }
+
+    /// Returns information about a block from serialized block bytes, without committing it.
+    fn decode_raw_block(&self, block_bytes: String) -> Result<DecodedBlock, RpcError> {
+        let block_bytes = hex::decode(block_bytes)?;
+        if block_bytes.len() < BlockHeader::size() {
+            return Err(RpcError::Message(
+                "the block bytes are shorter than a block header".into(),
+            ));
+        }
+        let block = Block::<Tx>::deserialize(&block_bytes)?;
+
+        let mut transactions = Vec::with_capacity(block.transactions.len());
+        for transaction in block.transactions.iter() {
+            transactions.push(TransactionSummary {
+                txid: hex::encode(&transaction.transaction_id()?),
+                size: to_bytes![transaction]?.len(),
+                value_balance: transaction.value_balance.0,
+            });
+        }
+
+        Ok(DecodedBlock {
+            hash: hex::encode(&block.header.get_hash().0),
+            size: block_bytes.len(),
+            previous_block_hash: block.header.previous_block_hash.to_string(),
+            merkle_root: block.header.merkle_root_hash.to_string(),
+            pedersen_merkle_root_hash: block.header.pedersen_merkle_root_hash.to_string(),
+            proof: block.header.proof.to_string(),
+            time: block.header.time,
+            difficulty_target: block.header.difficulty_target,
+            nonce: block.header.nonce,
+            transactions,
         })
     }
 
@@ -260,6 +404,15 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
 
         storage.catch_up_secondary(false)?;
 
+        // A transaction spending a record twice wouldn't verify either, but this tells the caller why.
+        MemoryPool::<Tx>::check_internal_duplicates(&transaction)?;
+
+        // Verifying a transaction is expensive, so reject the blacklisted ones and the ones exceeding the
+        // memory pool limits first.
+        self.memory_pool()?
+            .lock()
+            .check_transaction_limits(&transaction, transaction_bytes.len())?;
+
         if !self.sync_handler()?.consensus.verify_transaction(&transaction)? {
             // TODO (raychu86) Add more descriptive message. (e.g. tx already exists)
             return Ok("Transaction did not verify".into());
@@ -270,14 +423,15 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
                 let entry = Entry::<Tx> {
                     size_in_bytes: transaction_bytes.len(),
                     transaction,
+                    origin: TxOrigin::Local,
+                    priority_class: PriorityClass::Standard,
+                    valid_from_height: None,
                 };
 
-                if let Ok(inserted) = self.memory_pool()?.lock().insert(&storage, entry) {
-                    if inserted.is_some() {
-                        info!("Transaction added to the memory pool.");
-                        // TODO(ljedrz): checks if needs to be propagated to the network; if need be, this could
-                        // be made automatic at the time when a tx from any source is added the memory pool
-                    }
+                if self.memory_pool()?.lock().insert(&storage, entry)?.is_some() {
+                    info!("Transaction added to the memory pool.");
+                    // TODO(ljedrz): checks if needs to be propagated to the network; if need be, this could
+                    // be made automatic at the time when a tx from any source is added the memory pool
                 }
 
                 Ok(transaction_hex_id)
@@ -306,12 +460,62 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
         Ok(number as usize)
     }
 
+    /// Returns the consensus parameters of the network the node is a part of.
+    fn get_consensus_params(&self) -> Result<ConsensusParams, RpcError> {
+        let parameters = self.consensus_parameters()?;
+
+        Ok(ConsensusParams {
+            network_id: parameters.network_id.id(),
+            max_block_size: parameters.max_block_size,
+            max_nonce: parameters.max_nonce,
+            target_block_time: parameters.target_block_time,
+            max_future_block_time_drift: parameters.max_future_block_time_drift,
+            initial_block_reward: INITIAL_BLOCK_REWARD,
+            block_reward_halving_interval: BLOCK_REWARD_HALVING_INTERVAL,
+            max_block_reward_halvings: MAX_BLOCK_REWARD_HALVINGS,
+        })
+    }
+
     /// Returns this nodes connected peers.
-    fn get_peer_info(&self) -> Result<PeerInfo, RpcError> {
-        // Create a temporary tokio runtime to make an asynchronous function call
-        let peers = self.node.peer_book.connected_peers().keys().copied().collect();
+    fn get_peer_info(&self, rtt_unit: Option<RttUnit>) -> Result<PeerInfo, RpcError> {
+        let rtt_unit = rtt_unit.unwrap_or_default();
+        let connected_peers = self.node.peer_book.connected_peers();
+
+        let peers = connected_peers.keys().copied().collect();
+        // Both units are derived from the same microsecond measurement, so that they're consistent.
+        let rtts = connected_peers
+            .values()
+            .map(|peer_info| {
+                let rtt_us = peer_info.quality.rtt_us.load(Ordering::SeqCst);
+                match rtt_unit {
+                    RttUnit::Milliseconds => rtt_us / 1000,
+                    RttUnit::Microseconds => rtt_us,
+                }
+            })
+            .collect();
 
-        Ok(PeerInfo { peers })
+        Ok(PeerInfo { peers, rtts, rtt_unit })
+    }
+
+    /// Returns information about the memory pool, including its fee rate histogram.
+    fn get_mempool_info(&self) -> Result<MempoolInfo, RpcError> {
+        let memory_pool = self.memory_pool()?.lock();
+
+        let fee_rate_histogram = memory_pool
+            .fee_rate_histogram(&FEE_RATE_HISTOGRAM_BUCKETS)
+            .into_iter()
+            .map(|(fee_rate, size_in_bytes)| FeeRateBucket {
+                fee_rate,
+                size_in_bytes,
+            })
+            .collect();
+
+        Ok(MempoolInfo {
+            size: memory_pool.transactions.len(),
+            total_size_in_bytes: memory_pool.total_size_in_bytes,
+            fee_rate_histogram,
+            min_fee_per_byte: memory_pool.current_min_fee_per_byte(),
+        })
     }
 
     /// Returns data about the node.
@@ -321,11 +525,22 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
             is_bootnode: self.node.config.is_bootnode(),
             is_miner: self.sync_handler()?.is_miner(),
             is_syncing: self.node.is_syncing_blocks(),
+            is_storage_degraded: self.storage.is_degraded(),
             launched: self.node.launched,
             version: env!("CARGO_PKG_VERSION").into(),
         })
     }
 
+    /// Returns the addresses of the node and whether it's reachable.
+    fn get_network_info(&self) -> Result<NetworkInfo, RpcError> {
+        Ok(NetworkInfo {
+            listen_addresses: self.node.listen_addresses().to_vec(),
+            advertised_address: self.node.local_address(),
+            is_reachable: self.node.has_accepted_inbound_connection(),
+            protocol_version: snarkos_network::PROTOCOL_VERSION,
+        })
+    }
+
     /// Returns statistics related to the node.
     fn get_node_stats(&self) -> Result<NodeStats, RpcError> {
         Ok(NodeStats {
@@ -369,6 +584,7 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
             },
             queues: NodeQueueStats {
                 inbound: NODE_STATS.queues.inbound.load(Ordering::SeqCst),
+                inbound_capacity: self.node.inbound.capacity() as u64,
                 outbound: NODE_STATS.queues.outbound.load(Ordering::SeqCst),
             },
             misc: NodeMiscStats {
@@ -381,6 +597,7 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
                 duplicate_blocks: NODE_STATS.misc.duplicate_blocks.load(Ordering::Relaxed),
                 duplicate_sync_blocks: NODE_STATS.misc.duplicate_sync_blocks.load(Ordering::Relaxed),
                 rpc_requests: NODE_STATS.misc.rpc_requests.load(Ordering::Relaxed),
+                duplicate_transactions: NODE_STATS.misc.duplicate_transactions.load(Ordering::Relaxed),
             },
         })
     }
@@ -395,17 +612,29 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
 
         let time = Utc::now().timestamp();
 
-        let full_transactions = self
-            .memory_pool()?
-            .lock()
-            .get_candidates(&storage, self.consensus_parameters()?.max_block_size)?;
+        let memory_pool = self.memory_pool()?.lock();
+        let candidates = memory_pool.get_candidates(&storage, self.consensus_parameters()?.max_block_size)?;
+
+        // Drop any candidates that no longer belong in the block template.
+        let invalid_transaction_ids = memory_pool.validate_template(&storage, &candidates)?;
+        drop(memory_pool);
+
+        let mut full_transactions = DPCTransactions::new();
+        for transaction in candidates.iter() {
+            if !invalid_transaction_ids.contains(&transaction.transaction_id()?.to_vec()) {
+                full_transactions.push(transaction.clone());
+            }
+        }
 
         let transaction_strings = full_transactions.serialize_as_str()?;
 
-        let mut coinbase_value = get_block_reward(block_height + 1);
+        let mut total_fees = AleoAmount::ZERO;
+        let mut total_size = 0;
         for transaction in full_transactions.iter() {
-            coinbase_value = coinbase_value.add(transaction.value_balance())
+            total_fees = total_fees.add(transaction.value_balance());
+            total_size += transaction.size();
         }
+        let coinbase_value = get_block_reward(block_height + 1).add(total_fees);
 
         Ok(BlockTemplate {
             previous_block_hash: hex::encode(&block.header.get_hash().0),
@@ -414,6 +643,105 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
             difficulty_target: self.consensus_parameters()?.get_block_difficulty(&block.header, time),
             transactions: transaction_strings,
             coinbase_value: coinbase_value.0 as u64,
+            total_fees: total_fees.0 as u64,
+            total_size,
+            transaction_count: full_transactions.len(),
+        })
+    }
+
+    /// Returns the transaction ids of the transactions in the orphan pool.
+    fn get_orphan_txs(&self) -> Result<Vec<String>, RpcError> {
+        let mut transaction_ids = self
+            .memory_pool()?
+            .lock()
+            .orphans
+            .keys()
+            .map(hex::encode)
+            .collect::<Vec<_>>();
+        transaction_ids.sort();
+
+        Ok(transaction_ids)
+    }
+
+    /// Returns the most recent transactions that weren't admitted into the memory pool, from the newest one.
+    fn get_rejected_transactions(&self) -> Result<Vec<RejectedTransaction>, RpcError> {
+        let rejected_transactions = self
+            .memory_pool()?
+            .lock()
+            .rejections
+            .iter()
+            .rev()
+            .map(|rejection| RejectedTransaction {
+                txid: hex::encode(&rejection.transaction_id),
+                reason: rejection.reason.to_string(),
+            })
+            .collect();
+
+        Ok(rejected_transactions)
+    }
+
+    /// Returns whether the given serial number was spent in the ledger, or is spent by a memory pool transaction.
+    fn get_spend_status(&self, serial_number: String) -> Result<SpendStatus, RpcError> {
+        self.storage.catch_up_secondary(false)?;
+
+        let sn_bytes = hex::decode(&serial_number)?;
+        let sn = <Tx as TransactionScheme>::SerialNumber::deserialize(&mut &sn_bytes[..])
+            .map_err(|_| RpcError::InvalidSerialNumber(serial_number))?;
+
+        let spent = self.storage.contains_sn(&sn);
+        let pending = !spent && self.memory_pool()?.lock().contains_sn(&sn);
+
+        Ok(SpendStatus { spent, pending })
+    }
+
+    /// Returns the block sync status of the node.
+    fn get_sync_status(&self) -> Result<SyncStatus, RpcError> {
+        let sync = self.sync_handler()?;
+        let current_block_height = sync.current_block_height();
+        let best_peer_block_height = self
+            .node
+            .peer_book
+            .best_height_peer()
+            .map(|(_, block_height)| block_height)
+            .unwrap_or(0);
+
+        Ok(SyncStatus {
+            is_syncing: self.node.is_syncing_blocks(),
+            current_block_height,
+            best_peer_block_height,
+            blocks_remaining: best_peer_block_height.saturating_sub(current_block_height),
+            blocks_per_second: sync
+                .progress()
+                .blocks_per_second(current_block_height, sync.clock().now()),
         })
     }
+
+    /// Returns an estimate of the fee per byte a transaction needs to pay to be included in a block soon.
+    fn estimate_fee(&self) -> Result<u64, RpcError> {
+        let (local_fee_rate, local_samples) = self.memory_pool()?.lock().median_fee_rate().unwrap_or((0.0, 0));
+        let local_samples = local_samples.min(MIN_LOCAL_FEE_SAMPLES);
+
+        let fee_rate = match self.node.peer_book.fee_rate_hint(Instant::now()) {
+            Some(peer_fee_rate) if local_samples < MIN_LOCAL_FEE_SAMPLES => {
+                let peer_samples = MIN_LOCAL_FEE_SAMPLES - local_samples;
+                (local_fee_rate * local_samples as f64 + peer_fee_rate * peer_samples as f64)
+                    / MIN_LOCAL_FEE_SAMPLES as f64
+            }
+            _ => local_fee_rate,
+        };
+
+        Ok(fee_rate.max(0.0).round() as u64)
+    }
+
+    /// Returns the hashes of the canon blocks the node would send to a peer to find their latest shared block.
+    fn get_block_locator(&self) -> Result<Vec<String>, RpcError> {
+        self.storage.catch_up_secondary(false)?;
+
+        let block_locator_hashes = self.storage.get_block_locator_hashes()?;
+
+        Ok(block_locator_hashes
+            .into_iter()
+            .map(|block_hash| hex::encode(block_hash.0))
+            .collect())
+    }
 }
@@ -262,6 +262,107 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
         Ok(Value::Null)
     }
 
+    /// Wrap authentication around `clear_orphan_txs`
+    pub async fn clear_orphan_txs_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        params.expect_no_params()?;
+
+        match self.clear_orphan_txs() {
+            Ok(num_cleared) => Ok(Value::from(num_cleared)),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `sync_now`
+    pub async fn sync_now_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        params.expect_no_params()?;
+
+        match self.sync_now() {
+            Ok(started) => Ok(Value::from(started)),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `blacklist_transaction`
+    pub async fn blacklist_transaction_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 1 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 1 element",
+                value.len()
+            )));
+        }
+
+        let transaction_id: String = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.blacklist_transaction(transaction_id) {
+            Ok(changed) => Ok(Value::from(changed)),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `unblacklist_transaction`
+    pub async fn unblacklist_transaction_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 1 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 1 element",
+                value.len()
+            )));
+        }
+
+        let transaction_id: String = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.unblacklist_transaction(transaction_id) {
+            Ok(changed) => Ok(Value::from(changed)),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `prioritise_transaction`
+    pub async fn prioritise_transaction_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 2 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 2 elements",
+                value.len()
+            )));
+        }
+
+        let transaction_id: String = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+        let fee_delta: i64 = serde_json::from_value(value[1].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.prioritise_transaction(transaction_id, fee_delta) {
+            Ok(prioritised) => Ok(Value::from(prioritised)),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
     /// Expose the protected functions as RPC enpoints
     pub fn add_protected(&self, io: &mut MetaIoHandler<Meta>) {
         let mut d = IoDelegate::<Self, Meta>::new(Arc::new(self.clone()));
@@ -306,6 +407,26 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
             let rpc = rpc.clone();
             rpc.disconnect_protected(params, meta)
         });
+        d.add_method_with_meta("clearorphantxs", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.clear_orphan_txs_protected(params, meta)
+        });
+        d.add_method_with_meta("syncnow", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.sync_now_protected(params, meta)
+        });
+        d.add_method_with_meta("blacklisttransaction", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.blacklist_transaction_protected(params, meta)
+        });
+        d.add_method_with_meta("unblacklisttransaction", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.unblacklist_transaction_protected(params, meta)
+        });
+        d.add_method_with_meta("prioritisetransaction", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.prioritise_transaction_protected(params, meta)
+        });
 
         io.extend_with(d)
     }
@@ -653,4 +774,34 @@ impl<S: Storage + Send + Sync + 'static> ProtectedRpcFunctions for RpcImpl<S> {
     fn disconnect(&self, address: SocketAddr) {
         self.node.disconnect_from_peer(address);
     }
+
+    /// Removes all the transactions from the orphan pool, returning their number.
+    fn clear_orphan_txs(&self) -> Result<usize, RpcError> {
+        Ok(self.memory_pool()?.lock().take_orphans().len())
+    }
+
+    /// Starts a block sync round unless one is already in progress, returning whether it was started.
+    fn sync_now(&self) -> Result<bool, RpcError> {
+        Ok(self.node.run_block_sync())
+    }
+
+    /// Blacklists the given transaction, removing it from the memory pool, and returns whether it wasn't
+    /// blacklisted already.
+    fn blacklist_transaction(&self, transaction_id: String) -> Result<bool, RpcError> {
+        Ok(self.memory_pool()?.lock().blacklist(&hex::decode(transaction_id)?)?)
+    }
+
+    /// Removes the given transaction from the blacklist, returning whether it was blacklisted.
+    fn unblacklist_transaction(&self, transaction_id: String) -> Result<bool, RpcError> {
+        Ok(self.memory_pool()?.lock().unblacklist(&hex::decode(transaction_id)?))
+    }
+
+    /// Adjusts the fee of the given memory pool transaction by the given delta when selecting the transactions
+    /// for a block, returning whether it's in the memory pool.
+    fn prioritise_transaction(&self, transaction_id: String, fee_delta: i64) -> Result<bool, RpcError> {
+        Ok(self
+            .memory_pool()?
+            .lock()
+            .prioritise_transaction(&hex::decode(transaction_id)?, fee_delta))
+    }
 }
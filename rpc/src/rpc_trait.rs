@@ -20,7 +20,7 @@ use crate::{error::RpcError, rpc_types::*};
 
 use jsonrpc_derive::rpc;
 
-use std::net::SocketAddr;
+use std::{collections::BTreeMap, net::SocketAddr};
 
 /// Definition of public RPC endpoints.
 #[rpc]
@@ -37,6 +37,10 @@ pub trait RpcFunctions {
     #[rpc(name = "getbestblockhash")]
     fn get_best_block_hash(&self) -> Result<String, RpcError>;
 
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getbestchaininfo.md"))]
+    #[rpc(name = "getbestchaininfo")]
+    fn get_best_chain_info(&self) -> Result<BestChainInfo, RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getblockhash.md"))]
     #[rpc(name = "getblockhash")]
     fn get_block_hash(&self, block_height: u32) -> Result<String, RpcError>;
@@ -45,6 +49,10 @@ pub trait RpcFunctions {
     #[rpc(name = "getrawtransaction")]
     fn get_raw_transaction(&self, transaction_id: String) -> Result<String, RpcError>;
 
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getrawtransactions.md"))]
+    #[rpc(name = "getrawtransactions")]
+    fn get_raw_transactions(&self, transaction_ids: Vec<String>) -> Result<BTreeMap<String, Option<String>>, RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/gettransactioninfo.md"))]
     #[rpc(name = "gettransactioninfo")]
     fn get_transaction_info(&self, transaction_id: String) -> Result<TransactionInfo, RpcError>;
@@ -53,6 +61,10 @@ pub trait RpcFunctions {
     #[rpc(name = "decoderawtransaction")]
     fn decode_raw_transaction(&self, transaction_bytes: String) -> Result<TransactionInfo, RpcError>;
 
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/decoderawblock.md"))]
+    #[rpc(name = "decoderawblock")]
+    fn decode_raw_block(&self, block_bytes: String) -> Result<DecodedBlock, RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/sendtransaction.md"))]
     #[rpc(name = "sendtransaction")]
     fn send_raw_transaction(&self, transaction_bytes: String) -> Result<String, RpcError>;
@@ -68,14 +80,26 @@ pub trait RpcFunctions {
     #[rpc(name = "getconnectioncount")]
     fn get_connection_count(&self) -> Result<usize, RpcError>;
 
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getconsensusparams.md"))]
+    #[rpc(name = "getconsensusparams")]
+    fn get_consensus_params(&self) -> Result<ConsensusParams, RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getpeerinfo.md"))]
     #[rpc(name = "getpeerinfo")]
-    fn get_peer_info(&self) -> Result<PeerInfo, RpcError>;
+    fn get_peer_info(&self, rtt_unit: Option<RttUnit>) -> Result<PeerInfo, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getmempoolinfo.md"))]
+    #[rpc(name = "getmempoolinfo")]
+    fn get_mempool_info(&self) -> Result<MempoolInfo, RpcError>;
 
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getnodeinfo.md"))]
     #[rpc(name = "getnodeinfo")]
     fn get_node_info(&self) -> Result<NodeInfo, RpcError>;
 
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getnetworkinfo.md"))]
+    #[rpc(name = "getnetworkinfo")]
+    fn get_network_info(&self) -> Result<NetworkInfo, RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getnodestats.md"))]
     #[rpc(name = "getnodestats")]
     fn get_node_stats(&self) -> Result<NodeStats, RpcError>;
@@ -83,6 +107,33 @@ pub trait RpcFunctions {
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getblocktemplate.md"))]
     #[rpc(name = "getblocktemplate")]
     fn get_block_template(&self) -> Result<BlockTemplate, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getorphantxs.md"))]
+    #[rpc(name = "getorphantxs")]
+    fn get_orphan_txs(&self) -> Result<Vec<String>, RpcError>;
+
+    #[cfg_attr(
+        nightly,
+        doc(include = "../documentation/public_endpoints/getrejectedtransactions.md")
+    )]
+    #[rpc(name = "getrejectedtransactions")]
+    fn get_rejected_transactions(&self) -> Result<Vec<RejectedTransaction>, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getspendstatus.md"))]
+    #[rpc(name = "getspendstatus")]
+    fn get_spend_status(&self, serial_number: String) -> Result<SpendStatus, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getsyncstatus.md"))]
+    #[rpc(name = "getsyncstatus")]
+    fn get_sync_status(&self) -> Result<SyncStatus, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/estimatefee.md"))]
+    #[rpc(name = "estimatefee")]
+    fn estimate_fee(&self) -> Result<u64, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getblocklocator.md"))]
+    #[rpc(name = "getblocklocator")]
+    fn get_block_locator(&self) -> Result<Vec<String>, RpcError>;
 }
 
 /// Definition of private RPC endpoints that require authentication.
@@ -125,4 +176,25 @@ pub trait ProtectedRpcFunctions {
 
     #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/disconnect.md"))]
     fn disconnect(&self, address: SocketAddr);
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/clearorphantxs.md"))]
+    fn clear_orphan_txs(&self) -> Result<usize, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/syncnow.md"))]
+    fn sync_now(&self) -> Result<bool, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/blacklisttransaction.md"))]
+    fn blacklist_transaction(&self, transaction_id: String) -> Result<bool, RpcError>;
+
+    #[cfg_attr(
+        nightly,
+        doc(include = "../documentation/private_endpoints/unblacklisttransaction.md")
+    )]
+    fn unblacklist_transaction(&self, transaction_id: String) -> Result<bool, RpcError>;
+
+    #[cfg_attr(
+        nightly,
+        doc(include = "../documentation/private_endpoints/prioritisetransaction.md")
+    )]
+    fn prioritise_transaction(&self, transaction_id: String, fee_delta: i64) -> Result<bool, RpcError>;
 }
@@ -16,10 +16,16 @@
 
 //! Structures for RPC endpoint requests and responses.
 
+use crate::error::RpcError;
+use snarkos_toolkit::{
+    account::PrivateKey,
+    signature::{Signature, SignaturePublicKey},
+};
+
 use chrono::{DateTime, Utc};
 use jsonrpc_core::Metadata;
 use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
+use std::{net::SocketAddr, str::FromStr};
 
 /// Defines the authentication format for accessing private endpoints on the RPC server
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -30,6 +36,71 @@ pub struct RpcCredentials {
     pub password: String,
 }
 
+/// A successful RPC response signed with the node's signing key, which allows light clients to verify
+/// that it originates from a node they trust
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SignedResponse {
+    /// The method of the request that was answered
+    pub method: String,
+    /// The parameters of the request that was answered
+    pub params: serde_json::Value,
+    /// The id of the request that was answered
+    pub id: serde_json::Value,
+    /// The height of the node's canon chain when the response was signed
+    pub height: u32,
+    /// The result of the RPC call
+    pub result: serde_json::Value,
+    /// The hex-encoded signature of the canonical encoding of the fields above
+    pub signature: String,
+}
+
+impl SignedResponse {
+    /// Signs the result of an RPC call, binding it to the request it answers and to the current height.
+    pub fn sign(
+        signing_key: &PrivateKey,
+        method: String,
+        params: serde_json::Value,
+        id: serde_json::Value,
+        height: u32,
+        result: serde_json::Value,
+    ) -> Result<Self, RpcError> {
+        let message = Self::message(&method, &params, &id, height, &result)?;
+        let signature = Signature::sign(signing_key, &message, &mut rand::thread_rng())?.to_string();
+
+        Ok(Self {
+            method,
+            params,
+            id,
+            height,
+            result,
+            signature,
+        })
+    }
+
+    /// Returns `true` if the response was signed with the private key of the given public key.
+    ///
+    /// Callers should also check that `method`, `params` and `id` match the request they sent,
+    /// and that `height` is recent enough, so that a response can't be replayed for another request.
+    pub fn verify(&self, public_key: &SignaturePublicKey) -> Result<bool, RpcError> {
+        let message = Self::message(&self.method, &self.params, &self.id, self.height, &self.result)?;
+        let signature = Signature::from_str(&self.signature)?;
+
+        Ok(signature.verify(public_key, &message)?)
+    }
+
+    /// Returns the canonical encoding that is signed: the compact JSON encoding of the array
+    /// `[method, params, id, height, result]`, with the keys of every object in sorted order.
+    fn message(
+        method: &str,
+        params: &serde_json::Value,
+        id: &serde_json::Value,
+        height: u32,
+        result: &serde_json::Value,
+    ) -> Result<Vec<u8>, RpcError> {
+        Ok(serde_json::to_vec(&(method, params, id, height, result))?)
+    }
+}
+
 /// RPC metadata for encoding authentication
 #[derive(Default, Clone)]
 pub struct Meta {
@@ -39,6 +110,19 @@ pub struct Meta {
 
 impl Metadata for Meta {}
 
+/// Returned value for the `getbestchaininfo` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BestChainInfo {
+    /// The block hash of the head of the best valid chain
+    pub best_block_hash: String,
+
+    /// The block height of the head of the best valid chain
+    pub height: u32,
+
+    /// The median timestamp of the latest 11 blocks
+    pub median_time_past: i64,
+}
+
 /// Returned value for the `getblock` rpc call
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct BlockInfo {
@@ -48,7 +132,7 @@ pub struct BlockInfo {
     /// Block Height
     pub height: Option<u32>,
 
-    /// Number of confirmations
+    /// Number of canon blocks on top of the block, which isn't counted itself
     pub confirmations: u32,
 
     /// Block Size
@@ -79,6 +163,53 @@ pub struct BlockInfo {
     pub transactions: Vec<String>,
 }
 
+/// Returned value for the `decoderawblock` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DecodedBlock {
+    /// Block Hash
+    pub hash: String,
+
+    /// Block Size
+    pub size: usize,
+
+    /// Previous block hash
+    pub previous_block_hash: String,
+
+    /// Merkle root representing the transactions in the block
+    pub merkle_root: String,
+
+    /// Merkle root of the transactions in the block using a Pedersen hash
+    pub pedersen_merkle_root_hash: String,
+
+    /// Proof of Succinct Work
+    pub proof: String,
+
+    /// Block time
+    pub time: i64,
+
+    /// Block difficulty target
+    pub difficulty_target: u64,
+
+    /// Nonce
+    pub nonce: u32,
+
+    /// List of transaction summaries
+    pub transactions: Vec<TransactionSummary>,
+}
+
+/// A summary of a transaction contained in a decoded block
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TransactionSummary {
+    /// Transaction id
+    pub txid: String,
+
+    /// Transaction size
+    pub size: usize,
+
+    /// Transaction value balance
+    pub value_balance: i64,
+}
+
 /// Returned value for the `getblocktemplate` rpc call
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct BlockTemplate {
@@ -99,6 +230,15 @@ pub struct BlockTemplate {
 
     /// Amount spendable by the coinbase transaction (block rewards + transaction fees)
     pub coinbase_value: u64,
+
+    /// Total fees paid by the transactions to include in the block
+    pub total_fees: u64,
+
+    /// Total size in bytes of the transactions to include in the block
+    pub total_size: usize,
+
+    /// Number of transactions to include in the block (excluding the coinbase transaction)
+    pub transaction_count: usize,
 }
 
 /// Output for the `createrawtransaction` rpc call
@@ -120,6 +260,32 @@ pub struct DecryptRecordInput {
     pub account_view_key: String,
 }
 
+/// Returned value for the `getmempoolinfo` rpc call
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MempoolInfo {
+    /// The number of transactions in the memory pool
+    pub size: usize,
+
+    /// The total size in bytes of the transactions in the memory pool
+    pub total_size_in_bytes: usize,
+
+    /// The total size in bytes of the transactions in each fee rate bucket
+    pub fee_rate_histogram: Vec<FeeRateBucket>,
+
+    /// The minimum fee per byte a transaction currently needs to pay to enter the memory pool
+    pub min_fee_per_byte: u64,
+}
+
+/// A single bucket of the memory pool fee rate histogram
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeeRateBucket {
+    /// The lowest fee per byte of the transactions in the bucket
+    pub fee_rate: f64,
+
+    /// The total size in bytes of the transactions in the bucket
+    pub size_in_bytes: usize,
+}
+
 /// Returned value for the `getnodeinfo` rpc call
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct NodeInfo {
@@ -135,6 +301,9 @@ pub struct NodeInfo {
     /// Flag indicating if the node is currently syncing
     pub is_syncing: bool,
 
+    /// Flag indicating if the node's storage is in degraded read-only mode
+    pub is_storage_degraded: bool,
+
     /// The timestamp of when the node was launched.
     pub launched: DateTime<Utc>,
 
@@ -142,6 +311,89 @@ pub struct NodeInfo {
     pub version: String,
 }
 
+/// Returned value for the `getnetworkinfo` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NetworkInfo {
+    /// The addresses the node listens at, starting with its local address.
+    pub listen_addresses: Vec<SocketAddr>,
+
+    /// The address whose port the node advertises to its peers, if it's listening.
+    pub advertised_address: Option<SocketAddr>,
+
+    /// Flag indicating if the node has accepted any inbound connections
+    pub is_reachable: bool,
+
+    /// The version of the network protocol the node uses
+    pub protocol_version: u64,
+}
+
+/// Returned value for the `getconsensusparams` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ConsensusParams {
+    /// The id of the network the node is a part of
+    pub network_id: u8,
+
+    /// The maximum permitted block size in bytes
+    pub max_block_size: usize,
+
+    /// The maximum permitted nonce value
+    pub max_nonce: u32,
+
+    /// The anticipated number of seconds for finding a new block
+    pub target_block_time: i64,
+
+    /// The number of seconds a block timestamp is allowed to be ahead of the node's clock
+    pub max_future_block_time_drift: i64,
+
+    /// The reward for mining a block before the first halving
+    pub initial_block_reward: i64,
+
+    /// The number of blocks after which the block reward halves
+    pub block_reward_halving_interval: u32,
+
+    /// The maximum number of times the block reward halves
+    pub max_block_reward_halvings: u32,
+}
+
+/// A transaction that wasn't admitted into the memory pool, returned by the `getrejectedtransactions` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RejectedTransaction {
+    /// Transaction id
+    pub txid: String,
+
+    /// The reason the transaction was rejected
+    pub reason: String,
+}
+
+/// Returned value for the `getspendstatus` rpc call
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpendStatus {
+    /// Flag indicating if the serial number is in the ledger
+    pub spent: bool,
+
+    /// Flag indicating if the serial number is spent by a memory pool transaction
+    pub pending: bool,
+}
+
+/// Returned value for the `getsyncstatus` rpc call
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyncStatus {
+    /// Flag indicating if the node is currently syncing
+    pub is_syncing: bool,
+
+    /// The current block height of the node
+    pub current_block_height: u32,
+
+    /// The highest block height advertised by a connected peer
+    pub best_peer_block_height: u32,
+
+    /// The number of blocks the node is behind the best peer
+    pub blocks_remaining: u32,
+
+    /// The average number of blocks per second imported in the current sync round
+    pub blocks_per_second: f64,
+}
+
 /// Returned value for the `getnodestats` rpc call
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct NodeStats {
@@ -238,6 +490,8 @@ pub struct NodeHandshakeStats {
 pub struct NodeQueueStats {
     /// The number of messages queued in the common inbound channel.
     pub inbound: u64,
+    /// The maximum number of messages that can be queued in the common inbound channel.
+    pub inbound_capacity: u64,
     /// The number of messages queued in the individual outbound channels.
     pub outbound: u64,
 }
@@ -254,6 +508,25 @@ pub struct NodeMiscStats {
     pub duplicate_sync_blocks: u64,
     /// The number of RPC requests received.
     pub rpc_requests: u64,
+    /// The number of submitted transactions that were already in the memory pool.
+    pub duplicate_transactions: u64,
+}
+
+/// The unit of the round-trip times returned by the `getpeerinfo` rpc call
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RttUnit {
+    /// Milliseconds, denoted by `"ms"`
+    #[serde(rename = "ms")]
+    Milliseconds,
+    /// Microseconds, denoted by `"us"`
+    #[serde(rename = "us")]
+    Microseconds,
+}
+
+impl Default for RttUnit {
+    fn default() -> Self {
+        Self::Milliseconds
+    }
 }
 
 /// Returned value for the `getpeerinfo` rpc call
@@ -261,6 +534,10 @@ pub struct NodeMiscStats {
 pub struct PeerInfo {
     /// The peers connected to this node
     pub peers: Vec<SocketAddr>,
+    /// The round-trip times to the peers, in the same order as the peers
+    pub rtts: Vec<u64>,
+    /// The unit of the round-trip times
+    pub rtt_unit: RttUnit,
 }
 
 /// Record payload data
@@ -356,6 +633,10 @@ pub struct TransactionInfo {
 
     /// Block the transaction lives in
     pub transaction_metadata: TransactionMetadata,
+
+    /// Number of blocks confirming the transaction, counting the block containing it, or 0 if it is not in the
+    /// canonical chain; note that `BlockInfo::confirmations` doesn't count the block itself
+    pub confirmations: u32,
 }
 
 /// Input for the `createrawtransaction` rpc call
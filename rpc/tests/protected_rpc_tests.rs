@@ -16,7 +16,11 @@
 
 /// Tests for protected RPC endpoints
 mod protected_rpc_tests {
-    use snarkos_consensus::{Consensus, MerkleTreeLedger};
+    use snarkos_consensus::{
+        memory_pool::{Entry, PriorityClass, TxOrigin},
+        Consensus,
+        MerkleTreeLedger,
+    };
     use snarkos_network::Node;
     use snarkos_rpc::*;
     use snarkos_storage::LedgerStorage;
@@ -35,6 +39,7 @@ mod protected_rpc_tests {
         AccountPrivateKey,
         AccountViewKey,
         RecordScheme,
+        TransactionScheme,
     };
     use snarkvm_utilities::{
         bytes::{FromBytes, ToBytes},
@@ -43,7 +48,7 @@ mod protected_rpc_tests {
 
     use jsonrpc_core::MetaIoHandler;
     use serde_json::Value;
-    use std::{str::FromStr, sync::Arc, time::Duration};
+    use std::{net::SocketAddr, str::FromStr, sync::Arc, time::Duration};
 
     const TEST_USERNAME: &str = "TEST_USERNAME";
     const TEST_PASSWORD: &str = "TEST_PASSWORD";
@@ -73,6 +78,14 @@ mod protected_rpc_tests {
     async fn initialize_test_rpc(
         ledger: Arc<MerkleTreeLedger<LedgerStorage>>,
     ) -> (MetaIoHandler<Meta>, Arc<Consensus<LedgerStorage>>) {
+        let (io, consensus, _node) = initialize_test_rpc_with_node(ledger).await;
+
+        (io, consensus)
+    }
+
+    async fn initialize_test_rpc_with_node(
+        ledger: Arc<MerkleTreeLedger<LedgerStorage>>,
+    ) -> (MetaIoHandler<Meta>, Arc<Consensus<LedgerStorage>>, Node<LedgerStorage>) {
         let credentials = RpcCredentials {
             username: TEST_USERNAME.to_string(),
             password: TEST_PASSWORD.to_string(),
@@ -92,12 +105,13 @@ mod protected_rpc_tests {
 
         node.set_sync(node_consensus);
 
-        let rpc_impl = RpcImpl::new(ledger, Some(credentials), node);
+        let rpc_impl = RpcImpl::new(ledger, Some(credentials), node.clone(), 0);
         let mut io = jsonrpc_core::MetaIoHandler::default();
 
         rpc_impl.add_protected(&mut io);
+        io.extend_with(rpc_impl.to_delegate());
 
-        (io, consensus)
+        (io, consensus, node)
     }
 
     #[tokio::test]
@@ -116,6 +130,72 @@ mod protected_rpc_tests {
         assert_eq!(extracted["error"]["message"], expected_result);
     }
 
+    #[tokio::test]
+    async fn test_rpc_get_and_clear_orphan_txs() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let meta = authentication();
+        let (rpc, consensus) = initialize_test_rpc(storage).await;
+
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let transaction_id = hex::encode(transaction.transaction_id().unwrap());
+        consensus
+            .memory_pool
+            .lock()
+            .insert_orphan(Entry {
+                size_in_bytes: TRANSACTION_2.len(),
+                transaction,
+                origin: TxOrigin::Local,
+                priority_class: PriorityClass::Standard,
+                valid_from_height: None,
+            })
+            .unwrap();
+
+        let get_request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"getorphantxs\" }";
+        let response = rpc.handle_request_sync(get_request, meta.clone()).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(extracted["result"], Value::Array(vec![Value::String(transaction_id)]));
+
+        let clear_request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"clearorphantxs\" }";
+        let response = rpc.handle_request_sync(clear_request, meta.clone()).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(extracted["result"], 1);
+
+        let response = rpc.handle_request_sync(get_request, meta).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(extracted["result"], Value::Array(vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_sync_now() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let meta = authentication();
+        let (rpc, _consensus, node) = initialize_test_rpc_with_node(storage).await;
+
+        let request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"syncnow\" }";
+
+        // There's no peer to sync with
+        let response = rpc.handle_request_sync(request, meta.clone()).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(extracted["result"], false);
+        assert!(!node.is_syncing_blocks());
+
+        // A connected peer is ahead of the node
+        let peer_address: SocketAddr = "127.0.0.1:4031".parse().unwrap();
+        node.peer_book.set_connected(peer_address, None);
+        node.peer_book.received_ping(peer_address, 10, 0);
+
+        let response = rpc.handle_request_sync(request, meta.clone()).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(extracted["result"], true);
+        assert!(node.is_syncing_blocks());
+        assert_eq!(node.expect_sync().sync_peer(), Some(peer_address));
+
+        // Another round isn't started while the current one is in progress
+        let response = rpc.handle_request_sync(request, meta).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(extracted["result"], false);
+    }
+
     #[tokio::test]
     async fn test_rpc_fetch_record_commitment_count() {
         let storage = Arc::new(FIXTURE_VK.ledger());
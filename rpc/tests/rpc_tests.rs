@@ -16,15 +16,26 @@
 
 /// Tests for public RPC endpoints
 mod rpc_tests {
-    use snarkos_consensus::{get_block_reward, MerkleTreeLedger};
+    use snarkos_consensus::{
+        error::ConsensusError,
+        get_block_reward,
+        memory_pool::{Entry, PriorityClass, TxOrigin},
+        MerkleTreeLedger,
+    };
     use snarkos_network::Node;
-    use snarkos_rpc::*;
+    use snarkos_rpc::{error::RpcError, *};
     use snarkos_storage::LedgerStorage;
     use snarkos_testing::{
-        network::{test_config, ConsensusSetup, TestSetup},
+        network::{handshaken_peer, random_bound_address, test_config, ConsensusSetup, TestSetup},
         sync::*,
+        wait_until,
+    };
+    use snarkvm_dpc::{
+        testnet1::{instantiated::Tx, transaction::amount::AleoAmount},
+        Block,
+        TransactionScheme,
+        Transactions,
     };
-    use snarkvm_dpc::{testnet1::instantiated::Tx, TransactionScheme};
     use snarkvm_utilities::{
         bytes::{FromBytes, ToBytes},
         serialize::CanonicalSerialize,
@@ -33,9 +44,22 @@ mod rpc_tests {
 
     use jsonrpc_test::Rpc;
     use serde_json::Value;
-    use std::{net::SocketAddr, sync::Arc, time::Duration};
+    use std::{
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
 
     async fn initialize_test_rpc(ledger: Arc<MerkleTreeLedger<LedgerStorage>>) -> Rpc {
+        let node = initialize_test_node(ledger.clone()).await;
+
+        Rpc::new(RpcImpl::new(ledger, None, node, 0).to_delegate())
+    }
+
+    async fn initialize_test_node(ledger: Arc<MerkleTreeLedger<LedgerStorage>>) -> Node<LedgerStorage> {
         let environment = test_config(TestSetup::default());
         let mut node = Node::new(environment).await.unwrap();
         let consensus_setup = ConsensusSetup::default();
@@ -49,7 +73,7 @@ mod rpc_tests {
         );
         node.set_sync(node_consensus);
 
-        Rpc::new(RpcImpl::new(ledger, None, node).to_delegate())
+        node
     }
 
     fn verify_transaction_info(transaction_bytes: Vec<u8>, transaction_info: Value) {
@@ -150,6 +174,43 @@ mod rpc_tests {
         assert_eq!(genesis_block.header.nonce, block_response["nonce"]);
     }
 
+    #[tokio::test]
+    async fn test_rpc_decode_raw_block() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let rpc = initialize_test_rpc(storage).await;
+
+        let genesis_block = genesis();
+        let block_bytes = genesis_block.serialize().unwrap();
+
+        let response = rpc.request("decoderawblock", &[hex::encode(&block_bytes)]);
+        let decoded: Value = serde_json::from_str(&response).unwrap();
+
+        let response = rpc.request("getblock", &[hex::encode(GENESIS_BLOCK_HEADER_HASH.to_vec())]);
+        let block_response: Value = serde_json::from_str(&response).unwrap();
+
+        for field in &[
+            "hash",
+            "previous_block_hash",
+            "merkle_root",
+            "pedersen_merkle_root_hash",
+            "proof",
+            "time",
+            "difficulty_target",
+            "nonce",
+        ] {
+            assert_eq!(decoded[field], block_response[field]);
+        }
+        assert_eq!(decoded["size"], block_bytes.len());
+
+        let txids: Vec<Value> = decoded["transactions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|summary| summary["txid"].clone())
+            .collect();
+        assert_eq!(&txids, block_response["transactions"].as_array().unwrap());
+    }
+
     #[tokio::test]
     async fn test_rpc_get_block_count() {
         let storage = Arc::new(FIXTURE_VK.ledger());
@@ -162,6 +223,32 @@ mod rpc_tests {
         assert_eq!(result.as_u64().unwrap(), 1u64);
     }
 
+    #[tokio::test]
+    async fn test_rpc_chain_queries_require_peers() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let node = initialize_test_node(storage.clone()).await;
+        let rpc_impl = RpcImpl::new(storage, None, node.clone(), 1);
+
+        // An isolated node that hasn't synced yet doesn't serve chain queries.
+        assert!(matches!(
+            rpc_impl.get_block_count(),
+            Err(RpcError::InsufficientPeers(0, 1))
+        ));
+        assert!(matches!(
+            rpc_impl.get_best_block_hash(),
+            Err(RpcError::InsufficientPeers(0, 1))
+        ));
+
+        // Once it's connected to enough peers, it does.
+        node.peer_book.set_connected("127.0.0.1:4131".parse().unwrap(), None);
+
+        assert_eq!(rpc_impl.get_block_count().unwrap(), 1);
+        assert_eq!(
+            rpc_impl.get_best_block_hash().unwrap(),
+            hex::encode(GENESIS_BLOCK_HEADER_HASH.to_vec())
+        );
+    }
+
     #[tokio::test]
     async fn test_rpc_get_best_block_hash() {
         let storage = Arc::new(FIXTURE_VK.ledger());
@@ -177,6 +264,39 @@ mod rpc_tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_rpc_get_best_chain_info() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+
+        // Seed the chain with blocks with known timestamps
+        let timestamps = [50, 20, 90, 10, 70, 30, 110, 40, 80, 60, 100, 0];
+        let mut previous_block = genesis();
+        for time in timestamps.iter() {
+            let mut block = Block {
+                header: previous_block.header.clone(),
+                transactions: Transactions::new(),
+            };
+            block.header.previous_block_hash = previous_block.header.get_hash();
+            block.header.time = *time;
+            storage.insert_and_commit(&block).unwrap();
+            previous_block = block;
+        }
+
+        let rpc = initialize_test_rpc(storage).await;
+
+        let result = make_request_no_params(&rpc, "getbestchaininfo".to_string());
+
+        let best_chain_info: BestChainInfo = serde_json::from_value(result).unwrap();
+
+        assert_eq!(
+            best_chain_info.best_block_hash,
+            hex::encode(previous_block.header.get_hash().0)
+        );
+        assert_eq!(best_chain_info.height, timestamps.len() as u32);
+        // The median of the latest 11 timestamps, i.e. all but the first one
+        assert_eq!(best_chain_info.median_time_past, 60);
+    }
+
     #[tokio::test]
     async fn test_rpc_get_block_hash() {
         let storage = Arc::new(FIXTURE_VK.ledger());
@@ -204,6 +324,58 @@ mod rpc_tests {
         ]);
     }
 
+    #[tokio::test]
+    async fn test_rpc_get_raw_transactions() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let node = initialize_test_node(storage.clone()).await;
+        let rpc_impl = RpcImpl::new(storage.clone(), None, node.clone(), 0);
+        let rpc = Rpc::new(rpc_impl.clone().to_delegate());
+
+        // One transaction is in the ledger, another one in the memory pool
+        let genesis_block = genesis();
+        let ledger_transaction = &genesis_block.transactions.0[0];
+        let ledger_transaction_id = hex::encode(ledger_transaction.transaction_id().unwrap());
+
+        let pooled_transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let pooled_transaction_id = hex::encode(pooled_transaction.transaction_id().unwrap());
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: pooled_transaction,
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        node.expect_sync().memory_pool().lock().insert(&storage, entry).unwrap();
+
+        let unknown_transaction_id = hex::encode([0u8; 32]);
+
+        let response = rpc.request("getrawtransactions", &[vec![
+            ledger_transaction_id.clone(),
+            pooled_transaction_id.clone(),
+            unknown_transaction_id.clone(),
+        ]]);
+        let raw_transactions: std::collections::BTreeMap<String, Option<String>> =
+            serde_json::from_str(&response).unwrap();
+
+        assert_eq!(raw_transactions.len(), 3);
+        assert_eq!(
+            raw_transactions[&ledger_transaction_id],
+            Some(hex::encode(to_bytes![ledger_transaction].unwrap()))
+        );
+        assert_eq!(
+            raw_transactions[&pooled_transaction_id],
+            Some(hex::encode(&TRANSACTION_2[..]))
+        );
+        assert_eq!(raw_transactions[&unknown_transaction_id], None);
+
+        // The number of transactions requested at once is bounded
+        let too_many_ids = vec![unknown_transaction_id; MAX_RAW_TRANSACTIONS_PER_REQUEST + 1];
+        assert!(matches!(
+            rpc_impl.get_raw_transactions(too_many_ids),
+            Err(RpcError::TooManyTransactionIds(_, MAX_RAW_TRANSACTIONS_PER_REQUEST))
+        ));
+    }
+
     #[tokio::test]
     async fn test_rpc_get_transaction_info() {
         let storage = Arc::new(FIXTURE_VK.ledger());
@@ -221,6 +393,34 @@ mod rpc_tests {
         verify_transaction_info(to_bytes![transaction].unwrap(), transaction_info);
     }
 
+    #[tokio::test]
+    async fn test_rpc_get_transaction_info_confirmations() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2 = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+        storage.insert_and_commit(&block_1).unwrap();
+        storage.insert_and_commit(&block_2).unwrap();
+        let rpc = initialize_test_rpc(storage).await;
+
+        let genesis_block = genesis();
+        let transaction = &genesis_block.transactions.0[0];
+
+        let response = rpc.request("gettransactioninfo", &[hex::encode(
+            transaction.transaction_id().unwrap(),
+        )]);
+
+        let transaction_info: Value = serde_json::from_str(&response).unwrap();
+
+        // The genesis block and the two blocks on top of it
+        assert_eq!(transaction_info["confirmations"], 3);
+
+        // A transaction in the latest block has a single confirmation
+        let response = rpc.request("decoderawtransaction", &[hex::encode(TRANSACTION_2.to_vec())]);
+        let transaction_info: Value = serde_json::from_str(&response).unwrap();
+
+        assert_eq!(transaction_info["confirmations"], 1);
+    }
+
     #[tokio::test]
     async fn test_rpc_decode_raw_transaction() {
         let storage = Arc::new(FIXTURE_VK.ledger());
@@ -230,7 +430,10 @@ mod rpc_tests {
 
         let transaction_info: Value = serde_json::from_str(&response).unwrap();
 
-        verify_transaction_info(TRANSACTION_1.to_vec(), transaction_info);
+        verify_transaction_info(TRANSACTION_1.to_vec(), transaction_info.clone());
+
+        // The transaction isn't part of the canonical chain yet
+        assert_eq!(transaction_info["confirmations"], 0);
     }
 
     #[tokio::test]
@@ -246,6 +449,22 @@ mod rpc_tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_rpc_send_raw_transaction_internal_double_spend() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let node = initialize_test_node(storage.clone()).await;
+        let rpc_impl = RpcImpl::new(storage, None, node, 0);
+
+        // A transaction spending its first serial number twice is rejected with a distinct error.
+        let mut transaction = Tx::read(&TRANSACTION_1[..]).unwrap();
+        transaction.old_serial_numbers[1] = transaction.old_serial_numbers[0];
+
+        assert!(matches!(
+            rpc_impl.send_raw_transaction(hex::encode(to_bytes![transaction].unwrap())),
+            Err(RpcError::ConsensusError(ConsensusError::InternalDoubleSpend(..)))
+        ));
+    }
+
     #[tokio::test]
     async fn test_rpc_validate_transaction() {
         let storage = Arc::new(FIXTURE_VK.ledger());
@@ -285,6 +504,34 @@ mod rpc_tests {
         assert_eq!(peer_info.peers, expected_peers);
     }
 
+    #[tokio::test]
+    async fn test_rpc_get_peer_info_rtt_units() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let node = initialize_test_node(storage.clone()).await;
+        let rpc = Rpc::new(RpcImpl::new(storage, None, node.clone(), 0).to_delegate());
+
+        let peer_address: SocketAddr = "127.0.0.1:4031".parse().unwrap();
+        node.peer_book.set_connected(peer_address, None);
+        let quality = node.peer_book.get_peer(peer_address, true).unwrap().quality;
+        quality.rtt_us.store(12_345, Ordering::SeqCst);
+
+        // The round-trip times are reported in milliseconds by default.
+        let result = make_request_no_params(&rpc, "getpeerinfo".to_string());
+        let peer_info: PeerInfo = serde_json::from_value(result).unwrap();
+        assert_eq!(peer_info.peers, vec![peer_address]);
+        assert_eq!(peer_info.rtts, vec![12]);
+        assert_eq!(peer_info.rtt_unit, RttUnit::Milliseconds);
+
+        let response = rpc.request("getpeerinfo", &["ms"]);
+        let peer_info: PeerInfo = serde_json::from_str(&response).unwrap();
+        assert_eq!(peer_info.rtts, vec![12]);
+
+        let response = rpc.request("getpeerinfo", &["us"]);
+        let peer_info: PeerInfo = serde_json::from_str(&response).unwrap();
+        assert_eq!(peer_info.rtts, vec![12_345]);
+        assert_eq!(peer_info.rtt_unit, RttUnit::Microseconds);
+    }
+
     #[tokio::test]
     async fn test_rpc_get_node_info() {
         let storage = Arc::new(FIXTURE_VK.ledger());
@@ -298,6 +545,212 @@ mod rpc_tests {
 
         assert_eq!(peer_info.is_miner, false);
         assert_eq!(peer_info.is_syncing, false);
+        assert_eq!(peer_info.is_storage_degraded, false);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_network_info() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let (listen_address, listener) = random_bound_address().await;
+        drop(listener);
+
+        let setup = TestSetup {
+            socket_address: listen_address,
+            ..Default::default()
+        };
+        let node = Node::new(test_config(setup)).await.unwrap();
+        node.listen().await.unwrap();
+        let rpc = Rpc::new(RpcImpl::new(storage, None, node.clone(), 0).to_delegate());
+
+        let result = make_request_no_params(&rpc, "getnetworkinfo".to_string());
+        let network_info: NetworkInfo = serde_json::from_value(result).unwrap();
+
+        assert_eq!(network_info.listen_addresses, vec![listen_address]);
+        assert_eq!(network_info.advertised_address, Some(listen_address));
+        assert!(!network_info.is_reachable);
+        assert_eq!(network_info.protocol_version, snarkos_network::PROTOCOL_VERSION);
+
+        // The node is reachable once it accepts an inbound connection
+        let _peer = handshaken_peer(listen_address).await;
+        wait_until!(5, node.has_accepted_inbound_connection());
+
+        let result = make_request_no_params(&rpc, "getnetworkinfo".to_string());
+        let network_info: NetworkInfo = serde_json::from_value(result).unwrap();
+        assert!(network_info.is_reachable);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_mempool_info() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let node = initialize_test_node(storage.clone()).await;
+        let rpc = Rpc::new(RpcImpl::new(storage.clone(), None, node.clone(), 0).to_delegate());
+
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        node.expect_sync().memory_pool().lock().insert(&storage, entry).unwrap();
+
+        let result = make_request_no_params(&rpc, "getmempoolinfo".to_string());
+
+        let mempool_info: MempoolInfo = serde_json::from_value(result).unwrap();
+
+        assert_eq!(mempool_info.size, 1);
+        assert_eq!(mempool_info.total_size_in_bytes, TRANSACTION_2.len());
+
+        let histogram_size: usize = mempool_info
+            .fee_rate_histogram
+            .iter()
+            .map(|bucket| bucket.size_in_bytes)
+            .sum();
+        assert_eq!(histogram_size, mempool_info.total_size_in_bytes);
+        assert_eq!(mempool_info.min_fee_per_byte, 0);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_spend_status() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let genesis_block = storage.get_block_from_block_number(0).unwrap();
+        let node = initialize_test_node(storage.clone()).await;
+        let rpc = Rpc::new(RpcImpl::new(storage.clone(), None, node.clone(), 0).to_delegate());
+
+        let get_spend_status = |sn: &<Tx as TransactionScheme>::SerialNumber| {
+            let mut sn_bytes = vec![];
+            CanonicalSerialize::serialize(sn, &mut sn_bytes).unwrap();
+
+            let response = rpc.request("getspendstatus", &[hex::encode(sn_bytes)]);
+            serde_json::from_str::<SpendStatus>(&response).unwrap()
+        };
+
+        // The serial numbers of the genesis transactions are spent.
+        let genesis_sn = &genesis_block.transactions.0[0].old_serial_numbers()[0];
+        assert_eq!(get_spend_status(genesis_sn), SpendStatus {
+            spent: true,
+            pending: false
+        });
+
+        // The serial numbers of a memory pool transaction are pending.
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let pooled_sn = transaction.old_serial_numbers()[0];
+        assert_eq!(get_spend_status(&pooled_sn), SpendStatus {
+            spent: false,
+            pending: false
+        });
+
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction,
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        node.expect_sync().memory_pool().lock().insert(&storage, entry).unwrap();
+
+        assert_eq!(get_spend_status(&pooled_sn), SpendStatus {
+            spent: false,
+            pending: true
+        });
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_sync_status() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let node = initialize_test_node(storage.clone()).await;
+        let rpc = Rpc::new(RpcImpl::new(storage, None, node.clone(), 0).to_delegate());
+
+        for (port, block_height) in [(4031, 3), (4032, 10), (4033, 6)].iter() {
+            let peer_address: SocketAddr = format!("127.0.0.1:{}", port).parse().unwrap();
+            node.peer_book.set_connected(peer_address, None);
+            node.peer_book.received_ping(peer_address, *block_height, 0);
+        }
+
+        let result = make_request_no_params(&rpc, "getsyncstatus".to_string());
+
+        let sync_status: SyncStatus = serde_json::from_value(result).unwrap();
+
+        assert!(!sync_status.is_syncing);
+        assert_eq!(sync_status.current_block_height, 0);
+        assert_eq!(sync_status.best_peer_block_height, 10);
+        assert_eq!(sync_status.blocks_remaining, 10);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_estimate_fee() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let node = initialize_test_node(storage.clone()).await;
+        let rpc = Rpc::new(RpcImpl::new(storage, None, node.clone(), 0).to_delegate());
+
+        // Without any memory pool transactions or peer hints, no fee is needed.
+        let result = make_request_no_params(&rpc, "estimatefee".to_string());
+        assert_eq!(result.as_u64().unwrap(), 0);
+
+        // With an empty memory pool, the estimate follows the fee rate hints shared by the peers.
+        for (port, min_fee_per_byte) in [(4031, 40), (4032, 60)].iter() {
+            let peer_address: SocketAddr = format!("127.0.0.1:{}", port).parse().unwrap();
+            node.peer_book.set_connected(peer_address, None);
+            node.peer_book.received_ping(peer_address, 0, *min_fee_per_byte);
+        }
+
+        let result = make_request_no_params(&rpc, "estimatefee".to_string());
+        assert_eq!(result.as_u64().unwrap(), 50);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_block_locator() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let genesis_block = genesis();
+
+        // Seed the chain with enough blocks for the locator to skip some of them
+        let mut previous_block = genesis_block.clone();
+        for time in 0..30 {
+            let mut block = Block {
+                header: previous_block.header.clone(),
+                transactions: Transactions::new(),
+            };
+            block.header.previous_block_hash = previous_block.header.get_hash();
+            block.header.time = time;
+            storage.insert_and_commit(&block).unwrap();
+            previous_block = block;
+        }
+
+        let rpc = initialize_test_rpc(storage.clone()).await;
+
+        let result = make_request_no_params(&rpc, "getblocklocator".to_string());
+        let block_locator: Vec<String> = serde_json::from_value(result).unwrap();
+
+        assert_eq!(
+            block_locator.first().unwrap(),
+            &hex::encode(previous_block.header.get_hash().0)
+        );
+        assert_eq!(
+            block_locator.last().unwrap(),
+            &hex::encode(genesis_block.header.get_hash().0)
+        );
+        assert!(block_locator.len() < 31);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_consensus_params() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let genesis_block = storage.get_block_from_block_number(0).unwrap();
+        let rpc = initialize_test_rpc(storage).await;
+
+        let result = make_request_no_params(&rpc, "getconsensusparams".to_string());
+
+        let consensus_params: ConsensusParams = serde_json::from_value(result).unwrap();
+
+        // The network id matches the one of the transactions in the ledger.
+        for transaction in genesis_block.transactions.iter() {
+            assert_eq!(consensus_params.network_id, transaction.network.id());
+        }
+        assert_eq!(consensus_params.initial_block_reward, get_block_reward(0).0);
+        assert_eq!(
+            consensus_params.initial_block_reward / 2,
+            get_block_reward(consensus_params.block_reward_halving_interval).0
+        );
     }
 
     #[tokio::test]
@@ -324,4 +777,126 @@ mod rpc_tests {
         assert_eq!(template.transactions, expected_transactions);
         assert!(template.coinbase_value >= block_reward.0 as u64);
     }
+
+    #[tokio::test]
+    async fn test_rpc_get_rejected_transactions() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let node = initialize_test_node(storage.clone()).await;
+        let rpc = Rpc::new(RpcImpl::new(storage.clone(), None, node.clone(), 0).to_delegate());
+
+        let original = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        let mut double_spend = original.clone();
+        double_spend.transaction.memorandum[..8].copy_from_slice(&1u64.to_le_bytes());
+
+        let mut memory_pool = node.expect_sync().memory_pool().lock();
+        memory_pool.insert(&storage, original).unwrap().unwrap();
+        assert!(memory_pool.insert(&storage, double_spend.clone()).unwrap().is_none());
+        drop(memory_pool);
+
+        let result = make_request_no_params(&rpc, "getrejectedtransactions".to_string());
+
+        let rejected_transactions: Vec<RejectedTransaction> = serde_json::from_value(result).unwrap();
+
+        assert_eq!(rejected_transactions, vec![RejectedTransaction {
+            txid: hex::encode(double_spend.transaction.transaction_id().unwrap()),
+            reason: "conflicting serial number".to_string(),
+        }]);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_block_template_fees() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let node = initialize_test_node(storage.clone()).await;
+        let rpc = Rpc::new(RpcImpl::new(storage.clone(), None, node.clone(), 0).to_delegate());
+
+        let fee = 1_000;
+        let mut transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        transaction.value_balance = AleoAmount(fee);
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction,
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        node.expect_sync().memory_pool().lock().insert(&storage, entry).unwrap();
+
+        let result = make_request_no_params(&rpc, "getblocktemplate".to_string());
+
+        let template: BlockTemplate = serde_json::from_value(result).unwrap();
+
+        let block_reward = get_block_reward(template.block_height);
+
+        assert_eq!(template.transaction_count, 1);
+        assert_eq!(template.transactions.len(), 1);
+        assert_eq!(template.total_fees, fee as u64);
+        assert_eq!(template.total_size, TRANSACTION_2.len());
+        assert_eq!(template.coinbase_value, block_reward.0 as u64 + fee as u64);
+    }
+
+    #[tokio::test]
+    async fn test_slow_request_logging() {
+        let slow_request_threshold = Duration::from_millis(10);
+
+        let slow_handler = async {
+            std::thread::sleep(Duration::from_millis(50));
+            42
+        };
+        let (output, is_slow) = time_request("getblock", slow_request_threshold, slow_handler).await;
+
+        assert_eq!(output, 42);
+        assert!(is_slow);
+
+        let (_, is_slow) = time_request("getblockcount", slow_request_threshold, async {}).await;
+
+        assert!(!is_slow);
+    }
+
+    #[tokio::test]
+    async fn test_expensive_request_concurrency_limit() {
+        let limit = ConcurrencyLimit::new(2, Duration::from_secs(10));
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+
+        let requests = (0..10)
+            .map(|_| {
+                let limit = limit.clone();
+                let running = running.clone();
+                let max_running = max_running.clone();
+
+                tokio::spawn(async move {
+                    limit
+                        .run(async {
+                            let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                            max_running.fetch_max(now_running, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(20)).await;
+                            running.fetch_sub(1, Ordering::SeqCst);
+                        })
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // All the requests are executed eventually, but never more than the limit at once.
+        for request in requests {
+            assert!(request.await.unwrap().is_some());
+        }
+        assert_eq!(max_running.load(Ordering::SeqCst), 2);
+
+        // A request that can't be executed within the queue timeout is rejected.
+        let limit = ConcurrencyLimit::new(1, Duration::from_millis(10));
+        let busy_limit = limit.clone();
+        let busy_request =
+            tokio::spawn(async move { busy_limit.run(tokio::time::sleep(Duration::from_millis(200))).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(limit.run(async {}).await.is_none());
+        assert!(busy_request.await.unwrap().is_some());
+    }
 }
@@ -65,6 +65,11 @@ pub struct JsonRPC {
     pub port: u16,
     pub username: Option<String>,
     pub password: Option<String>,
+    pub slow_request_ms: u64,
+    pub signing_key_file: Option<PathBuf>,
+    pub max_expensive_requests: usize,
+    pub expensive_request_timeout_ms: u64,
+    pub min_chain_query_peers: u16,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -72,9 +77,17 @@ pub struct Node {
     pub dir: PathBuf,
     pub db: String,
     pub is_bootnode: bool,
+    pub no_mempool: bool,
     pub ip: String,
     pub port: u16,
+    pub additional_listen_addresses: Vec<String>,
     pub verbose: u8,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub export_snapshot: Option<String>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub import_snapshot: Option<String>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub import_snapshot_hash: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -93,6 +106,8 @@ pub struct P2P {
     pub peer_sync_interval: u16,
     pub min_peers: u16,
     pub max_peers: u16,
+    pub reserved_new_peer_slots: u16,
+    pub inbound_channel_capacity: usize,
 }
 
 impl Default for Config {
@@ -103,9 +118,14 @@ impl Default for Config {
                 dir: Self::snarkos_dir(),
                 db: "snarkos_testnet1".into(),
                 is_bootnode: false,
+                no_mempool: false,
                 ip: "0.0.0.0".into(),
                 port: 4131,
+                additional_listen_addresses: vec![],
                 verbose: 2,
+                export_snapshot: None,
+                import_snapshot: None,
+                import_snapshot_hash: None,
             },
             miner: Miner {
                 is_miner: false,
@@ -118,6 +138,11 @@ impl Default for Config {
                 // TODO (raychu86) Establish a random username and password for the node operator by default
                 username: Some("Username".into()),
                 password: Some("Password".into()),
+                slow_request_ms: 1000,
+                signing_key_file: None,
+                max_expensive_requests: 4,
+                expensive_request_timeout_ms: 10_000,
+                min_chain_query_peers: 0,
             },
             p2p: P2P {
                 bootnodes: TESTNET_BOOTNODES
@@ -129,6 +154,8 @@ impl Default for Config {
                 block_sync_interval: 4,
                 min_peers: 20,
                 max_peers: 50,
+                reserved_new_peer_slots: 5,
+                inbound_channel_capacity: snarkos_network::INBOUND_CHANNEL_DEPTH,
             },
         }
     }
@@ -200,13 +227,24 @@ impl Config {
             "is-bootnode" => self.is_bootnode(arguments.is_present(option)),
             "is-miner" => self.is_miner(arguments.is_present(option)),
             "no-jsonrpc" => self.no_jsonrpc(arguments.is_present(option)),
+            "no-mempool" => self.no_mempool(arguments.is_present(option)),
             // Options
+            "additional-listen-addresses" => self.additional_listen_addresses(arguments.value_of(option)),
             "connect" => self.connect(arguments.value_of(option)),
+            "export-snapshot" => self.export_snapshot(arguments.value_of(option)),
+            "import-snapshot" => self.import_snapshot(arguments.value_of(option)),
+            "import-snapshot-hash" => self.import_snapshot_hash(arguments.value_of(option)),
             "ip" => self.ip(arguments.value_of(option)),
             "miner-address" => self.miner_address(arguments.value_of(option)),
             "mempool-interval" => self.mempool_interval(clap::value_t!(arguments.value_of(*option), u8).ok()),
             "max-peers" => self.max_peers(clap::value_t!(arguments.value_of(*option), u16).ok()),
+            "reserved-new-peer-slots" => {
+                self.reserved_new_peer_slots(clap::value_t!(arguments.value_of(*option), u16).ok())
+            }
             "min-peers" => self.min_peers(clap::value_t!(arguments.value_of(*option), u16).ok()),
+            "inbound-channel-capacity" => {
+                self.inbound_channel_capacity(clap::value_t!(arguments.value_of(*option), usize).ok())
+            }
             "network" => self.network(clap::value_t!(arguments.value_of(*option), u8).ok()),
             "path" => self.path(arguments.value_of(option)),
             "port" => self.port(clap::value_t!(arguments.value_of(*option), u16).ok()),
@@ -214,6 +252,17 @@ impl Config {
             "rpc-port" => self.rpc_port(clap::value_t!(arguments.value_of(*option), u16).ok()),
             "rpc-username" => self.rpc_username(arguments.value_of(option)),
             "rpc-password" => self.rpc_password(arguments.value_of(option)),
+            "rpc-slow-request-ms" => self.rpc_slow_request_ms(clap::value_t!(arguments.value_of(*option), u64).ok()),
+            "rpc-signing-key-file" => self.rpc_signing_key_file(arguments.value_of(option)),
+            "rpc-max-expensive-requests" => {
+                self.rpc_max_expensive_requests(clap::value_t!(arguments.value_of(*option), usize).ok())
+            }
+            "rpc-expensive-request-timeout-ms" => {
+                self.rpc_expensive_request_timeout_ms(clap::value_t!(arguments.value_of(*option), u64).ok())
+            }
+            "rpc-min-chain-query-peers" => {
+                self.rpc_min_chain_query_peers(clap::value_t!(arguments.value_of(*option), u16).ok())
+            }
             "verbose" => self.verbose(clap::value_t!(arguments.value_of(*option), u8).ok()),
             _ => (),
         });
@@ -249,6 +298,10 @@ impl Config {
         self.rpc.json_rpc = !argument;
     }
 
+    fn no_mempool(&mut self, argument: bool) {
+        self.node.no_mempool = argument;
+    }
+
     fn is_bootnode(&mut self, argument: bool) {
         self.node.is_bootnode = argument;
     }
@@ -275,6 +328,14 @@ impl Config {
         }
     }
 
+    fn additional_listen_addresses(&mut self, argument: Option<&str>) {
+        if let Some(addresses) = argument {
+            let sanitize_addresses = addresses.replace(&['[', ']', ' '][..], "");
+            let addresses: Vec<String> = sanitize_addresses.split(',').map(|s| s.to_string()).collect();
+            self.node.additional_listen_addresses = addresses;
+        }
+    }
+
     fn connect(&mut self, argument: Option<&str>) {
         if let Some(bootnodes) = argument {
             let sanitize_bootnodes = bootnodes.replace(&['[', ']', ' '][..], "");
@@ -283,6 +344,24 @@ impl Config {
         }
     }
 
+    fn export_snapshot(&mut self, argument: Option<&str>) {
+        if let Some(path) = argument {
+            self.node.export_snapshot = Some(path.into());
+        }
+    }
+
+    fn import_snapshot(&mut self, argument: Option<&str>) {
+        if let Some(path) = argument {
+            self.node.import_snapshot = Some(path.into());
+        }
+    }
+
+    fn import_snapshot_hash(&mut self, argument: Option<&str>) {
+        if let Some(hash) = argument {
+            self.node.import_snapshot_hash = Some(hash.into());
+        }
+    }
+
     fn miner_address(&mut self, argument: Option<&str>) {
         if let Some(miner_address) = argument {
             self.miner.miner_address = miner_address.to_string();
@@ -307,6 +386,18 @@ impl Config {
         }
     }
 
+    fn reserved_new_peer_slots(&mut self, argument: Option<u16>) {
+        if let Some(reserved_new_peer_slots) = argument {
+            self.p2p.reserved_new_peer_slots = reserved_new_peer_slots;
+        }
+    }
+
+    fn inbound_channel_capacity(&mut self, argument: Option<usize>) {
+        if let Some(inbound_channel_capacity) = argument {
+            self.p2p.inbound_channel_capacity = inbound_channel_capacity;
+        }
+    }
+
     fn rpc_ip(&mut self, argument: Option<&str>) {
         if let Some(ip) = argument {
             self.rpc.ip = ip.to_string();
@@ -331,6 +422,36 @@ impl Config {
         }
     }
 
+    fn rpc_slow_request_ms(&mut self, argument: Option<u64>) {
+        if let Some(slow_request_ms) = argument {
+            self.rpc.slow_request_ms = slow_request_ms;
+        }
+    }
+
+    fn rpc_signing_key_file(&mut self, argument: Option<&str>) {
+        if let Some(signing_key_file) = argument {
+            self.rpc.signing_key_file = Some(PathBuf::from(signing_key_file));
+        }
+    }
+
+    fn rpc_max_expensive_requests(&mut self, argument: Option<usize>) {
+        if let Some(max_expensive_requests) = argument {
+            self.rpc.max_expensive_requests = max_expensive_requests;
+        }
+    }
+
+    fn rpc_expensive_request_timeout_ms(&mut self, argument: Option<u64>) {
+        if let Some(expensive_request_timeout_ms) = argument {
+            self.rpc.expensive_request_timeout_ms = expensive_request_timeout_ms;
+        }
+    }
+
+    fn rpc_min_chain_query_peers(&mut self, argument: Option<u16>) {
+        if let Some(min_chain_query_peers) = argument {
+            self.rpc.min_chain_query_peers = min_chain_query_peers;
+        }
+    }
+
     fn verbose(&mut self, argument: Option<u8>) {
         if let Some(verbose) = argument {
             self.node.verbose = verbose
@@ -352,6 +473,11 @@ impl Config {
             return Err(CliError::MinerBootstrapper);
         }
 
+        // A miner needs a memory pool to select the transactions of its blocks from.
+        if self.node.no_mempool && self.miner.is_miner {
+            return Err(CliError::MinerWithoutMemoryPool);
+        }
+
         // TODO (howardwu): Check the memory pool interval.
 
         Ok(())
@@ -365,22 +491,33 @@ impl CLI for ConfigCli {
     type Config = Config;
 
     const ABOUT: AboutType = "Run an Aleo node (include -h for more options)";
-    const FLAGS: &'static [FlagType] = &[flag::NO_JSONRPC, flag::IS_BOOTNODE, flag::IS_MINER];
+    const FLAGS: &'static [FlagType] = &[flag::NO_JSONRPC, flag::NO_MEMPOOL, flag::IS_BOOTNODE, flag::IS_MINER];
     const NAME: NameType = "snarkOS";
     const OPTIONS: &'static [OptionType] = &[
         option::IP,
         option::PORT,
+        option::ADDITIONAL_LISTEN_ADDRESSES,
         option::PATH,
         option::CONNECT,
+        option::EXPORT_SNAPSHOT,
+        option::IMPORT_SNAPSHOT,
+        option::IMPORT_SNAPSHOT_HASH,
         option::MINER_ADDRESS,
         option::MEMPOOL_INTERVAL,
         option::MIN_PEERS,
         option::MAX_PEERS,
+        option::RESERVED_NEW_PEER_SLOTS,
+        option::INBOUND_CHANNEL_CAPACITY,
         option::NETWORK,
         option::RPC_IP,
         option::RPC_PORT,
         option::RPC_USERNAME,
         option::RPC_PASSWORD,
+        option::RPC_SLOW_REQUEST_MS,
+        option::RPC_SIGNING_KEY_FILE,
+        option::RPC_MAX_EXPENSIVE_REQUESTS,
+        option::RPC_EXPENSIVE_REQUEST_TIMEOUT_MS,
+        option::RPC_MIN_CHAIN_QUERY_PEERS,
         option::VERBOSE,
     ];
     const SUBCOMMANDS: &'static [SubCommandType] = &[subcommand::UPDATE];
@@ -391,20 +528,32 @@ impl CLI for ConfigCli {
         config.parse(arguments, &[
             "network",
             "no-jsonrpc",
+            "no-mempool",
             "is-bootnode",
             "is-miner",
             "ip",
             "port",
+            "additional-listen-addresses",
             "path",
             "connect",
+            "export-snapshot",
+            "import-snapshot",
+            "import-snapshot-hash",
             "miner-address",
             "mempool-interval",
             "min-peers",
             "max-peers",
+            "reserved-new-peer-slots",
+            "inbound-channel-capacity",
             "rpc-ip",
             "rpc-port",
             "rpc-username",
             "rpc-password",
+            "rpc-slow-request-ms",
+            "rpc-signing-key-file",
+            "rpc-max-expensive-requests",
+            "rpc-expensive-request-timeout-ms",
+            "rpc-min-chain-query-peers",
             "verbose",
         ]);
 
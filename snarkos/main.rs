@@ -23,14 +23,15 @@ use snarkos::{
     display::render_welcome,
     errors::NodeError,
 };
-use snarkos_consensus::{Consensus, ConsensusParameters, MemoryPool, MerkleTreeLedger};
+use snarkos_consensus::{Consensus, ConsensusParameters, MemoryPool, MerkleTreeLedger, TWO_HOURS_UNIX};
 use snarkos_network::{config::Config as NodeConfig, MinerInstance, Node, Sync};
-use snarkos_rpc::start_rpc_server;
+use snarkos_rpc::{start_rpc_server, ConcurrencyLimit};
 use snarkos_storage::LedgerStorage;
 use snarkvm_algorithms::{CRH, SNARK};
 use snarkvm_dpc::{
     testnet1::{instantiated::Components, parameters::PublicParameters, BaseDPCComponents},
     AccountAddress,
+    BlockHeaderHash,
     Network,
     Storage,
 };
@@ -88,6 +89,12 @@ async fn start_server(config: Config) -> anyhow::Result<()> {
 
     let address = format!("{}:{}", config.node.ip, config.node.port);
     let desired_address = address.parse::<SocketAddr>()?;
+    let additional_listen_addresses = config
+        .node
+        .additional_listen_addresses
+        .iter()
+        .map(|address| address.parse::<SocketAddr>())
+        .collect::<Result<Vec<_>, _>>()?;
 
     let mut path = config.node.dir;
     path.push(&config.node.db);
@@ -100,7 +107,10 @@ async fn start_server(config: Config) -> anyhow::Result<()> {
         config.node.is_bootnode,
         // Set sync intervals for peers, blocks and transactions (memory pool).
         Duration::from_secs(config.p2p.peer_sync_interval.into()),
-    )?;
+    )?
+    .with_additional_listen_addresses(additional_listen_addresses)
+    .with_reserved_new_peer_slots(config.p2p.reserved_new_peer_slots)
+    .with_inbound_channel_capacity(config.p2p.inbound_channel_capacity);
 
     // Construct the node instance. Note this does not start the network services.
     // This is done early on, so that the local address can be discovered
@@ -117,9 +127,40 @@ async fn start_server(config: Config) -> anyhow::Result<()> {
         Arc::new(MerkleTreeLedger::<LedgerStorage>::open_at_path(path.clone())?)
     };
 
+    if let Some(snapshot_path) = &config.node.import_snapshot {
+        // The snapshot's blocks aren't verified, so its tip has to match a hash obtained independently.
+        let expected_tip_hash = config
+            .node
+            .import_snapshot_hash
+            .as_ref()
+            .and_then(|hash| hex::decode(hash).ok())
+            .filter(|hash| hash.len() == 32)
+            .ok_or_else(|| NodeError::Message("a valid --import-snapshot-hash is required to import a snapshot".into()))?;
+        let expected_tip_hash = BlockHeaderHash::new(expected_tip_hash);
+
+        info!("Importing a ledger snapshot from {}", snapshot_path);
+        storage.import_snapshot(&std::fs::read(snapshot_path)?, &expected_tip_hash)?;
+        info!("Imported a ledger snapshot at block {}", storage.get_current_block_height());
+    }
+
+    if let Some(snapshot_path) = &config.node.export_snapshot {
+        std::fs::write(snapshot_path, storage.export_snapshot()?)?;
+        info!(
+            "Exported a ledger snapshot at block {} to {}",
+            storage.get_current_block_height(),
+            snapshot_path
+        );
+        return Ok(());
+    }
+
     // Enable the sync layer.
     {
-        let memory_pool = Mutex::new(MemoryPool::from_storage(&storage)?);
+        let memory_pool = if config.node.no_mempool {
+            info!("Running without a memory pool");
+            Mutex::new(MemoryPool::new().with_disabled(true))
+        } else {
+            Mutex::new(MemoryPool::from_storage(&storage)?)
+        };
 
         debug!("Loading Aleo parameters...");
         let dpc_parameters = PublicParameters::<Components>::load(!config.miner.is_miner)?;
@@ -140,6 +181,7 @@ async fn start_server(config: Config) -> anyhow::Result<()> {
             max_block_size: 1_000_000_000usize,
             max_nonce: u32::max_value(),
             target_block_time: 10i64,
+            max_future_block_time_drift: TWO_HOURS_UNIX,
             network_id: Network::from_network_id(config.aleo.network_id),
             verifier: PoswMarlin::verify_only().expect("could not instantiate PoSW verifier"),
             authorized_inner_snark_ids,
@@ -188,6 +230,13 @@ async fn start_server(config: Config) -> anyhow::Result<()> {
             node.clone(),
             config.rpc.username,
             config.rpc.password,
+            Duration::from_millis(config.rpc.slow_request_ms),
+            config.rpc.signing_key_file,
+            ConcurrencyLimit::new(
+                config.rpc.max_expensive_requests,
+                Duration::from_millis(config.rpc.expensive_request_timeout_ms),
+            ),
+            config.rpc.min_chain_query_peers,
         );
         node.register_task(rpc_handle);
 
@@ -211,7 +260,10 @@ async fn start_server(config: Config) -> anyhow::Result<()> {
         }
     }
 
-    std::future::pending::<()>().await;
+    // Allow the block sync in progress to finish before exiting.
+    tokio::signal::ctrl_c().await?;
+    info!("Shutting down...");
+    node.shut_down_gracefully().await;
 
     Ok(())
 }
@@ -23,4 +23,7 @@ pub const IS_BOOTNODE: &str =
 
 pub const IS_MINER: &str = "[is-miner] --is-miner 'Start mining blocks from this node'";
 
+pub const NO_MEMPOOL: &str =
+    "[no-mempool] --no-mempool 'Run the node without a memory pool, only validating and relaying blocks'";
+
 pub const LIST: &str = "[list] -l --list 'List all available releases of snarkOS'";
@@ -37,6 +37,13 @@ pub const PORT: OptionType = (
     &[],
 );
 
+pub const ADDITIONAL_LISTEN_ADDRESSES: OptionType = (
+    "[additional-listen-addresses] --additional-listen-addresses=[addresses] 'Specify one or more addresses, e.g. of other network interfaces, the node accepts peers on'",
+    &[],
+    &[],
+    &[],
+);
+
 pub const CONNECT: OptionType = (
     "[connect] --connect=[ip] 'Specify one or more node ip addresses to connect to on startup'",
     &[],
@@ -44,6 +51,27 @@ pub const CONNECT: OptionType = (
     &[],
 );
 
+pub const EXPORT_SNAPSHOT: OptionType = (
+    "[export-snapshot] --export-snapshot=[path] 'Export a snapshot of the ledger to the specified file and exit'",
+    &["import-snapshot"],
+    &[],
+    &[],
+);
+
+pub const IMPORT_SNAPSHOT: OptionType = (
+    "[import-snapshot] --import-snapshot=[path] 'Import a ledger snapshot from the specified file into a fresh ledger on startup'",
+    &["export-snapshot"],
+    &[],
+    &["import-snapshot-hash"],
+);
+
+pub const IMPORT_SNAPSHOT_HASH: OptionType = (
+    "[import-snapshot-hash] --import-snapshot-hash=[hash] 'Specify the hex-encoded hash of the tip block of the imported ledger snapshot, obtained from a trusted source'",
+    &["export-snapshot"],
+    &[],
+    &["import-snapshot"],
+);
+
 pub const MINER_ADDRESS: OptionType = (
     "[miner-address] --miner-address=[miner-address] 'Specify the address that will receive miner rewards'",
     &[],
@@ -72,6 +100,20 @@ pub const MAX_PEERS: OptionType = (
     &[],
 );
 
+pub const RESERVED_NEW_PEER_SLOTS: OptionType = (
+    "[reserved-new-peer-slots] --reserved-new-peer-slots=[reserved-new-peer-slots] 'Specify the number of connection slots reserved for peers the node has never connected to'",
+    &[],
+    &[],
+    &[],
+);
+
+pub const INBOUND_CHANNEL_CAPACITY: OptionType = (
+    "[inbound-channel-capacity] --inbound-channel-capacity=[inbound-channel-capacity] 'Specify the maximum number of inbound messages the node can queue for processing'",
+    &[],
+    &[],
+    &[],
+);
+
 pub const NETWORK: OptionType = (
     "[network] --network=[network-id] 'Specify the network id (default = 1) of the node'",
     &[],
@@ -107,6 +149,41 @@ pub const RPC_PASSWORD: OptionType = (
     &["rpc-username"],
 );
 
+pub const RPC_SLOW_REQUEST_MS: OptionType = (
+    "[rpc-slow-request-ms] --rpc-slow-request-ms=[rpc-slow-request-ms] 'Specify the duration in milliseconds after which an rpc request is logged as slow'",
+    &["no-jsonrpc"],
+    &[],
+    &[],
+);
+
+pub const RPC_SIGNING_KEY_FILE: OptionType = (
+    "[rpc-signing-key-file] --rpc-signing-key-file=[rpc-signing-key-file] 'Specify the path to a file containing the private key used to sign successful rpc responses'",
+    &["no-jsonrpc"],
+    &[],
+    &[],
+);
+
+pub const RPC_MAX_EXPENSIVE_REQUESTS: OptionType = (
+    "[rpc-max-expensive-requests] --rpc-max-expensive-requests=[rpc-max-expensive-requests] 'Specify the maximum number of expensive rpc requests executed concurrently'",
+    &["no-jsonrpc"],
+    &[],
+    &[],
+);
+
+pub const RPC_EXPENSIVE_REQUEST_TIMEOUT_MS: OptionType = (
+    "[rpc-expensive-request-timeout-ms] --rpc-expensive-request-timeout-ms=[rpc-expensive-request-timeout-ms] 'Specify the duration in milliseconds an expensive rpc request can be queued for before it is rejected'",
+    &["no-jsonrpc"],
+    &[],
+    &[],
+);
+
+pub const RPC_MIN_CHAIN_QUERY_PEERS: OptionType = (
+    "[rpc-min-chain-query-peers] --rpc-min-chain-query-peers=[rpc-min-chain-query-peers] 'Specify the number of connected peers required to serve chain queries over rpc before the first block sync'",
+    &["no-jsonrpc"],
+    &[],
+    &[],
+);
+
 pub const VERBOSE: OptionType = (
     "[verbose] --verbose=[verbose] 'Specify the verbosity (default = 1) of the node'",
     &[],
@@ -21,24 +21,38 @@ use snarkvm_dpc::{errors::StorageError, Block, DatabaseTransaction, LedgerScheme
 use snarkvm_parameters::{traits::genesis::Genesis, LedgerMerkleTreeParameters, Parameter};
 use snarkvm_utilities::bytes::FromBytes;
 
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use std::{
-    fs,
+    fs, io,
     marker::PhantomData,
     path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicU32, Ordering},
+        atomic::{AtomicBool, AtomicU32, Ordering},
         Arc,
     },
+    time::{Duration, Instant},
 };
+use tracing::*;
 
 pub type BlockHeight = u32;
 
+/// The minimum time between two writes probing whether a degraded storage accepts writes again.
+pub const DEGRADED_STORAGE_PROBE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The `StorageError::Crate` source of the write errors that mean the storage can't accept any writes until the
+/// node's environment changes, i.e. that its filesystem is read-only or out of space.
+pub const UNWRITABLE_STORAGE: &str = "unwritable storage";
+
 pub struct Ledger<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> {
     pub current_block_height: AtomicU32,
     pub ledger_parameters: Arc<P>,
     pub cm_merkle_tree: RwLock<MerkleTree<P>>,
     pub storage: S,
+    /// Set once a write to the storage fails because it can't accept writes anymore; the ledger keeps serving
+    /// reads, but rejects writes until a probe write succeeds.
+    pub degraded: AtomicBool,
+    /// The time of the last write probing whether the degraded storage accepts writes again.
+    pub last_write_probe: Mutex<Option<Instant>>,
     pub _transaction: PhantomData<T>,
 }
 
@@ -79,6 +93,74 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
         self.get_latest_block().is_err()
     }
 
+    /// Returns true if the ledger is in the degraded read-only mode after a storage write failure.
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::SeqCst)
+    }
+
+    /// Writes the given database transaction to the storage. If the write fails because the filesystem is
+    /// read-only or out of space, the ledger enters the degraded read-only mode, in which all subsequent writes
+    /// are rejected until a probe write, attempted at most once per `DEGRADED_STORAGE_PROBE_INTERVAL`, succeeds;
+    /// other write errors are only returned.
+    pub(crate) fn batch(&self, transaction: DatabaseTransaction) -> Result<(), StorageError> {
+        if self.is_degraded() && !self.probe_degraded_storage() {
+            return Err(StorageError::Message(
+                "the storage is in degraded read-only mode; writes are disabled".into(),
+            ));
+        }
+
+        self.storage.batch(transaction).map_err(|error| {
+            if is_storage_unwritable(&error) && !self.degraded.swap(true, Ordering::SeqCst) {
+                *self.last_write_probe.lock() = Some(Instant::now());
+                error!(
+                    "Failed to write to the storage ({}); switching to degraded read-only mode",
+                    error
+                );
+            }
+            error
+        })
+    }
+
+    /// Probes the degraded storage with a write unless it was probed less than `DEGRADED_STORAGE_PROBE_INTERVAL`
+    /// ago, so that the writes attempted in the degraded read-only mode don't all hit the storage. Returns true if
+    /// the ledger accepts writes.
+    fn probe_degraded_storage(&self) -> bool {
+        {
+            let mut last_write_probe = self.last_write_probe.lock();
+            if matches!(*last_write_probe, Some(probed_at) if probed_at.elapsed() < DEGRADED_STORAGE_PROBE_INTERVAL) {
+                return false;
+            }
+            *last_write_probe = Some(Instant::now());
+        }
+
+        self.try_leave_degraded_mode()
+    }
+
+    /// Probes the storage with a write if the ledger is in the degraded read-only mode, and leaves that mode
+    /// if it succeeds. Returns true if the ledger accepts writes.
+    pub fn try_leave_degraded_mode(&self) -> bool {
+        if !self.is_degraded() {
+            return true;
+        }
+
+        let op = Op::Insert {
+            col: COL_META,
+            key: KEY_WRITE_PROBE.as_bytes().to_vec(),
+            value: vec![],
+        };
+        match self.storage.batch(DatabaseTransaction(vec![op])) {
+            Ok(()) => {
+                self.degraded.store(false, Ordering::SeqCst);
+                info!("The storage accepts writes again; leaving degraded read-only mode");
+                true
+            }
+            Err(error) => {
+                debug!("The storage still doesn't accept writes: {}", error);
+                false
+            }
+        }
+    }
+
     /// Get the latest block height of the chain.
     pub fn get_current_block_height(&self) -> BlockHeight {
         self.current_block_height.load(Ordering::SeqCst)
@@ -111,7 +193,7 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
             key: KEY_PEER_BOOK.as_bytes().to_vec(),
             value: peers_serialized,
         };
-        self.storage.batch(DatabaseTransaction(vec![op]))
+        self.batch(DatabaseTransaction(vec![op]))
     }
 
     /// Returns a `Ledger` with the latest state loaded from storage at a given path as
@@ -163,6 +245,8 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
                     storage,
                     cm_merkle_tree: RwLock::new(merkle_tree),
                     ledger_parameters,
+                    degraded: AtomicBool::new(false),
+                    last_write_probe: Default::default(),
                     _transaction: PhantomData,
                 })
             }
@@ -211,3 +295,136 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
         Ok(())
     }
 }
+
+/// Converts the I/O error of a failed storage write into a `StorageError`, classifying it as an unwritable storage
+/// error if the filesystem is read-only or out of space.
+pub fn storage_write_error(error: io::Error) -> StorageError {
+    if is_filesystem_unwritable(&error) {
+        StorageError::Crate(UNWRITABLE_STORAGE, error.to_string())
+    } else {
+        error.into()
+    }
+}
+
+/// Returns true if the given I/O error means that the filesystem is read-only or out of space.
+pub fn is_filesystem_unwritable(error: &io::Error) -> bool {
+    matches!(error.kind(), io::ErrorKind::ReadOnlyFilesystem | io::ErrorKind::StorageFull)
+}
+
+/// Returns true if the given write error means that the storage can't accept any writes until the node's
+/// environment changes, i.e. that its filesystem is read-only or out of space.
+pub fn is_storage_unwritable(error: &StorageError) -> bool {
+    matches!(error, StorageError::Crate(source, _) if *source == UNWRITABLE_STORAGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::MemDb;
+    use snarkvm_dpc::testnet1::instantiated::{CommitmentMerkleParameters, Tx};
+
+    /// An in-memory storage whose writes can be made to fail with a given error on demand.
+    struct FailingStorage {
+        inner: MemDb,
+        write_error: RwLock<Option<io::ErrorKind>>,
+    }
+
+    impl Storage for FailingStorage {
+        const IN_MEMORY: bool = true;
+
+        fn open(path: Option<&Path>, secondary_path: Option<&Path>) -> Result<Self, StorageError> {
+            Ok(Self {
+                inner: MemDb::open(path, secondary_path)?,
+                write_error: RwLock::new(None),
+            })
+        }
+
+        fn get(&self, col: u32, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+            self.inner.get(col, key)
+        }
+
+        #[allow(clippy::type_complexity)]
+        fn get_col(&self, col: u32) -> Result<Vec<(Box<[u8]>, Box<[u8]>)>, StorageError> {
+            self.inner.get_col(col)
+        }
+
+        fn get_keys(&self, col: u32) -> Result<Vec<Box<[u8]>>, StorageError> {
+            self.inner.get_keys(col)
+        }
+
+        fn put<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, col: u32, key: K, value: V) -> Result<(), StorageError> {
+            self.inner.put(col, key, value)
+        }
+
+        fn batch(&self, transaction: DatabaseTransaction) -> Result<(), StorageError> {
+            if let Some(kind) = *self.write_error.read() {
+                return Err(storage_write_error(kind.into()));
+            }
+            self.inner.batch(transaction)
+        }
+
+        fn exists(&self, col: u32, key: &[u8]) -> bool {
+            self.inner.exists(col, key)
+        }
+
+        fn try_catch_up_with_primary(&self) -> Result<(), StorageError> {
+            self.inner.try_catch_up_with_primary()
+        }
+    }
+
+    #[test]
+    fn degraded_mode_on_write_failure() {
+        let ledger = Ledger::<Tx, CommitmentMerkleParameters, FailingStorage>::new_empty(None::<&Path>).unwrap();
+        assert!(!ledger.is_degraded());
+
+        // A successful write doesn't affect the mode.
+        ledger.store_to_memory_pool(vec![0u8; 4]).unwrap();
+        assert!(!ledger.is_degraded());
+
+        // A write error that doesn't mean the storage is unwritable doesn't affect the mode either.
+        *ledger.storage.write_error.write() = Some(io::ErrorKind::InvalidData);
+        assert!(ledger.store_to_memory_pool(vec![1u8; 4]).is_err());
+        assert!(!ledger.is_degraded());
+
+        // Simulate the storage becoming read-only.
+        *ledger.storage.write_error.write() = Some(io::ErrorKind::ReadOnlyFilesystem);
+        assert!(ledger.store_to_memory_pool(vec![1u8; 4]).is_err());
+        assert!(ledger.is_degraded());
+
+        // Reads keep working.
+        assert_eq!(ledger.get_block_count(), 1);
+        assert!(ledger.get_latest_block().is_ok());
+        assert_eq!(ledger.get_memory_pool().unwrap(), Some(vec![0u8; 4]));
+
+        // The ledger stays degraded while the storage is unwritable.
+        assert!(!ledger.try_leave_degraded_mode());
+        assert!(ledger.is_degraded());
+
+        // Writes are rejected even once the storage recovers...
+        *ledger.storage.write_error.write() = None;
+        match ledger.save_peer_book_to_storage(vec![2u8; 4]) {
+            Err(StorageError::Message(msg)) => assert!(msg.contains("degraded")),
+            other => panic!("expected a degraded mode error, got {:?}", other),
+        }
+        assert_eq!(ledger.get_peer_book().unwrap(), None);
+
+        // ...until the probe interval elapses, after which the next write probes the storage and goes through.
+        *ledger.last_write_probe.lock() = None;
+        ledger.save_peer_book_to_storage(vec![2u8; 4]).unwrap();
+        assert!(!ledger.is_degraded());
+        assert_eq!(ledger.get_peer_book().unwrap(), Some(vec![2u8; 4]));
+
+        // An explicit probe leaves the degraded mode regardless of the probe interval.
+        *ledger.storage.write_error.write() = Some(io::ErrorKind::ReadOnlyFilesystem);
+        assert!(ledger.store_to_memory_pool(vec![3u8; 4]).is_err());
+        assert!(ledger.is_degraded());
+        *ledger.storage.write_error.write() = None;
+        assert!(ledger.try_leave_degraded_mode());
+        assert!(!ledger.is_degraded());
+
+        // Running out of space degrades the ledger as well.
+        *ledger.storage.write_error.write() = Some(io::ErrorKind::StorageFull);
+        assert!(ledger.store_to_memory_pool(vec![4u8; 4]).is_err());
+        assert!(ledger.is_degraded());
+    }
+}
@@ -152,7 +152,7 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
             }
         }
 
-        self.storage.batch(database_transaction)
+        self.batch(database_transaction)
     }
 
     /// De-commit the latest block and return its header hash.
@@ -234,7 +234,7 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
             key: block_hash.0.to_vec(),
         });
 
-        self.storage.batch(database_transaction)?;
+        self.batch(database_transaction)?;
 
         self.current_block_height.fetch_sub(1, Ordering::SeqCst);
 
@@ -19,6 +19,9 @@ use snarkvm_algorithms::traits::LoadableMerkleParameters;
 use snarkvm_dpc::{errors::StorageError, Block, BlockHeader, BlockHeaderHash, Storage, TransactionScheme};
 use snarkvm_utilities::FromBytes;
 
+/// The number of the latest canon blocks whose median timestamp is the median time past.
+pub const MEDIAN_TIME_PAST_BLOCKS: u32 = 11;
+
 impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P, S> {
     /// Returns true if the block for the given block header hash exists.
     pub fn block_hash_exists(&self, block_hash: &BlockHeaderHash) -> bool {
@@ -37,6 +40,22 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
         }
     }
 
+    /// Returns the median time past, i.e. the median timestamp of the latest `MEDIAN_TIME_PAST_BLOCKS`
+    /// canon blocks, or of all of them if the chain is shorter.
+    pub fn get_median_time_past(&self) -> Result<i64, StorageError> {
+        let current_block_height = self.get_current_block_height();
+        let first_block_height = current_block_height.saturating_sub(MEDIAN_TIME_PAST_BLOCKS - 1);
+
+        let mut timestamps = Vec::with_capacity(MEDIAN_TIME_PAST_BLOCKS as usize);
+        for block_height in first_block_height..=current_block_height {
+            let block_hash = self.get_block_hash(block_height)?;
+            timestamps.push(self.get_block_header(&block_hash)?.time);
+        }
+        timestamps.sort_unstable();
+
+        Ok(timestamps[timestamps.len() / 2])
+    }
+
     /// Returns true if the block corresponding to this block's previous_block_hash exists.
     pub fn previous_block_hash_exists(&self, block: &Block<T>) -> bool {
         self.block_hash_exists(&block.header.previous_block_hash)
@@ -78,16 +97,15 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
 
             // Check whether it is appropriate to terminate
             if index < step {
-                // If the genesis block has not already been include, add it to the final output
-                if index != 1 {
-                    block_locator_hashes.push(self.get_block_hash(0)?);
-                }
                 break;
             }
 
             index -= step;
         }
 
+        // The genesis block is never reached by the loop, so it's always added to the final output
+        block_locator_hashes.push(self.get_block_hash(0)?);
+
         Ok(block_locator_hashes)
     }
 }
@@ -104,22 +104,27 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
         }
     }
 
-    /// Build a new commitment merkle tree from the stored commitments
-    pub fn rebuild_merkle_tree(&self, additional_cms: Vec<(T::Commitment, usize)>) -> Result<(), StorageError> {
-        let mut new_cm_and_indices = additional_cms;
-
-        let mut old_cm_and_indices = vec![];
+    /// Get the stored commitments, ordered by their index
+    pub fn get_commitments(&self) -> Result<Vec<T::Commitment>, StorageError> {
+        let mut cm_and_indices = vec![];
         for (commitment_key, index_value) in self.storage.get_col(COL_COMMITMENT)? {
             let commitment: T::Commitment = FromBytes::read(&commitment_key[..])?;
             let index = bytes_to_u32(&index_value) as usize;
 
-            old_cm_and_indices.push((commitment, index));
+            cm_and_indices.push((commitment, index));
         }
 
-        old_cm_and_indices.sort_by(|&(_, i), &(_, j)| i.cmp(&j));
+        cm_and_indices.sort_by(|&(_, i), &(_, j)| i.cmp(&j));
+
+        Ok(cm_and_indices.into_iter().map(|(cm, _)| cm).collect())
+    }
+
+    /// Build a new commitment merkle tree from the stored commitments
+    pub fn rebuild_merkle_tree(&self, additional_cms: Vec<(T::Commitment, usize)>) -> Result<(), StorageError> {
+        let mut new_cm_and_indices = additional_cms;
         new_cm_and_indices.sort_by(|&(_, i), &(_, j)| i.cmp(&j));
 
-        let old_commitments = old_cm_and_indices.into_iter().map(|(cm, _)| cm);
+        let old_commitments = self.get_commitments()?.into_iter();
         let new_commitments = new_cm_and_indices.into_iter().map(|(cm, _)| cm).collect::<Vec<_>>();
 
         let new_tree = {
@@ -150,6 +155,6 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
             value: to_bytes![new_digest]?.to_vec(),
         });
 
-        self.storage.batch(database_transaction)
+        self.batch(database_transaction)
     }
 }
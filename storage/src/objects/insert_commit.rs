@@ -104,6 +104,18 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
         }
 
         let mut database_transaction = DatabaseTransaction::new();
+        database_transaction.push_vec(self.insert_ops(block)?);
+
+        self.batch(database_transaction)?;
+
+        Ok(())
+    }
+
+    /// Returns the storage operations inserting a block without canonizing/committing it.
+    pub(crate) fn insert_ops(&self, block: &Block<T>) -> Result<Vec<Op>, StorageError> {
+        let block_hash = block.header.get_hash();
+
+        let mut ops = vec![];
 
         let mut transaction_serial_numbers = Vec::with_capacity(block.transactions.0.len());
         let mut transaction_commitments = Vec::with_capacity(block.transactions.0.len());
@@ -137,19 +149,19 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
                 index: index as u32,
                 block_hash: block.header.get_hash().0,
             };
-            database_transaction.push(Op::Insert {
+            ops.push(Op::Insert {
                 col: COL_TRANSACTION_LOCATION,
                 key: transaction.transaction_id()?.to_vec(),
                 value: to_bytes![transaction_location]?.to_vec(),
             });
         }
 
-        database_transaction.push(Op::Insert {
+        ops.push(Op::Insert {
             col: COL_BLOCK_HEADER,
             key: block_hash.0.to_vec(),
             value: to_bytes![block.header]?.to_vec(),
         });
-        database_transaction.push(Op::Insert {
+        ops.push(Op::Insert {
             col: COL_BLOCK_TRANSACTIONS,
             key: block.header.get_hash().0.to_vec(),
             value: to_bytes![block.transactions]?.to_vec(),
@@ -160,22 +172,20 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
         if !child_hashes.contains(&block_hash) {
             child_hashes.push(block_hash);
 
-            database_transaction.push(Op::Insert {
+            ops.push(Op::Insert {
                 col: COL_CHILD_HASHES,
                 key: block.header.previous_block_hash.0.to_vec(),
                 value: bincode::serialize(&child_hashes)?,
             });
         }
 
-        database_transaction.push(Op::Insert {
+        ops.push(Op::Insert {
             col: COL_BLOCK_TRANSACTIONS,
             key: block.header.get_hash().0.to_vec(),
             value: to_bytes![block.transactions]?.to_vec(),
         });
 
-        self.storage.batch(database_transaction)?;
-
-        Ok(())
+        Ok(ops)
     }
 
     /// Commit/canonize a particular block.
@@ -294,7 +304,7 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
             value: to_bytes![new_digest]?.to_vec(),
         });
 
-        self.storage.batch(database_transaction)?;
+        self.batch(database_transaction)?;
 
         if !is_genesis {
             self.current_block_height.fetch_add(1, Ordering::SeqCst);
@@ -62,6 +62,8 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> LedgerScheme
             storage,
             cm_merkle_tree: RwLock::new(empty_cm_merkle_tree),
             ledger_parameters: parameters,
+            degraded: Default::default(),
+            last_write_probe: Default::default(),
             _transaction: PhantomData,
         };
 
@@ -31,6 +31,6 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
             key: KEY_MEMORY_POOL.as_bytes().to_vec(),
             value: transactions_serialized,
         };
-        self.storage.batch(DatabaseTransaction(vec![op]))
+        self.batch(DatabaseTransaction(vec![op]))
     }
 }
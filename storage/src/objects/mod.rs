@@ -39,5 +39,11 @@ pub use memory_pool::*;
 pub mod records;
 pub use records::*;
 
+pub mod snapshot;
+pub use snapshot::*;
+
+pub mod staged;
+pub use staged::*;
+
 pub mod transaction;
 pub use transaction::*;
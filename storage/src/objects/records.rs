@@ -63,7 +63,7 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
             value: to_bytes![record]?.to_vec(),
         });
 
-        self.storage.batch(database_transaction)
+        self.batch(database_transaction)
     }
 
     /// Get a transaction bytes given the transaction id.
@@ -78,7 +78,7 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
             });
         }
 
-        self.storage.batch(database_transaction)
+        self.batch(database_transaction)
     }
 
     /// Removes a record from storage.
@@ -90,6 +90,6 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
             key: to_bytes![record.commitment()]?.to_vec(),
         });
 
-        self.storage.batch(database_transaction)
+        self.batch(database_transaction)
     }
 }
@@ -0,0 +1,147 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::*;
+use snarkvm_algorithms::traits::LoadableMerkleParameters;
+use snarkvm_dpc::{Block, BlockHeaderHash, Storage, StorageError, TransactionScheme};
+use snarkvm_posw::txids_to_roots;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The version of the ledger snapshot format.
+pub const SNAPSHOT_VERSION: u16 = 2;
+
+/// A versioned and checksummed snapshot of the canon ledger state.
+#[derive(Serialize, Deserialize)]
+struct LedgerSnapshot {
+    /// The version of the snapshot format.
+    version: u16,
+    /// The SHA-256 checksum of the serialized `state`.
+    checksum: Vec<u8>,
+    /// The serialized `LedgerState`.
+    state: Vec<u8>,
+}
+
+/// The canon chain required to resume syncing from its tip.
+#[derive(Serialize, Deserialize)]
+struct LedgerState {
+    /// The hash of the genesis block the chain builds on.
+    genesis_hash: [u8; 32],
+    /// The serialized canon blocks following the genesis block, in ascending order of height.
+    blocks: Vec<Vec<u8>>,
+}
+
+impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P, S> {
+    /// Exports a snapshot of the canon chain, which can be imported into a fresh ledger in order to skip
+    /// downloading and verifying the chain up to the current tip.
+    pub fn export_snapshot(&self) -> Result<Vec<u8>, StorageError> {
+        let tip_height = self.get_current_block_height();
+
+        let mut blocks = Vec::with_capacity(tip_height as usize);
+        for height in 1..=tip_height {
+            blocks.push(self.get_block(&self.get_block_hash(height)?)?.serialize()?);
+        }
+
+        let state = LedgerState {
+            genesis_hash: self.get_block_hash(0)?.0,
+            blocks,
+        };
+        let state = bincode::serialize(&state)?;
+
+        let snapshot = LedgerSnapshot {
+            version: SNAPSHOT_VERSION,
+            checksum: Sha256::digest(&state).to_vec(),
+            state,
+        };
+
+        Ok(bincode::serialize(&snapshot)?)
+    }
+
+    /// Imports a snapshot created with `export_snapshot` into a ledger that only contains the genesis block;
+    /// the blocks following the snapshot's tip can then be synced as usual. The snapshot's blocks aren't
+    /// verified, so its tip must match `expected_tip_hash`, which needs to be obtained from a trusted source;
+    /// the blocks' transactions must match the roots in their headers. Either all the blocks are imported,
+    /// or none of them are.
+    pub fn import_snapshot(&self, snapshot: &[u8], expected_tip_hash: &BlockHeaderHash) -> Result<(), StorageError> {
+        let snapshot: LedgerSnapshot = bincode::deserialize(snapshot)?;
+
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(StorageError::Message(format!(
+                "unsupported ledger snapshot version {} (expected {})",
+                snapshot.version, SNAPSHOT_VERSION
+            )));
+        }
+
+        if Sha256::digest(&snapshot.state)[..] != snapshot.checksum[..] {
+            return Err(StorageError::Message(
+                "the ledger snapshot checksum doesn't match".into(),
+            ));
+        }
+
+        let state: LedgerState = bincode::deserialize(&snapshot.state)?;
+
+        if self.get_current_block_height() != 0 {
+            return Err(StorageError::Message(
+                "a ledger snapshot can only be imported into a fresh ledger".into(),
+            ));
+        }
+
+        if state.genesis_hash != self.get_block_hash(0)?.0 {
+            return Err(StorageError::Message(
+                "the ledger snapshot has a different genesis block".into(),
+            ));
+        }
+
+        // Check that the blocks form a chain from the genesis block to the expected tip, and that their transactions
+        // match their headers, before writing anything; the blocks are staged so that they're committed all at once.
+        let mut staged_ledger = self.stage()?;
+        for block in state.blocks {
+            let block = Block::<T>::deserialize(&block)?;
+            if block.header.previous_block_hash != staged_ledger.latest_block_header().get_hash() {
+                return Err(StorageError::Message(
+                    "the ledger snapshot blocks don't form a chain".into(),
+                ));
+            }
+
+            let transaction_ids = block.transactions.to_transaction_ids()?;
+            let (merkle_root_hash, pedersen_merkle_root_hash, _) = txids_to_roots(&transaction_ids);
+            if block.header.merkle_root_hash != merkle_root_hash
+                || block.header.pedersen_merkle_root_hash != pedersen_merkle_root_hash
+            {
+                return Err(StorageError::Message(format!(
+                    "the transactions of the ledger snapshot block {} don't match its header",
+                    block.header.get_hash()
+                )));
+            }
+
+            staged_ledger.push(&block)?;
+        }
+
+        let tip_hash = staged_ledger.latest_block_header().get_hash();
+        if tip_hash != *expected_tip_hash {
+            return Err(StorageError::Message(format!(
+                "the ledger snapshot tip {} doesn't match the expected block hash {}",
+                tip_hash, expected_tip_hash
+            )));
+        }
+
+        // Committing the staged blocks rebuilds all the ledger state, including the block bodies.
+        staged_ledger.commit()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::*;
+use snarkvm_algorithms::{merkle_tree::*, traits::LoadableMerkleParameters};
+use snarkvm_dpc::{
+    Block,
+    BlockHeader,
+    DatabaseTransaction,
+    LedgerError,
+    LedgerScheme,
+    Op,
+    Storage,
+    StorageError,
+    TransactionScheme,
+};
+use snarkvm_utilities::{bytes::ToBytes, to_bytes};
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::{atomic::Ordering, Arc},
+};
+
+/// The ledger extended by a chain of canon blocks that aren't written to the storage yet. The blocks can be
+/// verified against it one after another, and are then either committed all at once or dropped.
+pub struct StagedLedger<'a, T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> {
+    ledger: &'a Ledger<T, P, S>,
+    /// The height of the ledger the blocks are staged on.
+    base_block_height: BlockHeight,
+    /// The number of staged blocks.
+    block_count: u32,
+    /// The header of the latest staged block, or the ledger's latest one if there are none.
+    latest_block_header: BlockHeader,
+    /// The storage operations inserting and committing the staged blocks.
+    ops: Vec<Op>,
+    serial_numbers: HashSet<Vec<u8>>,
+    commitments: HashMap<Vec<u8>, usize>,
+    memos: HashSet<Vec<u8>>,
+    digests: HashSet<Vec<u8>>,
+    sn_index: usize,
+    cm_index: usize,
+    memo_index: usize,
+    /// All the commitments, including the staged ones, ordered by their index.
+    cm_leaves: Vec<T::Commitment>,
+    /// The commitment Merkle tree including the staged commitments; `None` while it's still the ledger's one.
+    cm_merkle_tree: Option<MerkleTree<P>>,
+}
+
+impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P, S> {
+    /// Returns a view of the ledger on which canon blocks can be staged before committing them at once.
+    pub fn stage(&self) -> Result<StagedLedger<'_, T, P, S>, StorageError> {
+        let base_block_height = self.get_current_block_height();
+
+        Ok(StagedLedger {
+            ledger: self,
+            base_block_height,
+            block_count: 0,
+            latest_block_header: self.get_block_header(&self.get_block_hash(base_block_height)?)?,
+            ops: vec![],
+            serial_numbers: Default::default(),
+            commitments: Default::default(),
+            memos: Default::default(),
+            digests: Default::default(),
+            sn_index: self.current_sn_index()?,
+            cm_index: self.current_cm_index()?,
+            memo_index: self.current_memo_index()?,
+            cm_leaves: self.get_commitments()?,
+            cm_merkle_tree: None,
+        })
+    }
+}
+
+impl<'a, T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> StagedLedger<'a, T, P, S> {
+    /// Returns the header of the latest staged block, or the ledger's latest one if there are none.
+    pub fn latest_block_header(&self) -> &BlockHeader {
+        &self.latest_block_header
+    }
+
+    /// Stages a block extending the latest staged one. The block isn't verified, but it's rejected if it
+    /// conflicts with the ledger or the staged blocks; in that case, the staged ledger remains unchanged.
+    pub fn push(&mut self, block: &Block<T>) -> Result<(), StorageError> {
+        let block_hash = block.header.get_hash();
+
+        if block.header.previous_block_hash != self.latest_block_header.get_hash() {
+            return Err(StorageError::Message(format!(
+                "block {} doesn't extend the staged chain",
+                block_hash
+            )));
+        }
+        if self.ledger.is_canon(&block_hash) {
+            return Err(StorageError::ExistingCanonBlock(block_hash.to_string()));
+        }
+
+        // The block may already be stored, e.g. as part of a side chain.
+        let mut ops = if self.ledger.block_hash_exists(&block_hash) {
+            vec![]
+        } else {
+            self.ledger.insert_ops(block)?
+        };
+
+        let mut sn_index = self.sn_index;
+        let mut cm_index = self.cm_index;
+        let mut memo_index = self.memo_index;
+        let mut serial_numbers = HashSet::new();
+        let mut commitments = HashMap::new();
+        let mut memos = HashSet::new();
+        let mut new_cms = vec![];
+
+        // The ledger's state is checked while committing each transaction, the staged one here.
+        for transaction in block.transactions.0.iter() {
+            let (tx_ops, cms) =
+                self.ledger
+                    .commit_transaction(&mut sn_index, &mut cm_index, &mut memo_index, transaction)?;
+
+            for sn in transaction.old_serial_numbers() {
+                let sn_bytes = to_bytes![sn]?;
+                if self.serial_numbers.contains(&sn_bytes) || !serial_numbers.insert(sn_bytes.clone()) {
+                    return Err(StorageError::ExistingSn(sn_bytes));
+                }
+            }
+            for (cm, index) in &cms {
+                let cm_bytes = to_bytes![cm]?;
+                if self.commitments.contains_key(&cm_bytes) || commitments.insert(cm_bytes.clone(), *index).is_some() {
+                    return Err(StorageError::ExistingCm(cm_bytes));
+                }
+            }
+            let memo_bytes = to_bytes![transaction.memorandum()]?;
+            if self.memos.contains(&memo_bytes) || !memos.insert(memo_bytes.clone()) {
+                return Err(StorageError::ExistingMemo(memo_bytes));
+            }
+
+            ops.extend(tx_ops);
+            new_cms.extend(cms.into_iter().map(|(cm, _)| cm));
+        }
+
+        // Rebuild the commitment Merkle tree with the new commitments.
+        let new_cm_merkle_tree = match &self.cm_merkle_tree {
+            Some(tree) => tree.rebuild(self.cm_leaves.iter().cloned(), &new_cms[..])?,
+            None => self
+                .ledger
+                .cm_merkle_tree
+                .read()
+                .rebuild(self.cm_leaves.iter().cloned(), &new_cms[..])?,
+        };
+        let new_digest = to_bytes![new_cm_merkle_tree.root()]?;
+
+        // Canonize the block.
+        let block_number = self.base_block_height + self.block_count + 1;
+        ops.push(Op::Insert {
+            col: COL_BLOCK_LOCATOR,
+            key: block_hash.0.to_vec(),
+            value: block_number.to_le_bytes().to_vec(),
+        });
+        ops.push(Op::Insert {
+            col: COL_BLOCK_LOCATOR,
+            key: block_number.to_le_bytes().to_vec(),
+            value: block_hash.0.to_vec(),
+        });
+        ops.push(Op::Insert {
+            col: COL_DIGEST,
+            key: new_digest.clone(),
+            value: block_number.to_le_bytes().to_vec(),
+        });
+
+        self.block_count += 1;
+        self.latest_block_header = block.header.clone();
+        self.ops.extend(ops);
+        self.serial_numbers.extend(serial_numbers);
+        self.commitments.extend(commitments);
+        self.memos.extend(memos);
+        self.digests.insert(new_digest);
+        self.sn_index = sn_index;
+        self.cm_index = cm_index;
+        self.memo_index = memo_index;
+        self.cm_leaves.extend(new_cms);
+        self.cm_merkle_tree = Some(new_cm_merkle_tree);
+
+        Ok(())
+    }
+
+    /// Writes all the staged blocks to the storage in a single database transaction.
+    pub fn commit(self) -> Result<(), StorageError> {
+        if self.block_count == 0 {
+            return Ok(());
+        }
+
+        if self.ledger.get_current_block_height() != self.base_block_height {
+            return Err(StorageError::Message(
+                "the canon chain changed while blocks were staged on it".into(),
+            ));
+        }
+
+        let best_block_number = self.base_block_height + self.block_count;
+        let current_digest = match &self.cm_merkle_tree {
+            Some(tree) => to_bytes![tree.root()]?,
+            None => self.ledger.current_digest()?,
+        };
+
+        let mut database_transaction = DatabaseTransaction::new();
+        database_transaction.push_vec(self.ops);
+
+        // Update the database state for current indexes, the best block number and the digest
+
+        database_transaction.push(Op::Insert {
+            col: COL_META,
+            key: KEY_CURR_SN_INDEX.as_bytes().to_vec(),
+            value: (self.sn_index as u32).to_le_bytes().to_vec(),
+        });
+        database_transaction.push(Op::Insert {
+            col: COL_META,
+            key: KEY_CURR_CM_INDEX.as_bytes().to_vec(),
+            value: (self.cm_index as u32).to_le_bytes().to_vec(),
+        });
+        database_transaction.push(Op::Insert {
+            col: COL_META,
+            key: KEY_CURR_MEMO_INDEX.as_bytes().to_vec(),
+            value: (self.memo_index as u32).to_le_bytes().to_vec(),
+        });
+        database_transaction.push(Op::Insert {
+            col: COL_META,
+            key: KEY_BEST_BLOCK_NUMBER.as_bytes().to_vec(),
+            value: best_block_number.to_le_bytes().to_vec(),
+        });
+        database_transaction.push(Op::Insert {
+            col: COL_META,
+            key: KEY_CURR_DIGEST.as_bytes().to_vec(),
+            value: current_digest,
+        });
+
+        self.ledger.batch(database_transaction)?;
+
+        if let Some(tree) = self.cm_merkle_tree {
+            *self.ledger.cm_merkle_tree.write() = tree;
+        }
+        self.ledger
+            .current_block_height
+            .fetch_add(self.block_count, Ordering::SeqCst);
+
+        Ok(())
+    }
+}
+
+impl<'a, T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> LedgerScheme for StagedLedger<'a, T, P, S> {
+    type Block = Block<Self::Transaction>;
+    type Commitment = T::Commitment;
+    type MerkleParameters = P;
+    type MerklePath = MerklePath<Self::MerkleParameters>;
+    type MerkleTreeDigest = MerkleTreeDigest<Self::MerkleParameters>;
+    type SerialNumber = T::SerialNumber;
+    type Transaction = T;
+
+    /// A staged ledger can only be obtained from an existing ledger.
+    fn new(
+        _path: Option<&Path>,
+        _parameters: Arc<Self::MerkleParameters>,
+        _genesis_block: Self::Block,
+    ) -> anyhow::Result<Self> {
+        Err(LedgerError::Message("a staged ledger can only be obtained from an existing ledger".into()).into())
+    }
+
+    /// Returns the number of blocks including the genesis block and the staged ones
+    fn len(&self) -> usize {
+        (self.base_block_height + self.block_count) as usize + 1
+    }
+
+    /// Return the parameters used to construct the ledger Merkle tree.
+    fn parameters(&self) -> &Arc<Self::MerkleParameters> {
+        self.ledger.parameters()
+    }
+
+    /// Return a digest of the latest ledger Merkle tree.
+    fn digest(&self) -> Option<Self::MerkleTreeDigest> {
+        match &self.cm_merkle_tree {
+            Some(tree) => Some(tree.root()),
+            None => self.ledger.digest(),
+        }
+    }
+
+    /// Check that st_{ts} is a valid digest for some (past) ledger state.
+    fn validate_digest(&self, digest: &Self::MerkleTreeDigest) -> bool {
+        self.digests.contains(&to_bytes![digest].unwrap()) || self.ledger.validate_digest(digest)
+    }
+
+    /// Returns true if the given commitment exists in the ledger.
+    fn contains_cm(&self, cm: &Self::Commitment) -> bool {
+        self.commitments.contains_key(&to_bytes![cm].unwrap()) || self.ledger.contains_cm(cm)
+    }
+
+    /// Returns true if the given serial number exists in the ledger.
+    fn contains_sn(&self, sn: &Self::SerialNumber) -> bool {
+        self.serial_numbers.contains(&to_bytes![sn].unwrap()) || self.ledger.contains_sn(sn)
+    }
+
+    /// Returns true if the given memo exists in the ledger.
+    fn contains_memo(&self, memo: &<Self::Transaction as TransactionScheme>::Memorandum) -> bool {
+        self.memos.contains(&to_bytes![memo].unwrap()) || self.ledger.contains_memo(memo)
+    }
+
+    /// Returns the Merkle path to the latest ledger digest
+    /// for a given commitment, if it exists in the ledger.
+    fn prove_cm(&self, cm: &Self::Commitment) -> anyhow::Result<Self::MerklePath> {
+        let tree = match &self.cm_merkle_tree {
+            Some(tree) => tree,
+            None => return self.ledger.prove_cm(cm),
+        };
+
+        let cm_bytes = to_bytes![cm]?;
+        let cm_index = match self.commitments.get(&cm_bytes) {
+            Some(cm_index) => *cm_index,
+            None => self
+                .ledger
+                .get_cm_index(&cm_bytes)?
+                .ok_or(LedgerError::InvalidCmIndex)?,
+        };
+
+        Ok(tree.generate_proof(cm_index, cm)?)
+    }
+
+    /// Returns true if the given Merkle path is a valid witness for
+    /// the given ledger digest and commitment.
+    fn verify_cm(
+        _parameters: &Arc<Self::MerkleParameters>,
+        digest: &Self::MerkleTreeDigest,
+        cm: &Self::Commitment,
+        witness: &Self::MerklePath,
+    ) -> bool {
+        witness.verify(digest, cm).unwrap()
+    }
+}
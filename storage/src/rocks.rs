@@ -14,11 +14,15 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::NUM_COLS;
+use crate::{is_filesystem_unwritable, NUM_COLS, UNWRITABLE_STORAGE};
 use snarkvm_dpc::{errors::StorageError, DatabaseTransaction, Op, Storage};
 
 use rocksdb::{ColumnFamily, ColumnFamilyDescriptor, IteratorMode, Options, WriteBatch, DB};
-use std::path::Path;
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
 
 fn convert_err(err: rocksdb::Error) -> StorageError {
     StorageError::Crate("rocksdb", err.to_string())
@@ -83,7 +87,7 @@ impl Storage for RocksDb {
             };
         }
 
-        self.db().write(batch).map_err(convert_err)?;
+        self.db().write(batch).map_err(|err| self.convert_write_err(err))?;
 
         Ok(())
     }
@@ -123,6 +127,23 @@ impl RocksDb {
         self.db.as_ref().unwrap()
     }
 
+    /// Converts the error of a failed write into a `StorageError`. RocksDB errors only carry a message, so the
+    /// error is classified by writing a file to the database directory: if that fails because the filesystem is
+    /// read-only or out of space, the storage can't accept any writes.
+    fn convert_write_err(&self, err: rocksdb::Error) -> StorageError {
+        let probe_path = self.db().path().join("WRITE_PROBE");
+        let probe = File::create(&probe_path).and_then(|mut file| {
+            file.write_all(&[0])?;
+            file.sync_all()
+        });
+        let _ = fs::remove_file(&probe_path);
+
+        match probe {
+            Err(error) if is_filesystem_unwritable(&error) => StorageError::Crate(UNWRITABLE_STORAGE, err.to_string()),
+            _ => convert_err(err),
+        }
+    }
+
     /// Opens storage from the given path with its given names. If storage does not exists,
     /// it creates a new storage file at the given path with its given names, and opens it.
     /// If RocksDB fails to open, returns [StorageError](snarkvm_errors::storage::StorageError).
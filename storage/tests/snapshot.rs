@@ -0,0 +1,102 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod snapshot {
+    use snarkos_storage::COL_COMMITMENT;
+    use snarkos_testing::sync::*;
+    use snarkvm_dpc::{testnet1::instantiated::Tx, Block, BlockHeaderHash, Storage};
+    use snarkvm_utilities::bytes::FromBytes;
+
+    #[test]
+    fn export_import_snapshot() {
+        let ledger = FIXTURE_VK.ledger();
+        for block in &[&BLOCK_1[..], &BLOCK_2[..]] {
+            ledger.insert_and_commit(&Block::<Tx>::read(*block).unwrap()).unwrap();
+        }
+
+        let snapshot = ledger.export_snapshot().unwrap();
+        let tip_hash = BlockHeaderHash::new(BLOCK_2_HEADER_HASH.to_vec());
+
+        // A corrupted snapshot is rejected
+        let fresh_ledger = FIXTURE_VK.ledger();
+        let mut corrupted_snapshot = snapshot.clone();
+        *corrupted_snapshot.last_mut().unwrap() ^= 1;
+        assert!(fresh_ledger.import_snapshot(&corrupted_snapshot, &tip_hash).is_err());
+        assert_eq!(0, fresh_ledger.get_current_block_height());
+
+        // So is a snapshot whose tip doesn't match the independently known one
+        let block_1_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+        assert!(fresh_ledger.import_snapshot(&snapshot, &block_1_hash).is_err());
+        assert_eq!(0, fresh_ledger.get_current_block_height());
+
+        fresh_ledger.import_snapshot(&snapshot, &tip_hash).unwrap();
+
+        // The tip and the commitment set match
+        assert_eq!(2, fresh_ledger.get_current_block_height());
+        assert_eq!(tip_hash, fresh_ledger.get_latest_block().unwrap().header.get_hash());
+        let mut commitments = ledger.storage.get_col(COL_COMMITMENT).unwrap();
+        let mut imported_commitments = fresh_ledger.storage.get_col(COL_COMMITMENT).unwrap();
+        commitments.sort();
+        imported_commitments.sort();
+        assert_eq!(commitments, imported_commitments);
+        assert_eq!(ledger.current_digest().unwrap(), fresh_ledger.current_digest().unwrap());
+        assert_eq!(
+            ledger.cm_merkle_tree.read().root(),
+            fresh_ledger.cm_merkle_tree.read().root()
+        );
+        assert_eq!(
+            ledger.get_block_locator_hashes().unwrap(),
+            fresh_ledger.get_block_locator_hashes().unwrap()
+        );
+
+        // All the blocks below the tip can be served too
+        let block_1 = fresh_ledger
+            .get_block(&fresh_ledger.get_block_hash(1).unwrap())
+            .unwrap();
+        assert_eq!(block_1_hash, block_1.header.get_hash());
+        assert_eq!(ledger.get_block(&block_1_hash).unwrap(), block_1);
+
+        // A snapshot can only be imported into a fresh ledger
+        assert!(fresh_ledger.import_snapshot(&snapshot, &tip_hash).is_err());
+    }
+
+    #[test]
+    fn forged_snapshot_transactions() {
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2 = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+
+        // A block whose transactions don't match its genuine header
+        let mut forged_block_1 = block_1.clone();
+        forged_block_1.transactions.0.last_mut().unwrap().memorandum[..8].copy_from_slice(&[7u8; 8]);
+        assert_eq!(block_1.header, forged_block_1.header);
+
+        let ledger = FIXTURE_VK.ledger();
+        ledger.insert_and_commit(&forged_block_1).unwrap();
+        ledger.insert_and_commit(&block_2).unwrap();
+        let snapshot = ledger.export_snapshot().unwrap();
+
+        // The snapshot is rejected even though its tip matches, and nothing is imported
+        let fresh_ledger = FIXTURE_VK.ledger();
+        let tip_hash = BlockHeaderHash::new(BLOCK_2_HEADER_HASH.to_vec());
+        assert!(fresh_ledger.import_snapshot(&snapshot, &tip_hash).is_err());
+        assert_eq!(0, fresh_ledger.get_current_block_height());
+        assert!(!fresh_ledger.block_hash_exists(&block_1.header.get_hash()));
+        assert_eq!(
+            FIXTURE_VK.ledger().storage.get_col(COL_COMMITMENT).unwrap().len(),
+            fresh_ledger.storage.get_col(COL_COMMITMENT).unwrap().len()
+        );
+    }
+}
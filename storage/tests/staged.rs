@@ -0,0 +1,95 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod staged {
+    use snarkos_storage::COL_COMMITMENT;
+    use snarkos_testing::sync::*;
+    use snarkvm_dpc::{testnet1::instantiated::Tx, Block, LedgerScheme, Storage, TransactionScheme};
+    use snarkvm_utilities::bytes::FromBytes;
+
+    #[test]
+    fn commit_staged_blocks() {
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2 = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+
+        let ledger = FIXTURE_VK.ledger();
+        ledger.insert_and_commit(&block_1).unwrap();
+        ledger.insert_and_commit(&block_2).unwrap();
+
+        let staging_ledger = FIXTURE_VK.ledger();
+        let mut staged_ledger = staging_ledger.stage().unwrap();
+
+        // A block that doesn't extend the staged chain is rejected
+        assert!(staged_ledger.push(&block_2).is_err());
+
+        staged_ledger.push(&block_1).unwrap();
+
+        // The staged block's state is visible through the staged ledger only
+        let cm = &block_1.transactions.0[0].new_commitments()[0];
+        assert_eq!(2, staged_ledger.len());
+        assert!(staged_ledger.contains_cm(cm));
+        assert!(staged_ledger.validate_digest(&staged_ledger.digest().unwrap()));
+        assert!(!staging_ledger.contains_cm(cm));
+        assert!(!staging_ledger.block_hash_exists(&block_1.header.get_hash()));
+
+        // The same block can't be staged twice
+        assert!(staged_ledger.push(&block_1).is_err());
+
+        staged_ledger.push(&block_2).unwrap();
+        assert_eq!(block_2.header, *staged_ledger.latest_block_header());
+
+        // Dropping the staged ledger leaves the storage untouched
+        drop(staged_ledger);
+        assert_eq!(0, staging_ledger.get_current_block_height());
+
+        let mut staged_ledger = staging_ledger.stage().unwrap();
+        staged_ledger.push(&block_1).unwrap();
+        staged_ledger.push(&block_2).unwrap();
+        staged_ledger.commit().unwrap();
+
+        // The state matches the one of the blocks committed one by one
+        assert_eq!(2, staging_ledger.get_current_block_height());
+        assert_eq!(
+            ledger.get_best_block_number().unwrap(),
+            staging_ledger.get_best_block_number().unwrap()
+        );
+        assert_eq!(
+            ledger.current_digest().unwrap(),
+            staging_ledger.current_digest().unwrap()
+        );
+        assert_eq!(ledger.past_digests().unwrap(), staging_ledger.past_digests().unwrap());
+        assert_eq!(
+            ledger.cm_merkle_tree.read().root(),
+            staging_ledger.cm_merkle_tree.read().root()
+        );
+        assert_eq!(
+            ledger.get_block_locator_hashes().unwrap(),
+            staging_ledger.get_block_locator_hashes().unwrap()
+        );
+        let mut commitments = ledger.storage.get_col(COL_COMMITMENT).unwrap();
+        let mut staged_commitments = staging_ledger.storage.get_col(COL_COMMITMENT).unwrap();
+        commitments.sort();
+        staged_commitments.sort();
+        assert_eq!(commitments, staged_commitments);
+        assert_eq!(block_1, staging_ledger.get_block(&block_1.header.get_hash()).unwrap());
+        assert_eq!(
+            vec![block_2.header.get_hash()],
+            staging_ledger
+                .get_child_block_hashes(&block_1.header.get_hash())
+                .unwrap()
+        );
+    }
+}
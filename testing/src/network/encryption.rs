@@ -69,3 +69,21 @@ async fn encrypt_and_decrypt_small_payloads() {
         }
     }
 }
+
+#[tokio::test]
+async fn coalesce_small_payloads() {
+    let (mut node0, mut node1) = spawn_2_fake_nodes().await;
+
+    // queue several small messages on node0 and write them all at once
+    for height in 0..10u32 {
+        node0.queue_message(&Payload::Ping(height, 0));
+    }
+    node0.flush().await;
+    assert_eq!(node0.write_count(), 1);
+
+    // check if node1 received all of them, in order
+    for height in 0..10u32 {
+        let payload = node1.read_payload().await.unwrap();
+        assert!(matches!(payload, Payload::Ping(h, _) if h == height));
+    }
+}
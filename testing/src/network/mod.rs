@@ -72,6 +72,12 @@ pub struct ConsensusSetup {
     pub is_miner: bool,
     pub block_sync_interval: u64,
     pub tx_sync_interval: u64,
+    pub sync_segment_size: usize,
+    pub max_requested_blocks: u32,
+    pub max_served_blocks_per_second: u32,
+    pub max_served_sync_hashes: u32,
+    pub sync_commit_batch_size: usize,
+    pub rng_seed: Option<u64>,
 }
 
 impl ConsensusSetup {
@@ -80,6 +86,12 @@ impl ConsensusSetup {
             is_miner,
             block_sync_interval,
             tx_sync_interval,
+            sync_segment_size: snarkos_network::SYNC_SEGMENT_SIZE,
+            max_requested_blocks: snarkos_network::MAX_BLOCK_SYNC_COUNT,
+            max_served_blocks_per_second: snarkos_network::MAX_SERVED_BLOCKS_PER_SECOND,
+            max_served_sync_hashes: snarkos_network::MAX_BLOCK_SYNC_COUNT,
+            sync_commit_batch_size: 1,
+            rng_seed: None,
         }
     }
 }
@@ -90,6 +102,12 @@ impl Default for ConsensusSetup {
             is_miner: false,
             block_sync_interval: 600,
             tx_sync_interval: 600,
+            sync_segment_size: snarkos_network::SYNC_SEGMENT_SIZE,
+            max_requested_blocks: snarkos_network::MAX_BLOCK_SYNC_COUNT,
+            max_served_blocks_per_second: snarkos_network::MAX_SERVED_BLOCKS_PER_SECOND,
+            max_served_sync_hashes: snarkos_network::MAX_BLOCK_SYNC_COUNT,
+            sync_commit_batch_size: 1,
+            rng_seed: None,
         }
     }
 }
@@ -98,10 +116,16 @@ impl Default for ConsensusSetup {
 pub struct TestSetup {
     pub node_id: u64,
     pub socket_address: SocketAddr,
+    pub additional_listen_addresses: Vec<SocketAddr>,
     pub consensus_setup: Option<ConsensusSetup>,
     pub peer_sync_interval: u64,
     pub min_peers: u16,
     pub max_peers: u16,
+    pub reserved_new_peer_slots: u16,
+    pub shared_peer_count: usize,
+    pub peer_read_timeout: u64,
+    pub peer_write_timeout: u64,
+    pub relay_queue_depth: usize,
     pub is_bootnode: bool,
     pub bootnodes: Vec<String>,
     pub tokio_handle: Option<runtime::Handle>,
@@ -123,10 +147,16 @@ impl TestSetup {
         Self {
             node_id,
             socket_address,
+            additional_listen_addresses: vec![],
             consensus_setup,
             peer_sync_interval,
             min_peers,
             max_peers,
+            reserved_new_peer_slots: snarkos_network::RESERVED_NEW_PEER_SLOTS,
+            shared_peer_count: snarkos_network::SHARED_PEER_COUNT,
+            peer_read_timeout: snarkos_network::PEER_READ_TIMEOUT_SECS,
+            peer_write_timeout: snarkos_network::PEER_WRITE_TIMEOUT_SECS,
+            relay_queue_depth: snarkos_network::RELAY_QUEUE_DEPTH,
             is_bootnode,
             bootnodes,
             tokio_handle,
@@ -139,10 +169,16 @@ impl Default for TestSetup {
         Self {
             node_id: u64::MAX,
             socket_address: "127.0.0.1:0".parse().unwrap(),
+            additional_listen_addresses: vec![],
             consensus_setup: Some(Default::default()),
             peer_sync_interval: 600,
             min_peers: 1,
             max_peers: 100,
+            reserved_new_peer_slots: snarkos_network::RESERVED_NEW_PEER_SLOTS,
+            shared_peer_count: snarkos_network::SHARED_PEER_COUNT,
+            peer_read_timeout: snarkos_network::PEER_READ_TIMEOUT_SECS,
+            peer_write_timeout: snarkos_network::PEER_WRITE_TIMEOUT_SECS,
+            relay_queue_depth: snarkos_network::RELAY_QUEUE_DEPTH,
             is_bootnode: false,
             bootnodes: vec![],
             tokio_handle: None,
@@ -153,12 +189,22 @@ impl Default for TestSetup {
 pub fn test_consensus(setup: ConsensusSetup) -> Sync<LedgerStorage> {
     let consensus = Arc::new(crate::sync::create_test_consensus());
 
-    Sync::new(
+    let sync = Sync::new(
         consensus,
         setup.is_miner,
         Duration::from_secs(setup.block_sync_interval),
         Duration::from_secs(setup.tx_sync_interval),
     )
+    .with_sync_segment_size(setup.sync_segment_size)
+    .with_max_requested_blocks(setup.max_requested_blocks)
+    .with_max_served_blocks_per_second(setup.max_served_blocks_per_second)
+    .with_max_served_sync_hashes(setup.max_served_sync_hashes)
+    .with_sync_commit_batch_size(setup.sync_commit_batch_size);
+
+    match setup.rng_seed {
+        Some(seed) => sync.with_rng_seed(seed),
+        None => sync,
+    }
 }
 
 /// Returns a `Config` struct based on the given `TestSetup`.
@@ -172,6 +218,14 @@ pub fn test_config(setup: TestSetup) -> Config {
         Duration::from_secs(setup.peer_sync_interval),
     )
     .unwrap()
+    .with_additional_listen_addresses(setup.additional_listen_addresses)
+    .with_reserved_new_peer_slots(setup.reserved_new_peer_slots)
+    .with_shared_peer_count(setup.shared_peer_count)
+    .with_peer_io_timeouts(
+        Duration::from_secs(setup.peer_read_timeout),
+        Duration::from_secs(setup.peer_write_timeout),
+    )
+    .with_relay_queue_depth(setup.relay_queue_depth)
 }
 
 /// Starts a node with the specified bootnodes.
@@ -214,6 +268,10 @@ impl FakeNode {
         Self { reader, writer }
     }
 
+    pub fn addr(&self) -> SocketAddr {
+        self.reader.addr
+    }
+
     pub async fn read_payload(&mut self) -> Result<Payload, NetworkError> {
         let message = match self.reader.read_message().await {
             Ok(msg) => {
@@ -234,6 +292,19 @@ impl FakeNode {
         debug!("wrote a message containing a {} to the stream", payload);
     }
 
+    pub fn queue_message(&mut self, payload: &Payload) {
+        self.writer.queue_message(payload).unwrap();
+        debug!("queued a message containing a {}", payload);
+    }
+
+    pub async fn flush(&mut self) {
+        self.writer.flush().await.unwrap();
+    }
+
+    pub fn write_count(&self) -> u64 {
+        self.writer.write_count()
+    }
+
     pub async fn write_bytes(&mut self, bytes: &[u8]) {
         self.writer.writer.write_all(bytes).await.unwrap();
         debug!("wrote {}B to the stream", bytes.len());
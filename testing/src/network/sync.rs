@@ -17,19 +17,39 @@
 use tokio::time::sleep;
 
 use crate::{
-    network::{handshaken_node_and_peer, test_node, ConsensusSetup, TestSetup},
+    network::{
+        handshaken_node_and_peer,
+        handshaken_peer,
+        test_config,
+        test_consensus,
+        test_node,
+        ConsensusSetup,
+        TestSetup,
+    },
     sync::{BLOCK_1, BLOCK_1_HEADER_HASH, BLOCK_2, BLOCK_2_HEADER_HASH, TRANSACTION_1, TRANSACTION_2},
     wait_until,
 };
 
-use snarkos_consensus::memory_pool::Entry;
-use snarkos_network::message::*;
+use snarkos_consensus::{
+    memory_pool::{Entry, MemoryPool, PriorityClass, TxOrigin},
+    Clock,
+    MockClock,
+};
+use snarkos_network::{message::*, Node};
 
-use snarkvm_dpc::{block_header_hash::BlockHeaderHash, testnet1::instantiated::Tx};
+use snarkvm_dpc::{
+    block_header_hash::BlockHeaderHash,
+    testnet1::{instantiated::Tx, transaction::amount::AleoAmount},
+    Block,
+    TransactionScheme,
+};
 #[cfg(test)]
 use snarkvm_utilities::FromBytes;
 
-use std::time::Duration;
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
 
 #[tokio::test]
 async fn block_initiator_side() {
@@ -51,7 +71,7 @@ async fn block_initiator_side() {
     sleep(Duration::from_secs(1)).await;
 
     // trigger the full node to request synchronization by sending it a higher block_height than it has
-    let ping = Payload::Ping(2u32);
+    let ping = Payload::Ping(2u32, 0);
     peer.write_message(&ping).await;
 
     // read the Pong
@@ -94,6 +114,168 @@ async fn block_initiator_side() {
     wait_until!(1, node.expect_sync().storage().block_hash_exists(&block_2_header_hash));
 }
 
+#[tokio::test]
+async fn shutdown_drains_block_sync() {
+    // handshake between a fake node and a full node
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            block_sync_interval: 1,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (node, mut peer) = handshaken_node_and_peer(setup).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // wait for the block_sync_interval to "expire"
+    sleep(Duration::from_secs(1)).await;
+
+    // trigger the full node to request synchronization by sending it a higher block_height than it has
+    peer.write_message(&Payload::Ping(2u32, 0)).await;
+
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Pong));
+
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetSync(..)));
+
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    let block_2_header_hash = BlockHeaderHash::new(BLOCK_2_HEADER_HASH.to_vec());
+
+    peer.write_message(&Payload::Sync(vec![
+        block_1_header_hash.clone(),
+        block_2_header_hash.clone(),
+    ]))
+    .await;
+
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetBlocks(..)));
+
+    // signal the shutdown in the middle of the sync round
+    let node_clone = node.clone();
+    let shutdown = tokio::spawn(async move { node_clone.shut_down_gracefully().await });
+    wait_until!(1, node.is_shutting_down());
+
+    // the requested blocks are delivered after the shutdown was signalled
+    peer.write_message(&Payload::SyncBlock(BLOCK_1.to_vec())).await;
+    peer.write_message(&Payload::SyncBlock(BLOCK_2.to_vec())).await;
+
+    tokio::time::timeout(Duration::from_secs(5), shutdown)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // the blocks received during the round are committed before the node shuts down
+    assert!(node.expect_sync().storage().block_hash_exists(&block_1_header_hash));
+    assert!(node.expect_sync().storage().block_hash_exists(&block_2_header_hash));
+    assert!(!node.is_syncing_blocks());
+}
+
+#[tokio::test]
+async fn shutdown_stores_memory_pool() {
+    let node = test_node(TestSetup::default()).await;
+
+    let entry = Entry {
+        size_in_bytes: TRANSACTION_1.len(),
+        transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
+        origin: TxOrigin::Local,
+        priority_class: PriorityClass::Standard,
+        valid_from_height: None,
+    };
+    {
+        let sync = node.expect_sync();
+        sync.memory_pool()
+            .lock()
+            .insert(sync.storage(), entry)
+            .unwrap()
+            .unwrap();
+        assert!(sync.memory_pool().lock().is_dirty());
+    }
+
+    node.shut_down();
+
+    // the transaction inserted since the last periodic store is persisted on shutdown
+    let sync = node.expect_sync();
+    assert!(!sync.memory_pool().lock().is_dirty());
+    let stored_memory_pool = MemoryPool::<Tx>::from_storage(sync.storage()).unwrap();
+    assert_eq!(stored_memory_pool.transactions.len(), 1);
+}
+
+#[tokio::test]
+async fn best_block_height_updates() {
+    // handshake between a fake node and a full node
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // only the genesis block is in the ledger
+    let mut best_block_height = node.best_block_height();
+    assert_eq!(*best_block_height.borrow(), 0);
+
+    // each committed block is announced in order
+    for (expected_height, block) in [(1, BLOCK_1.to_vec()), (2, BLOCK_2.to_vec())].iter() {
+        peer.write_message(&Payload::Block(block.clone())).await;
+
+        tokio::time::timeout(Duration::from_secs(5), best_block_height.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*best_block_height.borrow(), *expected_height);
+    }
+}
+
+#[tokio::test]
+async fn queued_block_after_tip_change() {
+    // handshake between a fake node and a full node
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // the second block is validated while the tip is still the genesis block, and is committed once the
+    // first one moved the tip, so its transactions are verified again against the new tip
+    peer.write_message(&Payload::Block(BLOCK_1.to_vec())).await;
+    peer.write_message(&Payload::Block(BLOCK_2.to_vec())).await;
+
+    let block_2_header_hash = BlockHeaderHash::new(BLOCK_2_HEADER_HASH.to_vec());
+    wait_until!(5, node.expect_sync().current_block_height() == 2);
+    assert!(node.expect_sync().storage().is_canon(&block_2_header_hash));
+}
+
+#[tokio::test]
+async fn chain_leadership() {
+    let node = test_node(TestSetup::default()).await;
+
+    // with no peers, the node is trivially the leader
+    assert!(node.is_chain_leader());
+
+    // the node is at height 2
+    for block in [&BLOCK_1[..], &BLOCK_2[..]].iter() {
+        let block = Block::deserialize(block).unwrap();
+        node.expect_sync().storage().insert_and_commit(&block).unwrap();
+    }
+
+    // peers at lower or equal heights don't take over the leadership
+    for (port, block_height) in [(4141, 0), (4142, 1), (4143, 2)].iter() {
+        let address = format!("127.0.0.1:{}", port).parse().unwrap();
+        node.peer_book.set_connected(address, None);
+        node.peer_book.received_ping(address, *block_height, 0);
+    }
+    assert!(node.is_chain_leader());
+
+    // a peer with a higher block height does
+    let address = "127.0.0.1:4144".parse().unwrap();
+    node.peer_book.set_connected(address, None);
+    node.peer_book.received_ping(address, 3, 0);
+    assert!(!node.is_chain_leader());
+}
+
 #[tokio::test]
 async fn block_responder_side() {
     // handshake between a fake node and a full node
@@ -230,11 +412,17 @@ async fn transaction_initiator_side() {
     let entry_1 = Entry {
         size_in_bytes: TRANSACTION_1.len(),
         transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
+        origin: TxOrigin::Local,
+        priority_class: PriorityClass::Standard,
+        valid_from_height: None,
     };
 
     let entry_2 = Entry {
         size_in_bytes: TRANSACTION_2.len(),
         transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+        origin: TxOrigin::Local,
+        priority_class: PriorityClass::Standard,
+        valid_from_height: None,
     };
 
     // Verify the transactions have been stored in the node's memory pool
@@ -242,6 +430,405 @@ async fn transaction_initiator_side() {
     wait_until!(1, node.expect_sync().memory_pool().lock().contains(&entry_2));
 }
 
+#[tokio::test]
+async fn sync_peer_reconnect_attempt() {
+    // handshake between a fake node and a full node
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            block_sync_interval: 1,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (node, mut peer) = handshaken_node_and_peer(setup).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // wait for the block_sync_interval to "expire"
+    sleep(Duration::from_secs(1)).await;
+
+    // make the full node pick the peer as its sync peer by advertising a longer chain
+    peer.write_message(&Payload::Ping(10u32, 0)).await;
+
+    // read the Pong
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Pong));
+
+    // check if a GetSync message was received
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetSync(..)));
+
+    let sync_peer = node.expect_sync().sync_peer().unwrap();
+    assert_eq!(node.expect_sync().sync_peer_reconnect_attempts(sync_peer), 0);
+
+    // disconnect in the middle of the sync round
+    drop(peer);
+
+    // the full node attempts to reconnect to the sync peer that was ahead of it
+    wait_until!(5, node.expect_sync().sync_peer_reconnect_attempts(sync_peer) != 0);
+}
+
+#[tokio::test]
+async fn sticky_sync_peer() {
+    let node = test_node(TestSetup::default()).await;
+
+    // several peers advertise a longer chain
+    for port in 4141..4146 {
+        let address = format!("127.0.0.1:{}", port).parse().unwrap();
+        node.peer_book.set_connected(address, None);
+        node.peer_book.received_ping(address, 10, 0);
+    }
+
+    // the first block sync round is started with a random one of them
+    assert!(node.run_block_sync());
+    let sync_peer = node.expect_sync().sync_peer().unwrap();
+    assert_eq!(node.expect_sync().sticky_sync_peer(), None);
+
+    // once it completes the round, it keeps serving the following ones
+    for _ in 0..5 {
+        node.finished_syncing_blocks();
+        assert_eq!(node.expect_sync().sticky_sync_peer(), Some(sync_peer));
+
+        assert!(node.run_block_sync());
+        assert_eq!(node.expect_sync().sync_peer(), Some(sync_peer));
+    }
+
+    // but it's no longer preferred once it's not ahead anymore
+    node.finished_syncing_blocks();
+    node.peer_book.received_ping(sync_peer, 0, 0);
+    assert!(node.run_block_sync());
+    assert_ne!(node.expect_sync().sync_peer(), Some(sync_peer));
+}
+
+#[tokio::test]
+async fn seeded_sync_peer_selection() {
+    // the order in which the sync peers are picked from the same peers
+    async fn sync_peer_picks(rng_seed: u64) -> Vec<std::net::SocketAddr> {
+        let setup = TestSetup {
+            consensus_setup: Some(ConsensusSetup {
+                rng_seed: Some(rng_seed),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let node = test_node(setup).await;
+
+        for port in 4141..4149 {
+            let address = format!("127.0.0.1:{}", port).parse().unwrap();
+            node.peer_book.set_connected(address, None);
+            node.peer_book.received_ping(address, 10, 0);
+        }
+
+        let mut picks = vec![];
+        while node.run_block_sync() {
+            let sync_peer = node.expect_sync().sync_peer().unwrap();
+            picks.push(sync_peer);
+
+            // the sync peer is no longer ahead once the round is finished
+            node.finished_syncing_blocks();
+            node.peer_book.received_ping(sync_peer, 0, 0);
+        }
+
+        picks
+    }
+
+    // the nodes with the same rng seed pick the same sync peers in the same order
+    let picks = sync_peer_picks(42).await;
+    assert_eq!(picks.len(), 8);
+    assert_eq!(sync_peer_picks(42).await, picks);
+}
+
+#[tokio::test]
+async fn implausible_peer_height() {
+    let node = test_node(TestSetup::default()).await;
+
+    // one peer advertises a plausibly longer chain, the other an absurdly long one
+    let honest_peer = "127.0.0.1:4141".parse().unwrap();
+    let lying_peer = "127.0.0.1:4142".parse().unwrap();
+    for peer in &[honest_peer, lying_peer] {
+        node.peer_book.set_connected(*peer, None);
+    }
+    node.peer_book.received_ping(honest_peer, 10, 0);
+    node.peer_book.received_ping(lying_peer, u32::MAX, 0);
+
+    let lying_quality = node.peer_book.get_peer(lying_peer, true).unwrap().quality;
+
+    // the lying peer is never chosen to sync from, and is penalized for its claim every time
+    for round in 1..=3 {
+        assert!(node.run_block_sync());
+        assert_eq!(node.expect_sync().sync_peer(), Some(honest_peer));
+        assert_eq!(lying_quality.failures.load(Ordering::SeqCst), round);
+        node.finished_syncing_blocks();
+    }
+}
+
+#[tokio::test]
+async fn excess_sync_hash_packets() {
+    // handshake between a fake node and a full node
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            block_sync_interval: 1,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (node, mut peer) = handshaken_node_and_peer(setup).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // wait for the block_sync_interval to "expire"
+    sleep(Duration::from_secs(1)).await;
+
+    // trigger the full node to request synchronization by sending it a higher block_height than it has
+    peer.write_message(&Payload::Ping(2u32, 0)).await;
+
+    // read the Pong
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Pong));
+
+    // check if a GetSync message was received
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetSync(..)));
+
+    let sync_peer = node.expect_sync().sync_peer().unwrap();
+    let quality = node.peer_book.get_peer(sync_peer, true).unwrap().quality;
+
+    // respond to GetSync with a single Sync message, as expected
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    let sync = Payload::Sync(vec![block_1_header_hash]);
+    peer.write_message(&sync).await;
+
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetBlocks(..)));
+    assert_eq!(quality.failures.load(Ordering::SeqCst), 0);
+
+    // send another, unrequested Sync message
+    let block_2_header_hash = BlockHeaderHash::new(BLOCK_2_HEADER_HASH.to_vec());
+    let sync = Payload::Sync(vec![block_2_header_hash]);
+    peer.write_message(&sync).await;
+
+    // the peer is penalized and excluded from the sync round
+    wait_until!(5, quality.failures.load(Ordering::SeqCst) == 1);
+    assert_eq!(quality.remaining_sync_blocks.load(Ordering::SeqCst), 0);
+    assert!(!node.is_syncing_blocks());
+}
+
+#[tokio::test]
+async fn unsolicited_sync_block() {
+    // handshake between a fake node and a full node
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    let quality = node.peer_book.get_peer(peer.addr(), true).unwrap().quality;
+
+    // the node is syncing, but it hasn't requested any blocks from the peer yet
+    node.register_block_sync_attempt(peer.addr(), 1);
+    peer.write_message(&Payload::SyncBlock(BLOCK_1.to_vec())).await;
+
+    // the unsolicited block is handled like a regular Block, which doesn't affect the sync round
+    peer.write_message(&Payload::Ping(0u32, 0)).await;
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Pong));
+    assert_eq!(quality.remaining_sync_blocks.load(Ordering::SeqCst), 0);
+    assert!(node.is_syncing_blocks());
+
+    // a requested block is processed as a sync block and finishes the round
+    assert!(node.peer_book.expecting_sync_blocks(peer.addr(), 1));
+    peer.write_message(&Payload::SyncBlock(BLOCK_1.to_vec())).await;
+
+    wait_until!(5, !node.is_syncing_blocks());
+    assert_eq!(quality.remaining_sync_blocks.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn out_of_order_sync_block_batch() {
+    // handshake between a fake node and a full node committing the sync blocks in batches of 2
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            sync_commit_batch_size: 2,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (node, mut peer) = handshaken_node_and_peer(setup).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    let quality = node.peer_book.get_peer(peer.addr(), true).unwrap().quality;
+
+    node.register_block_sync_attempt(peer.addr(), 2);
+    assert!(node.peer_book.expecting_sync_blocks(peer.addr(), 2));
+
+    // the requested blocks arrive in reverse order
+    peer.write_message(&Payload::SyncBlock(BLOCK_2.to_vec())).await;
+    peer.write_message(&Payload::SyncBlock(BLOCK_1.to_vec())).await;
+
+    // both blocks are committed as a batch, and the peer isn't penalized
+    wait_until!(5, node.expect_sync().current_block_height() == 2);
+    wait_until!(1, !node.is_syncing_blocks());
+    assert_eq!(quality.failures.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn excess_requested_blocks() {
+    // handshake between a fake node and a full node serving at most 2 blocks per request
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            max_requested_blocks: 2,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (node, mut peer) = handshaken_node_and_peer(setup).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    let quality = node.peer_book.get_peer(peer.addr(), true).unwrap().quality;
+
+    // request the genesis block 3 times in a single GetBlocks
+    let genesis_hash = node.expect_sync().storage().get_block_hash(0).unwrap();
+    peer.write_message(&Payload::GetBlocks(vec![genesis_hash; 3])).await;
+
+    // the peer is penalized and only the first 2 blocks are served
+    wait_until!(5, quality.failures.load(Ordering::SeqCst) == 1);
+    for _ in 0..2 {
+        let payload = peer.read_payload().await.unwrap();
+        assert!(matches!(payload, Payload::SyncBlock(..)));
+    }
+
+    // the next message is the response to a Ping, not another block
+    peer.write_message(&Payload::Ping(0u32, 0)).await;
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Pong));
+}
+
+#[tokio::test]
+async fn excess_replaced_transactions() {
+    // handshake between a fake node and a full node
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    let quality = node.peer_book.get_peer(peer.addr(), true).unwrap().quality;
+
+    // a replacement claiming to replace too many transactions is penalized and not processed
+    let replaced_ids = vec![vec![0u8; 32]; snarkos_network::MAX_REPLACED_TRANSACTIONS + 1];
+    peer.write_message(&Payload::TransactionReplacement(replaced_ids, TRANSACTION_1.to_vec()))
+        .await;
+
+    wait_until!(5, quality.failures.load(Ordering::SeqCst) == 1);
+    assert!(node.expect_sync().memory_pool().lock().transactions.is_empty());
+}
+
+#[tokio::test]
+async fn oversized_block_locator() {
+    // handshake between a fake node and a full node
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    let quality = node.peer_book.get_peer(peer.addr(), true).unwrap().quality;
+
+    // send a GetSync with more block locator hashes than accepted
+    let genesis_hash = node.expect_sync().storage().get_block_hash(0).unwrap();
+    let block_locator_hashes = vec![genesis_hash; snarkos_network::MAX_BLOCK_LOCATOR_HASHES + 1];
+    peer.write_message(&Payload::GetSync(block_locator_hashes)).await;
+
+    // the peer is penalized and the request isn't answered with a Sync
+    wait_until!(5, quality.failures.load(Ordering::SeqCst) == 1);
+    peer.write_message(&Payload::Ping(0u32, 0)).await;
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Pong));
+}
+
+#[tokio::test]
+async fn capped_sync_hashes() {
+    // handshake between a fake node and a full node serving at most 1 block hash per GetSync
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            max_served_sync_hashes: 1,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (node, mut peer) = handshaken_node_and_peer(setup).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // the node is at height 2
+    for block in [&BLOCK_1[..], &BLOCK_2[..]].iter() {
+        let block = Block::deserialize(block).unwrap();
+        node.expect_sync().storage().insert_and_commit(&block).unwrap();
+    }
+
+    // a peer with only the genesis block receives a single block hash
+    let genesis_hash = node.expect_sync().storage().get_block_hash(0).unwrap();
+    peer.write_message(&Payload::GetSync(vec![genesis_hash.clone()])).await;
+    let payload = peer.read_payload().await.unwrap();
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    assert!(matches!(payload, Payload::Sync(hashes) if hashes == vec![block_1_header_hash.clone()]));
+
+    // and the following one once it asks again with an updated block locator
+    peer.write_message(&Payload::GetSync(vec![block_1_header_hash, genesis_hash]))
+        .await;
+    let payload = peer.read_payload().await.unwrap();
+    let block_2_header_hash = BlockHeaderHash::new(BLOCK_2_HEADER_HASH.to_vec());
+    assert!(matches!(payload, Payload::Sync(hashes) if hashes == vec![block_2_header_hash]));
+}
+
+#[tokio::test]
+async fn block_serving_rate_limit() {
+    // handshake between a fake node and a full node serving at most 2 blocks per second
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            max_served_blocks_per_second: 2,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (node, mut peer) = handshaken_node_and_peer(setup).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // rapidly request the genesis block 6 times
+    let genesis_hash = node.expect_sync().storage().get_block_hash(0).unwrap();
+    let requested_at = std::time::Instant::now();
+    peer.write_message(&Payload::GetBlocks(vec![genesis_hash; 6])).await;
+
+    // the first 2 blocks are served at once
+    for _ in 0..2 {
+        let payload = peer.read_payload().await.unwrap();
+        assert!(matches!(payload, Payload::SyncBlock(..)));
+    }
+    assert!(requested_at.elapsed() < Duration::from_millis(500));
+
+    // while the remaining ones are served at the capped rate
+    for _ in 0..4 {
+        let payload = peer.read_payload().await.unwrap();
+        assert!(matches!(payload, Payload::SyncBlock(..)));
+    }
+    assert!(requested_at.elapsed() >= Duration::from_secs(2));
+}
+
 #[tokio::test]
 async fn transaction_responder_side() {
     // handshake between a fake node and a full node
@@ -258,11 +845,17 @@ async fn transaction_responder_side() {
     let entry_1 = Entry {
         size_in_bytes: TRANSACTION_1.len(),
         transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
+        origin: TxOrigin::Local,
+        priority_class: PriorityClass::Standard,
+        valid_from_height: None,
     };
 
     let entry_2 = Entry {
         size_in_bytes: TRANSACTION_2.len(),
         transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+        origin: TxOrigin::Local,
+        priority_class: PriorityClass::Standard,
+        valid_from_height: None,
     };
 
     memory_pool.insert(&storage, entry_1).unwrap().unwrap();
@@ -306,6 +899,9 @@ async fn transaction_two_node() {
     let entry = Entry {
         size_in_bytes: size,
         transaction: transaction.clone(),
+        origin: TxOrigin::Local,
+        priority_class: PriorityClass::Standard,
+        valid_from_height: None,
     };
 
     memory_pool.insert(&storage, entry.clone()).unwrap().unwrap();
@@ -327,3 +923,357 @@ async fn transaction_two_node() {
     // check transaction is present in bob's memory pool
     wait_until!(5, node_bob.expect_sync().memory_pool().lock().contains(&entry));
 }
+
+#[tokio::test]
+async fn segmented_block_sync() {
+    // handshake between two fake nodes and a full node downloading single-block segments
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            block_sync_interval: 1,
+            sync_segment_size: 1,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (node, mut peer_a) = handshaken_node_and_peer(setup).await;
+    let mut peer_b = handshaken_peer(node.local_address().unwrap()).await;
+
+    // check if the peers have received an automatic Ping message from the node
+    let payload = peer_a.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+    let payload = peer_b.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // both peers advertise a longer chain
+    for peer in &mut [&mut peer_a, &mut peer_b] {
+        peer.write_message(&Payload::Ping(10u32, 0)).await;
+        let payload = peer.read_payload().await.unwrap();
+        assert!(matches!(payload, Payload::Pong));
+    }
+
+    // the full node picks one of them as its sync peer
+    wait_until!(5, node.expect_sync().sync_peer().is_some());
+    let (mut sync_peer, mut other_peer) = if node.expect_sync().sync_peer() == Some(peer_a.addr()) {
+        (peer_a, peer_b)
+    } else {
+        (peer_b, peer_a)
+    };
+
+    let payload = sync_peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetSync(..)));
+
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    let block_2_header_hash = BlockHeaderHash::new(BLOCK_2_HEADER_HASH.to_vec());
+    sync_peer
+        .write_message(&Payload::Sync(vec![
+            block_1_header_hash.clone(),
+            block_2_header_hash.clone(),
+        ]))
+        .await;
+
+    // each segment is requested from a different peer
+    let payload = sync_peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetBlocks(hashes) if hashes == vec![block_1_header_hash]));
+    let payload = other_peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetBlocks(hashes) if hashes == vec![block_2_header_hash]));
+
+    // the second segment arrives first, so it's held back until the first one is processed
+    other_peer.write_message(&Payload::SyncBlock(BLOCK_2.to_vec())).await;
+    wait_until!(5, node.expect_sync().segments().buffered() == 1);
+    assert_eq!(node.expect_sync().current_block_height(), 0);
+
+    sync_peer.write_message(&Payload::SyncBlock(BLOCK_1.to_vec())).await;
+    wait_until!(5, node.expect_sync().current_block_height() == 2);
+    assert!(!node.is_syncing_blocks());
+}
+
+#[tokio::test]
+async fn sparse_sync_hashes_follow_up() {
+    // handshake between two fake nodes and a full node
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            block_sync_interval: 1,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (node, mut peer_a) = handshaken_node_and_peer(setup).await;
+    let mut peer_b = handshaken_peer(node.local_address().unwrap()).await;
+
+    // check if the peers have received an automatic Ping message from the node
+    let payload = peer_a.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+    let payload = peer_b.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // both peers advertise a longer chain
+    for peer in &mut [&mut peer_a, &mut peer_b] {
+        peer.write_message(&Payload::Ping(10u32, 0)).await;
+        let payload = peer.read_payload().await.unwrap();
+        assert!(matches!(payload, Payload::Pong));
+    }
+
+    // the full node picks one of them as its sync peer
+    wait_until!(5, node.expect_sync().sync_peer().is_some());
+    let (mut sync_peer, mut other_peer) = if node.expect_sync().sync_peer() == Some(peer_a.addr()) {
+        (peer_a, peer_b)
+    } else {
+        (peer_b, peer_a)
+    };
+
+    let payload = sync_peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetSync(..)));
+
+    // the sync peer only provides a single block hash out of the 10 it should have
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    let block_2_header_hash = BlockHeaderHash::new(BLOCK_2_HEADER_HASH.to_vec());
+    sync_peer
+        .write_message(&Payload::Sync(vec![block_1_header_hash.clone()]))
+        .await;
+
+    let payload = sync_peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetBlocks(hashes) if hashes == vec![block_1_header_hash.clone()]));
+
+    // the other peer is asked for block hashes as well
+    let payload = other_peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetSync(..)));
+    assert!(node.expect_sync().is_sync_hash_peer(other_peer.addr()));
+
+    // only the blocks that weren't requested from the sync peer are requested from the other peer
+    other_peer
+        .write_message(&Payload::Sync(vec![block_1_header_hash, block_2_header_hash.clone()]))
+        .await;
+
+    let payload = other_peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetBlocks(hashes) if hashes == vec![block_2_header_hash]));
+}
+
+#[tokio::test]
+async fn segmented_block_sync_tampered_block() {
+    // handshake between two fake nodes and a full node downloading single-block segments
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            block_sync_interval: 1,
+            sync_segment_size: 1,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (node, mut peer_a) = handshaken_node_and_peer(setup).await;
+    let mut peer_b = handshaken_peer(node.local_address().unwrap()).await;
+
+    // check if the peers have received an automatic Ping message from the node
+    let payload = peer_a.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+    let payload = peer_b.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // both peers advertise a longer chain
+    for peer in &mut [&mut peer_a, &mut peer_b] {
+        peer.write_message(&Payload::Ping(10u32, 0)).await;
+        let payload = peer.read_payload().await.unwrap();
+        assert!(matches!(payload, Payload::Pong));
+    }
+
+    // the full node picks one of them as its sync peer
+    wait_until!(5, node.expect_sync().sync_peer().is_some());
+    let (mut sync_peer, mut other_peer) = if node.expect_sync().sync_peer() == Some(peer_a.addr()) {
+        (peer_a, peer_b)
+    } else {
+        (peer_b, peer_a)
+    };
+
+    let payload = sync_peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetSync(..)));
+
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    let block_2_header_hash = BlockHeaderHash::new(BLOCK_2_HEADER_HASH.to_vec());
+    sync_peer
+        .write_message(&Payload::Sync(vec![
+            block_1_header_hash.clone(),
+            block_2_header_hash.clone(),
+        ]))
+        .await;
+
+    let payload = sync_peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetBlocks(hashes) if hashes == vec![block_1_header_hash]));
+    let payload = other_peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetBlocks(hashes) if hashes == vec![block_2_header_hash]));
+
+    let quality = node.peer_book.get_peer(other_peer.addr(), true).unwrap().quality;
+
+    // the second block arrives with the header it was requested by, but with the transactions of the first one
+    let mut tampered_block = Block::<Tx>::deserialize(&BLOCK_2).unwrap();
+    tampered_block.transactions = Block::<Tx>::deserialize(&BLOCK_1).unwrap().transactions;
+    other_peer
+        .write_message(&Payload::SyncBlock(tampered_block.serialize().unwrap()))
+        .await;
+
+    // the block is rejected instead of being held back, and the peer is penalized
+    wait_until!(5, quality.failures.load(Ordering::SeqCst) == 1);
+    assert_eq!(node.expect_sync().segments().buffered(), 0);
+    assert!(node.is_syncing_blocks());
+}
+
+#[tokio::test]
+async fn transaction_not_relayed_to_origin() {
+    // handshake between two fake nodes and a full node
+    let (node, mut origin_peer) = handshaken_node_and_peer(TestSetup::default()).await;
+    let mut other_peer = handshaken_peer(node.local_address().unwrap()).await;
+
+    // the transaction is valid on top of the first block
+    let block_1 = snarkvm_dpc::Block::deserialize(&BLOCK_1).unwrap();
+    node.expect_sync().storage().insert_and_commit(&block_1).unwrap();
+
+    // check if the peers have received an automatic Ping message from the node
+    let payload = origin_peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+    let payload = other_peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // one of the peers sends a new transaction to the full node
+    origin_peer
+        .write_message(&Payload::Transaction(TRANSACTION_2.to_vec()))
+        .await;
+
+    // the transaction is relayed to the other peer
+    let payload = other_peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Transaction(transaction) if transaction == TRANSACTION_2.to_vec()));
+
+    // the full node has recorded where the transaction came from
+    let transaction_id = Tx::read(&TRANSACTION_2[..]).unwrap().transaction_id().unwrap();
+    assert_eq!(
+        node.expect_sync().memory_pool().lock().origin(&transaction_id),
+        Some(TxOrigin::Peer(origin_peer.addr()))
+    );
+
+    // but it's not echoed back to the peer it originated from
+    let echo = tokio::time::timeout(Duration::from_secs(1), origin_peer.read_payload()).await;
+    assert!(echo.is_err());
+}
+
+#[tokio::test]
+async fn slow_peer_relay_queue() {
+    // a full node queueing at most 4 relays per peer, connected to a peer that reads and one that doesn't
+    let setup = TestSetup {
+        consensus_setup: None,
+        peer_write_timeout: 600,
+        relay_queue_depth: 4,
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+    let mut fast_peer = handshaken_peer(node.local_address().unwrap()).await;
+    let slow_peer = handshaken_peer(node.local_address().unwrap()).await;
+    wait_until!(5, node.peer_book.number_of_connected_peers() == 2);
+
+    // relay enough large blocks to fill up the slow peer's connection
+    let miner = "127.0.0.1:4141".parse().unwrap();
+    for i in 0..64u8 {
+        node.propagate_block(vec![i; 512 * 1024], miner);
+
+        // the fast peer keeps receiving the relays
+        wait_until!(
+            10,
+            matches!(fast_peer.read_payload().await, Ok(Payload::Block(block)) if block[0] == i)
+        );
+    }
+
+    // while the slow peer's backlog is bounded, with its oldest relays dropped
+    let slow_quality = node.peer_book.get_peer(slow_peer.addr(), true).unwrap().quality;
+    assert!(node.queued_relays(slow_peer.addr()) <= 4);
+    assert!(slow_quality.dropped_relays.load(Ordering::SeqCst) > 0);
+
+    let fast_quality = node.peer_book.get_peer(fast_peer.addr(), true).unwrap().quality;
+    assert_eq!(fast_quality.dropped_relays.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn transaction_replacement_two_node() {
+    let node_alice = test_node(TestSetup::default()).await;
+    let alice_address = node_alice.local_address().unwrap();
+
+    let setup = TestSetup {
+        peer_sync_interval: 1,
+        bootnodes: vec![alice_address.to_string()],
+        ..Default::default()
+    };
+    let node_bob = test_node(setup).await;
+    wait_until!(5, node_alice.peer_book.number_of_connected_peers() == 1);
+
+    // a variant of the second transaction that spends the same records, but pays half the fee
+    let replacement = Tx::read(&TRANSACTION_2[..]).unwrap();
+    let mut original = replacement.clone();
+    original.value_balance = AleoAmount(replacement.value_balance.0 / 2);
+    original.memorandum[..8].copy_from_slice(&[0xff; 8]);
+
+    // both nodes hold the original transaction, which is valid on top of the first block
+    let block_1 = Block::deserialize(&BLOCK_1).unwrap();
+    for node in &[&node_alice, &node_bob] {
+        let storage = node.expect_sync().storage();
+        storage.insert_and_commit(&block_1).unwrap();
+
+        let mut memory_pool = node.expect_sync().memory_pool().lock();
+        memory_pool.replacement_window = Duration::from_secs(0);
+
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: original.clone(),
+            origin: TxOrigin::Local,
+            priority_class: PriorityClass::Standard,
+            valid_from_height: None,
+        };
+        memory_pool.insert(storage, entry).unwrap().unwrap();
+    }
+
+    // a peer sends the replacement to alice
+    let mut peer = handshaken_peer(alice_address).await;
+    peer.write_message(&Payload::Transaction(TRANSACTION_2.to_vec())).await;
+
+    // alice relays it to bob, who replaces the original with it too
+    let original_id = original.transaction_id().unwrap().to_vec();
+    let replacement_id = replacement.transaction_id().unwrap().to_vec();
+    wait_until!(
+        5,
+        node_bob
+            .expect_sync()
+            .memory_pool()
+            .lock()
+            .transactions
+            .contains_key(&replacement_id)
+    );
+
+    for node in &[&node_alice, &node_bob] {
+        let memory_pool = node.expect_sync().memory_pool().lock();
+        assert!(memory_pool.transactions.contains_key(&replacement_id));
+        assert!(!memory_pool.transactions.contains_key(&original_id));
+    }
+}
+
+#[tokio::test]
+async fn block_sync_expiry() {
+    let clock = Arc::new(MockClock::new());
+
+    let mut node = Node::new(test_config(TestSetup::default())).await.unwrap();
+    node.set_sync(test_consensus(Default::default()).with_clock(clock.clone()));
+
+    // the very first sync attempt is always due
+    assert!(node.expect_sync().has_block_sync_expired());
+
+    node.register_block_sync_attempt("127.0.0.1:4141".parse().unwrap(), 10);
+    assert!(!node.expect_sync().has_block_sync_expired());
+
+    // the sync attempt expires only once the expiration time has fully elapsed
+    clock.advance(Duration::from_secs(snarkos_network::BLOCK_SYNC_EXPIRATION_SECS as u64));
+    assert!(!node.expect_sync().has_block_sync_expired());
+
+    clock.advance(Duration::from_secs(1));
+    assert!(node.expect_sync().has_block_sync_expired());
+
+    // no blocks were imported during the sync round
+    let sync = node.expect_sync();
+    assert_eq!(
+        sync.progress()
+            .blocks_per_second(sync.current_block_height(), clock.now()),
+        0.0
+    );
+}
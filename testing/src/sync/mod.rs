@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use snarkos_consensus::{ConsensusParameters, MerkleTreeLedger};
+use snarkos_consensus::{ConsensusParameters, MerkleTreeLedger, TWO_HOURS_UNIX};
 use snarkos_storage::LedgerStorage;
 use snarkvm_algorithms::CRH;
 use snarkvm_dpc::{testnet1::instantiated::Components, DPCComponents, Network, TransactionError, TransactionScheme};
@@ -51,6 +51,7 @@ pub static TEST_CONSENSUS_PARAMS: Lazy<ConsensusParameters> = Lazy::new(|| {
         max_block_size: 1_000_000usize,
         max_nonce: u32::max_value(),
         target_block_time: 2i64, //unix seconds
+        max_future_block_time_drift: TWO_HOURS_UNIX,
         network_id: Network::Mainnet,
         verifier: PoswMarlin::verify_only().unwrap(),
         authorized_inner_snark_ids: vec![inner_snark_id],